@@ -0,0 +1,100 @@
+//! Journal benchmarks
+//!
+//! Measures append latency, snapshot save/load, and raw event reads.
+//!
+//! Run with: `cargo bench --bench journal`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use seq_actors::{ActorId, Event, Journal, MapKey, Snapshot, TypedValue};
+use std::collections::BTreeMap;
+use tempfile::TempDir;
+
+/// A small account-style payload, typical of a single business event
+fn small_payload() -> TypedValue {
+    let mut map = BTreeMap::new();
+    map.insert(MapKey::String("amount".to_string()), TypedValue::Int(100));
+    map.insert(
+        MapKey::String("currency".to_string()),
+        TypedValue::String("USD".to_string()),
+    );
+    TypedValue::Map(map)
+}
+
+/// A state map with `n` entries, used for snapshot benchmarks
+fn state_with_entries(n: usize) -> TypedValue {
+    let mut map = BTreeMap::new();
+    for i in 0..n {
+        map.insert(
+            MapKey::String(format!("key-{}", i)),
+            TypedValue::Int(i as i64),
+        );
+    }
+    TypedValue::Map(map)
+}
+
+fn bench_append(c: &mut Criterion) {
+    let temp_dir = TempDir::new().unwrap();
+    let journal = Journal::new(temp_dir.path());
+    let actor_id = ActorId::new();
+    let event = Event::new(0, "Deposit".to_string(), small_payload());
+
+    let mut group = c.benchmark_group("journal_append");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("small_event", |b| {
+        b.iter(|| journal.append(&actor_id, &event).unwrap())
+    });
+    group.finish();
+}
+
+fn bench_snapshot(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot");
+
+    for entries in [10usize, 1_000, 10_000] {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let actor_id = ActorId::new();
+        let snapshot = Snapshot {
+            actor_id: Some(actor_id.clone()),
+            seq: 0,
+            state: state_with_entries(entries),
+            ts: 0,
+        };
+
+        group.bench_with_input(
+            BenchmarkId::new("save", entries),
+            &snapshot,
+            |b, snapshot| b.iter(|| journal.save_snapshot(&actor_id, snapshot).unwrap()),
+        );
+
+        journal.save_snapshot(&actor_id, &snapshot).unwrap();
+        group.bench_function(BenchmarkId::new("load", entries), |b| {
+            b.iter(|| journal.load_snapshot(&actor_id).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_read_events(c: &mut Criterion) {
+    let mut group = c.benchmark_group("journal_read");
+
+    for events in [100u64, 1_000, 10_000] {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let actor_id = ActorId::new();
+        for seq in 0..events {
+            let event = Event::new(seq, "Deposit".to_string(), small_payload());
+            journal.append(&actor_id, &event).unwrap();
+        }
+
+        group.throughput(Throughput::Elements(events));
+        group.bench_function(BenchmarkId::from_parameter(events), |b| {
+            b.iter(|| journal.read_events(&actor_id).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_append, bench_snapshot, bench_read_events);
+criterion_main!(benches);
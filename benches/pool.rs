@@ -0,0 +1,80 @@
+//! Stack node pool benchmarks
+//!
+//! Measures the push/pop pattern of a builtin call (pop two arguments,
+//! push one result) with nodes from the allocator vs from a `NodePool`.
+//!
+//! Run with: `cargo bench --bench pool`
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use seq_actors::pool::NodePool;
+use std::hint::black_box;
+
+/// Same shape as a Seq stack node: a 32-byte value and a next pointer
+struct Node {
+    value: [u8; 32],
+    next: *mut Node,
+}
+
+const CALLS: u64 = 1_000;
+
+fn bench_builtin_calls(c: &mut Criterion) {
+    let mut group = c.benchmark_group("builtin_calls");
+    group.throughput(Throughput::Elements(CALLS));
+
+    group.bench_function("boxed", |b| {
+        b.iter(|| {
+            let mut top: *mut Node = std::ptr::null_mut();
+            for i in 0..CALLS {
+                for _ in 0..2 {
+                    top = black_box(Box::into_raw(Box::new(Node {
+                        value: [i as u8; 32],
+                        next: top,
+                    })));
+                }
+                for _ in 0..2 {
+                    let node = unsafe { Box::from_raw(top) };
+                    black_box(node.value);
+                    top = node.next;
+                }
+                top = black_box(Box::into_raw(Box::new(Node {
+                    value: [0; 32],
+                    next: top,
+                })));
+                let node = unsafe { Box::from_raw(top) };
+                top = node.next;
+            }
+            black_box(top)
+        })
+    });
+
+    group.bench_function("pooled", |b| {
+        let mut pool = NodePool::default();
+        b.iter(|| {
+            let mut top: *mut Node = std::ptr::null_mut();
+            for i in 0..CALLS {
+                for _ in 0..2 {
+                    top = black_box(pool.alloc(Node {
+                        value: [i as u8; 32],
+                        next: top,
+                    }));
+                }
+                for _ in 0..2 {
+                    let node = unsafe { pool.recycle(top) };
+                    black_box(node.value);
+                    top = node.next;
+                }
+                top = black_box(pool.alloc(Node {
+                    value: [0; 32],
+                    next: top,
+                }));
+                top = unsafe { pool.recycle(top) }.next;
+            }
+            black_box(top)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_builtin_calls);
+criterion_main!(benches);
@@ -0,0 +1,142 @@
+//! Runtime benchmarks
+//!
+//! Measures the registry lookup behind every send (single-threaded and
+//! under cross-thread contention), per-message vs batched sends, and
+//! recovery time vs journal size.
+//!
+//! Run with: `cargo bench --bench runtime`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use seq_actors::{
+    ActorId, ActorRuntime, BehaviorContext, BehaviorError, Event, Mailbox, RuntimeConfig,
+    TypedValue,
+};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+fn runtime_in(temp_dir: &TempDir) -> ActorRuntime {
+    ActorRuntime::new(RuntimeConfig {
+        journal_path: temp_dir.path().to_path_buf(),
+        journaling_enabled: true,
+        snapshot_interval: 100,
+        ..RuntimeConfig::default()
+    })
+}
+
+fn bench_send_lookup(c: &mut Criterion) {
+    let temp_dir = TempDir::new().unwrap();
+    let runtime = Arc::new(runtime_in(&temp_dir));
+
+    let ids: Arc<Vec<ActorId>> = Arc::new(
+        (0..10_000)
+            .map(|i| {
+                let id = ActorId::new();
+                runtime.register_actor(id.clone(), Mailbox::new(i), "bench".to_string());
+                id
+            })
+            .collect(),
+    );
+
+    let mut group = c.benchmark_group("send_lookup");
+    group.throughput(Throughput::Elements(ids.len() as u64));
+
+    group.bench_function("single_thread", |b| {
+        b.iter(|| {
+            for id in ids.iter() {
+                criterion::black_box(runtime.get_mailbox(id));
+            }
+        })
+    });
+
+    for threads in [2usize, 8] {
+        group.throughput(Throughput::Elements((ids.len() * threads) as u64));
+        group.bench_function(BenchmarkId::new("contended", threads), |b| {
+            b.iter(|| {
+                let handles: Vec<_> = (0..threads)
+                    .map(|_| {
+                        let runtime = runtime.clone();
+                        let ids = ids.clone();
+                        std::thread::spawn(move || {
+                            for id in ids.iter() {
+                                criterion::black_box(runtime.get_mailbox(id));
+                            }
+                        })
+                    })
+                    .collect();
+                for handle in handles {
+                    handle.join().unwrap();
+                }
+            })
+        });
+    }
+
+    group.finish();
+
+    for id in ids.iter() {
+        runtime.unregister_actor(id);
+    }
+}
+
+fn bench_send_batch(c: &mut Criterion) {
+    let temp_dir = TempDir::new().unwrap();
+    let runtime = runtime_in(&temp_dir);
+    runtime.register_behavior(
+        "sink",
+        |_ctx: &mut BehaviorContext, state: &TypedValue, _msg: &TypedValue| {
+            Ok::<_, BehaviorError>(state.clone())
+        },
+    );
+    let id = runtime.spawn("sink").unwrap();
+    let msgs: Vec<TypedValue> = (0..1_000).map(TypedValue::Int).collect();
+
+    let mut group = c.benchmark_group("send_batch");
+    group.throughput(Throughput::Elements(msgs.len() as u64));
+
+    group.bench_function("one_by_one", |b| {
+        b.iter(|| {
+            for msg in &msgs {
+                runtime.send(&id, msg.clone()).unwrap();
+            }
+            runtime.run_until_idle().unwrap();
+        })
+    });
+    group.bench_function("batched", |b| {
+        b.iter(|| {
+            runtime.send_batch(&id, msgs.clone()).unwrap();
+            runtime.run_until_idle().unwrap();
+        })
+    });
+
+    group.finish();
+    runtime.unregister_actor(&id);
+}
+
+fn bench_recovery(c: &mut Criterion) {
+    let mut group = c.benchmark_group("recovery");
+
+    for events in [100u64, 1_000, 10_000] {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = runtime_in(&temp_dir);
+        let id = ActorId::new();
+        for seq in 0..events {
+            let event = Event::new(seq, "Tick".to_string(), TypedValue::Int(seq as i64));
+            runtime.persist_event(&id, &event).unwrap();
+        }
+
+        group.bench_function(BenchmarkId::new("journal_only", events), |b| {
+            b.iter(|| runtime.recover_state(&id).unwrap())
+        });
+
+        runtime
+            .save_snapshot(&id, &TypedValue::Int(0), events / 2)
+            .unwrap();
+        group.bench_function(BenchmarkId::new("snapshot_plus_tail", events), |b| {
+            b.iter(|| runtime.recover_state(&id).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_send_lookup, bench_send_batch, bench_recovery);
+criterion_main!(benches);
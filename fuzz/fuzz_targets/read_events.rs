@@ -0,0 +1,13 @@
+//! Feed arbitrary bytes to the journal record decoder.
+//!
+//! Run with: `cargo +nightly fuzz run read_events`
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use seq_actors::Journal;
+
+fuzz_target!(|data: &[u8]| {
+    // Corrupt input must surface as an error, never a panic
+    let _ = Journal::decode_events(data);
+});
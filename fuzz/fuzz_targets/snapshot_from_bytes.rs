@@ -0,0 +1,12 @@
+//! Feed arbitrary bytes to the snapshot file decoder (header and body).
+//!
+//! Run with: `cargo +nightly fuzz run snapshot_from_bytes`
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use seq_actors::Journal;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Journal::decode_snapshot(data);
+});
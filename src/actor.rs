@@ -7,19 +7,85 @@
 //! - Journal (for event persistence)
 
 use crate::serialize::TypedValue;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::str::FromStr;
 use uuid::Uuid;
 
 /// Unique identifier for an actor
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+///
+/// The stable textual form is the hyphenated lowercase UUID produced by
+/// `Display`; `FromStr` and `TryFrom<&str>` accept it back. Serializes as
+/// the UUID itself, so tooling can read IDs out of events and snapshots
+/// without parsing payloads.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct ActorId(pub Uuid);
 
+/// How new actor IDs are generated (`RuntimeConfig::id_scheme`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdScheme {
+    /// Random (UUIDv4)
+    #[default]
+    Random,
+    /// Time-ordered (UUIDv7): IDs, and the journal directories named
+    /// after them, sort by creation time to the millisecond
+    TimeOrdered,
+}
+
 impl ActorId {
+    /// Reserved ID whose journal records runtime configuration changes
+    /// (see `crate::audit`)
+    pub const CONFIG: ActorId = ActorId(Uuid::nil());
+
+    /// Reserved ID that runtime lifecycle events are sent from (see
+    /// `crate::system`), and whose journal records throughput (see
+    /// `crate::throughput`)
+    pub const SYSTEM: ActorId = ActorId(Uuid::from_u128(1));
+
+    /// Root of the name-based IDs of `from_name`: the UUIDv5 of the URL
+    /// `https://github.com/navicore/seq-actors`
+    pub const NAME_ROOT: Uuid = Uuid::from_u128(0x28da57e0_95bc_52f7_8564_93587faec17b);
+
     /// Create a new random actor ID
     pub fn new() -> Self {
         ActorId(Uuid::new_v4())
     }
 
+    /// Create a new time-ordered actor ID
+    ///
+    /// IDs created later sort after earlier ones as text; within one
+    /// millisecond they are ordered by a per-process counter.
+    pub fn time_ordered() -> Self {
+        ActorId(Uuid::now_v7())
+    }
+
+    /// The actor ID for a name, the same in every run and on every node
+    ///
+    /// Lets entities such as `("account", "1234")` be found again by name
+    /// for sharding and recovery. A standard UUIDv5: `name` under the
+    /// UUIDv5 of `namespace` under `NAME_ROOT`, so any UUID library can
+    /// derive the same IDs.
+    pub fn from_name(namespace: &str, name: &str) -> Self {
+        let namespace = Uuid::new_v5(&Self::NAME_ROOT, namespace.as_bytes());
+        ActorId(Uuid::new_v5(&namespace, name.as_bytes()))
+    }
+
+    /// Create a new actor ID using `scheme`
+    pub fn generate(scheme: IdScheme) -> Self {
+        match scheme {
+            IdScheme::Random => ActorId::new(),
+            IdScheme::TimeOrdered => ActorId::time_ordered(),
+        }
+    }
+
+    /// Creation time of a time-ordered ID, as a Unix timestamp in
+    /// milliseconds (`None` for other IDs)
+    pub fn timestamp_millis(&self) -> Option<u64> {
+        let (secs, nanos) = self.0.get_timestamp()?.to_unix();
+        Some(secs * 1000 + nanos as u64 / 1_000_000)
+    }
+
     /// Create from an existing UUID
     pub fn from_uuid(uuid: Uuid) -> Self {
         ActorId(uuid)
@@ -43,6 +109,44 @@ impl std::fmt::Display for ActorId {
     }
 }
 
+impl FromStr for ActorId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s).map(ActorId)
+    }
+}
+
+impl TryFrom<&str> for ActorId {
+    type Error = uuid::Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Compact numeric handle for an actor
+///
+/// Handles are interned by the registry when an actor is registered and
+/// are what Seq code carries on the stack, so FFI calls don't allocate and
+/// re-parse a UUID string on every crossing. Handles are never reused;
+/// `0` is never issued and can be treated as "no actor".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ActorHandle(pub u64);
+
+impl ActorHandle {
+    /// Get the raw value pushed onto the Seq stack
+    pub fn as_raw(&self) -> u64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for ActorHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
 /// Reference to an actor (for sending messages)
 #[derive(Debug, Clone)]
 pub struct ActorRef {
@@ -54,6 +158,25 @@ impl ActorRef {
     pub fn new(id: ActorId) -> Self {
         ActorRef { id }
     }
+
+    /// Ask the actor through the global runtime and await the reply
+    ///
+    /// Resolves to an error right away if no global runtime is installed
+    /// or the actor can't take the message.
+    #[cfg(feature = "async-bridge")]
+    pub fn ask_async(
+        &self,
+        msg: TypedValue,
+    ) -> impl std::future::Future<Output = Result<TypedValue, crate::error::RuntimeError>> {
+        use crate::reply::Reply;
+
+        match crate::runtime::global_runtime() {
+            Some(runtime) => runtime
+                .ask(&self.id, msg)
+                .unwrap_or_else(|e| Reply::failed(self.id.clone(), e)),
+            None => Reply::failed(self.id.clone(), crate::error::RuntimeError::NoGlobalRuntime),
+        }
+    }
 }
 
 /// Actor instance
@@ -125,6 +248,56 @@ mod tests {
         assert_ne!(id1, id2);
     }
 
+    #[test]
+    fn test_actor_id_text_round_trip() {
+        let id = ActorId::new();
+        let text = id.to_string();
+
+        assert_eq!(text.parse::<ActorId>().unwrap(), id);
+        assert_eq!(ActorId::try_from(text.as_str()).unwrap(), id);
+        assert!("not-a-uuid".parse::<ActorId>().is_err());
+    }
+
+    #[test]
+    fn test_actor_id_serde_round_trip() {
+        let id = ActorId::new();
+        let bytes = bincode::serialize(&id).unwrap();
+        assert_eq!(bincode::deserialize::<ActorId>(&bytes).unwrap(), id);
+    }
+
+    #[test]
+    fn test_time_ordered_ids_sort_by_creation() {
+        let ids: Vec<ActorId> = (0..100)
+            .map(|_| ActorId::generate(IdScheme::TimeOrdered))
+            .collect();
+        let mut sorted = ids.clone();
+        sorted.sort_by_key(|id| id.to_string());
+        assert_eq!(sorted, ids);
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        assert!(ids[99].timestamp_millis().unwrap().abs_diff(now) < 60_000);
+        assert_eq!(ActorId::new().timestamp_millis(), None);
+    }
+
+    #[test]
+    fn test_actor_id_from_name_is_deterministic() {
+        let id = ActorId::from_name("account", "1234");
+        assert_eq!(ActorId::from_name("account", "1234"), id);
+        assert_ne!(ActorId::from_name("account", "1235"), id);
+        assert_ne!(ActorId::from_name("accoun", "t1234"), id);
+        assert_eq!(id.0.get_version_num(), 5);
+        // Pinned, so a change to the derivation can't go unnoticed
+        assert_eq!(id.to_string(), "96a433e0-f006-5f67-8c44-e1ef223253b2");
+        let root = Uuid::new_v5(
+            &Uuid::NAMESPACE_URL,
+            b"https://github.com/navicore/seq-actors",
+        );
+        assert_eq!(ActorId::NAME_ROOT, root);
+    }
+
     #[test]
     fn test_actor_creation() {
         let actor = Actor::new("my-behavior".to_string());
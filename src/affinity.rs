@@ -0,0 +1,127 @@
+//! Dedicated threads for pinned actors
+//!
+//! Actors pinned to a pool (`ActorRuntime::pin`, `pin_behavior`,
+//! `spawn_pinned`) are left alone by `run_until_idle`. A `PoolWorker`
+//! gives a pool its own thread that handles their messages, so a
+//! latency-sensitive pool never waits behind bulk processing on the
+//! shared run loop.
+//!
+//! ```rust,ignore
+//! let runtime = Arc::new(ActorRuntime::with_defaults());
+//! runtime.pin_behavior("order-book", "latency")?;
+//! let _worker = PoolWorker::spawn(&runtime, "latency", Duration::from_millis(1));
+//! // ... the worker stops when dropped
+//! ```
+//!
+//! TODO: Pin pools to May worker threads once actors run as coroutines.
+
+use crate::error::RuntimeError;
+use crate::runtime::ActorRuntime;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Background thread handling one pool's actors
+///
+/// Stops when dropped, when the runtime is dropped, or at the first error
+/// other than a behavior failure.
+pub struct PoolWorker {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Result<(), RuntimeError>>>,
+}
+
+impl PoolWorker {
+    /// Start handling `pool`, checking for new messages every
+    /// `idle_interval` once its inboxes are empty
+    pub fn spawn(runtime: &Arc<ActorRuntime>, pool: &str, idle_interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let runtime: Weak<ActorRuntime> = Arc::downgrade(runtime);
+        let pool = pool.to_string();
+
+        let thread = {
+            let stop = stop.clone();
+            std::thread::Builder::new()
+                .name(format!("seq-actors-pool-{}", pool))
+                .spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        let Some(runtime) = runtime.upgrade() else {
+                            break;
+                        };
+                        let handled = runtime.run_pool_until_idle(&pool)?;
+                        drop(runtime);
+                        if handled == 0 {
+                            std::thread::park_timeout(idle_interval);
+                        }
+                    }
+                    Ok(())
+                })
+                .expect("failed to spawn pool worker thread")
+        };
+
+        PoolWorker {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// Stop the worker, returning the error that ended it early, if any
+    pub fn stop(mut self) -> Result<(), RuntimeError> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> Result<(), RuntimeError> {
+        self.stop.store(true, Ordering::Relaxed);
+        match self.thread.take() {
+            Some(thread) => {
+                thread.thread().unpark();
+                thread.join().expect("pool worker thread panicked")
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for PoolWorker {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavior::{BehaviorContext, BehaviorError};
+    use crate::runtime::RuntimeConfig;
+    use crate::serialize::TypedValue;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_worker_handles_pinned_actor() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = Arc::new(ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            ..RuntimeConfig::default()
+        }));
+        runtime.register_behavior(
+            "doubler",
+            |ctx: &mut BehaviorContext, state: &TypedValue, msg: &TypedValue| {
+                if let TypedValue::Int(n) = msg {
+                    ctx.reply(TypedValue::Int(n * 2));
+                }
+                Ok::<_, BehaviorError>(state.clone())
+            },
+        );
+        let id = runtime.spawn_pinned("doubler", "fast").unwrap();
+
+        let worker = PoolWorker::spawn(&runtime, "fast", Duration::from_millis(1));
+        let reply = runtime.ask(&id, TypedValue::Int(4)).unwrap();
+        assert_eq!(
+            reply.wait(Duration::from_secs(5)).unwrap(),
+            TypedValue::Int(8)
+        );
+        worker.stop().unwrap();
+
+        runtime.unregister_actor(&id);
+    }
+}
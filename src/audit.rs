@@ -0,0 +1,106 @@
+//! Audit log of runtime reconfiguration
+//!
+//! Operators change a running system through the runtime's setters: rate
+//! limits, panic policies, sandboxes, message ages and timeouts, state
+//! limits, pools and scheduling groups (per actor or per behavior),
+//! aliases, delivery strategies. With journaling enabled, each change is
+//! appended as a `ConfigChanged` event to the journal of the reserved
+//! actor `ActorId::CONFIG`, so who-changed-what can be reconstructed
+//! after an incident (`ActorRuntime::config_history`), and the history
+//! is archived, verified and replicated like any other journal.
+//!
+//! Each event's payload is a map of `setting` (the setter's name without
+//! `set_`, e.g. `rate_limit`), what it applies to (an `actor`, a
+//! `behavior` or a `delivery_group`; see `ConfigTarget`), and `value`
+//! (the new value's debug form, `None` when cleared). Changes are
+//! recorded before they take effect, and only take effect once recorded:
+//! if the event can't be appended, the setter fails and nothing changes.
+//! Settings fixed at startup in `RuntimeConfig` are not events.
+
+use crate::actor::ActorId;
+use crate::journal::Event;
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::collections::BTreeMap;
+
+/// Event type of a recorded change
+pub const CONFIG_CHANGED: &str = "ConfigChanged";
+
+fn key(name: &str) -> TypedMapKey {
+    TypedMapKey::String(name.to_string())
+}
+
+/// What a configuration change applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigTarget {
+    Actor(ActorId),
+    /// Actors spawned with the behavior from then on
+    Behavior(String),
+    DeliveryGroup(String),
+}
+
+impl ConfigTarget {
+    /// Payload key the target is recorded under
+    fn key(&self) -> &'static str {
+        match self {
+            ConfigTarget::Actor(_) => "actor",
+            ConfigTarget::Behavior(_) => "behavior",
+            ConfigTarget::DeliveryGroup(_) => "delivery_group",
+        }
+    }
+}
+
+/// One recorded configuration change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigChange {
+    pub seq: u64,
+    /// Unix timestamp (milliseconds)
+    pub ts: u64,
+    /// What was changed, e.g. `rate_limit`
+    pub setting: String,
+    pub target: ConfigTarget,
+    /// Debug form of the new value
+    pub value: String,
+}
+
+impl ConfigChange {
+    /// Payload of the `ConfigChanged` event for a change
+    pub(crate) fn payload(setting: &str, target: &ConfigTarget, value: String) -> TypedValue {
+        let name = match target {
+            ConfigTarget::Actor(id) => id.to_string(),
+            ConfigTarget::Behavior(name) | ConfigTarget::DeliveryGroup(name) => name.clone(),
+        };
+        let mut fields = BTreeMap::new();
+        fields.insert(key("setting"), TypedValue::String(setting.to_string()));
+        fields.insert(key(target.key()), TypedValue::String(name));
+        fields.insert(key("value"), TypedValue::String(value));
+        TypedValue::Map(fields)
+    }
+
+    /// Read a change back from its journaled event
+    pub fn from_event(event: &Event) -> Option<Self> {
+        if event.event_type != CONFIG_CHANGED {
+            return None;
+        }
+        let TypedValue::Map(fields) = &event.payload else {
+            return None;
+        };
+        let field = |name| match fields.get(&key(name)) {
+            Some(TypedValue::String(s)) => Some(s.clone()),
+            _ => None,
+        };
+        let target = if let Some(actor) = field("actor") {
+            ConfigTarget::Actor(actor.parse().ok()?)
+        } else if let Some(behavior) = field("behavior") {
+            ConfigTarget::Behavior(behavior)
+        } else {
+            ConfigTarget::DeliveryGroup(field("delivery_group")?)
+        };
+        Some(ConfigChange {
+            seq: event.seq,
+            ts: event.ts,
+            setting: field("setting")?,
+            target,
+            value: field("value")?,
+        })
+    }
+}
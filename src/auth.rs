@@ -0,0 +1,174 @@
+//! Authorization policy for operator-facing APIs
+//!
+//! Anything that exposes the runtime beyond the host process (an admin
+//! socket, an HTTP API, the remote transport) asks an `AuthPolicy` before
+//! acting on a request. The caller establishes *who* is asking (a bearer
+//! token, or the subject of a verified mTLS certificate). The policy
+//! decides *what* they may do.
+//!
+//! `ActorRuntime::set_auth_policy` installs the policy, and the `_as`
+//! entry points (`health_as`, `dump_actor_as`, `send_as`, `spawn_as`,
+//! `stop_actor_as`) check it before doing the plain call. Until a policy
+//! is installed they deny everything.
+//!
+//! TODO: Route the remote transport through the `_as` entry points once
+//! it exists.
+
+use crate::actor::ActorId;
+use std::collections::{HashMap, HashSet};
+
+/// Authenticated identity of a caller
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Principal {
+    /// No credentials presented
+    Anonymous,
+    /// Bearer token
+    Token(String),
+    /// Subject of a verified client certificate
+    Certificate(String),
+}
+
+/// An operation a caller wants to perform
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// Read runtime health and metrics
+    Health,
+    /// Inspect an actor's state or mailbox
+    Inspect(ActorId),
+    /// Send a message to an actor
+    Send(ActorId),
+    /// Spawn an actor with the named behavior
+    Spawn(String),
+    /// Stop an actor
+    Stop(ActorId),
+}
+
+/// Kind of operation, without its target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperationKind {
+    Health,
+    Inspect,
+    Send,
+    Spawn,
+    Stop,
+}
+
+impl Operation {
+    pub fn kind(&self) -> OperationKind {
+        match self {
+            Operation::Health => OperationKind::Health,
+            Operation::Inspect(_) => OperationKind::Inspect,
+            Operation::Send(_) => OperationKind::Send,
+            Operation::Spawn(_) => OperationKind::Spawn,
+            Operation::Stop(_) => OperationKind::Stop,
+        }
+    }
+}
+
+/// Decides whether a principal may perform an operation
+pub trait AuthPolicy: Send + Sync {
+    fn authorize(&self, principal: &Principal, op: &Operation) -> bool;
+}
+
+/// Policy that permits everything (local development only)
+pub struct AllowAll;
+
+impl AuthPolicy for AllowAll {
+    fn authorize(&self, _principal: &Principal, _op: &Operation) -> bool {
+        true
+    }
+}
+
+/// Static grants per principal
+///
+/// A grant names an operation kind, optionally restricted to specific
+/// actors. Unknown principals (including `Anonymous`, unless granted) are
+/// denied.
+#[derive(Default)]
+pub struct GrantPolicy {
+    grants: HashMap<Principal, Vec<Grant>>,
+}
+
+struct Grant {
+    kind: OperationKind,
+    /// `None` = any actor
+    actors: Option<HashSet<ActorId>>,
+}
+
+impl GrantPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow `principal` to perform `kind` on any actor
+    pub fn allow(mut self, principal: Principal, kind: OperationKind) -> Self {
+        self.grants
+            .entry(principal)
+            .or_default()
+            .push(Grant { kind, actors: None });
+        self
+    }
+
+    /// Allow `principal` to perform `kind` on the given actors only
+    pub fn allow_on(
+        mut self,
+        principal: Principal,
+        kind: OperationKind,
+        actors: impl IntoIterator<Item = ActorId>,
+    ) -> Self {
+        self.grants.entry(principal).or_default().push(Grant {
+            kind,
+            actors: Some(actors.into_iter().collect()),
+        });
+        self
+    }
+}
+
+impl AuthPolicy for GrantPolicy {
+    fn authorize(&self, principal: &Principal, op: &Operation) -> bool {
+        let Some(grants) = self.grants.get(principal) else {
+            return false;
+        };
+        let target = match op {
+            Operation::Inspect(id) | Operation::Send(id) | Operation::Stop(id) => Some(id),
+            Operation::Health | Operation::Spawn(_) => None,
+        };
+
+        grants.iter().any(|grant| {
+            grant.kind == op.kind()
+                && match (&grant.actors, target) {
+                    (None, _) => true,
+                    (Some(actors), Some(id)) => actors.contains(id),
+                    (Some(_), None) => false,
+                }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grant_policy() {
+        let ops = Principal::Token("ops".to_string());
+        let viewer = Principal::Certificate("CN=viewer".to_string());
+        let watched = ActorId::new();
+        let other = ActorId::new();
+
+        let policy = GrantPolicy::new()
+            .allow(ops.clone(), OperationKind::Stop)
+            .allow(ops.clone(), OperationKind::Health)
+            .allow_on(viewer.clone(), OperationKind::Inspect, [watched.clone()]);
+
+        assert!(policy.authorize(&ops, &Operation::Stop(other.clone())));
+        assert!(policy.authorize(&ops, &Operation::Health));
+        assert!(!policy.authorize(&ops, &Operation::Spawn("counter".to_string())));
+
+        assert!(policy.authorize(&viewer, &Operation::Inspect(watched)));
+        assert!(!policy.authorize(&viewer, &Operation::Inspect(other)));
+
+        assert!(!policy.authorize(&Principal::Anonymous, &Operation::Health));
+        assert!(AllowAll.authorize(&Principal::Anonymous, &Operation::Health));
+    }
+}
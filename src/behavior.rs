@@ -0,0 +1,382 @@
+//! Actor behaviors
+//!
+//! A behavior handles one message at a time: given the actor's current
+//! state and a message, it returns the next state. Events recorded through
+//! the `BehaviorContext` (the Rust side of `journal-append`) are returned to
+//! the caller for journaling.
+//!
+//! Rust hosts and tests implement `Behavior` directly; closures with the
+//! matching signature implement it automatically.
+//!
+//! ```rust,ignore
+//! let counter = |_ctx: &mut BehaviorContext, state: &TypedValue, _msg: &TypedValue| {
+//!     match state {
+//!         TypedValue::Int(n) => Ok(TypedValue::Int(n + 1)),
+//!         _ => Err(BehaviorError::new("state is not an Int")),
+//!     }
+//! };
+//! ```
+//!
+//! TODO: Adapt Seq quotations to `Behavior` once quotation invocation is
+//! wired through the FFI layer.
+
+use crate::actor::{Actor, ActorId};
+use crate::journal::Event;
+use crate::message::{TypedMessage, UNTAGGED_EVENT};
+use crate::outbox::{Intent, INTENT_EVENT};
+use crate::reply::ReplyToken;
+use crate::serialize::TypedValue;
+use std::collections::BTreeMap;
+
+/// Error returned by a behavior that could not handle a message
+///
+/// The actor's state is left unchanged and no events are journaled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BehaviorError(pub String);
+
+impl BehaviorError {
+    pub fn new(message: impl Into<String>) -> Self {
+        BehaviorError(message.into())
+    }
+}
+
+impl std::fmt::Display for BehaviorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "behavior failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for BehaviorError {}
+
+/// Per-message context handed to a behavior
+pub struct BehaviorContext {
+    actor_id: ActorId,
+    sequence: u64,
+    sender: Option<ActorId>,
+    events: Vec<(String, TypedValue)>,
+    self_sends: Vec<TypedValue>,
+    dead_letter: bool,
+    reply: Option<TypedValue>,
+    deferred: Option<ReplyToken>,
+    fulfilled: Vec<(ReplyToken, TypedValue)>,
+    forwards: Vec<(ActorId, TypedValue)>,
+}
+
+impl BehaviorContext {
+    pub(crate) fn new(actor_id: ActorId, sequence: u64) -> Self {
+        BehaviorContext {
+            actor_id,
+            sequence,
+            sender: None,
+            events: vec![],
+            self_sends: vec![],
+            dead_letter: false,
+            reply: None,
+            deferred: None,
+            fulfilled: vec![],
+            forwards: vec![],
+        }
+    }
+
+    /// ID of the actor handling the message
+    pub fn actor_id(&self) -> &ActorId {
+        &self.actor_id
+    }
+
+    /// Sequence number the next journaled event will get
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Whether handling has run past the message's timeout (see
+    /// `crate::timeout`)
+    ///
+    /// Long-running behaviors check this and give up early; their result
+    /// is discarded either way.
+    pub fn cancelled(&self) -> bool {
+        crate::timeout::cancelled()
+    }
+
+    /// Actor that sent the current message, if it came from one
+    pub fn sender(&self) -> Option<&ActorId> {
+        self.sender.as_ref()
+    }
+
+    /// Record an event to journal once the message is handled
+    pub fn emit(&mut self, event_type: impl Into<String>, payload: TypedValue) {
+        self.events.push((event_type.into(), payload));
+    }
+
+    /// Journal a value, using its message tag as the event type
+    ///
+    /// This is the Rust side of `journal-append`. Values without a tag are
+    /// journaled whole under `UNTAGGED_EVENT`; tagged ones are journaled
+    /// as their fields under their tag.
+    pub fn append(&mut self, event: TypedValue) {
+        match TypedMessage::try_from(&event) {
+            Ok(msg) => self.emit(msg.tag, msg.fields),
+            Err(event) => self.emit(UNTAGGED_EVENT, event),
+        }
+    }
+
+    /// Record a side effect to execute once the message is handled
+    ///
+    /// Journaled with the handling's events; an `Outbox` executes it (see
+    /// `crate::outbox`).
+    pub fn outbox(&mut self, intent: Intent) {
+        self.emit(INTENT_EVENT, intent.to_value());
+    }
+
+    /// Answer the `ask` that delivered the current message
+    ///
+    /// Ignored for plain sends. Only the last reply is sent, and only if
+    /// handling succeeds.
+    pub fn reply(&mut self, value: TypedValue) {
+        self.reply = Some(value);
+    }
+
+    /// Answer the current `ask` later
+    ///
+    /// Returns a token to keep in state or pass along in messages; the
+    /// reply stays pending until someone calls `fulfill` with it. An
+    /// immediate `reply` still takes precedence. For plain sends the token
+    /// is valid but fulfilling it does nothing.
+    pub fn defer_reply(&mut self) -> ReplyToken {
+        self.deferred.get_or_insert_with(ReplyToken::new).clone()
+    }
+
+    /// Answer a deferred reply once the current message is handled
+    pub fn fulfill(&mut self, token: &ReplyToken, value: TypedValue) {
+        self.fulfilled.push((token.clone(), value));
+    }
+
+    /// Report the current message as a dead letter (nothing handles it)
+    ///
+    /// The behavior should return its state unchanged.
+    pub fn dead_letter(&mut self) {
+        self.dead_letter = true;
+    }
+
+    /// Queue a message to this actor, delivered after the current one
+    ///
+    /// Self-sends are part of handling, so they are not recorded as
+    /// separate inputs in a session trace; replay reproduces them.
+    pub fn send_self(&mut self, msg: TypedValue) {
+        self.self_sends.push(msg);
+    }
+
+    /// Pass a message on to another actor as if its sender had sent it
+    /// there, once the current message is handled
+    ///
+    /// The forwarded envelope keeps the original sender and expiry, and
+    /// unless this actor replies (or defers) the first forward takes over
+    /// the pending `ask`, so the actor it reaches answers the asker. For
+    /// proxies and routers; a plain `send` would make this actor the
+    /// sender.
+    pub fn forward(&mut self, to: ActorId, msg: TypedValue) {
+        self.forwards.push((to, msg));
+    }
+}
+
+/// What handling one message produced
+#[derive(Debug)]
+pub(crate) struct Handled {
+    /// Emitted events, with sequence numbers assigned
+    pub(crate) events: Vec<Event>,
+    /// Messages the actor sent itself, in order
+    pub(crate) self_sends: Vec<TypedValue>,
+    /// The behavior had nothing to handle the message with
+    pub(crate) dead_letter: bool,
+    /// Answer for an `ask`
+    pub(crate) reply: Option<TypedValue>,
+    /// Token the `ask` was deferred to
+    pub(crate) deferred: Option<ReplyToken>,
+    /// Deferred replies answered while handling
+    pub(crate) fulfilled: Vec<(ReplyToken, TypedValue)>,
+    /// Messages passed on in the original sender's name
+    pub(crate) forwards: Vec<(ActorId, TypedValue)>,
+    /// The actor's state before the message
+    pub(crate) previous: TypedValue,
+}
+
+/// Message handler for an actor
+pub trait Behavior: Send + Sync {
+    /// Handle one message, returning the actor's next state
+    fn handle(
+        &self,
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError>;
+
+    /// State for a freshly spawned actor with nothing to recover
+    fn initial_state(&self) -> TypedValue {
+        TypedValue::Map(BTreeMap::new())
+    }
+
+    /// Fold a journaled event into state during recovery
+    ///
+    /// Events this behavior emitted after the latest snapshot are replayed
+    /// through here on spawn. The default ignores them, so the actor
+    /// resumes from its snapshot state.
+    fn apply_event(&self, state: &TypedValue, _event: &Event) -> TypedValue {
+        state.clone()
+    }
+
+    /// Called once when a stopped actor has drained its inbox
+    ///
+    /// Events emitted here are journaled before the final snapshot.
+    fn on_stop(&self, _ctx: &mut BehaviorContext, _state: &TypedValue) {}
+}
+
+impl<F> Behavior for F
+where
+    F: Fn(&mut BehaviorContext, &TypedValue, &TypedValue) -> Result<TypedValue, BehaviorError>
+        + Send
+        + Sync,
+{
+    fn handle(
+        &self,
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        self(ctx, state, msg)
+    }
+}
+
+/// Run one message through a behavior
+///
+/// On success the actor's state is replaced and the emitted events are
+/// returned with sequence numbers assigned, along with any self-sends. On
+/// failure the actor is left untouched.
+pub(crate) fn handle_message(
+    behavior: &dyn Behavior,
+    actor: &mut Actor,
+    msg: &TypedValue,
+) -> Result<Handled, BehaviorError> {
+    handle_message_checked(behavior, actor, msg, None, |_| Ok(()))
+}
+
+/// `handle_message` for a message from `sender`, with `check` given the
+/// sequenced events before the actor is updated
+///
+/// If `check` fails, the actor is left untouched as on a behavior failure.
+pub(crate) fn handle_message_checked<E: From<BehaviorError>>(
+    behavior: &dyn Behavior,
+    actor: &mut Actor,
+    msg: &TypedValue,
+    sender: Option<ActorId>,
+    check: impl FnOnce(&[Event]) -> Result<(), E>,
+) -> Result<Handled, E> {
+    let mut ctx = BehaviorContext::new(actor.id.clone(), actor.sequence);
+    ctx.sender = sender;
+    let next_state = behavior.handle(&mut ctx, &actor.state, msg)?;
+    let events = sequence_events(&actor.id, actor.sequence, std::mem::take(&mut ctx.events));
+    check(&events)?;
+
+    let previous = std::mem::replace(&mut actor.state, next_state);
+    actor.sequence += events.len() as u64;
+    let self_sends = std::mem::take(&mut ctx.self_sends);
+    let dead_letter = ctx.dead_letter;
+    let reply = ctx.reply.take();
+    let deferred = ctx.deferred.take();
+    let fulfilled = std::mem::take(&mut ctx.fulfilled);
+    let forwards = std::mem::take(&mut ctx.forwards);
+    Ok(Handled {
+        events,
+        self_sends,
+        dead_letter,
+        reply,
+        deferred,
+        fulfilled,
+        forwards,
+        previous,
+    })
+}
+
+/// Run a behavior's stop hook, returning the events it emitted
+pub(crate) fn handle_stop(behavior: &dyn Behavior, actor: &mut Actor) -> Vec<Event> {
+    let mut ctx = BehaviorContext::new(actor.id.clone(), actor.sequence);
+    behavior.on_stop(&mut ctx, &actor.state);
+    let events = sequence_events(&actor.id, actor.sequence, ctx.events);
+    actor.sequence += events.len() as u64;
+    events
+}
+
+/// Assign sequence numbers from `first` to emitted events
+fn sequence_events(id: &ActorId, first: u64, emitted: Vec<(String, TypedValue)>) -> Vec<Event> {
+    (first..)
+        .zip(emitted)
+        .map(|(seq, (event_type, payload))| {
+            let mut event = Event::new(seq, event_type, payload);
+            event.actor_id = Some(id.clone());
+            event
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adder(
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        match (state, msg) {
+            (TypedValue::Int(total), TypedValue::Int(n)) => {
+                ctx.emit("Added", TypedValue::Int(*n));
+                Ok(TypedValue::Int(total + n))
+            }
+            _ => Err(BehaviorError::new("expected Int")),
+        }
+    }
+
+    #[test]
+    fn test_handle_message_updates_state_and_sequences_events() {
+        let mut actor =
+            Actor::with_state(ActorId::new(), "adder".to_string(), TypedValue::Int(0), 0);
+
+        let events = handle_message(&adder, &mut actor, &TypedValue::Int(5))
+            .unwrap()
+            .events;
+        assert_eq!(actor.state, TypedValue::Int(5));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].seq, 0);
+        assert_eq!(events[0].event_type, "Added");
+
+        let events = handle_message(&adder, &mut actor, &TypedValue::Int(2))
+            .unwrap()
+            .events;
+        assert_eq!(events[0].seq, 1);
+        assert_eq!(actor.sequence, 2);
+    }
+
+    #[test]
+    fn test_append_uses_message_tag() {
+        let mut ctx = BehaviorContext::new(ActorId::new(), 0);
+        ctx.append(TypedMessage::new("Deposit", TypedValue::Int(5)).into());
+        ctx.append(TypedValue::Int(7));
+
+        assert_eq!(
+            ctx.events,
+            [
+                ("Deposit".to_string(), TypedValue::Int(5)),
+                (UNTAGGED_EVENT.to_string(), TypedValue::Int(7))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failure_leaves_actor_untouched() {
+        let mut actor =
+            Actor::with_state(ActorId::new(), "adder".to_string(), TypedValue::Int(7), 3);
+
+        let err = handle_message(&adder, &mut actor, &TypedValue::Bool(true)).unwrap_err();
+        assert_eq!(err, BehaviorError::new("expected Int"));
+        assert_eq!(actor.state, TypedValue::Int(7));
+        assert_eq!(actor.sequence, 3);
+    }
+}
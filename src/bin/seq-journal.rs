@@ -0,0 +1,95 @@
+//! seq-journal: offline tools for actor journals
+//!
+//! ```text
+//! seq-journal verify <journal-path> [actor-id ...]
+//! seq-journal repair <journal-path> <actor-id> <renumber|drop-duplicates|quarantine>
+//! ```
+//!
+//! `verify` checks the named actors (every actor under the path if none
+//! are named) with `Journal::verify` and exits with status 1 if any has
+//! issues. `repair` runs `Journal::repair` with the given policy and
+//! prints what it changed.
+
+use seq_actors::{ActorId, Journal, RepairPolicy};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: seq-journal verify <journal-path> [actor-id ...]
+       seq-journal repair <journal-path> <actor-id> <renumber|drop-duplicates|quarantine>";
+
+fn invalid(msg: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, msg)
+}
+
+fn parse_id(id: &str) -> std::io::Result<ActorId> {
+    id.parse()
+        .map_err(|_| invalid(format!("invalid actor id: {}", id)))
+}
+
+fn verify(path: &str, ids: &[String]) -> std::io::Result<bool> {
+    let journal = Journal::new(path);
+    let reports = if ids.is_empty() {
+        journal.verify_all()?
+    } else {
+        let mut reports = vec![];
+        for id in ids {
+            reports.push(journal.verify(&parse_id(id)?)?);
+        }
+        reports
+    };
+
+    let mut ok = true;
+    for report in &reports {
+        let range = match (report.first_seq, report.last_seq) {
+            (Some(first), Some(last)) => format!("seq {}..={}", first, last),
+            _ => "no events".to_string(),
+        };
+        let status = if report.is_ok() { "ok" } else { "FAILED" };
+        println!(
+            "{} {}: {} events, {}",
+            status, report.actor_id, report.events, range
+        );
+        for issue in &report.issues {
+            println!("    {}", issue);
+        }
+        ok &= report.is_ok();
+    }
+    Ok(ok)
+}
+
+fn repair(path: &str, id: &str, policy: &str) -> std::io::Result<bool> {
+    let policy = match policy {
+        "renumber" => RepairPolicy::Renumber,
+        "drop-duplicates" => RepairPolicy::DropDuplicates,
+        "quarantine" => RepairPolicy::Quarantine,
+        _ => return Err(invalid(format!("unknown repair policy: {}", policy))),
+    };
+    let log = Journal::new(path).repair(&parse_id(id)?, policy)?;
+    if log.actions.is_empty() {
+        println!("{}: nothing to repair", log.actor_id);
+    }
+    for action in &log.actions {
+        println!("{}: {}", log.actor_id, action);
+    }
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.as_slice() {
+        [command, path, ids @ ..] if command == "verify" => verify(path, ids),
+        [command, path, id, policy] if command == "repair" => repair(path, id, policy),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("seq-journal: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -14,43 +14,240 @@
 //! ```
 
 use seqc::config::{CompilerConfig, ExternalBuiltin};
+use std::fmt;
+
+/// A set of related builtins hosts can enable or disable together
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuiltinGroup {
+    /// Spawning, messaging, replies and the current actor's state
+    Core,
+    /// Journal appends and snapshots
+    Journal,
+    /// Scheduled messages (no builtins yet)
+    Timers,
+    /// Remote actors (no builtins yet; see DESIGN.md, Distributed Features)
+    Cluster,
+    /// Introspecting and rewiring the system: counts, other actors'
+    /// state, aliases
+    Admin,
+}
+
+use BuiltinGroup::*;
+
+/// A builtin's stack effect, e.g. `( ActorId Msg -- )`
+///
+/// Inputs and outputs are type names, deepest first (the last input is
+/// the top of the stack).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackEffect {
+    pub inputs: &'static [&'static str],
+    pub outputs: &'static [&'static str],
+}
+
+impl fmt::Display for StackEffect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+        for input in self.inputs {
+            write!(f, " {}", input)?;
+        }
+        write!(f, " --")?;
+        for output in self.outputs {
+            write!(f, " {}", output)?;
+        }
+        write!(f, " )")
+    }
+}
+
+/// `effect!(ActorId Msg -- Result)` is the `StackEffect`
+/// `( ActorId Msg -- Result )`
+macro_rules! effect {
+    ($($input:ident)* -- $($output:ident)*) => {
+        StackEffect {
+            inputs: &[$(stringify!($input)),*],
+            outputs: &[$(stringify!($output)),*],
+        }
+    };
+}
+
+/// Every actor builtin: group, Seq word, FFI symbol, stack effect
+#[rustfmt::skip]
+const BUILTINS: &[(BuiltinGroup, &str, &str, StackEffect)] = &[
+    // Actor lifecycle
+    (Core, "actor-spawn", "seq_actors_spawn",                  effect!(Behavior -- ActorId)),
+    (Core, "actor-send", "seq_actors_send",                    effect!(ActorId Msg --)),
+    (Core, "actor-send-self", "seq_actors_send_self",          effect!(Msg --)),
+    (Core, "actor-forward", "seq_actors_forward",              effect!(ActorId Msg --)),
+    (Core, "actor-yield", "seq_actors_yield",                  effect!(--)),
+    (Core, "actor-self", "seq_actors_self",                    effect!(-- ActorId)),
+    (Core, "actor-stop", "seq_actors_stop",                    effect!(ActorId --)),
+    (Core, "actor-await", "seq_actors_await",                  effect!(ActorId Timeout -- Bool)),
+    (Core, "actor-alive?", "seq_actors_is_alive",              effect!(ActorId -- Bool)),
+    (Core, "actor-id-string", "seq_actors_id_string",          effect!(ActorId -- String)),
+    (Core, "actor-fsm-state", "seq_actors_fsm_state",          effect!(ActorId -- String)),
+    // State access (within actor context)
+    (Core, "actor-state", "seq_actors_state",                  effect!(-- State)),
+    // Journal operations
+    (Journal, "journal-append", "seq_actors_journal_append",   effect!(Event --)),
+    (Journal, "actor-snapshot", "seq_actors_snapshot",         effect!(ActorId --)),
+    // System introspection
+    (Admin, "actor-count", "seq_actors_count",                 effect!(-- Int)),
+    (Admin, "runtime-uptime", "seq_actors_runtime_uptime",     effect!(-- Int)),
+    (Admin, "actor-alias", "seq_actors_alias",                 effect!(OldId NewId --)),
+    (Admin, "system-subscribe", "seq_actors_system_subscribe", effect!(--)),
+];
+
+/// Result flavors of the words that can fail: word, FFI symbol, stack
+/// effect (see `ffi`, "Errors")
+#[rustfmt::skip]
+const RESULT_FLAVORS: &[(&str, &str, StackEffect)] = &[
+    ("actor-spawn", "seq_actors_spawn_result",                 effect!(Behavior -- Result)),
+    ("actor-send", "seq_actors_send_result",                   effect!(ActorId Msg -- Result)),
+    ("actor-self", "seq_actors_self_result",                   effect!(-- Result)),
+    ("actor-stop", "seq_actors_stop_result",                   effect!(ActorId -- Result)),
+    ("actor-await", "seq_actors_await_result",                 effect!(ActorId Timeout -- Result)),
+];
+
+/// Which builtins `compiler_config_with` registers, and under what names
+///
+/// ```rust,ignore
+/// // Core and journal words only, as `actors.spawn`, `actors.send`, ...
+/// let options = BuiltinOptions::new()
+///     .without(BuiltinGroup::Admin)
+///     .namespace("actors");
+/// let config = compiler_config_with(&options);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BuiltinOptions {
+    groups: Vec<BuiltinGroup>,
+    namespace: Option<String>,
+    legacy_panics: bool,
+}
+
+impl BuiltinOptions {
+    /// Every group, under the plain word names
+    pub fn new() -> Self {
+        BuiltinOptions {
+            groups: vec![Core, Journal, Timers, Cluster, Admin],
+            namespace: None,
+            legacy_panics: false,
+        }
+    }
+
+    /// No groups; add them with `with`
+    pub fn none() -> Self {
+        BuiltinOptions {
+            groups: vec![],
+            namespace: None,
+            legacy_panics: false,
+        }
+    }
+
+    pub fn with(mut self, group: BuiltinGroup) -> Self {
+        if !self.groups.contains(&group) {
+            self.groups.push(group);
+        }
+        self
+    }
+
+    pub fn without(mut self, group: BuiltinGroup) -> Self {
+        self.groups.retain(|g| *g != group);
+        self
+    }
+
+    /// Register words as `{namespace}.{word}`, so they can't collide with
+    /// user-defined Seq words
+    ///
+    /// A leading `actor-` is dropped: `actor-spawn` becomes
+    /// `actors.spawn` under the namespace `actors`, and `system-subscribe`
+    /// becomes `actors.system-subscribe`. FFI symbols are unchanged.
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Link `actor-spawn`, `actor-send`, `actor-self`, `actor-stop` and
+    /// `actor-await` to the legacy builtins, which panic (or quietly do nothing) instead
+    /// of pushing a Result
+    pub fn legacy_panics(mut self) -> Self {
+        self.legacy_panics = true;
+        self
+    }
+
+    /// Registered word name, FFI symbol and stack effect of each selected
+    /// builtin
+    fn registered(&self) -> impl Iterator<Item = (String, &'static str, StackEffect)> + '_ {
+        BUILTINS
+            .iter()
+            .filter(|(group, ..)| self.groups.contains(group))
+            .map(|(_, word, symbol, effect)| {
+                let flavor = RESULT_FLAVORS.iter().find(|(w, ..)| w == word);
+                let (symbol, effect) = match flavor {
+                    Some((_, result_symbol, result_effect)) if !self.legacy_panics => {
+                        (result_symbol, result_effect)
+                    }
+                    _ => (symbol, effect),
+                };
+                (self.word_name(word), *symbol, *effect)
+            })
+    }
+
+    fn word_name(&self, word: &str) -> String {
+        match &self.namespace {
+            Some(ns) => format!("{}.{}", ns, word.strip_prefix("actor-").unwrap_or(word)),
+            None => word.to_string(),
+        }
+    }
+}
+
+/// Plain word names of the builtins in a group
+pub(crate) fn group_words(group: BuiltinGroup) -> impl Iterator<Item = &'static str> {
+    BUILTINS
+        .iter()
+        .filter(move |(g, ..)| *g == group)
+        .map(|(_, word, ..)| *word)
+}
+
+impl Default for BuiltinOptions {
+    fn default() -> Self {
+        BuiltinOptions::new()
+    }
+}
 
 /// Get the compiler configuration with actor builtins registered
 ///
 /// This configuration can be passed to `seqc::compile_file_with_config`
 /// to enable actor-related words in Seq programs.
 pub fn compiler_config() -> CompilerConfig {
-    CompilerConfig::new()
-        // Actor lifecycle
-        .with_builtin(ExternalBuiltin::new(
-            "actor-spawn",      // ( Behavior -- ActorId )
-            "seq_actors_spawn",
-        ))
-        .with_builtin(ExternalBuiltin::new(
-            "actor-send",       // ( ActorId Msg -- )
-            "seq_actors_send",
-        ))
-        .with_builtin(ExternalBuiltin::new(
-            "actor-self",       // ( -- ActorId )
-            "seq_actors_self",
-        ))
-        .with_builtin(ExternalBuiltin::new(
-            "actor-stop",       // ( ActorId -- )
-            "seq_actors_stop",
-        ))
-        // State access (within actor context)
-        .with_builtin(ExternalBuiltin::new(
-            "actor-state",      // ( -- State )
-            "seq_actors_state",
-        ))
-        // Journal operations
-        .with_builtin(ExternalBuiltin::new(
-            "journal-append",   // ( Event -- )
-            "seq_actors_journal_append",
-        ))
+    compiler_config_with(&BuiltinOptions::default())
+}
+
+/// Get a compiler configuration with the builtin groups and word names
+/// `options` selects
+pub fn compiler_config_with(options: &BuiltinOptions) -> CompilerConfig {
+    options
+        .registered()
+        .fold(CompilerConfig::new(), |config, (word, symbol, _)| {
+            config.with_builtin(ExternalBuiltin::new(word, symbol))
+        })
         .with_library("seq_actors_runtime")
 }
 
+/// Stack effects of the builtins `compiler_config_with(options)`
+/// registers, by registered word name
+///
+/// seqc's `ExternalBuiltin` has no room for type information yet, so the
+/// compiler treats actor words as untyped and misuse fails at runtime.
+/// Hosts running their own checks over Seq source can use this table.
+///
+/// TODO: Register effects with `ExternalBuiltin` once seqc's
+/// `CompilerConfig` accepts them.
+pub fn builtin_effects(options: &BuiltinOptions) -> Vec<(String, StackEffect)> {
+    options
+        .registered()
+        .map(|(word, _, effect)| (word, effect))
+        .collect()
+}
+
 /// Get a minimal config for testing (no library linking)
 #[cfg(test)]
 pub fn test_config() -> CompilerConfig {
@@ -80,6 +277,51 @@ mod tests {
         assert!(names.contains(&"actor-send"));
         assert!(names.contains(&"actor-self"));
         assert!(names.contains(&"actor-state"));
+        assert!(names.contains(&"actor-id-string"));
+    }
+
+    #[test]
+    fn test_compiler_config_with_groups_and_namespace() {
+        let options = BuiltinOptions::none()
+            .with(BuiltinGroup::Core)
+            .namespace("actors");
+        let config = compiler_config_with(&options);
+        let names: Vec<&str> = config
+            .external_builtins
+            .iter()
+            .map(|b| b.seq_name.as_str())
+            .collect();
+
+        assert!(names.contains(&"actors.spawn"));
+        assert_eq!(
+            options.word_name("system-subscribe"),
+            "actors.system-subscribe"
+        );
+        assert!(!names.iter().any(|n| n.ends_with("journal-append")));
+        assert!(!names.iter().any(|n| n.ends_with("count")));
+
+        let spawn = &config.external_builtins[0];
+        assert_eq!(spawn.symbol, "seq_actors_spawn_result");
+        let legacy = compiler_config_with(&BuiltinOptions::new().legacy_panics());
+        assert_eq!(legacy.external_builtins[0].symbol, "seq_actors_spawn");
+        assert_eq!(compiler_config().external_builtins.len(), BUILTINS.len());
+    }
+
+    #[test]
+    fn test_builtin_effects() {
+        let effects = builtin_effects(&BuiltinOptions::new().namespace("actors"));
+        assert_eq!(effects.len(), BUILTINS.len());
+
+        let (word, send) = &effects[1];
+        assert_eq!(word, "actors.send");
+        assert_eq!(send.inputs, ["ActorId", "Msg"]);
+        assert_eq!(send.outputs, ["Result"]);
+        assert_eq!(send.to_string(), "( ActorId Msg -- Result )");
+
+        let legacy = builtin_effects(&BuiltinOptions::new().legacy_panics());
+        assert!(legacy[1].1.outputs.is_empty());
+        assert_eq!(legacy[0].1.to_string(), "( Behavior -- ActorId )");
+        assert_eq!(legacy[4].1.to_string(), "( -- )");
     }
 
     #[test]
@@ -0,0 +1,215 @@
+//! Crash dumps for panicking behaviors
+//!
+//! A behavior that panics doesn't take its worker down: the runtime catches
+//! the panic and fails the message like a `BehaviorError`, then applies
+//! the actor's `PanicPolicy` (by default it carries on with the state from
+//! before the message). Before that it writes a crash dump into the
+//! actor's journal directory:
+//!
+//! ```text
+//! <journal_path>/<actor_id>/crash-<ts>-<id>.txt
+//! ```
+//!
+//! holding the panic message, the state and the offending message, the
+//! actor's last events and a backtrace of the panic. The dump's path is
+//! journaled in a `Failure` event:
+//!
+//! ```text
+//! { error: "panicked: ...", crash_dump: "<path>" }
+//! ```
+//!
+//! Backtraces are taken by a panic hook installed the first time a panic
+//! is caught, which then runs any previously installed hook. The runtime
+//! reports which actor panicked to its observer
+//! (`RuntimeObserver::on_panic`).
+
+use crate::actor::ActorId;
+use crate::journal::Event;
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+
+/// Event type journaled when a behavior panics
+pub const FAILURE_EVENT: &str = "Failure";
+
+/// What happens to an actor after its behavior panics
+///
+/// The crash dump and `Failure` event are written first in every case, and
+/// the message that panicked is failed like a `BehaviorError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Carry on with the state from before the message
+    #[default]
+    Resume,
+    /// Rebuild the state from the journal (snapshot plus events folded
+    /// with `Behavior::apply_event`), as on spawn, and carry on
+    RestartActor,
+    /// Stop the actor, dropping its queued messages; monitors get a
+    /// `Down` with an error reason
+    StopActor,
+    /// Stop the actor like `StopActor`, and fail the run loop handling it
+    /// with `RuntimeError::Escalated` so the code supervising it decides
+    EscalateToSupervisor,
+    /// Flush the journal and abort the process (after telling the
+    /// observer, `RuntimeObserver::on_abort`)
+    AbortProcess,
+}
+
+/// A caught panic
+#[derive(Debug, Clone)]
+pub(crate) struct Panic {
+    pub message: String,
+    /// Backtrace from where the panic started
+    pub backtrace: String,
+}
+
+thread_local! {
+    /// Whether `catch_panic` is running something on this thread
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+    /// Backtrace recorded by the hook for the panic being caught
+    static BACKTRACE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+static HOOK: Once = Once::new();
+
+/// Record backtraces for panics `catch_panic` is about to catch
+fn install_hook() {
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if CATCHING.get() {
+                let backtrace = Backtrace::force_capture().to_string();
+                BACKTRACE.set(Some(backtrace));
+            }
+            previous(info);
+        }));
+    });
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
+
+/// Run `f`, turning a panic into `Err`
+pub(crate) fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, Panic> {
+    install_hook();
+    let outer = CATCHING.replace(true);
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.set(outer);
+
+    result.map_err(|payload| Panic {
+        message: panic_message(payload.as_ref()),
+        backtrace: BACKTRACE
+            .take()
+            .unwrap_or_else(|| "<backtrace unavailable>".to_string()),
+    })
+}
+
+/// Everything known about an actor when its behavior panicked
+#[derive(Debug, Clone)]
+pub struct CrashDump {
+    pub actor_id: ActorId,
+    pub behavior: String,
+    /// Unix timestamp (milliseconds)
+    pub ts: u64,
+    pub panic: String,
+    /// State before the offending message
+    pub state: TypedValue,
+    /// The message being handled
+    pub message: TypedValue,
+    /// The actor's most recent journaled events, oldest first
+    pub events: Vec<Event>,
+    pub backtrace: String,
+}
+
+impl CrashDump {
+    /// Human-readable dump contents
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String can't fail
+        let _ = writeln!(out, "actor:     {}", self.actor_id);
+        let _ = writeln!(out, "behavior:  {}", self.behavior);
+        let _ = writeln!(out, "timestamp: {}", self.ts);
+        let _ = writeln!(out, "panic:     {}", self.panic);
+        let _ = writeln!(out, "\n== state ==\n{}", self.state.to_debug_string());
+        let _ = writeln!(out, "\n== message ==\n{}", self.message.to_debug_string());
+        let _ = writeln!(out, "\n== last {} events ==", self.events.len());
+        for event in &self.events {
+            let _ = writeln!(out, "{}", event.to_debug_string());
+        }
+        let _ = write!(out, "\n== backtrace ==\n{}", self.backtrace);
+        out
+    }
+}
+
+fn key(name: &str) -> TypedMapKey {
+    TypedMapKey::String(name.to_string())
+}
+
+/// Payload of a `Failure` event: the error and, if one was written, the
+/// crash dump's path
+pub fn failure_payload(error: &str, crash_dump: Option<&Path>) -> TypedValue {
+    let mut fields = BTreeMap::new();
+    fields.insert(key("error"), TypedValue::String(error.to_string()));
+    if let Some(path) = crash_dump {
+        fields.insert(
+            key("crash_dump"),
+            TypedValue::String(path.display().to_string()),
+        );
+    }
+    TypedValue::Map(fields)
+}
+
+/// The crash dump path recorded in a `Failure` event, if any
+pub fn crash_dump_path(event: &Event) -> Option<PathBuf> {
+    if event.event_type != FAILURE_EVENT {
+        return None;
+    }
+    let TypedValue::Map(fields) = &event.payload else {
+        return None;
+    };
+    match fields.get(&key("crash_dump")) {
+        Some(TypedValue::String(path)) => Some(PathBuf::from(path)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catch_panic() {
+        assert_eq!(catch_panic(|| 7).unwrap(), 7);
+
+        let caught = catch_panic(|| panic!("boom {}", 42)).unwrap_err();
+        assert_eq!(caught.message, "boom 42");
+        assert!(caught.backtrace.contains("test_catch_panic"));
+    }
+
+    #[test]
+    fn test_failure_payload_round_trip() {
+        let path = PathBuf::from("/tmp/crash-1.txt");
+        let mut event = Event::new(
+            3,
+            FAILURE_EVENT.to_string(),
+            failure_payload("boom", Some(&path)),
+        );
+        assert_eq!(crash_dump_path(&event), Some(path));
+
+        event.payload = failure_payload("boom", None);
+        assert_eq!(crash_dump_path(&event), None);
+    }
+}
@@ -0,0 +1,86 @@
+//! Delivery groups: competing consumers
+//!
+//! Actors that join the same delivery group
+//! (`ActorRuntime::join_delivery_group`) share its messages: each message
+//! sent with `ActorRuntime::send_to_group` is delivered to exactly one
+//! running member, chosen by the group's `DeliveryStrategy`:
+//!
+//! ```rust,ignore
+//! for _ in 0..4 {
+//!     let worker = runtime.spawn("resizer")?;
+//!     runtime.join_delivery_group("thumbnails", &worker);
+//! }
+//! runtime.set_delivery_strategy("thumbnails", DeliveryStrategy::LeastLoaded)?;
+//! let chosen = runtime.send_to_group("thumbnails", job)?;
+//! ```
+//!
+//! Stopped members are skipped, and members leave their groups when they
+//! are unregistered. Sending to a group with no running member fails with
+//! `RuntimeError::NoGroupMember`. Delivery groups are unrelated to the
+//! scheduling groups of `fairness`.
+
+use crate::actor::ActorId;
+
+/// How a delivery group picks the member a message goes to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DeliveryStrategy {
+    /// Members take turns
+    #[default]
+    RoundRobin,
+    /// The member with the fewest queued messages (ties take turns)
+    LeastLoaded,
+}
+
+/// Members of one delivery group
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DeliveryGroup {
+    pub(crate) members: Vec<ActorId>,
+    pub(crate) strategy: DeliveryStrategy,
+    /// Where the next turn starts
+    next: usize,
+}
+
+impl DeliveryGroup {
+    /// Pick the member the next message goes to
+    ///
+    /// `queued` gives a member's queued messages, or `None` if it can't
+    /// take messages.
+    pub(crate) fn pick(&mut self, queued: impl Fn(&ActorId) -> Option<usize>) -> Option<ActorId> {
+        let len = self.members.len();
+        let turns = (0..len).map(|i| (self.next + i) % len);
+        let mut candidates = turns.filter_map(|i| Some((i, queued(&self.members[i])?)));
+        let (index, _) = match self.strategy {
+            DeliveryStrategy::RoundRobin => candidates.next()?,
+            DeliveryStrategy::LeastLoaded => candidates.min_by_key(|&(_, queued)| queued)?,
+        };
+        self.next = (index + 1) % len;
+        Some(self.members[index].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_by_strategy() {
+        let (a, b, c) = (ActorId::new(), ActorId::new(), ActorId::new());
+        let mut group = DeliveryGroup {
+            members: vec![a.clone(), b.clone(), c.clone()],
+            ..DeliveryGroup::default()
+        };
+        // c is stopped
+        let queued = |id: &ActorId| match id {
+            id if *id == a => Some(5),
+            id if *id == b => Some(1),
+            _ => None,
+        };
+        let picks: Vec<_> = (0..4).map(|_| group.pick(queued).unwrap()).collect();
+        assert_eq!(picks, [a.clone(), b.clone(), a.clone(), b.clone()]);
+
+        group.strategy = DeliveryStrategy::LeastLoaded;
+        assert_eq!(group.pick(queued), Some(b.clone()));
+        assert_eq!(group.pick(queued), Some(b));
+        assert_eq!(group.pick(|_| None), None);
+    }
+}
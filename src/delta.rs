@@ -0,0 +1,220 @@
+//! State deltas in place of events
+//!
+//! A behavior that doesn't fold its events (`Behavior::apply_event`)
+//! recovers from its latest snapshot alone, so what it handled since is
+//! lost on a crash, and journaling its events buys nothing for recovery.
+//! List it in `RuntimeConfig::delta_behaviors` and its actors journal a
+//! structural diff of their state instead: one `StateDelta` event per
+//! message that changed the state (none for one that didn't), in place
+//! of the events the behavior emitted. Events the runtime acts on
+//! (outbox intents, answered calls) are still journaled after the delta.
+//!
+//! For a large map that changes in a few keys per message the delta is a
+//! fraction of the state's size:
+//!
+//! ```text
+//! {"set": {"hits": 41}, "remove": {"stale": true}, "patch": {"users": {...}}}
+//! ```
+//!
+//! Recovery and replay apply the deltas after the snapshot with
+//! `StateDelta::apply`, whatever the behavior's reducer does. Maps are
+//! diffed key by key, recursively; any other change replaces the value
+//! (`{"replace": value}`).
+
+use crate::genserver::CALL_REPLIED_EVENT;
+use crate::journal::Event;
+use crate::outbox::INTENT_EVENT;
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::collections::BTreeMap;
+
+/// Event type of a journaled state delta
+pub const STATE_DELTA_EVENT: &str = "StateDelta";
+
+/// Structural difference between two states
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateDelta {
+    /// The new value, whole
+    Replace(TypedValue),
+    /// Changes to a map
+    Map {
+        /// Keys added or given a new value
+        set: BTreeMap<TypedMapKey, TypedValue>,
+        /// Keys removed
+        remove: Vec<TypedMapKey>,
+        /// Nested maps changed in place
+        patch: BTreeMap<TypedMapKey, StateDelta>,
+    },
+}
+
+fn key(name: &str) -> TypedMapKey {
+    TypedMapKey::String(name.to_string())
+}
+
+/// The delta taking `old` to `new`, or `None` if they are equal
+pub fn diff(old: &TypedValue, new: &TypedValue) -> Option<StateDelta> {
+    if old == new {
+        return None;
+    }
+    let (TypedValue::Map(old), TypedValue::Map(new)) = (old, new) else {
+        return Some(StateDelta::Replace(new.clone()));
+    };
+    let mut set = BTreeMap::new();
+    let mut patch = BTreeMap::new();
+    for (k, value) in new {
+        match old.get(k) {
+            Some(before @ TypedValue::Map(_)) if matches!(value, TypedValue::Map(_)) => {
+                patch.extend(diff(before, value).map(|delta| (k.clone(), delta)));
+            }
+            Some(before) if before == value => {}
+            _ => {
+                set.insert(k.clone(), value.clone());
+            }
+        }
+    }
+    let remove = old
+        .keys()
+        .filter(|k| !new.contains_key(k))
+        .cloned()
+        .collect();
+    Some(StateDelta::Map { set, remove, patch })
+}
+
+impl StateDelta {
+    /// Apply the delta to `state`
+    ///
+    /// A map delta applied to anything but a map starts from an empty one.
+    pub fn apply(&self, state: &TypedValue) -> TypedValue {
+        let (set, remove, patch) = match self {
+            StateDelta::Replace(value) => return value.clone(),
+            StateDelta::Map { set, remove, patch } => (set, remove, patch),
+        };
+        let mut fields = match state {
+            TypedValue::Map(fields) => fields.clone(),
+            _ => BTreeMap::new(),
+        };
+        for k in remove {
+            fields.remove(k);
+        }
+        fields.extend(set.iter().map(|(k, v)| (k.clone(), v.clone())));
+        for (k, delta) in patch {
+            let before = fields.remove(k).unwrap_or(TypedValue::Map(BTreeMap::new()));
+            fields.insert(k.clone(), delta.apply(&before));
+        }
+        TypedValue::Map(fields)
+    }
+
+    /// Encode as an event payload
+    pub fn to_typed(&self) -> TypedValue {
+        let mut fields = BTreeMap::new();
+        match self {
+            StateDelta::Replace(value) => {
+                fields.insert(key("replace"), value.clone());
+            }
+            StateDelta::Map { set, remove, patch } => {
+                let removed = remove.iter().map(|k| (k.clone(), TypedValue::Bool(true)));
+                let patched = patch.iter().map(|(k, d)| (k.clone(), d.to_typed()));
+                fields.insert(key("set"), TypedValue::Map(set.clone()));
+                fields.insert(key("remove"), TypedValue::Map(removed.collect()));
+                fields.insert(key("patch"), TypedValue::Map(patched.collect()));
+            }
+        }
+        TypedValue::Map(fields)
+    }
+
+    /// Decode an event payload written by `to_typed`
+    pub fn from_typed(value: &TypedValue) -> Option<Self> {
+        let TypedValue::Map(fields) = value else {
+            return None;
+        };
+        if let Some(value) = fields.get(&key("replace")) {
+            return Some(StateDelta::Replace(value.clone()));
+        }
+        let map = |name: &str| match fields.get(&key(name)) {
+            Some(TypedValue::Map(entries)) => Some(entries),
+            _ => None,
+        };
+        let patch = map("patch")?
+            .iter()
+            .map(|(k, d)| Some((k.clone(), StateDelta::from_typed(d)?)))
+            .collect::<Option<_>>()?;
+        Some(StateDelta::Map {
+            set: map("set")?.clone(),
+            remove: map("remove")?.keys().cloned().collect(),
+            patch,
+        })
+    }
+
+    /// The delta as an event with sequence number `seq`
+    pub fn to_event(&self, seq: u64) -> Event {
+        Event::new(seq, STATE_DELTA_EVENT.to_string(), self.to_typed())
+    }
+}
+
+/// Whether a delta behavior still journals an event it emitted: those
+/// the runtime acts on, as opposed to changes of state
+pub(crate) fn keeps(event: &Event) -> bool {
+    matches!(event.event_type.as_str(), INTENT_EVENT | CALL_REPLIED_EVENT)
+}
+
+/// Fold a journaled event into state: deltas are applied, other events
+/// go through `reduce`
+pub fn fold(
+    state: &TypedValue,
+    event: &Event,
+    reduce: impl FnOnce(&TypedValue, &Event) -> TypedValue,
+) -> TypedValue {
+    let delta = (event.event_type == STATE_DELTA_EVENT)
+        .then(|| StateDelta::from_typed(&event.payload))
+        .flatten();
+    match delta {
+        Some(delta) => delta.apply(state),
+        None => reduce(state, event),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(entries: &[(&str, TypedValue)]) -> TypedValue {
+        TypedValue::Map(entries.iter().map(|(k, v)| (key(k), v.clone())).collect())
+    }
+
+    #[test]
+    fn test_diff_and_apply_round_trip() {
+        let old = map(&[
+            ("hits", TypedValue::Int(40)),
+            ("stale", TypedValue::Bool(true)),
+            (
+                "users",
+                map(&[("ann", TypedValue::Int(1)), ("bob", TypedValue::Int(2))]),
+            ),
+            ("big", TypedValue::String("x".repeat(1000))),
+        ]);
+        let new = map(&[
+            ("hits", TypedValue::Int(41)),
+            (
+                "users",
+                map(&[("ann", TypedValue::Int(1)), ("bob", TypedValue::Int(3))]),
+            ),
+            ("big", TypedValue::String("x".repeat(1000))),
+        ]);
+
+        let delta = diff(&old, &new).unwrap();
+        let StateDelta::Map { set, remove, patch } = &delta else {
+            panic!("expected a map delta, got {:?}", delta);
+        };
+        assert_eq!(set.keys().collect::<Vec<_>>(), [&key("hits")]);
+        assert_eq!(remove, &[key("stale")]);
+        assert_eq!(patch.keys().collect::<Vec<_>>(), [&key("users")]);
+        assert_eq!(delta.apply(&old), new);
+
+        let decoded = StateDelta::from_typed(&delta.to_event(0).payload).unwrap();
+        assert_eq!(decoded, delta);
+        assert!(diff(&new, &new).is_none());
+        assert_eq!(
+            diff(&new, &TypedValue::Int(1)).unwrap().apply(&new),
+            TypedValue::Int(1)
+        );
+    }
+}
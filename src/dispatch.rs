@@ -0,0 +1,157 @@
+//! Per-tag dispatch tables
+//!
+//! Most behaviors start by matching on the message tag. `Dispatch` does
+//! that matching for them: register one handler per tag and the table
+//! routes each message by its `TypedMessage` tag, passing the handler
+//! the message fields.
+//!
+//! ```rust,ignore
+//! let account = Dispatch::new()
+//!     .on("Deposit", deposit)
+//!     .on("Withdraw", withdraw)
+//!     .otherwise(log_unknown);
+//! runtime.register_behavior("account", account);
+//! ```
+//!
+//! Messages with an unknown tag (or no tag) go to the `otherwise`
+//! handler, which gets the whole message. Without one they are reported
+//! as dead letters and the state is left unchanged.
+//!
+//! TODO: Accept Seq quotations as handlers once quotation invocation is
+//! wired through the FFI layer.
+
+use crate::behavior::{Behavior, BehaviorContext, BehaviorError};
+use crate::message::TypedMessage;
+use crate::serialize::TypedValue;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+type Handler = dyn Fn(&mut BehaviorContext, &TypedValue, &TypedValue) -> Result<TypedValue, BehaviorError>
+    + Send
+    + Sync;
+
+/// A behavior that routes messages to handlers by tag
+#[derive(Clone, Default)]
+pub struct Dispatch {
+    handlers: HashMap<String, Arc<Handler>>,
+    otherwise: Option<Arc<Handler>>,
+}
+
+impl Dispatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle messages tagged `tag`; the handler gets `(ctx, state, fields)`
+    pub fn on(
+        mut self,
+        tag: impl Into<String>,
+        handler: impl Fn(&mut BehaviorContext, &TypedValue, &TypedValue) -> Result<TypedValue, BehaviorError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.handlers.insert(tag.into(), Arc::new(handler));
+        self
+    }
+
+    /// Handle every other message; the handler gets `(ctx, state, msg)`
+    pub fn otherwise(
+        mut self,
+        handler: impl Fn(&mut BehaviorContext, &TypedValue, &TypedValue) -> Result<TypedValue, BehaviorError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.otherwise = Some(Arc::new(handler));
+        self
+    }
+}
+
+impl Behavior for Dispatch {
+    fn handle(
+        &self,
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        if let Ok(tagged) = TypedMessage::try_from(msg) {
+            if let Some(handler) = self.handlers.get(&tagged.tag) {
+                return handler(ctx, state, &tagged.fields);
+            }
+        }
+
+        match &self.otherwise {
+            Some(handler) => handler(ctx, state, msg),
+            None => {
+                ctx.dead_letter();
+                Ok(state.clone())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::BehaviorHarness;
+
+    fn amount(fields: &TypedValue) -> Result<i64, BehaviorError> {
+        match fields {
+            TypedValue::Int(n) => Ok(*n),
+            _ => Err(BehaviorError::new("expected Int amount")),
+        }
+    }
+
+    fn balance(state: &TypedValue) -> i64 {
+        match state {
+            TypedValue::Int(n) => *n,
+            _ => 0,
+        }
+    }
+
+    fn account() -> Dispatch {
+        Dispatch::new()
+            .on(
+                "Deposit",
+                |_: &mut BehaviorContext, state: &TypedValue, fields: &TypedValue| {
+                    Ok(TypedValue::Int(balance(state) + amount(fields)?))
+                },
+            )
+            .on(
+                "Withdraw",
+                |_: &mut BehaviorContext, state: &TypedValue, fields: &TypedValue| {
+                    Ok(TypedValue::Int(balance(state) - amount(fields)?))
+                },
+            )
+    }
+
+    fn msg(tag: &str, amount: i64) -> TypedValue {
+        TypedMessage::new(tag, TypedValue::Int(amount)).into()
+    }
+
+    #[test]
+    fn test_routes_by_tag() {
+        let outcome = BehaviorHarness::new(account())
+            .run([msg("Deposit", 100), msg("Withdraw", 30)])
+            .unwrap();
+        assert_eq!(outcome.state, TypedValue::Int(70));
+    }
+
+    #[test]
+    fn test_unknown_tags() {
+        let mut harness = BehaviorHarness::new(account()).with_state(TypedValue::Int(5));
+        harness.send(msg("Audit", 0)).unwrap();
+        assert_eq!(harness.state(), &TypedValue::Int(5));
+
+        let with_fallback = account().otherwise(
+            |ctx: &mut BehaviorContext, state: &TypedValue, msg: &TypedValue| {
+                ctx.emit("Unhandled", msg.clone());
+                Ok(state.clone())
+            },
+        );
+        let mut harness = BehaviorHarness::new(with_fallback);
+        let events = harness.send(TypedValue::Int(1)).unwrap();
+        assert_eq!(events[0].event_type, "Unhandled");
+    }
+}
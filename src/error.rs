@@ -0,0 +1,128 @@
+//! Runtime error type
+//!
+//! Errors returned by the Rust-facing `ActorRuntime` API. Journal-only
+//! operations keep returning `std::io::Error`, which converts into
+//! `RuntimeError::Io`.
+
+use crate::actor::ActorId;
+use crate::auth::{Operation, Principal};
+use crate::behavior::BehaviorError;
+use crate::journal::quota::QuotaExceeded;
+use crate::validate::EventRejected;
+use std::time::Duration;
+
+/// Error from an `ActorRuntime` operation
+#[derive(Debug)]
+pub enum RuntimeError {
+    /// No behavior is registered under this name
+    UnknownBehavior(String),
+    /// The actor is not known to this runtime
+    ActorNotFound(ActorId),
+    /// The actor exists but has been stopped
+    ActorStopped(ActorId),
+    /// The actor's rate limit rejected a message
+    RateLimited(ActorId),
+    /// The actor is shedding load (see `RuntimeConfig::load_shedding`)
+    Overloaded(ActorId),
+    /// The actor handled an `ask` without replying, or never got it
+    NoReply(ActorId),
+    /// No reply to an `ask` arrived in time
+    AskTimeout(ActorId),
+    /// No global runtime has been installed
+    NoGlobalRuntime,
+    /// The journal's disk quota refused an append
+    QuotaExceeded(QuotaExceeded),
+    /// The behavior rejected a message
+    Behavior(BehaviorError),
+    /// A validator refused an event the behavior emitted (see `validate`)
+    EventRejected(EventRejected),
+    /// Handling a message ran past its timeout (see `timeout`)
+    TimedOut { actor: ActorId, timeout: Duration },
+    /// No storage tier by this name is configured (see `tier`)
+    UnknownTier(String),
+    /// The delivery group has no running member (see `delivery`)
+    NoGroupMember(String),
+    /// The actor's sandbox denies the builtin it called (see `sandbox`)
+    BuiltinDenied { actor: ActorId, word: String },
+    /// The auth policy denies the caller this operation (see `auth`)
+    Unauthorized { principal: Principal, op: Operation },
+    /// The actor's behavior panicked under `PanicPolicy::EscalateToSupervisor`
+    Escalated(ActorId, BehaviorError),
+    /// Journal or trace IO failed
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeError::UnknownBehavior(name) => write!(f, "unknown behavior: {}", name),
+            RuntimeError::ActorNotFound(id) => write!(f, "actor not found: {}", id),
+            RuntimeError::ActorStopped(id) => write!(f, "actor stopped: {}", id),
+            RuntimeError::RateLimited(id) => write!(f, "rate limited: {}", id),
+            RuntimeError::Overloaded(id) => write!(f, "overloaded: {}", id),
+            RuntimeError::NoReply(id) => write!(f, "no reply from: {}", id),
+            RuntimeError::AskTimeout(id) => write!(f, "ask timed out: {}", id),
+            RuntimeError::NoGlobalRuntime => write!(f, "no global runtime installed"),
+            RuntimeError::QuotaExceeded(e) => write!(f, "{}", e),
+            RuntimeError::Behavior(e) => write!(f, "{}", e),
+            RuntimeError::EventRejected(e) => write!(f, "{}", e),
+            RuntimeError::TimedOut { actor, timeout } => {
+                write!(f, "{} timed out after {:?}", actor, timeout)
+            }
+            RuntimeError::UnknownTier(name) => write!(f, "unknown storage tier: {}", name),
+            RuntimeError::NoGroupMember(group) => {
+                write!(f, "no running member in delivery group: {}", group)
+            }
+            RuntimeError::BuiltinDenied { actor, word } => {
+                write!(f, "builtin {} denied to {}", word, actor)
+            }
+            RuntimeError::Unauthorized { principal, op } => {
+                write!(f, "{:?} is not authorized for {:?}", principal, op)
+            }
+            RuntimeError::Escalated(id, e) => write!(f, "escalated from {}: {}", id, e),
+            RuntimeError::Io(e) => write!(f, "io error: {}", e),
+        }
+    }
+}
+
+impl RuntimeError {
+    /// Whether this is one message failing (a behavior error, rejected
+    /// event or timeout) rather than a problem with the actor or runtime
+    pub fn is_message_failure(&self) -> bool {
+        matches!(
+            self,
+            RuntimeError::Behavior(_)
+                | RuntimeError::EventRejected(_)
+                | RuntimeError::TimedOut { .. }
+        )
+    }
+}
+
+impl std::error::Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RuntimeError::QuotaExceeded(e) => Some(e),
+            RuntimeError::EventRejected(e) => Some(e),
+            RuntimeError::Behavior(e) | RuntimeError::Escalated(_, e) => Some(e),
+            RuntimeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for RuntimeError {
+    /// Quota refusals keep their type; other IO errors become `Io`
+    fn from(e: std::io::Error) -> Self {
+        if crate::journal::quota::exceeded(&e).is_none() {
+            return RuntimeError::Io(e);
+        }
+        let inner = e.into_inner().expect("quota error has an inner error");
+        RuntimeError::QuotaExceeded(*inner.downcast().expect("checked above"))
+    }
+}
+
+impl From<BehaviorError> for RuntimeError {
+    fn from(e: BehaviorError) -> Self {
+        RuntimeError::Behavior(e)
+    }
+}
@@ -0,0 +1,81 @@
+//! Message expiry
+//!
+//! A slow actor works through its backlog in order, so under load it can
+//! spend its time on requests whose senders gave up long ago. Messages can
+//! be given a deadline two ways:
+//!
+//! - per message: `ActorRuntime::send_with_ttl`
+//! - per actor: a maximum age, `RuntimeConfig::max_message_age` or
+//!   `ActorRuntime::set_max_message_age`, measured from when the message
+//!   was queued
+//!
+//! An expired message is dropped instead of handled, with
+//! `DropReason::Expired` (counted in `expired` and `dropped`); an `ask`
+//! whose message expires gets `RuntimeError::NoReply`. Expiry is checked
+//! when a message reaches the front of the inbox, so stale messages never
+//! reach the behavior. Messages behind a busy or paused actor still hold
+//! memory until then; `ActorRuntime::sweep_expired` drops them from every
+//! inbox, and an `ExpirySweeper` calls it periodically:
+//!
+//! ```rust,ignore
+//! let config = RuntimeConfig {
+//!     max_message_age: Some(Duration::from_secs(30)),
+//!     ..RuntimeConfig::default()
+//! };
+//! let runtime = Arc::new(ActorRuntime::new(config));
+//! let _sweeper = ExpirySweeper::spawn(&runtime, Duration::from_secs(1));
+//! ```
+
+use crate::runtime::ActorRuntime;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Background thread dropping expired messages from every inbox
+///
+/// Stops when dropped, or when the runtime it sweeps is dropped.
+pub struct ExpirySweeper {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ExpirySweeper {
+    /// Start calling `runtime.sweep_expired()` every `interval`
+    pub fn spawn(runtime: &Arc<ActorRuntime>, interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let runtime: Weak<ActorRuntime> = Arc::downgrade(runtime);
+
+        let thread = {
+            let stop = stop.clone();
+            std::thread::Builder::new()
+                .name("seq-actors-expiry".to_string())
+                .spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        let Some(runtime) = runtime.upgrade() else {
+                            break;
+                        };
+                        runtime.sweep_expired();
+                        drop(runtime);
+                        std::thread::park_timeout(interval);
+                    }
+                })
+                .expect("failed to spawn expiry sweeper thread")
+        };
+
+        ExpirySweeper {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for ExpirySweeper {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
@@ -15,12 +15,95 @@
 //! - Take a `Stack` (pointer to stack top)
 //! - Return a `Stack` (new stack top after operation)
 //! - Stack grows downward (push = allocate node, set next = old top)
+//!
+//! # Actor IDs on the Stack
+//!
+//! Actors are represented on the stack by their interned `ActorHandle`
+//! (an Int), not by UUID strings. Use `actor-id-string` to get the
+//! printable UUID for display or logging.
+//!
+//! # Strings
+//!
+//! `patch_seq_push_string` copies the bytes it's given into a string that
+//! seq-runtime owns; it doesn't keep the pointer. So strings are pushed
+//! from a `CString` that lives only for the call (`push_string`), and the
+//! Rust side never frees or reuses memory seq-runtime handed out. Strings
+//! with an interior NUL are cut short there, as C would read them.
+//!
+//! The tests below exercise that contract; run them under AddressSanitizer
+//! to catch a runtime that holds on to the pointer:
+//!
+//! ```text
+//! RUSTFLAGS=-Zsanitizer=address cargo +nightly test -Zbuild-std \
+//!     --target x86_64-unknown-linux-gnu --lib ffi::
+//! ```
+//!
+//! # Errors
+//!
+//! `actor-spawn`, `actor-send`, `actor-self`, `actor-stop` and
+//! `actor-await` come in two flavors, picked at compile time (`BuiltinOptions::legacy_panics`):
+//!
+//! - Result (the default, `seq_actors_*_result`): push a variant, `Ok`
+//!   (tag 0) holding the word's usual outputs or `Err` (tag 1) holding a
+//!   message String, so Seq code can handle the failure
+//! - Legacy (`seq_actors_*`): panic, or silently do nothing, as before
+//!
+//! Words that act on other actors or the journal first check the calling
+//! actor's sandbox (`check_builtin`, see `sandbox`); a denied Result word
+//! pushes `Err`, a denied legacy word panics.
+//!
+//! # Yielding
+//!
+//! Strands are scheduled cooperatively by May, so a behavior computing in
+//! a long loop keeps other actors on its thread waiting. It can give them
+//! a turn with `actor-yield`. With `RuntimeConfig::auto_yield_every` set,
+//! the builtins that check the sandbox also count their calls per thread
+//! and yield on every Nth one, so loops that call builtins yield without
+//! being written to.
+//!
+//! Builtins that wait on the runtime (for the journal writer, or for an
+//! actor to go idle) do their work under `strand::yielding`, so the wait
+//! yields the strand instead of blocking its thread.
+//!
+//! # seq-runtime ABI
+//!
+//! Builtins reach seq-runtime through the `abi::RuntimeAbi` trait. The
+//! `seq_actors_*` symbols are thin shims that pop their arguments and
+//! call helpers generic over it (`spawn_actor`, `send_message`), which
+//! unit tests run against a mock.
+//!
+//! # Node Pool
+//!
+//! Popped stack nodes go onto a per-thread `pool::NodePool`, and the push
+//! helpers here take their nodes from it, so a builtin that pops its
+//! arguments and pushes a result doesn't touch the allocator. Only nodes
+//! a pool allocated are taken back: a node seq-runtime pushed has its
+//! value moved out and its memory left to seq-runtime, which exports no
+//! way to free it from here (see `pool`). `pool_stats` reports the
+//! current thread's pool.
+//!
+//! Values can own seq-runtime memory (strings, maps, quotations). With the
+//! `drop-value` feature, a popped value is released through
+//! `patch_seq_drop_value` when it goes out of scope unless it's handed on
+//! (`Popped::into_value`) or known to be a plain Int (`pop_int`). Not every
+//! seq-runtime exports that symbol, so without the feature what such a
+//! value owns is leaked. Nodes are recycled as soon as they're
+//! popped; the pool frees whatever it holds when its thread exits.
 
 #![allow(dead_code)] // FFI functions used at link time, not called from Rust
 #![allow(private_interfaces)] // Stack is opaque pointer for C FFI
+#![allow(clippy::missing_safety_doc)] // Safety contract is the stack convention above
 
-use crate::actor::ActorId;
-use crate::runtime::{get_current_actor, Mailbox, REGISTRY};
+mod abi;
+
+use crate::actor::{ActorHandle, ActorId};
+use crate::pool::{NodePool, PoolStats};
+use crate::runtime::{get_current_actor, global_runtime, Mailbox, REGISTRY};
+use crate::strand;
+use abi::{RuntimeAbi, SeqRuntime};
+use std::cell::{Cell, RefCell};
+use std::ffi::CString;
+use std::time::Duration;
 
 // FFI types matching seq-runtime
 type Stack = *mut StackNode;
@@ -39,54 +122,74 @@ union Value {
     _padding: [u8; 32], // Match seq-runtime's Value size
 }
 
-// External seq-runtime functions we call
-extern "C" {
-    fn patch_seq_make_channel(stack: Stack) -> Stack;
-    fn patch_seq_chan_send(stack: Stack) -> Stack;
-    fn patch_seq_chan_receive(stack: Stack) -> Stack;
-    fn patch_seq_close_channel(stack: Stack) -> Stack;
-    fn patch_seq_strand_spawn(entry: extern "C" fn(Stack) -> Stack, initial_stack: Stack) -> i64;
-    fn patch_seq_push_int(stack: Stack, value: i64) -> Stack;
-    fn patch_seq_push_string(stack: Stack, s: *const i8) -> Stack;
+thread_local! {
+    /// Free stack nodes for this thread's pushes
+    static NODES: RefCell<NodePool<StackNode>> = RefCell::new(NodePool::default());
+    /// Sandbox-checked builtin calls on this thread since it last yielded
+    static CALLS_SINCE_YIELD: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Allocation counters for the current thread's stack node pool
+pub fn pool_stats() -> PoolStats {
+    NODES.with_borrow(|pool| pool.stats())
 }
 
 /// Actor spawn - create a new actor
 ///
 /// Stack: ( behavior_name -- actor_id )
 ///
-/// Creates a new actor with the given behavior and returns its ID.
+/// Creates a new actor with the given behavior and returns its handle.
 /// The actor runs as a may coroutine with its own mailbox.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn seq_actors_spawn(stack: Stack) -> Stack {
+    require_builtin("actor-spawn");
     // For MVP, we create the actor infrastructure but behavior execution
     // requires more integration with seq-runtime's quotation system.
     //
-    // Current implementation:
-    // 1. Generate ActorId
-    // 2. Create mailbox channel
-    // 3. Register in registry
-    // 4. Return actor ID as string
-    //
     // TODO: Actually spawn coroutine with behavior loop
 
     // Pop behavior name from stack (we'll use it later)
     let (stack, _behavior) = pop_value(stack);
 
-    // Generate actor ID
-    let actor_id = ActorId::new();
-    let id_string = actor_id.as_str();
+    let handle = spawn_actor(&SeqRuntime);
+    push_int(stack, handle.as_raw() as i64)
+}
+
+/// Actor spawn, Result flavor
+///
+/// Stack: ( behavior_name -- Result )
+///
+/// `Err` for now: the behavior name can't be read yet, and an actor that
+/// runs no behavior isn't what was asked for (the legacy word spawns one
+/// anyway).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_spawn_result(stack: Stack) -> Stack {
+    let (stack, _behavior) = pop_value(stack);
+    if let Err(e) = check_builtin("actor-spawn") {
+        return push_err(&SeqRuntime, stack, &e);
+    }
+    try_spawn(&SeqRuntime, stack)
+}
 
-    // Create mailbox channel
-    let temp_stack = patch_seq_make_channel(std::ptr::null_mut());
-    let (_, channel_id) = pop_int(temp_stack);
+unsafe fn try_spawn(abi: &impl RuntimeAbi, stack: Stack) -> Stack {
+    // TODO: Read the behavior name (needs the value bridge) and spawn it
+    // through runtime.spawn, pushing Ok(handle)
+    push_err(
+        abi,
+        stack,
+        "actor-spawn can't start a behavior from Seq yet",
+    )
+}
 
-    // Register actor
-    let mailbox = Mailbox::new(channel_id);
-    REGISTRY.register(actor_id, mailbox, "behavior".to_string());
+/// Register a new actor with its own mailbox channel
+fn spawn_actor(abi: &impl RuntimeAbi) -> ActorHandle {
+    let mailbox = Mailbox::new(abi.make_channel());
+    REGISTRY.register(new_actor_id(), mailbox, "behavior".to_string())
+}
 
-    // Push actor ID string onto stack
-    let c_string = std::ffi::CString::new(id_string).expect("actor ID should be valid");
-    patch_seq_push_string(stack, c_string.as_ptr())
+/// A fresh ID in the global runtime's `id_scheme` (random without one)
+fn new_actor_id() -> ActorId {
+    global_runtime().map_or_else(ActorId::new, |runtime| runtime.new_actor_id())
 }
 
 /// Actor send - send a message to an actor
@@ -97,26 +200,129 @@ pub unsafe extern "C" fn seq_actors_spawn(stack: Stack) -> Stack {
 /// This is non-blocking (message is queued).
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn seq_actors_send(stack: Stack) -> Stack {
-    // Pop actor ID and message
-    // For now, we pass through to channel send
+    require_builtin("actor-send");
     // Stack has: ... message actor_id
-    // Channel send expects: ... value channel_id
+    let (stack, handle) = pop_handle(stack);
+    send_message(&SeqRuntime, stack, handle)
+}
+
+/// Route the message on top of `stack` to `handle`'s mailbox channel,
+/// popping it
+///
+/// Messages for unknown actors are dropped, as are messages for actors
+/// dispatched by `ActorRuntime` (TODO: queue those through
+/// `runtime.send` once the value bridge exists).
+unsafe fn send_message(abi: &impl RuntimeAbi, stack: Stack, handle: ActorHandle) -> Stack {
+    route_message(abi, stack, handle).0
+}
+
+/// `send_message`, with whether the message was sent or why it was
+/// dropped
+unsafe fn route_message(
+    abi: &impl RuntimeAbi,
+    stack: Stack,
+    handle: ActorHandle,
+) -> (Stack, Result<(), String>) {
+    let Some(id) = REGISTRY.resolve(handle) else {
+        let error = format!("no actor with handle {}", handle.0);
+        return (pop_value(stack).0, Err(error));
+    };
+    if !REGISTRY.is_running(&id) {
+        return (pop_value(stack).0, Err(format!("actor {} is stopped", id)));
+    }
+    match REGISTRY.get_mailbox(&id) {
+        Some(mailbox) if !mailbox.is_local() => {
+            (abi.chan_send(stack, mailbox.channel_id()), Ok(()))
+        }
+        Some(_) => {
+            let error = format!("actor {} can't be sent to from Seq yet", id);
+            (pop_value(stack).0, Err(error))
+        }
+        None => (pop_value(stack).0, Err(format!("no actor {}", id))),
+    }
+}
 
-    // The message is already on the stack in the right position
-    // We just need to look up the actor's mailbox channel ID
+/// Actor send, Result flavor
+///
+/// Stack: ( actor_id message -- Result )
+///
+/// `Err` if the message wasn't sent: the handle is unknown, the actor
+/// was stopped, or it is dispatched by `ActorRuntime` (which Seq can't
+/// send to yet). The message is consumed either way.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_send_result(stack: Stack) -> Stack {
+    let (stack, handle) = pop_handle(stack);
+    if let Err(e) = check_builtin("actor-send") {
+        return push_err(&SeqRuntime, pop_value(stack).0, &e);
+    }
+    try_send(&SeqRuntime, stack, handle)
+}
 
-    // Pop actor ID (string)
-    let (stack, _actor_id_val) = pop_value(stack);
+unsafe fn try_send(abi: &impl RuntimeAbi, stack: Stack, handle: ActorHandle) -> Stack {
+    match route_message(abi, stack, handle) {
+        (stack, Ok(())) => push_ok(abi, stack, 0),
+        (stack, Err(e)) => push_err(abi, stack, &e),
+    }
+}
 
-    // TODO: Look up actor in registry, get mailbox channel ID
-    // For now, this is a stub that just drops the message
+/// Actor send self - send a message to the current actor
+///
+/// Stack: ( message -- )
+///
+/// `actor-send` to `actor-self`: queues the message on the current
+/// actor's own mailbox channel, with the same limits (messages for actors
+/// dispatched by `ActorRuntime` are dropped until the value bridge
+/// exists; those behaviors use `BehaviorContext::send_self`).
+/// Panics if called outside an actor context.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_send_self(stack: Stack) -> Stack {
+    require_builtin("actor-send-self");
+    send_to_self(&SeqRuntime, stack)
+}
 
-    // In full implementation:
-    // 1. Parse actor ID from string
-    // 2. Look up in registry
-    // 3. Get mailbox channel ID
-    // 4. Push channel ID, call patch_seq_chan_send
+unsafe fn send_to_self(abi: &impl RuntimeAbi, stack: Stack) -> Stack {
+    let Some(handle) = get_current_actor().and_then(|id| REGISTRY.handle_of(&id)) else {
+        panic!("actor-send-self called outside actor context");
+    };
+    send_message(abi, stack, handle)
+}
 
+/// Actor forward - pass a message on in its original sender's name
+///
+/// Stack: ( actor_id message -- )
+///
+/// Like `actor-send`, but for proxies and routers: the receiver sees the
+/// sender of the message being handled, not the current actor, and a
+/// pending `ask` moves with the message so the receiver's reply reaches
+/// the asker. Runtime-dispatched behaviors do the same through
+/// `BehaviorContext::forward`.
+/// Panics if called outside an actor context.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_forward(stack: Stack) -> Stack {
+    require_builtin("actor-forward");
+    if get_current_actor().is_none() {
+        panic!("actor-forward called outside actor context");
+    }
+    let (stack, handle) = pop_handle(stack);
+
+    // Channel mailboxes carry bare values, so there is no envelope to
+    // keep. TODO: forward through BehaviorContext::forward for actors
+    // dispatched by `ActorRuntime` (needs the same value bridge as
+    // actor-send)
+    send_message(&SeqRuntime, stack, handle)
+}
+
+/// Actor yield - let the scheduler run other actors
+///
+/// Stack: ( -- )
+///
+/// Gives up the thread for a scheduling round (May's `yield_now`), for
+/// behaviors computing for a long time between other builtin calls.
+/// Restarts the count towards the next automatic yield.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_yield(stack: Stack) -> Stack {
+    CALLS_SINCE_YIELD.set(0);
+    SeqRuntime.yield_strand();
     stack
 }
 
@@ -124,22 +330,176 @@ pub unsafe extern "C" fn seq_actors_send(stack: Stack) -> Stack {
 ///
 /// Stack: ( -- actor_id )
 ///
-/// Returns the ID of the currently executing actor.
+/// Returns the handle of the currently executing actor.
 /// Panics if called outside an actor context.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn seq_actors_self(stack: Stack) -> Stack {
-    match get_current_actor() {
-        Some(id) => {
-            let id_string = id.as_str();
-            let c_string = std::ffi::CString::new(id_string).expect("actor ID should be valid");
-            patch_seq_push_string(stack, c_string.as_ptr())
-        }
+    match get_current_actor().and_then(|id| REGISTRY.handle_of(&id)) {
+        Some(handle) => push_int(stack, handle.as_raw() as i64),
         None => {
             panic!("actor-self called outside actor context");
         }
     }
 }
 
+/// Actor alive? - check whether an actor is running
+///
+/// Stack: ( actor_id -- Bool )
+///
+/// False for unknown handles and for actors that were stopped, even if
+/// they are still draining their mailbox. A send can still fail if the
+/// actor stops right after the check.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_is_alive(stack: Stack) -> Stack {
+    let (stack, handle) = pop_handle(stack);
+    SeqRuntime.push_bool(stack, is_alive(handle))
+}
+
+fn is_alive(handle: ActorHandle) -> bool {
+    REGISTRY
+        .resolve(handle)
+        .is_some_and(|id| REGISTRY.is_running(&id))
+}
+
+/// Actor count - number of running actors
+///
+/// Stack: ( -- Int )
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_count(stack: Stack) -> Stack {
+    push_int(stack, REGISTRY.running_count() as i64)
+}
+
+/// Runtime uptime - milliseconds since the global runtime was created
+///
+/// Stack: ( -- Int )
+///
+/// Pushes 0 if no global runtime is installed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_runtime_uptime(stack: Stack) -> Stack {
+    let uptime = global_runtime().map(|runtime| runtime.uptime().as_millis() as i64);
+    push_int(stack, uptime.unwrap_or(0))
+}
+
+/// Actor self, Result flavor
+///
+/// Stack: ( -- Result )
+///
+/// `Err` outside an actor context.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_self_result(stack: Stack) -> Stack {
+    try_self(&SeqRuntime, stack)
+}
+
+unsafe fn try_self(abi: &impl RuntimeAbi, stack: Stack) -> Stack {
+    match get_current_actor().and_then(|id| REGISTRY.handle_of(&id)) {
+        Some(handle) => push_ok(abi, push_int(stack, handle.as_raw() as i64), 1),
+        None => push_err(abi, stack, "actor-self called outside actor context"),
+    }
+}
+
+/// Actor ID string - get the printable UUID for an actor handle
+///
+/// Stack: ( actor_id -- String )
+///
+/// Only needed for display; sends and lookups use the handle directly.
+/// Pushes an empty string if the handle is unknown.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_id_string(stack: Stack) -> Stack {
+    let (stack, handle) = pop_handle(stack);
+
+    let id_string = REGISTRY
+        .resolve(handle)
+        .map(|id| id.as_str())
+        .unwrap_or_default();
+    push_string(stack, &id_string)
+}
+
+/// Actor FSM state - get the current state name of an fsm actor
+///
+/// Stack: ( actor_id -- String )
+///
+/// Pushes an empty string if the actor is unknown, busy, or not an fsm.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_fsm_state(stack: Stack) -> Stack {
+    let (stack, handle) = pop_handle(stack);
+
+    let state = match (global_runtime(), REGISTRY.resolve(handle)) {
+        (Some(runtime), Some(id)) => {
+            strand::yielding(strand_yield, || crate::fsm::current_state(runtime, &id))
+                .unwrap_or_default()
+        }
+        _ => String::new(),
+    };
+    push_string(stack, &state)
+}
+
+/// Actor alias - redirect an old actor ID to a new one
+///
+/// Stack: ( old_id new_id -- )
+///
+/// Persisted under the journal path: messages and journal reads for the
+/// old ID go to the new actor from now on. Does nothing if either handle
+/// is unknown or no global runtime is installed. Panics if the alias
+/// can't be written (or would form a cycle).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_alias(stack: Stack) -> Stack {
+    require_builtin("actor-alias");
+    let (stack, new) = pop_handle(stack);
+    let (stack, old) = pop_handle(stack);
+
+    if let (Some(runtime), Some(old), Some(new)) = (
+        global_runtime(),
+        REGISTRY.resolve(old),
+        REGISTRY.resolve(new),
+    ) {
+        strand::yielding(strand_yield, || runtime.alias(&old, &new))
+            .unwrap_or_else(|e| panic!("actor-alias failed: {}", e));
+    }
+
+    stack
+}
+
+/// System subscribe - receive runtime lifecycle events
+///
+/// Stack: ( -- )
+///
+/// Subscribes the current actor to `ActorId::SYSTEM` (see `system`).
+/// Does nothing if no global runtime is installed. Panics if called
+/// outside an actor context.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_system_subscribe(stack: Stack) -> Stack {
+    require_builtin("system-subscribe");
+    let Some(id) = get_current_actor() else {
+        panic!("system-subscribe called outside actor context");
+    };
+    if let Some(runtime) = global_runtime() {
+        runtime.subscribe_system(&id);
+    }
+
+    stack
+}
+
+/// Actor snapshot - snapshot an actor's state now
+///
+/// Stack: ( actor_id -- )
+///
+/// For business boundaries (end of day, after a batch) rather than only
+/// every N events. Waits for the actor's in-flight message, if any. Does
+/// nothing if the handle is unknown or no global runtime is installed.
+/// Panics if the snapshot can't be written.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_snapshot(stack: Stack) -> Stack {
+    require_builtin("actor-snapshot");
+    let (stack, handle) = pop_handle(stack);
+
+    if let (Some(runtime), Some(id)) = (global_runtime(), REGISTRY.resolve(handle)) {
+        strand::yielding(strand_yield, || runtime.snapshot_now(&id))
+            .unwrap_or_else(|e| panic!("actor-snapshot failed: {}", e));
+    }
+
+    stack
+}
+
 /// Actor stop - stop an actor
 ///
 /// Stack: ( actor_id -- )
@@ -148,13 +508,90 @@ pub unsafe extern "C" fn seq_actors_self(stack: Stack) -> Stack {
 /// its current message before stopping.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn seq_actors_stop(stack: Stack) -> Stack {
-    // Pop actor ID
-    let (stack, _actor_id_val) = pop_value(stack);
+    require_builtin("actor-stop");
+    // Pop actor handle
+    let (stack, handle) = pop_handle(stack);
+    let _ = stop_actor(handle);
+    stack
+}
+
+/// Actor stop, Result flavor
+///
+/// Stack: ( actor_id -- Result )
+///
+/// `Err` if the handle is unknown.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_stop_result(stack: Stack) -> Stack {
+    let (stack, handle) = pop_handle(stack);
+    match check_builtin("actor-stop").and_then(|()| stop_actor(handle)) {
+        Ok(()) => push_ok(&SeqRuntime, stack, 0),
+        Err(e) => push_err(&SeqRuntime, stack, &e),
+    }
+}
 
-    // TODO: Look up actor, send stop signal
-    // For now, this is a stub
+/// Stop the actor behind `handle`, through the global runtime if one is
+/// installed (so it drains and terminates), otherwise in the registry
+fn stop_actor(handle: ActorHandle) -> Result<(), String> {
+    let id = REGISTRY
+        .resolve(handle)
+        .ok_or_else(|| format!("no actor with handle {}", handle.0))?;
+    match global_runtime() {
+        Some(runtime) => runtime.stop_actor(&id),
+        None => REGISTRY.mark_stopped(&id),
+    }
+    Ok(())
+}
 
-    stack
+/// Actor await - wait until an actor has fully stopped
+///
+/// Stack: ( actor_id timeout_ms -- Bool )
+///
+/// Pushes true once the actor has drained its mailbox, run its stop hook,
+/// and flushed its final snapshot. Pushes false if it is still running
+/// after `timeout_ms`, or when the current message runs past its own
+/// timeout (see `timeout`), and if the handle is unknown, no global
+/// runtime is installed, or termination fails (e.g. the snapshot can't be
+/// written). The wait yields the strand.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_await(stack: Stack) -> Stack {
+    let (stack, timeout_ms) = pop_int(stack);
+    let (stack, handle) = pop_handle(stack);
+    let stopped = await_actor(handle, timeout_ms).unwrap_or(false);
+    SeqRuntime.push_bool(stack, stopped)
+}
+
+/// Actor await, Result flavor
+///
+/// Stack: ( actor_id timeout_ms -- Result )
+///
+/// `Ok(Bool)` as `actor-await` pushes it; `Err` if the handle is unknown,
+/// no global runtime is installed, or termination fails.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn seq_actors_await_result(stack: Stack) -> Stack {
+    let (stack, timeout_ms) = pop_int(stack);
+    let (stack, handle) = pop_handle(stack);
+    match await_actor(handle, timeout_ms) {
+        Ok(stopped) => push_ok(&SeqRuntime, SeqRuntime.push_bool(stack, stopped), 1),
+        Err(e) => push_err(&SeqRuntime, stack, &e),
+    }
+}
+
+/// Wait up to `timeout_ms` (and the current message's deadline) for the
+/// actor behind `handle` to terminate, returning whether it did
+fn await_actor(handle: ActorHandle, timeout_ms: i64) -> Result<bool, String> {
+    let id = REGISTRY
+        .resolve(handle)
+        .ok_or_else(|| format!("no actor with handle {}", handle.0))?;
+    // Without a runtime nothing drains the actor, so there is no
+    // termination to wait for
+    let runtime =
+        global_runtime().ok_or_else(|| "actor-await needs a global runtime".to_string())?;
+    let mut timeout = Duration::from_millis(timeout_ms.max(0) as u64);
+    if let Some(left) = crate::timeout::remaining() {
+        timeout = timeout.min(left);
+    }
+    strand::yielding(strand_yield, || runtime.wait_for_stop(&id, timeout))
+        .map_err(|e| format!("actor-await failed: {}", e))
 }
 
 /// Actor state - get current actor's state
@@ -178,6 +615,7 @@ pub unsafe extern "C" fn seq_actors_state(stack: Stack) -> Stack {
 /// Must be called from within an actor context.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn seq_actors_journal_append(stack: Stack) -> Stack {
+    require_builtin("journal-append");
     // Pop event from stack
     let (stack, _event_val) = pop_value(stack);
 
@@ -187,26 +625,275 @@ pub unsafe extern "C" fn seq_actors_journal_append(stack: Stack) -> Stack {
     stack
 }
 
+/// Refuse `word` if the current actor's sandbox denies it (see
+/// `sandbox`)
+///
+/// Allowed outside an actor context or without a global runtime. Also
+/// where automatic yields happen (see "Yielding").
+fn check_builtin(word: &str) -> Result<(), String> {
+    match (global_runtime(), get_current_actor()) {
+        (Some(runtime), Some(id)) => {
+            if let Some(every) = runtime.config().auto_yield_every {
+                auto_yield(&SeqRuntime, every);
+            }
+            runtime.check_builtin(&id, word).map_err(|e| e.to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Count a builtin call, yielding on every `every`th one on this thread
+fn auto_yield(abi: &impl RuntimeAbi, every: u32) {
+    let calls = CALLS_SINCE_YIELD.get() + 1;
+    if calls >= every {
+        CALLS_SINCE_YIELD.set(0);
+        abi.yield_strand();
+    } else {
+        CALLS_SINCE_YIELD.set(calls);
+    }
+}
+
+/// Yield the current strand: how waits inside builtins give way
+fn strand_yield() {
+    SeqRuntime.yield_strand();
+}
+
+/// `check_builtin` for legacy-flavor words: panics if `word` is denied
+fn require_builtin(word: &str) {
+    if let Err(e) = check_builtin(word) {
+        panic!("{}", e);
+    }
+}
+
 // Helper functions for stack manipulation
 
-unsafe fn pop_value(stack: Stack) -> (Stack, Value) {
+/// A value popped off the stack, released by seq-runtime when dropped
+/// (with the `drop-value` feature)
+struct Popped(Value);
+
+impl Popped {
+    /// Take ownership of the value, e.g. to push it again
+    fn into_value(self) -> Value {
+        let this = std::mem::ManuallyDrop::new(self);
+        // Safety: `this` is never dropped, so the value is moved out once
+        unsafe { std::ptr::read(&this.0) }
+    }
+}
+
+#[cfg(feature = "drop-value")]
+impl Drop for Popped {
+    fn drop(&mut self) {
+        unsafe { abi::patch_seq_drop_value(&mut self.0) }
+    }
+}
+
+unsafe fn pop_value(stack: Stack) -> (Stack, Popped) {
     if stack.is_null() {
         panic!("Stack underflow");
     }
-    let node = &*stack;
-    let value = std::ptr::read(&node.value);
-    let next = node.next;
-    // Note: In real impl, return node to pool
-    (next, value)
+    let node = NODES.with_borrow_mut(|pool| pool.recycle(stack));
+    (node.next, Popped(node.value))
+}
+
+unsafe fn push_value(stack: Stack, value: Value) -> Stack {
+    NODES.with_borrow_mut(|pool| pool.alloc(StackNode { value, next: stack }))
+}
+
+unsafe fn push_int(stack: Stack, value: i64) -> Stack {
+    push_value(stack, Value { int_val: value })
+}
+
+/// Push a copy of `s`, up to its first NUL
+unsafe fn push_string(stack: Stack, s: &str) -> Stack {
+    push_string_with(&SeqRuntime, stack, s)
 }
 
+unsafe fn push_string_with(abi: &impl RuntimeAbi, stack: Stack, s: &str) -> Stack {
+    let end = s.find('\0').unwrap_or(s.len());
+    let c_string = CString::new(&s[..end]).expect("NUL-free prefix");
+    // seq-runtime copies the bytes; `c_string` is freed when we return
+    abi.push_string(stack, &c_string)
+}
+
+/// Variant tags of the Result builtins push (see "Errors" above)
+const OK_TAG: u32 = 0;
+const ERR_TAG: u32 = 1;
+
+/// Wrap the top `fields` values in `Ok`
+unsafe fn push_ok(abi: &impl RuntimeAbi, stack: Stack, fields: usize) -> Stack {
+    abi.make_variant(stack, OK_TAG, fields)
+}
+
+/// Push `Err(message)`
+unsafe fn push_err(abi: &impl RuntimeAbi, stack: Stack, message: &str) -> Stack {
+    abi.make_variant(push_string_with(abi, stack, message), ERR_TAG, 1)
+}
+
+/// Pop a value known to be an Int (nothing to release)
 unsafe fn pop_int(stack: Stack) -> (Stack, i64) {
     let (stack, value) = pop_value(stack);
-    (stack, value.int_val)
+    (stack, value.into_value().int_val)
+}
+
+unsafe fn pop_handle(stack: Stack) -> (Stack, ActorHandle) {
+    let (stack, raw) = pop_int(stack);
+    (stack, ActorHandle(raw as u64))
 }
 
 #[cfg(test)]
 mod tests {
-    // FFI tests require linking with seq-runtime
-    // These are integration tests that run with the full stack
+    // Builtins that call into seq-runtime are exercised through their C
+    // symbols by tests/ffi.rs; these cover the Rust-side helpers
+    use super::*;
+
+    #[test]
+    fn test_popped_nodes_are_reused() {
+        let before = pool_stats();
+        let mut stack: Stack = std::ptr::null_mut();
+        for round in 0..100 {
+            for i in 0..3 {
+                stack = unsafe { push_int(stack, round * 3 + i) };
+            }
+            for i in (0..3).rev() {
+                let (next, value) = unsafe { pop_int(stack) };
+                assert_eq!(value, round * 3 + i);
+                stack = next;
+            }
+        }
+        assert!(stack.is_null());
+
+        let after = pool_stats();
+        assert_eq!(after.allocated - before.allocated, 3);
+        assert_eq!(after.reused - before.reused, 297);
+        assert_eq!(after.free, 3);
+    }
+
+    #[test]
+    fn test_auto_yield_every_nth_call() {
+        let abi = abi::MockAbi::default();
+        CALLS_SINCE_YIELD.set(0);
+        for _ in 0..7 {
+            auto_yield(&abi, 3);
+        }
+        assert_eq!(abi.yields.get(), 2);
+        assert_eq!(CALLS_SINCE_YIELD.get(), 1);
+        auto_yield(&abi, 1);
+        assert_eq!(abi.yields.get(), 3);
+    }
+
+    #[test]
+    fn test_spawn_and_send_use_mailbox_channel() {
+        let abi = abi::MockAbi::default();
+        let handle = spawn_actor(&abi);
+        let id = REGISTRY.resolve(handle).unwrap();
+        assert_eq!(REGISTRY.get_mailbox(&id).unwrap().channel_id(), 1);
+
+        let stack = unsafe { push_int(std::ptr::null_mut(), 42) };
+        let stack = unsafe { send_message(&abi, stack, handle) };
+        assert!(stack.is_null());
+        assert_eq!(*abi.sent.borrow(), [1]);
+
+        // Unknown actors get nothing; the message is still consumed
+        let stack = unsafe { push_int(std::ptr::null_mut(), 42) };
+        let stack = unsafe { send_message(&abi, stack, ActorHandle(u64::MAX)) };
+        assert!(stack.is_null());
+        assert_eq!(abi.sent.borrow().len(), 1);
+
+        REGISTRY.unregister(&id);
+    }
+
+    #[test]
+    fn test_send_self_uses_own_channel() {
+        let abi = abi::MockAbi::default();
+        let handle = spawn_actor(&abi);
+        let id = REGISTRY.resolve(handle).unwrap();
+
+        crate::runtime::set_current_actor(id.clone());
+        let stack = unsafe { push_int(std::ptr::null_mut(), 42) };
+        let stack = unsafe { send_to_self(&abi, stack) };
+        crate::runtime::clear_current_actor();
+        assert!(stack.is_null());
+        assert_eq!(*abi.sent.borrow(), [1]);
+
+        REGISTRY.unregister(&id);
+    }
+
+    #[test]
+    fn test_result_builtins() {
+        let abi = abi::MockAbi::default();
+        let pop = |stack| unsafe { pop_int(stack) };
+
+        let (stack, tag) = pop(unsafe { try_self(&abi, std::ptr::null_mut()) });
+        assert!(stack.is_null());
+        assert_eq!(tag, ERR_TAG as i64);
+        assert_eq!(
+            abi.strings.borrow()[0],
+            "actor-self called outside actor context"
+        );
+
+        // Nothing is spawned while the behavior name can't be read
+        let (stack, tag) = pop(unsafe { try_spawn(&abi, std::ptr::null_mut()) });
+        assert!(stack.is_null());
+        assert_eq!(tag, ERR_TAG as i64);
+        assert_eq!(abi.channels.get(), 0);
+
+        let handle = spawn_actor(&abi);
+        let message = unsafe { push_int(std::ptr::null_mut(), 42) };
+        let (stack, tag) = pop(unsafe { try_send(&abi, message, handle) });
+        assert!(stack.is_null());
+        assert_eq!(tag, OK_TAG as i64);
+
+        let message = unsafe { push_int(std::ptr::null_mut(), 42) };
+        let (stack, tag) = pop(unsafe { try_send(&abi, message, ActorHandle(u64::MAX)) });
+        assert!(stack.is_null());
+        assert_eq!(tag, ERR_TAG as i64);
+
+        // Dropped, not sent: an actor the runtime dispatches
+        let local_id = ActorId::new();
+        let local = REGISTRY.register(local_id.clone(), Mailbox::local(), "local".to_string());
+        let message = unsafe { push_int(std::ptr::null_mut(), 42) };
+        let (stack, tag) = pop(unsafe { try_send(&abi, message, local) });
+        assert!(stack.is_null());
+        assert_eq!(tag, ERR_TAG as i64);
+        REGISTRY.unregister(&local_id);
+        assert_eq!(*abi.sent.borrow(), [1]);
+        let tags: Vec<_> = abi.variants.borrow().iter().map(|(tag, _)| *tag).collect();
+        assert_eq!(tags, [ERR_TAG, ERR_TAG, OK_TAG, ERR_TAG, ERR_TAG]);
+
+        let id = REGISTRY.resolve(handle).unwrap();
+        assert_eq!(stop_actor(handle), Ok(()));
+        assert!(!REGISTRY.is_running(&id));
+        REGISTRY.unregister(&id);
+        assert!(stop_actor(handle).is_err());
+    }
+
+    #[test]
+    fn test_is_alive() {
+        let handle = spawn_actor(&abi::MockAbi::default());
+        assert!(is_alive(handle));
+
+        let id = REGISTRY.resolve(handle).unwrap();
+        REGISTRY.mark_stopped(&id);
+        assert!(!is_alive(handle));
+        REGISTRY.unregister(&id);
+        assert!(!is_alive(handle));
+    }
+
+    #[test]
+    fn test_pushed_strings_outlive_their_cstring() {
+        let long = "x".repeat(4096);
+        let mut stack: Stack = std::ptr::null_mut();
+        for s in ["", "actor", "cut\0short", long.as_str()] {
+            stack = unsafe { push_string(stack, s) };
+        }
+        // Churn the allocator so a runtime still pointing at a freed
+        // CString would read someone else's bytes (ASAN flags the read)
+        let noise: Vec<String> = (0..64).map(|i| format!("{:0>4096}", i)).collect();
+        drop(noise);
+
+        for _ in 0..4 {
+            stack = unsafe { pop_value(stack) }.0;
+        }
+        assert!(stack.is_null());
+    }
 }
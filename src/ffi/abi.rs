@@ -0,0 +1,147 @@
+//! The seq-runtime functions actor builtins call
+//!
+//! Builtins go through `RuntimeAbi` instead of calling `patch_seq_*`
+//! directly, so what they do above the FFI boundary (registering spawned
+//! actors, routing sends to mailbox channels) runs against `MockAbi` in
+//! plain Rust unit tests. `SeqRuntime` is the real implementation.
+
+use super::{pop_int, Stack};
+use std::ffi::CStr;
+
+// External seq-runtime functions we call
+extern "C" {
+    fn patch_seq_make_channel(stack: Stack) -> Stack;
+    fn patch_seq_chan_send(stack: Stack) -> Stack;
+    fn patch_seq_chan_receive(stack: Stack) -> Stack;
+    fn patch_seq_close_channel(stack: Stack) -> Stack;
+    fn patch_seq_strand_spawn(entry: extern "C" fn(Stack) -> Stack, initial_stack: Stack) -> i64;
+    fn patch_seq_push_int(stack: Stack, value: i64) -> Stack;
+    fn patch_seq_push_bool(stack: Stack, value: bool) -> Stack;
+    fn patch_seq_make_variant(stack: Stack, tag: i64, fields: i64) -> Stack;
+    fn patch_seq_yield_strand(stack: Stack) -> Stack;
+    /// Copies `s`, which only needs to be valid for the call
+    fn patch_seq_push_string(stack: Stack, s: *const i8) -> Stack;
+    #[cfg(feature = "drop-value")]
+    pub(super) fn patch_seq_drop_value(value: *mut super::Value);
+}
+
+/// seq-runtime operations behind the actor builtins
+pub(super) trait RuntimeAbi {
+    /// Create a channel, returning its ID
+    fn make_channel(&self) -> i64;
+
+    /// Close a channel created by `make_channel`
+    fn close_channel(&self, channel: i64);
+
+    /// Send the value on top of `stack` to `channel`, popping it
+    unsafe fn chan_send(&self, stack: Stack, channel: i64) -> Stack;
+
+    /// Push a copy of `s`
+    unsafe fn push_string(&self, stack: Stack, s: &CStr) -> Stack;
+
+    unsafe fn push_bool(&self, stack: Stack, value: bool) -> Stack;
+
+    /// Replace the top `fields` values with a variant holding them
+    unsafe fn make_variant(&self, stack: Stack, tag: u32, fields: usize) -> Stack;
+
+    /// Let the scheduler run other strands before continuing
+    fn yield_strand(&self);
+}
+
+/// The linked seq-runtime
+pub(super) struct SeqRuntime;
+
+impl RuntimeAbi for SeqRuntime {
+    fn make_channel(&self) -> i64 {
+        unsafe {
+            let stack = patch_seq_make_channel(std::ptr::null_mut());
+            pop_int(stack).1
+        }
+    }
+
+    fn close_channel(&self, channel: i64) {
+        unsafe {
+            let stack = patch_seq_push_int(std::ptr::null_mut(), channel);
+            patch_seq_close_channel(stack);
+        }
+    }
+
+    unsafe fn chan_send(&self, stack: Stack, channel: i64) -> Stack {
+        patch_seq_chan_send(patch_seq_push_int(stack, channel))
+    }
+
+    unsafe fn push_string(&self, stack: Stack, s: &CStr) -> Stack {
+        patch_seq_push_string(stack, s.as_ptr())
+    }
+
+    unsafe fn push_bool(&self, stack: Stack, value: bool) -> Stack {
+        patch_seq_push_bool(stack, value)
+    }
+
+    unsafe fn make_variant(&self, stack: Stack, tag: u32, fields: usize) -> Stack {
+        patch_seq_make_variant(stack, tag as i64, fields as i64)
+    }
+
+    fn yield_strand(&self) {
+        unsafe {
+            patch_seq_yield_strand(std::ptr::null_mut());
+        }
+    }
+}
+
+/// Records what builtins asked of seq-runtime
+#[cfg(test)]
+#[derive(Default)]
+pub(super) struct MockAbi {
+    pub channels: std::cell::Cell<i64>,
+    pub closed: std::cell::RefCell<Vec<i64>>,
+    /// Channels sent to, in order
+    pub sent: std::cell::RefCell<Vec<i64>>,
+    pub strings: std::cell::RefCell<Vec<String>>,
+    /// Variants made, as (tag, fields)
+    pub variants: std::cell::RefCell<Vec<(u32, Vec<i64>)>>,
+    pub yields: std::cell::Cell<usize>,
+}
+
+#[cfg(test)]
+impl RuntimeAbi for MockAbi {
+    fn make_channel(&self) -> i64 {
+        self.channels.set(self.channels.get() + 1);
+        self.channels.get()
+    }
+
+    fn close_channel(&self, channel: i64) {
+        self.closed.borrow_mut().push(channel);
+    }
+
+    unsafe fn chan_send(&self, stack: Stack, channel: i64) -> Stack {
+        self.sent.borrow_mut().push(channel);
+        pop_int(stack).0
+    }
+
+    /// Pushes the string's index in `strings`
+    unsafe fn push_string(&self, stack: Stack, s: &CStr) -> Stack {
+        let mut strings = self.strings.borrow_mut();
+        strings.push(s.to_string_lossy().into_owned());
+        super::push_int(stack, strings.len() as i64 - 1)
+    }
+
+    /// Pushes 1 or 0
+    unsafe fn push_bool(&self, stack: Stack, value: bool) -> Stack {
+        super::push_int(stack, value as i64)
+    }
+
+    /// Pops the fields (all Ints here) and pushes the tag
+    unsafe fn make_variant(&self, mut stack: Stack, tag: u32, fields: usize) -> Stack {
+        let mut values = vec![0; fields];
+        for value in values.iter_mut().rev() {
+            (stack, *value) = pop_int(stack);
+        }
+        self.variants.borrow_mut().push((tag, values));
+        super::push_int(stack, tag as i64)
+    }
+
+    fn yield_strand(&self) {
+        self.yields.set(self.yields.get() + 1);
+    }
+}
@@ -0,0 +1,255 @@
+//! Declarative finite state machines
+//!
+//! The most common actor shape is a state machine: the actor is in one
+//! of a fixed set of states, and each message names an event that moves it
+//! along a declared transition. `Fsm` is a `Behavior` built from that
+//! declaration:
+//!
+//! ```rust,ignore
+//! let door = Fsm::new("closed")
+//!     .state("open")
+//!     .state("locked")
+//!     .transition("closed", "open", "open")
+//!     .transition("open", "close", "closed")
+//!     .transition("closed", "lock", "locked")
+//!     .transition("locked", "unlock", "closed");
+//! runtime.register_behavior("door", door);
+//! ```
+//!
+//! The actor's state is the current state name (a String). A message is
+//! an event name, given either as a String or as a Map with an `"event"`
+//! key. Undeclared transitions are rejected (a `BehaviorError`, so the
+//! state is unchanged). Each accepted transition is journaled as an
+//! `FsmTransition` event with `from`, `event`, and `to` fields.
+
+use crate::actor::ActorId;
+use crate::behavior::{Behavior, BehaviorContext, BehaviorError};
+use crate::journal::Event;
+use crate::runtime::ActorRuntime;
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Event type journaled for each transition
+pub const TRANSITION_EVENT: &str = "FsmTransition";
+
+/// A state machine behavior
+#[derive(Debug, Clone)]
+pub struct Fsm {
+    initial: String,
+    states: BTreeSet<String>,
+    /// (from, event) -> to
+    transitions: HashMap<(String, String), String>,
+}
+
+impl Fsm {
+    /// Start a machine in `initial`
+    pub fn new(initial: impl Into<String>) -> Self {
+        let initial = initial.into();
+        Fsm {
+            states: BTreeSet::from([initial.clone()]),
+            initial,
+            transitions: HashMap::new(),
+        }
+    }
+
+    /// Declare a state
+    pub fn state(mut self, name: impl Into<String>) -> Self {
+        self.states.insert(name.into());
+        self
+    }
+
+    /// Declare that `event` moves the machine from `from` to `to`
+    ///
+    /// Panics if either state is undeclared.
+    pub fn transition(
+        mut self,
+        from: impl Into<String>,
+        event: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Self {
+        let (from, to) = (from.into(), to.into());
+        assert!(
+            self.states.contains(&from),
+            "undeclared fsm state: {}",
+            from
+        );
+        assert!(self.states.contains(&to), "undeclared fsm state: {}", to);
+        self.transitions.insert((from, event.into()), to);
+        self
+    }
+
+    /// The state a transition leads to, if declared
+    pub fn next(&self, from: &str, event: &str) -> Option<&str> {
+        self.transitions
+            .get(&(from.to_string(), event.to_string()))
+            .map(String::as_str)
+    }
+
+    /// Current state name held in an actor state value
+    fn current<'a>(&'a self, state: &'a TypedValue) -> &'a str {
+        match state {
+            TypedValue::String(name) if self.states.contains(name) => name,
+            _ => &self.initial,
+        }
+    }
+}
+
+/// Event name carried by a message
+fn event_name(msg: &TypedValue) -> Option<&str> {
+    match msg {
+        TypedValue::String(name) => Some(name),
+        TypedValue::Map(fields) => match fields.get(&TypedMapKey::String("event".to_string())) {
+            Some(TypedValue::String(name)) => Some(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl Behavior for Fsm {
+    fn handle(
+        &self,
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        let from = self.current(state);
+        let event = event_name(msg)
+            .ok_or_else(|| BehaviorError::new(format!("message is not an fsm event: {:?}", msg)))?;
+        let to = self.next(from, event).ok_or_else(|| {
+            BehaviorError::new(format!("no transition from {} on {}", from, event))
+        })?;
+
+        let field = |key: &str, value: &str| {
+            (
+                TypedMapKey::String(key.to_string()),
+                TypedValue::String(value.to_string()),
+            )
+        };
+        ctx.emit(
+            TRANSITION_EVENT,
+            TypedValue::Map(BTreeMap::from([
+                field("from", from),
+                field("event", event),
+                field("to", to),
+            ])),
+        );
+
+        Ok(TypedValue::String(to.to_string()))
+    }
+
+    fn initial_state(&self) -> TypedValue {
+        TypedValue::String(self.initial.clone())
+    }
+
+    fn apply_event(&self, state: &TypedValue, event: &Event) -> TypedValue {
+        match &event.payload {
+            TypedValue::Map(fields) if event.event_type == TRANSITION_EVENT => {
+                match fields.get(&TypedMapKey::String("to".to_string())) {
+                    Some(to @ TypedValue::String(_)) => to.clone(),
+                    _ => state.clone(),
+                }
+            }
+            _ => state.clone(),
+        }
+    }
+}
+
+/// Current state name of an fsm actor
+///
+/// `None` if the actor is unknown, is handling a message right now, or
+/// its state is not a state name.
+pub fn current_state(runtime: &ActorRuntime, id: &ActorId) -> Option<String> {
+    match runtime.peek_state(id)? {
+        TypedValue::String(name) => Some(name),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::BehaviorHarness;
+
+    fn door() -> Fsm {
+        Fsm::new("closed")
+            .state("open")
+            .state("locked")
+            .transition("closed", "open", "open")
+            .transition("open", "close", "closed")
+            .transition("closed", "lock", "locked")
+            .transition("locked", "unlock", "closed")
+    }
+
+    fn event(name: &str) -> TypedValue {
+        TypedValue::String(name.to_string())
+    }
+
+    #[test]
+    fn test_transitions_are_journaled() {
+        let mut harness = BehaviorHarness::new(door());
+        assert_eq!(harness.state(), &event("closed"));
+
+        harness.send(event("lock")).unwrap();
+        harness
+            .send(TypedValue::Map(BTreeMap::from([(
+                TypedMapKey::String("event".to_string()),
+                event("unlock"),
+            )])))
+            .unwrap();
+        harness.send(event("open")).unwrap();
+
+        assert_eq!(harness.state(), &event("open"));
+        let events = harness.events();
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|e| e.event_type == TRANSITION_EVENT));
+    }
+
+    #[test]
+    fn test_invalid_transition_rejected() {
+        let mut harness = BehaviorHarness::new(door());
+        harness.send(event("open")).unwrap();
+
+        let err = harness.send(event("lock")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "behavior failed: no transition from open on lock"
+        );
+        assert!(harness.send(TypedValue::Int(1)).is_err());
+        assert_eq!(harness.state(), &event("open"));
+        assert_eq!(harness.events().len(), 1);
+    }
+
+    #[test]
+    fn test_current_state_in_runtime() {
+        use crate::runtime::RuntimeConfig;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            ..RuntimeConfig::default()
+        });
+        runtime.register_behavior("door", door());
+
+        let id = runtime.spawn("door").unwrap();
+        assert_eq!(current_state(&runtime, &id).as_deref(), Some("closed"));
+
+        runtime.send(&id, event("open")).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert_eq!(current_state(&runtime, &id).as_deref(), Some("open"));
+
+        // A respawn replays the journaled transitions
+        runtime.unregister_actor(&id);
+        runtime.spawn_with_id(id.clone(), "door").unwrap();
+        assert_eq!(current_state(&runtime, &id).as_deref(), Some("open"));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    #[should_panic(expected = "undeclared fsm state: ajar")]
+    fn test_undeclared_state_panics() {
+        let _ = Fsm::new("closed").transition("closed", "push", "ajar");
+    }
+}
@@ -0,0 +1,267 @@
+//! GenServer-style calls and casts
+//!
+//! OTP's `gen_server` splits an actor's messages into synchronous calls,
+//! which the caller waits on with a timeout, and fire-and-forget casts.
+//! `GenServer` is that contract, and `GenServerBehavior` runs it as a
+//! `Behavior`:
+//!
+//! ```rust,ignore
+//! struct Stack;
+//!
+//! impl GenServer for Stack {
+//!     fn handle_call(&self, _ctx: &mut BehaviorContext, state: &TypedValue, _req: &TypedValue)
+//!         -> Result<CallResult, BehaviorError> {
+//!         Ok(CallResult::reply(top(state), pop(state)))
+//!     }
+//!
+//!     fn handle_cast(&self, _ctx: &mut BehaviorContext, state: &TypedValue, req: &TypedValue)
+//!         -> Result<TypedValue, BehaviorError> {
+//!         Ok(push(state, req))
+//!     }
+//! }
+//!
+//! runtime.register_behavior("stack", GenServerBehavior::new(Stack));
+//! genserver::cast(&runtime, &id, TypedValue::Int(1))?;
+//! let top = genserver::call(&runtime, &id, TypedValue::Bool(true), Duration::from_secs(5))?;
+//! ```
+//!
+//! Calls and casts travel as tagged messages (`CALL_TAG`, `CAST_TAG`);
+//! anything else goes to `handle_info`. Every call answered is journaled
+//! as a `CallReplied` event with its `request` and `reply`, so call
+//! results are part of the actor's history. A call that can't be
+//! answered in one turn returns `CallResult::NoReply` after
+//! `BehaviorContext::defer_reply`, and is answered later with `reply`.
+//!
+//! Rust only for now: the proposed Seq words `gen-call`, `gen-cast` and
+//! `gen-reply` wait on the value bridge (see DESIGN.md, Proposed
+//! Builtins).
+
+use crate::actor::ActorId;
+use crate::behavior::{Behavior, BehaviorContext, BehaviorError};
+use crate::error::RuntimeError;
+use crate::message::TypedMessage;
+use crate::reply::ReplyToken;
+use crate::runtime::ActorRuntime;
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Tag of a call message
+pub const CALL_TAG: &str = "$call";
+
+/// Tag of a cast message
+pub const CAST_TAG: &str = "$cast";
+
+/// Event type journaled for each answered call
+pub const CALL_REPLIED_EVENT: &str = "CallReplied";
+
+/// Outcome of `GenServer::handle_call`
+#[derive(Debug, Clone, PartialEq)]
+pub enum CallResult {
+    /// Answer now, moving to `state`
+    Reply {
+        reply: TypedValue,
+        state: TypedValue,
+    },
+    /// Answer later (after `BehaviorContext::defer_reply`), moving to
+    /// `state`
+    NoReply { state: TypedValue },
+}
+
+impl CallResult {
+    pub fn reply(reply: TypedValue, state: TypedValue) -> Self {
+        CallResult::Reply { reply, state }
+    }
+}
+
+/// Handlers of a gen server
+pub trait GenServer: Send + Sync {
+    /// State of a freshly spawned server
+    fn init(&self) -> TypedValue {
+        TypedValue::Map(BTreeMap::new())
+    }
+
+    /// Answer a call
+    fn handle_call(
+        &self,
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        request: &TypedValue,
+    ) -> Result<CallResult, BehaviorError>;
+
+    /// Handle a cast; the default reports it as a dead letter
+    fn handle_cast(
+        &self,
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        _request: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        ctx.dead_letter();
+        Ok(state.clone())
+    }
+
+    /// Handle any other message; the default reports it as a dead letter
+    fn handle_info(
+        &self,
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        _msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        ctx.dead_letter();
+        Ok(state.clone())
+    }
+}
+
+/// Runs a `GenServer` as a behavior
+pub struct GenServerBehavior<G: GenServer>(G);
+
+impl<G: GenServer> GenServerBehavior<G> {
+    pub fn new(server: G) -> Self {
+        GenServerBehavior(server)
+    }
+}
+
+fn replied_payload(request: Option<&TypedValue>, reply: &TypedValue) -> TypedValue {
+    let mut fields = BTreeMap::new();
+    if let Some(request) = request {
+        fields.insert(TypedMapKey::String("request".to_string()), request.clone());
+    }
+    fields.insert(TypedMapKey::String("reply".to_string()), reply.clone());
+    TypedValue::Map(fields)
+}
+
+impl<G: GenServer> Behavior for GenServerBehavior<G> {
+    fn handle(
+        &self,
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        let message = TypedMessage::try_from(msg).ok();
+        match message.as_ref().map(|m| (m.tag.as_str(), &m.fields)) {
+            Some((CALL_TAG, request)) => match self.0.handle_call(ctx, state, request)? {
+                CallResult::Reply { reply, state } => {
+                    ctx.emit(CALL_REPLIED_EVENT, replied_payload(Some(request), &reply));
+                    ctx.reply(reply);
+                    Ok(state)
+                }
+                CallResult::NoReply { state } => Ok(state),
+            },
+            Some((CAST_TAG, request)) => self.0.handle_cast(ctx, state, request),
+            _ => self.0.handle_info(ctx, state, msg),
+        }
+    }
+
+    fn initial_state(&self) -> TypedValue {
+        self.0.init()
+    }
+}
+
+/// The message a call of `request` travels as
+pub fn call_message(request: TypedValue) -> TypedValue {
+    TypedMessage::new(CALL_TAG, request).into()
+}
+
+/// The message a cast of `request` travels as
+pub fn cast_message(request: TypedValue) -> TypedValue {
+    TypedMessage::new(CAST_TAG, request).into()
+}
+
+/// Call a gen server and wait up to `timeout` for its reply
+///
+/// Fails with `RuntimeError::AskTimeout` if no reply arrives in time, or
+/// with the server's error if it fails handling the call. Someone else
+/// must be running the runtime's scheduler meanwhile.
+pub fn call(
+    runtime: &ActorRuntime,
+    to: &ActorId,
+    request: TypedValue,
+    timeout: Duration,
+) -> Result<TypedValue, RuntimeError> {
+    runtime.ask(to, call_message(request))?.wait(timeout)
+}
+
+/// Cast to a gen server, without waiting
+pub fn cast(runtime: &ActorRuntime, to: &ActorId, request: TypedValue) -> Result<(), RuntimeError> {
+    runtime.send(to, cast_message(request))
+}
+
+/// Answer a call deferred with `CallResult::NoReply`, journaling the
+/// reply like an immediate one
+pub fn reply(ctx: &mut BehaviorContext, token: &ReplyToken, reply: TypedValue) {
+    ctx.emit(CALL_REPLIED_EVENT, replied_payload(None, &reply));
+    ctx.fulfill(token, reply);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::RuntimeConfig;
+    use tempfile::TempDir;
+
+    /// A counter: casts add, a call returns the total and resets it
+    struct Counter;
+
+    impl GenServer for Counter {
+        fn init(&self) -> TypedValue {
+            TypedValue::Int(0)
+        }
+
+        fn handle_call(
+            &self,
+            _ctx: &mut BehaviorContext,
+            state: &TypedValue,
+            _request: &TypedValue,
+        ) -> Result<CallResult, BehaviorError> {
+            Ok(CallResult::reply(state.clone(), TypedValue::Int(0)))
+        }
+
+        fn handle_cast(
+            &self,
+            _ctx: &mut BehaviorContext,
+            state: &TypedValue,
+            request: &TypedValue,
+        ) -> Result<TypedValue, BehaviorError> {
+            match (state, request) {
+                (TypedValue::Int(total), TypedValue::Int(n)) => Ok(TypedValue::Int(total + n)),
+                _ => Err(BehaviorError::new("expected Int")),
+            }
+        }
+    }
+
+    #[test]
+    fn test_calls_are_answered_and_journaled() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            ..RuntimeConfig::default()
+        });
+        runtime.register_behavior("counter", GenServerBehavior::new(Counter));
+        let id = runtime.spawn("counter").unwrap();
+
+        cast(&runtime, &id, TypedValue::Int(2)).unwrap();
+        cast(&runtime, &id, TypedValue::Int(3)).unwrap();
+        let reply = runtime
+            .ask(&id, call_message(TypedValue::Bool(true)))
+            .unwrap();
+        runtime.send(&id, TypedValue::Int(9)).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert_eq!(
+            reply.wait(Duration::from_secs(1)).unwrap(),
+            TypedValue::Int(5)
+        );
+        assert_eq!(runtime.read_state(&id).unwrap(), TypedValue::Int(0));
+
+        runtime.flush_journal().unwrap();
+        let events = runtime.journal().read_events(&id).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, CALL_REPLIED_EVENT);
+        assert_eq!(
+            events[0].payload,
+            replied_payload(Some(&TypedValue::Bool(true)), &TypedValue::Int(5))
+        );
+        assert_eq!(runtime.metrics().dead_letters, 1);
+
+        runtime.unregister_actor(&id);
+    }
+}
@@ -0,0 +1,134 @@
+//! Runtime health reporting
+//!
+//! `ActorRuntime::health()` produces a `HealthReport` suitable for
+//! liveness/readiness probes (e.g. Kubernetes):
+//!
+//! - **Liveness**: no actor is stuck (messages queued but no progress for
+//!   longer than `RuntimeConfig::stuck_actor_threshold`)
+//! - **Readiness**: live, and the journal directory is writable
+
+use crate::actor::ActorId;
+use crate::serialize::{TypedMapKey, TypedValue};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Health of a single actor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActorHealth {
+    pub id: ActorId,
+    /// Behavior name
+    pub behavior: String,
+    /// Still accepting messages
+    pub running: bool,
+    /// Messages waiting to be handled
+    pub mailbox_depth: usize,
+    /// Unix timestamp (milliseconds) the last message finished, if any
+    pub last_processed: Option<u64>,
+    /// Messages are queued but none has been handled within the threshold
+    pub stuck: bool,
+    /// State size in bytes when last measured (see `crate::state_size`)
+    pub state_size: Option<usize>,
+    /// Messages handled since the actor was spawned
+    pub messages: MessageCounts,
+}
+
+/// Everything a debug console shows about one actor
+/// (`ActorRuntime::dump_actor`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActorDump {
+    pub id: ActorId,
+    pub behavior: String,
+    pub state: TypedValue,
+    /// Messages waiting to be handled
+    pub mailbox_depth: usize,
+    /// Sequence number of the actor's next event
+    pub seq: u64,
+    /// Time since the actor was spawned (or recovered) in this runtime
+    pub uptime: Duration,
+    /// Times the actor was restarted after a panic
+    pub restarts: u32,
+}
+
+impl ActorDump {
+    /// The dump as a Map with keys `state`, `mailbox_depth`, `behavior`,
+    /// `seq`, `uptime_ms` and `restarts`
+    pub fn to_value(&self) -> TypedValue {
+        let key = |name: &str| TypedMapKey::String(name.to_string());
+        TypedValue::Map(BTreeMap::from([
+            (key("state"), self.state.clone()),
+            (
+                key("mailbox_depth"),
+                TypedValue::Int(self.mailbox_depth as i64),
+            ),
+            (key("behavior"), TypedValue::String(self.behavior.clone())),
+            (key("seq"), TypedValue::Int(self.seq as i64)),
+            (
+                key("uptime_ms"),
+                TypedValue::Int(self.uptime.as_millis() as i64),
+            ),
+            (key("restarts"), TypedValue::Int(self.restarts as i64)),
+        ]))
+    }
+}
+
+/// Messages an actor has handled, and how many of those failed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageCounts {
+    pub handled: u64,
+    /// Handlings that returned an error or panicked
+    pub failed: u64,
+}
+
+impl MessageCounts {
+    /// Share of handlings that failed (0 if none were handled)
+    pub fn failure_rate(&self) -> f64 {
+        if self.handled == 0 {
+            return 0.0;
+        }
+        self.failed as f64 / self.handled as f64
+    }
+
+    /// Counts accumulated since `earlier` was taken
+    pub fn since(&self, earlier: &MessageCounts) -> MessageCounts {
+        MessageCounts {
+            handled: self.handled.saturating_sub(earlier.handled),
+            failed: self.failed.saturating_sub(earlier.failed),
+        }
+    }
+}
+
+/// Status of the journal's storage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalHealth {
+    /// A probe file could be written and removed under the journal path
+    pub writable: bool,
+    /// Error from the write probe, if it failed
+    pub error: Option<String>,
+}
+
+/// Point-in-time health of the whole runtime
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthReport {
+    /// Unix timestamp (milliseconds) the report was taken
+    pub ts: u64,
+    pub actors: Vec<ActorHealth>,
+    pub journal: JournalHealth,
+}
+
+impl HealthReport {
+    /// Actors currently considered stuck
+    pub fn stuck_actors(&self) -> impl Iterator<Item = &ActorHealth> {
+        self.actors.iter().filter(|a| a.stuck)
+    }
+
+    /// Liveness: no actor is stuck
+    pub fn is_live(&self) -> bool {
+        self.stuck_actors().next().is_none()
+    }
+
+    /// Readiness: live and able to journal
+    pub fn is_ready(&self) -> bool {
+        self.is_live() && self.journal.writable
+    }
+}
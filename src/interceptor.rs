@@ -0,0 +1,203 @@
+//! Behavior interceptors
+//!
+//! Interceptors wrap message handling for every actor running a given
+//! behavior. They are registered per behavior name on the runtime with
+//! `ActorRuntime::add_interceptor`. Each one can:
+//!
+//! - rewrite the message before the behavior sees it,
+//! - reject it (a `BehaviorError`: consumed, state unchanged),
+//! - annotate the journal by emitting events through the context,
+//! - observe the outcome afterwards (for metrics or auditing).
+//!
+//! `before` hooks run in registration order; `after` hooks run in reverse,
+//! and only for interceptors whose `before` passed the message on.
+//!
+//! ```rust,ignore
+//! runtime.add_interceptor("account", |_ctx: &mut BehaviorContext, msg: TypedValue| {
+//!     match msg {
+//!         TypedValue::Int(n) if n < 0 => Err(BehaviorError::new("negative amount")),
+//!         msg => Ok(msg),
+//!     }
+//! });
+//! ```
+//!
+//! TODO: Accept Seq quotations as interceptors once quotation invocation
+//! is wired through the FFI layer.
+
+use crate::behavior::{Behavior, BehaviorContext, BehaviorError};
+use crate::journal::Event;
+use crate::serialize::TypedValue;
+use std::sync::Arc;
+
+/// Hooks around a behavior's message handling
+pub trait Interceptor: Send + Sync {
+    /// Pass the message on (possibly rewritten), or reject it
+    fn before(
+        &self,
+        _ctx: &mut BehaviorContext,
+        msg: TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        Ok(msg)
+    }
+
+    /// See the message the behavior was given and what it returned
+    fn after(
+        &self,
+        _ctx: &mut BehaviorContext,
+        _msg: &TypedValue,
+        _outcome: &Result<TypedValue, BehaviorError>,
+    ) {
+    }
+}
+
+/// Closures act as `before` hooks
+impl<F> Interceptor for F
+where
+    F: Fn(&mut BehaviorContext, TypedValue) -> Result<TypedValue, BehaviorError> + Send + Sync,
+{
+    fn before(
+        &self,
+        ctx: &mut BehaviorContext,
+        msg: TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        self(ctx, msg)
+    }
+}
+
+/// A behavior wrapped in its interceptor chain
+pub(crate) struct Intercepted {
+    pub(crate) inner: Arc<dyn Behavior>,
+    pub(crate) chain: Vec<Arc<dyn Interceptor>>,
+}
+
+impl Behavior for Intercepted {
+    fn handle(
+        &self,
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        let mut msg = msg.clone();
+        let mut passed = 0;
+        let mut outcome = None;
+        for interceptor in &self.chain {
+            match interceptor.before(ctx, msg.clone()) {
+                Ok(next) => {
+                    msg = next;
+                    passed += 1;
+                }
+                Err(e) => {
+                    outcome = Some(Err(e));
+                    break;
+                }
+            }
+        }
+
+        let outcome = outcome.unwrap_or_else(|| self.inner.handle(ctx, state, &msg));
+        for interceptor in self.chain[..passed].iter().rev() {
+            interceptor.after(ctx, &msg, &outcome);
+        }
+        outcome
+    }
+
+    fn initial_state(&self) -> TypedValue {
+        self.inner.initial_state()
+    }
+
+    fn apply_event(&self, state: &TypedValue, event: &Event) -> TypedValue {
+        self.inner.apply_event(state, event)
+    }
+
+    fn on_stop(&self, ctx: &mut BehaviorContext, state: &TypedValue) {
+        self.inner.on_stop(ctx, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testkit::BehaviorHarness;
+    use std::sync::Mutex;
+
+    fn adder(
+        _ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        match (state, msg) {
+            (TypedValue::Int(total), TypedValue::Int(n)) => Ok(TypedValue::Int(total + n)),
+            _ => Err(BehaviorError::new("expected Int")),
+        }
+    }
+
+    /// Records the order hooks run in
+    struct Trace(&'static str, Arc<Mutex<Vec<String>>>);
+
+    impl Interceptor for Trace {
+        fn before(
+            &self,
+            _ctx: &mut BehaviorContext,
+            msg: TypedValue,
+        ) -> Result<TypedValue, BehaviorError> {
+            self.1.lock().unwrap().push(format!("before {}", self.0));
+            Ok(msg)
+        }
+
+        fn after(
+            &self,
+            _ctx: &mut BehaviorContext,
+            _msg: &TypedValue,
+            outcome: &Result<TypedValue, BehaviorError>,
+        ) {
+            let ok = outcome.is_ok();
+            self.1
+                .lock()
+                .unwrap()
+                .push(format!("after {} {}", self.0, ok));
+        }
+    }
+
+    #[test]
+    fn test_chain_rewrites_rejects_and_annotates() {
+        let trace = Arc::new(Mutex::new(vec![]));
+        let reject_negative = |ctx: &mut BehaviorContext, msg: TypedValue| match msg {
+            TypedValue::Int(n) if n < 0 => Err(BehaviorError::new("negative amount")),
+            msg => {
+                ctx.emit("Validated", msg.clone());
+                Ok(msg)
+            }
+        };
+        let double = |_: &mut BehaviorContext, msg: TypedValue| match msg {
+            TypedValue::Int(n) => Ok(TypedValue::Int(n * 2)),
+            msg => Ok(msg),
+        };
+        let intercepted = Intercepted {
+            inner: Arc::new(adder),
+            chain: vec![
+                Arc::new(Trace("outer", trace.clone())),
+                Arc::new(reject_negative),
+                Arc::new(double),
+            ],
+        };
+
+        let mut harness = BehaviorHarness::new(intercepted).with_state(TypedValue::Int(0));
+        let events = harness.send(TypedValue::Int(5)).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, "Validated");
+        assert_eq!(harness.state(), &TypedValue::Int(10));
+
+        let err = harness.send(TypedValue::Int(-1)).unwrap_err();
+        assert_eq!(err, BehaviorError::new("negative amount"));
+        assert_eq!(harness.state(), &TypedValue::Int(10));
+
+        assert_eq!(
+            *trace.lock().unwrap(),
+            [
+                "before outer",
+                "after outer true",
+                "before outer",
+                "after outer false"
+            ]
+        );
+    }
+}
@@ -7,33 +7,224 @@
 //!
 //! # Storage Format
 //!
-//! Events are stored as length-prefixed bincode records:
+//! Journal and snapshot files start with an 8-byte `FileHeader`, followed
+//! by the body. In a journal the body is length-prefixed bincode records:
 //! ```text
+//! [4 bytes: magic "SQJL"][1: format version][1: codec][1: flags][1: reserved]
 //! [4 bytes: length][bincode event data]
 //! [4 bytes: length][bincode event data]
 //! ...
 //! ```
 //!
+//! Files written before format version 1 (by 0.1.0) have no header
+//! (version 0), and their events and snapshots have neither an actor ID
+//! nor attachments. They are still read, and appends to an existing
+//! headerless journal stay headerless and in that layout.
+//!
+//! From version 2, the top bit of a length prefix (`CONTINUES`) marks a
+//! record written together with the next one, as the events of one message
+//! handling are. Readers drop a group whose last record is missing, so a
+//! torn write never keeps part of it. Appends to version 0 and 1 journals
+//! don't mark groups until the journal is rewritten by `compact` or
+//! `repair`.
+//!
 //! This format is:
 //! - Fast to read/write (no parsing overhead)
 //! - Compact (binary encoding)
 //! - Streamable (can read events one at a time)
 //!
+//! # Attachments
+//!
+//! Large binary data (files, images) is stored once in a content-addressed
+//! blob store under `{base_path}/blobs/` and referenced from events by
+//! `BlobId`. `Journal::collect_blobs` removes blobs no event or checkpoint
+//! references. A blob is stored before the event that references it is
+//! appended, so blobs stored (or stored again) within the last
+//! `DEFAULT_BLOB_GRACE` are kept regardless (see `Journal::with_blob_grace`).
+//!
+//! # Aliases
+//!
+//! `Journal::set_alias` redirects an actor ID to another (after the actor
+//! was migrated or replaced) by writing `{base_path}/{old_id}/alias`. Reads
+//! addressed to the old ID then return the new actor's history, and the
+//! runtime routes messages for it to the new actor. Writes always go to
+//! the addressed actor's own files, so an old actor still running can't
+//! write into the new one's journal. Aliases are cached once read; set
+//! them through the journal that reads them.
+//!
+//! # Singletons
+//!
+//! `{base_path}/singletons/{name}` records the ID of the actor
+//! `ActorRuntime::spawn_singleton` created under `name`, so a restarted
+//! runtime brings the singleton back with the same ID and state.
+//!
+//! # Quotas
+//!
+//! `Journal::with_quota` caps disk use (see `quota`). `Journal::compact`
+//! drops events an actor's latest snapshot already covers.
+//!
+//! # Subscriptions
+//!
+//! `Journal::subscribe_persistent` reads new events from every actor for
+//! an external consumer, checkpointing what it acknowledged under
+//! `{base_path}/subscriptions/` (see `subscription`).
+//!
+//! # Crash Dumps
+//!
+//! When a behavior panics the runtime writes a text dump next to the
+//! actor's journal (`crash-{ts}-{id}.txt`, see `crate::crash`).
+//!
+//! # Archives
+//!
+//! `Journal::archive` exports old events as a checksummed segment for cold
+//! storage, and `Journal::read_archive` reads one back (see `archive`).
+//! With feature `object-store`, `object_store::ObjectStoreBackend` keeps
+//! snapshots and archives in S3 or another object store.
+//!
+//! # Verification
+//!
+//! `Journal::verify` checks an actor's files for damage (see `verify`);
+//! the `seq-journal verify` command runs it from the shell.
+//! `Journal::repair` fixes out-of-order and duplicate sequence numbers
+//! (see `repair`).
+//!
+//! # Corrections
+//!
+//! `Journal::append_correction` fixes bad data without rewriting history:
+//! it appends a record that replaces an earlier event when the journal is
+//! replayed (see `correction`).
+//!
+//! # Decode Limits
+//!
+//! Reads are bounded by `DecodeLimits` (record size, nesting depth and
+//! collection length), so corrupted or untrusted files fail with a typed
+//! error rather than an unbounded allocation (see `limits`).
+//!
+//! # Lazy Snapshots
+//!
+//! `Journal::with_lazy_snapshots` writes Map states one entry at a time,
+//! so `Journal::load_snapshot_lazy` can decode entries on access; full
+//! loads, recovery among them, still decode every entry (see `lazy`).
+//!
+//! # In Memory
+//!
+//! `Journal::in_memory` keeps everything in process memory instead, for
+//! tests and ephemeral runtimes (see `memory`).
+//!
+//! # Compatibility
+//!
+//! Journals outlive the code that wrote them. `fixtures` writes golden
+//! journals that later versions are tested against.
+//!
 //! # Debugging
 //!
 //! Use `Event::to_debug_string()` or the journal inspection utilities
 //! for human-readable output when debugging.
 
+pub mod archive;
+pub mod checkpoint;
+pub mod correction;
+pub mod fixtures;
+pub mod lazy;
+pub mod limits;
+pub mod memory;
+#[cfg(feature = "object-store")]
+pub mod object_store;
+pub mod quota;
+pub mod repair;
+pub mod rollout;
+pub mod stats;
+pub mod subscription;
+pub mod verify;
+pub mod writer;
+
 use crate::actor::ActorId;
 use crate::serialize::TypedValue;
+use lazy::LazySnapshot;
+use limits::DecodeLimits;
+use memory::MemoryStore;
+use quota::{Quota, QuotaAction, QuotaExceeded, QuotaScope};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File, OpenOptions};
-use std::io::{BufReader, BufWriter, Read, Write};
-use std::path::PathBuf;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+/// File in an actor's directory naming the actor it is an alias for
+const ALIAS_FILE: &str = "alias";
+
+/// An actor's event file, in its directory
+const JOURNAL_FILE: &str = "journal.bin";
+
+/// An actor's snapshot file, in its directory
+const SNAPSHOT_FILE: &str = "snapshot.bin";
+
+/// Directory under the base path holding singleton records
+const SINGLETONS_DIR: &str = "singletons";
+
+/// Longest alias chain followed before giving up
+const MAX_ALIAS_HOPS: usize = 16;
+
+/// How long `collect_blobs` keeps a freshly stored blob nothing references
+/// yet, so an append on its way isn't left pointing at nothing
+pub const DEFAULT_BLOB_GRACE: Duration = Duration::from_secs(60 * 60);
+
+/// Largest record the journal will write or read (64 MiB)
+///
+/// A length prefix above this is treated as corruption rather than an
+/// allocation request, so a damaged journal can't exhaust memory.
+pub const MAX_RECORD_LEN: usize = 64 * 1024 * 1024;
+
+/// Content address of a blob (lowercase hex SHA-256)
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BlobId(String);
+
+impl BlobId {
+    /// Compute the ID for some content
+    pub fn of(data: &[u8]) -> Self {
+        let digest = Sha256::digest(data);
+        BlobId(digest.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    /// Get the hex digest
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for BlobId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::str::FromStr for BlobId {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() == 64
+            && s.bytes()
+                .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+        {
+            Ok(BlobId(s.to_string()))
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid blob id: {}", s),
+            ))
+        }
+    }
+}
 
 /// A persisted event
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Event {
+    /// Actor this event belongs to (stamped by the journal on append)
+    pub actor_id: Option<ActorId>,
+
     /// Sequence number within this actor's journal
     pub seq: u64,
 
@@ -45,6 +236,9 @@ pub struct Event {
 
     /// Unix timestamp (milliseconds)
     pub ts: u64,
+
+    /// Blobs this event references (kept alive by `collect_blobs`)
+    pub attachments: Vec<BlobId>,
 }
 
 impl Event {
@@ -55,25 +249,76 @@ impl Event {
             .unwrap_or(0);
 
         Event {
+            actor_id: None,
             seq,
             event_type,
             payload,
             ts,
+            attachments: vec![],
         }
     }
 
+    /// Reference a blob from this event
+    pub fn with_attachment(mut self, blob: BlobId) -> Self {
+        self.attachments.push(blob);
+        self
+    }
+
     /// Serialize to binary format
     pub fn to_bytes(&self) -> Result<Vec<u8>, std::io::Error> {
         bincode::serialize(self)
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
     }
 
-    /// Deserialize from binary format
+    /// Deserialize from binary format, within the default decode limits
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, std::io::Error> {
-        bincode::deserialize(bytes)
+        Self::from_bytes_within(bytes, &DecodeLimits::default())
+    }
+
+    /// Deserialize from binary format, within `limits`
+    pub fn from_bytes_within(bytes: &[u8], limits: &DecodeLimits) -> std::io::Result<Self> {
+        limits.decode(bytes, |event: &Event| &event.payload)
+    }
+
+    /// Encode as a record of a journal in format `version`
+    ///
+    /// Version 0 records leave out the actor ID, and can't hold
+    /// attachments.
+    pub(crate) fn to_record(&self, version: u8) -> std::io::Result<Vec<u8>> {
+        if version > 0 {
+            return self.to_bytes();
+        }
+        if !self.attachments.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "a headerless journal can't hold attachments; compact it first",
+            ));
+        }
+        bincode::serialize(&(self.seq, &self.event_type, &self.payload, self.ts))
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
     }
 
+    /// Decode a record of a journal in format `version`, within `limits`
+    pub(crate) fn from_record(
+        bytes: &[u8],
+        version: u8,
+        limits: &DecodeLimits,
+    ) -> std::io::Result<Self> {
+        if version > 0 {
+            return Self::from_bytes_within(bytes, limits);
+        }
+        let (seq, event_type, payload, ts) =
+            limits.decode(bytes, |record: &(u64, String, TypedValue, u64)| &record.2)?;
+        Ok(Event {
+            actor_id: None,
+            seq,
+            event_type,
+            payload,
+            ts,
+            attachments: vec![],
+        })
+    }
+
     /// Human-readable debug representation
     pub fn to_debug_string(&self) -> String {
         format!(
@@ -87,9 +332,15 @@ impl Event {
 }
 
 /// A snapshot of actor state at a point in time
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Snapshot {
+    /// Actor this snapshot belongs to (stamped by the journal on save)
+    pub actor_id: Option<ActorId>,
+
     /// Sequence number this snapshot was taken at
+    ///
+    /// This is the actor's next sequence number: events with
+    /// `seq >= self.seq` happened after the snapshot.
     pub seq: u64,
 
     /// The actor's state at this point
@@ -106,18 +357,335 @@ impl Snapshot {
             .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
     }
 
-    /// Deserialize from binary format
+    /// Deserialize from binary format, within the default decode limits
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, std::io::Error> {
-        bincode::deserialize(bytes)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+        Self::from_bytes_within(bytes, &DecodeLimits::default())
+    }
+
+    /// Deserialize from binary format, within `limits`
+    pub fn from_bytes_within(bytes: &[u8], limits: &DecodeLimits) -> std::io::Result<Self> {
+        limits.decode(bytes, |snapshot: &Snapshot| &snapshot.state)
+    }
+
+    /// Deserialize a headerless (version 0) snapshot, which has no actor
+    /// ID, within `limits`
+    fn from_legacy_bytes_within(bytes: &[u8], limits: &DecodeLimits) -> std::io::Result<Self> {
+        let (seq, state, ts) = limits.decode(bytes, |record: &(u64, TypedValue, u64)| &record.1)?;
+        Ok(Snapshot {
+            actor_id: None,
+            seq,
+            state,
+            ts,
+        })
+    }
+}
+
+/// Current on-disk format version
+pub const FORMAT_VERSION: u8 = 2;
+
+/// First format version whose journals mark records written together
+const GROUPED_VERSION: u8 = 2;
+
+/// Length-prefix bit set on a journal record written together with the
+/// next one (see the module docs)
+pub(crate) const CONTINUES: u32 = 1 << 31;
+
+/// Magic bytes opening a journal file
+pub const JOURNAL_MAGIC: [u8; 4] = *b"SQJL";
+
+/// Magic bytes opening a snapshot file
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"SQSN";
+
+/// Length of an encoded `FileHeader`
+pub const HEADER_LEN: usize = 8;
+
+/// Header flag: body is compressed (reserved; not yet supported)
+pub const FLAG_COMPRESSED: u8 = 0b0000_0001;
+
+/// Encoding of records in a journal or snapshot body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Bincode,
+    /// Snapshots only: bincode, one record per top-level entry of a Map
+    /// state (see `lazy`)
+    Keyed,
+}
+
+impl Codec {
+    fn to_u8(self) -> u8 {
+        match self {
+            Codec::Bincode => 0,
+            Codec::Keyed => 1,
+        }
+    }
+
+    fn from_u8(b: u8) -> std::io::Result<Self> {
+        match b {
+            0 => Ok(Codec::Bincode),
+            1 => Ok(Codec::Keyed),
+            _ => Err(unsupported(format!("unknown codec {}", b))),
+        }
+    }
+}
+
+fn unsupported(msg: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Unsupported, msg)
+}
+
+/// Format header at the start of journal and snapshot files
+///
+/// Encoded as `[4: magic][1: version][1: codec][1: flags][1: reserved]`.
+/// A legacy journal can never start with the magic bytes: as a length
+/// prefix they exceed `MAX_RECORD_LEN`. A legacy snapshot starts with its
+/// seq, so only one taken at a seq whose low bytes spell the magic (over
+/// a billion) would be mistaken for a headered file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileHeader {
+    pub version: u8,
+    pub codec: Codec,
+    pub flags: u8,
+}
+
+impl FileHeader {
+    /// Header for newly written files
+    pub fn current() -> Self {
+        FileHeader {
+            version: FORMAT_VERSION,
+            codec: Codec::Bincode,
+            flags: 0,
+        }
+    }
+
+    /// Stand-in header for files written before headers existed
+    pub fn legacy() -> Self {
+        FileHeader {
+            version: 0,
+            codec: Codec::Bincode,
+            flags: 0,
+        }
+    }
+
+    fn to_bytes(self, magic: [u8; 4]) -> [u8; HEADER_LEN] {
+        let mut bytes = [0u8; HEADER_LEN];
+        bytes[..4].copy_from_slice(&magic);
+        bytes[4] = self.version;
+        bytes[5] = self.codec.to_u8();
+        bytes[6] = self.flags;
+        bytes
+    }
+
+    /// Parse the header after its magic bytes, rejecting formats this
+    /// version can't read
+    fn parse(rest: &[u8]) -> std::io::Result<Self> {
+        let header = FileHeader {
+            version: rest[0],
+            codec: Codec::from_u8(rest[1])?,
+            flags: rest[2],
+        };
+        if header.version == 0 || header.version > FORMAT_VERSION {
+            return Err(unsupported(format!(
+                "unsupported format version {}",
+                header.version
+            )));
+        }
+        if header.flags & FLAG_COMPRESSED != 0 {
+            return Err(unsupported(
+                "compressed files are not supported".to_string(),
+            ));
+        }
+        Ok(header)
+    }
+
+    /// Read a header from the start of a stream
+    ///
+    /// Returns the legacy header if the stream doesn't open with `magic`,
+    /// along with the bytes consumed while looking, which belong to the body.
+    fn read<R: Read>(reader: &mut R, magic: [u8; 4]) -> std::io::Result<(Self, Vec<u8>)> {
+        let mut prefix = Vec::with_capacity(4);
+        reader.by_ref().take(4).read_to_end(&mut prefix)?;
+        if prefix != magic {
+            return Ok((FileHeader::legacy(), prefix));
+        }
+
+        let mut rest = [0u8; HEADER_LEN - 4];
+        reader.read_exact(&mut rest)?;
+        Ok((FileHeader::parse(&rest)?, vec![]))
+    }
+}
+
+/// Write one length-prefixed record
+///
+/// Format: [4-byte little-endian length][data]
+pub(crate) fn write_frame<W: Write>(writer: &mut W, data: &[u8]) -> std::io::Result<()> {
+    write_record(writer, data, false)
+}
+
+/// Write one journal record, marked if the next belongs to the same write
+fn write_record<W: Write>(writer: &mut W, data: &[u8], continues: bool) -> std::io::Result<()> {
+    let mut len = data.len() as u32;
+    if continues {
+        len |= CONTINUES;
+    }
+
+    // Write length prefix (little-endian)
+    writer.write_all(&len.to_le_bytes())?;
+    // Write record data
+    writer.write_all(data)
+}
+
+/// Read one length-prefixed record, or `None` at end of input
+pub(crate) fn read_frame<R: Read>(reader: &mut R) -> std::io::Result<Option<Vec<u8>>> {
+    Ok(read_prefixed(reader, 0)?.map(|(data, _)| data))
+}
+
+/// Read one journal record, along with whether the next belongs to the
+/// same write
+fn read_record<R: Read>(reader: &mut R) -> std::io::Result<Option<(Vec<u8>, bool)>> {
+    read_prefixed(reader, CONTINUES)
+}
+
+/// Read one length-prefixed record whose prefix may carry `flags`,
+/// returning whether any were set
+fn read_prefixed<R: Read>(reader: &mut R, flags: u32) -> std::io::Result<Option<(Vec<u8>, bool)>> {
+    let mut len_buf = [0u8; 4];
+
+    // Read length prefix
+    match reader.read_exact(&mut len_buf) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let prefix = u32::from_le_bytes(len_buf);
+    let len = (prefix & !flags) as usize;
+    if len > MAX_RECORD_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("record length {} exceeds max record length", len),
+        ));
+    }
+
+    // Read record data
+    let mut data = vec![0u8; len];
+    reader.read_exact(&mut data)?;
+    Ok(Some((data, prefix & flags != 0)))
+}
+
+/// Decode event records to the end of `reader`, returning them with the
+/// number of bytes they took
+///
+/// A group of records written together (see `CONTINUES`) whose last
+/// record is missing is left out, along with its bytes.
+fn decode_records<R: Read>(
+    mut reader: R,
+    version: u8,
+    limits: &DecodeLimits,
+) -> std::io::Result<(Vec<Event>, u64)> {
+    let mut events = vec![];
+    let mut read = 0;
+    // Records of a group not seen to its end yet
+    let mut group = vec![];
+    let mut group_len = 0;
+    while let Some((data, continues)) = read_record(&mut reader)? {
+        group_len += 4 + data.len() as u64;
+        group.push(Event::from_record(&data, version, limits)?);
+        if !continues {
+            events.append(&mut group);
+            read += std::mem::take(&mut group_len);
+        }
+    }
+    Ok((events, read))
+}
+
+/// Where reading an actor's journal file stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ReadOffset {
+    file: FileId,
+    /// Format version of the file, from its header
+    version: u8,
+    /// Byte offset of the first record not read yet
+    offset: u64,
+}
+
+/// Identity of a file, telling a rewritten journal from the one an
+/// offset was taken in (a rewrite renames a new file into place)
+///
+/// The creation time guards against a later rewrite reusing the inode.
+type FileId = (u64, u64, Option<SystemTime>);
+
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino(), meta.created().ok()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &fs::Metadata) -> Option<FileId> {
+    None
+}
+
+/// Total size of the files under `path` (0 if it doesn't exist)
+fn dir_size(path: &std::path::Path) -> std::io::Result<u64> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut total = 0;
+    for entry in entries {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        total += if meta.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            meta.len()
+        };
+    }
+    Ok(total)
+}
+
+/// Refuse an event or snapshot stamped for another actor than the one
+/// it is being stored for
+fn check_owner(actor_id: &ActorId, owner: Option<&ActorId>) -> std::io::Result<()> {
+    match owner {
+        Some(owner) if owner != actor_id => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("record of actor {owner} stored for actor {actor_id}"),
+        )),
+        _ => Ok(()),
     }
 }
 
+/// Append locks of every journal in the process, by actor directory
+fn append_locks() -> &'static Mutex<HashMap<PathBuf, Arc<Mutex<()>>>> {
+    static LOCKS: OnceLock<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>> = OnceLock::new();
+    LOCKS.get_or_init(Mutex::default)
+}
+
 /// File-based event journal
 ///
 /// Stores events in `{base_path}/{actor_id}/journal.bin`
+///
+/// Appends for one actor are serialized through a per-actor lock, so
+/// concurrent `append` calls never interleave records, even through two
+/// `Journal`s opened on the same path. Other processes writing the same
+/// journal are not coordinated with.
 pub struct Journal {
     base_path: PathBuf,
+    /// `base_path` canonicalized, once it exists (keys the append locks)
+    canonical_base: OnceLock<PathBuf>,
+    quota: Option<Quota>,
+    /// Bytes under the base path, once measured
+    usage: Mutex<Option<u64>>,
+    /// Set for an in-memory journal, which has no files
+    memory: Option<MemoryStore>,
+    decode_limits: DecodeLimits,
+    /// Write Map snapshots in the keyed layout (see `lazy`)
+    lazy_snapshots: bool,
+    /// How long unreferenced blobs survive `collect_blobs` after being stored
+    blob_grace: Duration,
+    /// Aliases read so far (`None` for an actor without one)
+    aliases: RwLock<HashMap<ActorId, Option<ActorId>>>,
 }
 
 impl Journal {
@@ -125,84 +693,615 @@ impl Journal {
     pub fn new(base_path: impl Into<PathBuf>) -> Self {
         Journal {
             base_path: base_path.into(),
+            canonical_base: OnceLock::new(),
+            quota: None,
+            usage: Mutex::new(None),
+            memory: None,
+            decode_limits: DecodeLimits::default(),
+            lazy_snapshots: false,
+            blob_grace: DEFAULT_BLOB_GRACE,
+            aliases: RwLock::new(HashMap::new()),
         }
     }
 
-    /// Get the journal directory for an actor
-    fn actor_dir(&self, actor_id: &ActorId) -> PathBuf {
+    /// Create a journal kept in memory, which never touches the
+    /// filesystem (see `memory`)
+    pub fn in_memory() -> Self {
+        Journal {
+            memory: Some(MemoryStore::default()),
+            ..Journal::new(PathBuf::new())
+        }
+    }
+
+    pub fn is_in_memory(&self) -> bool {
+        self.memory.is_some()
+    }
+
+    /// Fail with `Unsupported` if this journal is in memory
+    fn require_files(&self, operation: &str) -> std::io::Result<()> {
+        match self.memory {
+            Some(_) => Err(memory::unsupported(operation)),
+            None => Ok(()),
+        }
+    }
+
+    /// Enforce a disk quota on appends
+    pub fn with_quota(mut self, quota: Quota) -> Self {
+        self.quota = Some(quota);
+        self
+    }
+
+    /// Bound what reading events, snapshots and offloaded messages may
+    /// decode (see `limits`)
+    pub fn with_decode_limits(mut self, limits: DecodeLimits) -> Self {
+        self.decode_limits = limits;
+        self
+    }
+
+    /// Write snapshots of Map states so their entries can be decoded
+    /// one at a time (see `lazy`)
+    pub fn with_lazy_snapshots(mut self, lazy: bool) -> Self {
+        self.lazy_snapshots = lazy;
+        self
+    }
+
+    /// Keep unreferenced blobs stored within `grace` out of
+    /// `collect_blobs` (`DEFAULT_BLOB_GRACE` unless set)
+    pub fn with_blob_grace(mut self, grace: Duration) -> Self {
+        self.blob_grace = grace;
+        self
+    }
+
+    /// Get an actor's own directory, ignoring aliases
+    fn raw_dir(&self, actor_id: &ActorId) -> PathBuf {
         self.base_path.join(actor_id.as_str())
     }
 
-    /// Get the journal file path for an actor
+    /// Get the journal directory for an actor (following aliases)
+    fn actor_dir(&self, actor_id: &ActorId) -> PathBuf {
+        self.raw_dir(&self.resolve_alias(actor_id))
+    }
+
+    /// Redirect `old` to `new`: reads for `old` use `new`'s journal and
+    /// snapshot from now on
+    ///
+    /// `old`'s own files are left in place, and still written by appends
+    /// addressed to `old` (see `remove_alias`). Fails with `InvalidInput`
+    /// if the alias would form a cycle.
+    pub fn set_alias(&self, old: &ActorId, new: &ActorId) -> std::io::Result<()> {
+        if self.resolve_alias(new) == *old {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("aliasing {} to {} would form a cycle", old, new),
+            ));
+        }
+        if let Some(memory) = &self.memory {
+            memory.lock().aliases.insert(old.clone(), new.clone());
+            return Ok(());
+        }
+        let dir = self.raw_dir(old);
+        fs::create_dir_all(&dir)?;
+        // Write then rename so readers never see a partial ID
+        let tmp = dir.join(format!("{}.tmp", ALIAS_FILE));
+        fs::write(&tmp, new.to_string())?;
+        fs::rename(tmp, dir.join(ALIAS_FILE))?;
+        self.cache_alias(old, Some(new.clone()));
+        Ok(())
+    }
+
+    fn cache_alias(&self, id: &ActorId, target: Option<ActorId>) {
+        self.aliases
+            .write()
+            .expect("alias cache lock poisoned")
+            .insert(id.clone(), target);
+    }
+
+    /// Remove `old`'s alias, returning whether it had one
+    pub fn remove_alias(&self, old: &ActorId) -> std::io::Result<bool> {
+        if let Some(memory) = &self.memory {
+            return Ok(memory.lock().aliases.remove(old).is_some());
+        }
+        let removed = match fs::remove_file(self.raw_dir(old).join(ALIAS_FILE)) {
+            Ok(()) => true,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+            Err(e) => return Err(e),
+        };
+        self.cache_alias(old, None);
+        Ok(removed)
+    }
+
+    /// The actor `id` is directly aliased to, if any
+    ///
+    /// An unreadable alias file counts as no alias.
+    pub fn alias_of(&self, id: &ActorId) -> Option<ActorId> {
+        if let Some(memory) = &self.memory {
+            return memory.lock().aliases.get(id).cloned();
+        }
+        let cached = self
+            .aliases
+            .read()
+            .expect("alias cache lock poisoned")
+            .get(id)
+            .cloned();
+        if let Some(target) = cached {
+            return target;
+        }
+        let target = fs::read_to_string(self.raw_dir(id).join(ALIAS_FILE))
+            .ok()
+            .and_then(|target| target.trim().parse().ok());
+        self.cache_alias(id, target.clone());
+        target
+    }
+
+    /// Follow aliases from `id` to the actor that holds its journal
+    ///
+    /// Returns `id` itself if it has no alias.
+    pub fn resolve_alias(&self, id: &ActorId) -> ActorId {
+        let mut current = id.clone();
+        for _ in 0..MAX_ALIAS_HOPS {
+            match self.alias_of(&current) {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        current
+    }
+
+    /// The actor recorded as singleton `name`, if any
+    ///
+    /// An unreadable record counts as none.
+    pub fn singleton(&self, name: &str) -> Option<ActorId> {
+        check_name("singleton", name).ok()?;
+        if let Some(memory) = &self.memory {
+            return memory.lock().singletons.get(name).cloned();
+        }
+        fs::read_to_string(self.base_path.join(SINGLETONS_DIR).join(name))
+            .ok()
+            .and_then(|id| id.trim().parse().ok())
+    }
+
+    /// Record `id` as singleton `name`
+    ///
+    /// Fails with `InvalidInput` if `name` isn't a plain file name.
+    pub fn set_singleton(&self, name: &str, id: &ActorId) -> std::io::Result<()> {
+        check_name("singleton", name)?;
+        if let Some(memory) = &self.memory {
+            memory
+                .lock()
+                .singletons
+                .insert(name.to_string(), id.clone());
+            return Ok(());
+        }
+        let dir = self.base_path.join(SINGLETONS_DIR);
+        fs::create_dir_all(&dir)?;
+        let tmp = dir.join(format!("{}.tmp", name));
+        fs::write(&tmp, id.to_string())?;
+        fs::rename(tmp, dir.join(name))
+    }
+
+    /// Get the journal file path for an actor (following aliases)
     fn journal_path(&self, actor_id: &ActorId) -> PathBuf {
-        self.actor_dir(actor_id).join("journal.bin")
+        self.actor_dir(actor_id).join(JOURNAL_FILE)
     }
 
-    /// Get the snapshot file path for an actor
+    /// Get the snapshot file path for an actor (following aliases)
     fn snapshot_path(&self, actor_id: &ActorId) -> PathBuf {
-        self.actor_dir(actor_id).join("snapshot.bin")
+        self.actor_dir(actor_id).join(SNAPSHOT_FILE)
     }
 
-    /// Ensure the actor's journal directory exists
+    /// Get the journal file an actor writes, ignoring aliases
+    fn own_journal_path(&self, actor_id: &ActorId) -> PathBuf {
+        self.raw_dir(actor_id).join(JOURNAL_FILE)
+    }
+
+    /// Get the snapshot file an actor writes, ignoring aliases
+    fn own_snapshot_path(&self, actor_id: &ActorId) -> PathBuf {
+        self.raw_dir(actor_id).join(SNAPSHOT_FILE)
+    }
+
+    /// Ensure the actor's own directory exists
     fn ensure_dir(&self, actor_id: &ActorId) -> std::io::Result<()> {
-        fs::create_dir_all(self.actor_dir(actor_id))
+        fs::create_dir_all(self.raw_dir(actor_id))
+    }
+
+    /// Key of an actor's append lock: its own directory, canonicalized
+    /// so every `Journal` on the same path agrees on it
+    fn lock_key(&self, actor_id: &ActorId) -> PathBuf {
+        let base = match self.canonical_base.get() {
+            Some(base) => base.clone(),
+            None => match fs::canonicalize(&self.base_path) {
+                Ok(base) => self.canonical_base.get_or_init(|| base).clone(),
+                // Not created yet: the absolute path is as good until it is
+                Err(_) => {
+                    std::path::absolute(&self.base_path).unwrap_or_else(|_| self.base_path.clone())
+                }
+            },
+        };
+        base.join(actor_id.as_str())
+    }
+
+    /// Get the append lock for an actor's own files
+    fn append_lock(&self, actor_id: &ActorId) -> Arc<Mutex<()>> {
+        append_locks()
+            .lock()
+            .expect("append locks poisoned")
+            .entry(self.lock_key(actor_id))
+            .or_default()
+            .clone()
+    }
+
+    /// Forget an unregistered actor's append lock, unless an append
+    /// still holds it
+    pub(crate) fn release(&self, actor_id: &ActorId) {
+        let key = self.lock_key(actor_id);
+        let mut locks = append_locks().lock().expect("append locks poisoned");
+        if locks
+            .get(&key)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            locks.remove(&key);
+        }
     }
 
     /// Append an event to the journal
     ///
     /// Format: [4-byte length][bincode data]
+    ///
+    /// An event without an `actor_id` is stamped with `actor_id`; one that
+    /// names another actor is refused with `InvalidInput`.
     pub fn append(&self, actor_id: &ActorId, event: &Event) -> std::io::Result<()> {
-        self.ensure_dir(actor_id)?;
+        self.append_all(actor_id, std::slice::from_ref(event))
+    }
 
+    /// Append events in a single write
+    ///
+    /// Used for the events of one message handling, so a state change and
+    /// the side effects it records (see `crate::outbox`) land together.
+    pub fn append_all(&self, actor_id: &ActorId, events: &[Event]) -> std::io::Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        for event in events {
+            check_owner(actor_id, event.actor_id.as_ref())?;
+        }
+        let stamped = |event: &Event| match event.actor_id {
+            Some(_) => event.clone(),
+            None => Event {
+                actor_id: Some(actor_id.clone()),
+                ..event.clone()
+            },
+        };
+        if self.memory.is_none() {
+            self.ensure_dir(actor_id)?;
+        }
+
+        let records = events
+            .iter()
+            .map(|event| {
+                let data = stamped(event).to_bytes()?;
+                if data.len() > MAX_RECORD_LEN {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("event of {} bytes exceeds max record length", data.len()),
+                    ));
+                }
+                Ok(data)
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let body_len: usize = records.iter().map(|data| 4 + data.len()).sum();
+        if let Some(memory) = &self.memory {
+            let mut memory = memory.lock();
+            memory
+                .events
+                .entry(actor_id.clone())
+                .or_default()
+                .extend(events.iter().map(stamped));
+            return Ok(());
+        }
+
+        let lock = self.append_lock(actor_id);
+        let _guard = lock.lock().expect("append lock poisoned");
+        let path = self.own_journal_path(actor_id);
+        if let Some(quota) = &self.quota {
+            let fresh = fs::metadata(&path).map_or(true, |m| m.len() == 0);
+            let header = if fresh { HEADER_LEN } else { 0 };
+            self.enforce_quota(actor_id, quota, (header + body_len) as u64)?;
+        }
         let mut file = OpenOptions::new()
             .create(true)
+            .read(true)
             .append(true)
-            .open(self.journal_path(actor_id))?;
+            .open(path)?;
+
+        // One write for every record, header included on a fresh file
+        let mut buf = Vec::with_capacity(HEADER_LEN + body_len);
+        let version = if file.metadata()?.len() == 0 {
+            buf.extend_from_slice(&FileHeader::current().to_bytes(JOURNAL_MAGIC));
+            FORMAT_VERSION
+        } else {
+            // Older versions keep their format, readable by what wrote them
+            FileHeader::read(&mut file, JOURNAL_MAGIC)?.0.version
+        };
+        let records = match version {
+            0 => events
+                .iter()
+                .map(|event| event.to_record(0))
+                .collect::<std::io::Result<_>>()?,
+            _ => records,
+        };
+        let grouped = version >= GROUPED_VERSION;
+        for (i, data) in records.iter().enumerate() {
+            write_record(&mut buf, data, grouped && i + 1 < records.len())?;
+        }
+        file.write_all(&buf)?;
+        self.adjust_usage(buf.len() as i64);
+        Ok(())
+    }
 
-        let data = event.to_bytes()?;
-        let len = data.len() as u32;
+    /// Fail (or compact, then fail) if appending `incoming` bytes for
+    /// `actor_id` would exceed the quota
+    ///
+    /// Called with the actor's append lock held.
+    fn enforce_quota(
+        &self,
+        actor_id: &ActorId,
+        quota: &Quota,
+        incoming: u64,
+    ) -> std::io::Result<()> {
+        let over = || -> std::io::Result<Option<QuotaExceeded>> {
+            let exceeded = |scope, limit, used| QuotaExceeded {
+                actor_id: actor_id.clone(),
+                scope,
+                limit,
+                used,
+            };
+            if let Some(limit) = quota.max_actor_bytes {
+                let used = self.actor_bytes(actor_id)?;
+                if used + incoming > limit {
+                    return Ok(Some(exceeded(QuotaScope::Actor, limit, used)));
+                }
+            }
+            if let Some(limit) = quota.max_total_bytes {
+                let used = self.disk_usage()?;
+                if used + incoming > limit {
+                    return Ok(Some(exceeded(QuotaScope::Total, limit, used)));
+                }
+            }
+            Ok(None)
+        };
 
-        // Write length prefix (little-endian)
-        file.write_all(&len.to_le_bytes())?;
-        // Write event data
-        file.write_all(&data)?;
+        let Some(exceeded) = over()? else {
+            return Ok(());
+        };
+        if quota.on_exceeded == QuotaAction::Compact && self.compact_locked(actor_id)? > 0 {
+            return match over()? {
+                Some(exceeded) => Err(exceeded.into()),
+                None => Ok(()),
+            };
+        }
+        Err(exceeded.into())
+    }
 
-        Ok(())
+    /// Bytes of an actor's own journal and snapshot files
+    fn actor_bytes(&self, actor_id: &ActorId) -> std::io::Result<u64> {
+        let mut total = 0;
+        for path in [
+            self.own_journal_path(actor_id),
+            self.own_snapshot_path(actor_id),
+        ] {
+            match fs::metadata(path) {
+                Ok(meta) => total += meta.len(),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(total)
+    }
+
+    /// Bytes used under the base path
+    ///
+    /// Measured on first call, then tracked as the journal writes.
+    pub fn disk_usage(&self) -> std::io::Result<u64> {
+        if self.memory.is_some() {
+            return Ok(0);
+        }
+        let mut usage = self.usage.lock().expect("usage lock poisoned");
+        match *usage {
+            Some(bytes) => Ok(bytes),
+            None => {
+                let bytes = dir_size(&self.base_path)?;
+                *usage = Some(bytes);
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Re-measure `disk_usage` from the filesystem
+    pub fn refresh_usage(&self) -> std::io::Result<u64> {
+        *self.usage.lock().expect("usage lock poisoned") = None;
+        self.disk_usage()
+    }
+
+    /// Track a change in bytes used (if measured yet)
+    fn adjust_usage(&self, delta: i64) {
+        if let Some(bytes) = self.usage.lock().expect("usage lock poisoned").as_mut() {
+            *bytes = bytes.saturating_add_signed(delta);
+        }
+    }
+
+    /// Drop journaled events the actor's latest snapshot already covers
+    ///
+    /// Recovery is unaffected (it starts from the snapshot), but the
+    /// dropped events are gone for replay. Returns the bytes reclaimed;
+    /// 0 if the actor has no snapshot. Compacts the actor's own files,
+    /// ignoring aliases.
+    ///
+    /// Outbox intents (see `crate::outbox`) a persistent subscription
+    /// hasn't acknowledged yet are still to be delivered: compaction stops
+    /// at the oldest of them, keeping it and everything after.
+    pub fn compact(&self, actor_id: &ActorId) -> std::io::Result<u64> {
+        let lock = self.append_lock(actor_id);
+        let _guard = lock.lock().expect("append lock poisoned");
+        self.compact_locked(actor_id)
+    }
+
+    /// `compact`, with the actor's append lock held
+    fn compact_locked(&self, actor_id: &ActorId) -> std::io::Result<u64> {
+        if let Some(memory) = &self.memory {
+            let mut memory = memory.lock();
+            let Some(seq) = memory.snapshots.get(actor_id).map(|s| s.seq) else {
+                return Ok(0);
+            };
+            let Some(events) = memory.events.get_mut(actor_id) else {
+                return Ok(0);
+            };
+            let mut reclaimed = 0;
+            for event in events.iter().filter(|e| e.seq < seq) {
+                reclaimed += 4 + event.to_bytes()?.len() as u64;
+            }
+            events.retain(|e| e.seq >= seq);
+            return Ok(reclaimed);
+        }
+        let Some(snapshot) = self.load_snapshot_at(self.own_snapshot_path(actor_id))? else {
+            return Ok(0);
+        };
+        let snapshot = snapshot.into_snapshot()?;
+        let path = self.own_journal_path(actor_id);
+        let Ok(before) = fs::metadata(&path).map(|m| m.len()) else {
+            return Ok(0);
+        };
+
+        let events = self.read_events_at(&path)?;
+        let is_intent = |e: &Event| e.event_type == crate::outbox::INTENT_EVENT;
+        let mut keep_from = snapshot.seq;
+        if events.iter().any(|e| e.seq < keep_from && is_intent(e)) {
+            let acked = self.acknowledged_from(actor_id)?;
+            if let Some(intent) = events.iter().find(|e| e.seq >= acked && is_intent(e)) {
+                keep_from = keep_from.min(intent.seq);
+            }
+        }
+
+        let mut rewritten = FileHeader::current().to_bytes(JOURNAL_MAGIC).to_vec();
+        for event in events {
+            if event.seq >= keep_from {
+                write_frame(&mut rewritten, &event.to_bytes()?)?;
+            }
+        }
+        let after = rewritten.len() as u64;
+        if after >= before {
+            return Ok(0);
+        }
+
+        // Write then rename so a crash leaves either journal intact
+        let tmp = path.with_extension("bin.compact");
+        fs::write(&tmp, &rewritten)?;
+        fs::rename(tmp, &path)?;
+        self.adjust_usage(after as i64 - before as i64);
+        Ok(before - after)
+    }
+
+    /// Sync an actor's journal file to disk
+    pub fn sync(&self, actor_id: &ActorId) -> std::io::Result<()> {
+        if self.memory.is_some() {
+            return Ok(());
+        }
+        File::open(self.own_journal_path(actor_id))?.sync_all()
     }
 
     /// Read all events for an actor
     pub fn read_events(&self, actor_id: &ActorId) -> std::io::Result<Vec<Event>> {
-        let path = self.journal_path(actor_id);
+        if let Some(memory) = &self.memory {
+            let owner = self.resolve_alias(actor_id);
+            let events = memory.lock().events.get(&owner).cloned();
+            return Ok(events.unwrap_or_default());
+        }
+        self.read_events_at(&self.journal_path(actor_id))
+    }
 
+    /// Read all events from a journal file, if there is one
+    fn read_events_at(&self, path: &Path) -> std::io::Result<Vec<Event>> {
         if !path.exists() {
             return Ok(vec![]);
         }
 
         let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
-
-        let mut events = vec![];
-        let mut len_buf = [0u8; 4];
+        Self::decode_events_within(BufReader::new(file), &self.decode_limits)
+    }
 
-        loop {
-            // Read length prefix
-            match reader.read_exact(&mut len_buf) {
-                Ok(()) => {}
-                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
-                Err(e) => return Err(e),
+    /// Events journaled since `from`, and where to read from next time
+    ///
+    /// Reads the whole journal if `from` is `None`, or was taken before
+    /// the journal was rewritten (by `compact` or `repair`). Where files
+    /// can't be told apart the whole journal is read every time, and
+    /// `None` is returned as the offset.
+    pub(crate) fn read_events_from(
+        &self,
+        actor_id: &ActorId,
+        from: Option<ReadOffset>,
+    ) -> std::io::Result<(Vec<Event>, Option<ReadOffset>)> {
+        if self.memory.is_some() {
+            return Ok((self.read_events(actor_id)?, None));
+        }
+        let mut file = match File::open(self.journal_path(actor_id)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((vec![], None)),
+            Err(e) => return Err(e),
+        };
+        let meta = file.metadata()?;
+        let Some(id) = file_id(&meta) else {
+            let events = Self::decode_events_within(BufReader::new(file), &self.decode_limits)?;
+            return Ok((events, None));
+        };
+        let resume = from.filter(|from| from.file == id && from.offset <= meta.len());
+        let read = match resume {
+            Some(from) => {
+                file.seek(SeekFrom::Start(from.offset))?;
+                let reader = BufReader::new(file);
+                let (events, read) = decode_records(reader, from.version, &self.decode_limits)?;
+                (events, from.offset + read, from.version)
             }
+            None => {
+                let mut reader = BufReader::new(file);
+                let (header, consumed) = FileHeader::read(&mut reader, JOURNAL_MAGIC)?;
+                let start = match header.version {
+                    0 => 0,
+                    _ => HEADER_LEN as u64,
+                };
+                let reader = std::io::Cursor::new(consumed).chain(reader);
+                let (events, read) = decode_records(reader, header.version, &self.decode_limits)?;
+                (events, start + read, header.version)
+            }
+        };
+        let (events, offset, version) = read;
+        // Until the header is written, a file is read from the start
+        let offset = (offset > 0).then_some(ReadOffset {
+            file: id,
+            version,
+            offset,
+        });
+        Ok((events, offset))
+    }
 
-            let len = u32::from_le_bytes(len_buf) as usize;
-
-            // Read event data
-            let mut data = vec![0u8; len];
-            reader.read_exact(&mut data)?;
-
-            let event = Event::from_bytes(&data)?;
-            events.push(event);
-        }
+    /// Decode a stream of length-prefixed event records
+    ///
+    /// A clean end of input (including a torn length prefix from an
+    /// interrupted write) ends the stream, leaving out a group of records
+    /// written together that it cuts short; anything malformed after that
+    /// is returned as an `InvalidData` error, never a panic.
+    pub fn decode_events<R: Read>(reader: R) -> std::io::Result<Vec<Event>> {
+        Self::decode_events_within(reader, &DecodeLimits::default())
+    }
 
-        Ok(events)
+    /// `decode_events`, within `limits`
+    pub fn decode_events_within<R: Read>(
+        mut reader: R,
+        limits: &DecodeLimits,
+    ) -> std::io::Result<Vec<Event>> {
+        // Versions share the framing; only version 2 and later set
+        // `CONTINUES`, which earlier lengths can never have
+        let (header, consumed) = FileHeader::read(&mut reader, JOURNAL_MAGIC)?;
+        let reader = std::io::Cursor::new(consumed).chain(reader);
+        Ok(decode_records(reader, header.version, limits)?.0)
     }
 
     /// Read events after a specific sequence number
@@ -212,33 +1311,331 @@ impl Journal {
     }
 
     /// Save a snapshot
+    ///
+    /// Stamped and checked against `actor_id` as events are on `append`.
     pub fn save_snapshot(&self, actor_id: &ActorId, snapshot: &Snapshot) -> std::io::Result<()> {
+        check_owner(actor_id, snapshot.actor_id.as_ref())?;
+        let snapshot = match snapshot.actor_id {
+            Some(_) => snapshot.clone(),
+            None => Snapshot {
+                actor_id: Some(actor_id.clone()),
+                ..snapshot.clone()
+            },
+        };
+        if let Some(memory) = &self.memory {
+            memory.lock().snapshots.insert(actor_id.clone(), snapshot);
+            return Ok(());
+        }
         self.ensure_dir(actor_id)?;
 
-        let data = snapshot.to_bytes()?;
-        let file = File::create(self.snapshot_path(actor_id))?;
+        let (codec, data) = match self.lazy_snapshots.then(|| lazy::encode_keyed(&snapshot)) {
+            Some(Some(body)) => (Codec::Keyed, body?),
+            _ => (Codec::Bincode, snapshot.to_bytes()?),
+        };
+        let header = FileHeader {
+            codec,
+            ..FileHeader::current()
+        };
+        let path = self.own_snapshot_path(actor_id);
+        let before = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let file = File::create(path)?;
         let mut writer = BufWriter::new(file);
+        writer.write_all(&header.to_bytes(SNAPSHOT_MAGIC))?;
         writer.write_all(&data)?;
+        writer.flush()?;
+        self.adjust_usage((HEADER_LEN + data.len()) as i64 - before as i64);
 
         Ok(())
     }
 
     /// Load the latest snapshot
     pub fn load_snapshot(&self, actor_id: &ActorId) -> std::io::Result<Option<Snapshot>> {
-        let path = self.snapshot_path(actor_id);
+        self.load_snapshot_lazy(actor_id)?
+            .map(LazySnapshot::into_snapshot)
+            .transpose()
+    }
 
+    /// Load the latest snapshot, leaving the entries of a keyed snapshot
+    /// to be decoded on access (see `lazy`)
+    pub fn load_snapshot_lazy(&self, actor_id: &ActorId) -> std::io::Result<Option<LazySnapshot>> {
+        if let Some(memory) = &self.memory {
+            let owner = self.resolve_alias(actor_id);
+            return Ok(memory
+                .lock()
+                .snapshots
+                .get(&owner)
+                .cloned()
+                .map(LazySnapshot::from));
+        }
+        self.load_snapshot_at(self.snapshot_path(actor_id))
+    }
+
+    /// Load a snapshot file, if there is one
+    fn load_snapshot_at(&self, path: PathBuf) -> std::io::Result<Option<LazySnapshot>> {
         if !path.exists() {
             return Ok(None);
         }
 
         let data = fs::read(path)?;
-        let snapshot = Snapshot::from_bytes(&data)?;
+        if Self::snapshot_codec(&data)? == Some(Codec::Keyed) {
+            return LazySnapshot::parse(data, HEADER_LEN, &self.decode_limits).map(Some);
+        }
+        Ok(Some(
+            Self::decode_snapshot_within(&data, &self.decode_limits)?.into(),
+        ))
+    }
+
+    /// Codec of a snapshot file's header (`None` without a header)
+    fn snapshot_codec(data: &[u8]) -> std::io::Result<Option<Codec>> {
+        match data.strip_prefix(&SNAPSHOT_MAGIC[..]) {
+            Some(rest) if rest.len() >= HEADER_LEN - 4 => Ok(Some(FileHeader::parse(rest)?.codec)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Decode the contents of a snapshot file, with or without a header
+    pub fn decode_snapshot(data: &[u8]) -> std::io::Result<Snapshot> {
+        Self::decode_snapshot_within(data, &DecodeLimits::default())
+    }
+
+    /// `decode_snapshot`, within `limits`
+    pub fn decode_snapshot_within(data: &[u8], limits: &DecodeLimits) -> std::io::Result<Snapshot> {
+        match data.strip_prefix(&SNAPSHOT_MAGIC[..]) {
+            Some(rest) if rest.len() >= HEADER_LEN - 4 => match FileHeader::parse(rest)?.codec {
+                Codec::Bincode => Snapshot::from_bytes_within(&rest[HEADER_LEN - 4..], limits),
+                Codec::Keyed => lazy::decode_keyed(data, HEADER_LEN, limits),
+            },
+            Some(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "truncated snapshot header",
+            )),
+            None => Snapshot::from_legacy_bytes_within(data, limits),
+        }
+    }
+
+    /// Directory holding offloaded in-flight messages
+    fn offload_dir(&self) -> PathBuf {
+        self.base_path.join("offload")
+    }
+
+    /// Write an oversized in-flight message to disk
+    ///
+    /// Returns the path to hand back to `take_offloaded` on receive.
+    pub fn offload_message(&self, msg: &TypedValue) -> std::io::Result<PathBuf> {
+        self.require_files("offloading messages")?;
+        fs::create_dir_all(self.offload_dir())?;
+
+        let data = bincode::serialize(msg)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let path = self
+            .offload_dir()
+            .join(format!("{}.bin", uuid::Uuid::new_v4()));
+        fs::write(&path, data)?;
+
+        Ok(path)
+    }
+
+    /// Read back an offloaded message and delete it
+    ///
+    /// Offloaded messages are delivered exactly once, so the file is
+    /// removed as soon as it has been read.
+    pub fn take_offloaded(&self, path: &std::path::Path) -> std::io::Result<TypedValue> {
+        let msg = self.read_offloaded(path)?;
+        fs::remove_file(path)?;
+
+        Ok(msg)
+    }
+
+    /// Read back an offloaded message, leaving its file in place
+    pub fn read_offloaded(&self, path: &std::path::Path) -> std::io::Result<TypedValue> {
+        let data = fs::read(path)?;
+        self.decode_limits.decode(&data, |msg| msg)
+    }
+
+    /// Directory holding content-addressed blobs
+    fn blob_dir(&self) -> PathBuf {
+        self.base_path.join("blobs")
+    }
+
+    /// Path of a blob, fanned out by the first two hex digits
+    fn blob_path(&self, id: &BlobId) -> PathBuf {
+        self.blob_dir().join(&id.0[..2]).join(&id.0)
+    }
+
+    /// Store a blob, returning its content address
+    ///
+    /// Storing the same content twice only renews the blob's grace period
+    /// (see `collect_blobs`).
+    pub fn put_blob(&self, data: &[u8]) -> std::io::Result<BlobId> {
+        let id = BlobId::of(data);
+        if let Some(memory) = &self.memory {
+            let mut memory = memory.lock();
+            let blob = memory
+                .blobs
+                .entry(id.clone())
+                .or_insert_with(|| (data.to_vec(), SystemTime::now()));
+            blob.1 = SystemTime::now();
+            return Ok(id);
+        }
+        let path = self.blob_path(&id);
+        if path.exists() {
+            OpenOptions::new()
+                .write(true)
+                .open(&path)?
+                .set_modified(SystemTime::now())?;
+            return Ok(id);
+        }
+
+        let dir = path.parent().expect("blob path has a parent");
+        fs::create_dir_all(dir)?;
+
+        // Write to a temp file first so readers never see a partial blob
+        let tmp = dir.join(format!("{}.tmp-{}", id, uuid::Uuid::new_v4()));
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, &path)?;
+        self.adjust_usage(data.len() as i64);
+
+        Ok(id)
+    }
+
+    /// Load a blob by content address
+    pub fn get_blob(&self, id: &BlobId) -> std::io::Result<Option<Vec<u8>>> {
+        if let Some(memory) = &self.memory {
+            return Ok(memory.lock().blobs.get(id).map(|(data, _)| data.clone()));
+        }
+        let path = self.blob_path(id);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(fs::read(path)?))
+    }
+
+    /// List every actor with a journal directory under the base path
+    pub fn actor_ids(&self) -> std::io::Result<Vec<ActorId>> {
+        if let Some(memory) = &self.memory {
+            let memory = memory.lock();
+            let mut ids: Vec<ActorId> = memory.events.keys().cloned().collect();
+            let others = memory.snapshots.keys().chain(memory.aliases.keys());
+            for id in others {
+                if !ids.contains(id) {
+                    ids.push(id.clone());
+                }
+            }
+            ids.sort_by_key(|id| id.0);
+            return Ok(ids);
+        }
+        if !self.base_path.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut ids = vec![];
+        for entry in fs::read_dir(&self.base_path)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            if let Some(id) = entry.file_name().to_str().and_then(|n| n.parse().ok()) {
+                ids.push(id);
+            }
+        }
+        ids.sort_by_key(|id: &ActorId| id.0);
+
+        Ok(ids)
+    }
+
+    /// Actors whose journal or snapshot file was modified after `since`
+    ///
+    /// Always empty for an in-memory journal.
+    pub fn modified_since(&self, since: SystemTime) -> std::io::Result<Vec<ActorId>> {
+        if self.memory.is_some() {
+            return Ok(vec![]);
+        }
+        let modified = |path: PathBuf| fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut ids = self.actor_ids()?;
+        ids.retain(|id| {
+            let latest = modified(self.journal_path(id)).max(modified(self.snapshot_path(id)));
+            latest.is_some_and(|at| at > since)
+        });
+        Ok(ids)
+    }
+
+    /// Delete blobs that no journaled event references
+    ///
+    /// Blobs stored within the grace period (`with_blob_grace`) are kept
+    /// even if unreferenced: the event attaching one may not be appended
+    /// yet. Returns the number of blobs removed.
+    pub fn collect_blobs(&self) -> std::io::Result<usize> {
+        if self.memory.is_none() && !self.blob_dir().exists() {
+            return Ok(0);
+        }
+        let fresh = |stored: SystemTime| stored.elapsed().map_or(true, |age| age < self.blob_grace);
+
+        let mut referenced = HashSet::new();
+        for actor_id in self.actor_ids()? {
+            for event in self.read_events(&actor_id)? {
+                referenced.extend(event.attachments);
+            }
+        }
+        for checkpoint in self.checkpoints()? {
+            referenced.extend(checkpoint.actors.into_iter().map(|entry| entry.state));
+        }
+        if let Some(memory) = &self.memory {
+            let blobs = &mut memory.lock().blobs;
+            let before = blobs.len();
+            blobs.retain(|id, (_, stored)| referenced.contains(id) || fresh(*stored));
+            return Ok(before - blobs.len());
+        }
+
+        let mut removed = 0;
+        for fan_out in fs::read_dir(self.blob_dir())? {
+            let fan_out = fan_out?;
+            if !fan_out.file_type()?.is_dir() {
+                continue;
+            }
+            for blob in fs::read_dir(fan_out.path())? {
+                let blob = blob?;
+                let metadata = blob.metadata()?;
+                let keep = blob
+                    .file_name()
+                    .to_str()
+                    .and_then(|n| n.parse::<BlobId>().ok())
+                    .is_some_and(|id| referenced.contains(&id))
+                    || fresh(metadata.modified()?);
+                if !keep {
+                    let size = metadata.len();
+                    fs::remove_file(blob.path())?;
+                    self.adjust_usage(-(size as i64));
+                    removed += 1;
+                }
+            }
+        }
+
+        Ok(removed)
+    }
 
-        Ok(Some(snapshot))
+    /// Verify the base path is writable by writing and removing a probe file
+    pub fn check_writable(&self) -> std::io::Result<()> {
+        if self.memory.is_some() {
+            return Ok(());
+        }
+        fs::create_dir_all(&self.base_path)?;
+        let probe = self
+            .base_path
+            .join(format!(".probe-{}", uuid::Uuid::new_v4()));
+        fs::write(&probe, b"ok")?;
+        fs::remove_file(probe)
     }
 
     /// Check if an actor has any persisted state
     pub fn exists(&self, actor_id: &ActorId) -> bool {
+        if let Some(memory) = &self.memory {
+            let memory = memory.lock();
+            return memory.events.contains_key(actor_id)
+                || memory.snapshots.contains_key(actor_id)
+                || memory.aliases.contains_key(actor_id);
+        }
         self.actor_dir(actor_id).exists()
     }
 
@@ -247,15 +1644,296 @@ impl Journal {
         let events = self.read_events(actor_id)?;
         Ok(events.iter().map(|e| e.to_debug_string()).collect())
     }
+
+    /// Write a crash dump into the actor's directory, returning its path
+    pub fn write_crash_dump(&self, dump: &crate::crash::CrashDump) -> std::io::Result<PathBuf> {
+        self.require_files("crash dumps")?;
+        let dir = self.raw_dir(&dump.actor_id);
+        fs::create_dir_all(&dir)?;
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let path = dir.join(format!("crash-{}-{}.txt", dump.ts, &suffix[..8]));
+        let contents = dump.render();
+        fs::write(&path, &contents)?;
+        self.adjust_usage(contents.len() as i64);
+        Ok(path)
+    }
+}
+
+/// Reject names that aren't plain file names (`kind` is for the error)
+pub(crate) fn check_name(kind: &str, name: &str) -> std::io::Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.starts_with('.');
+    if valid {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("invalid {} name {:?}", kind, name),
+        ))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::serialize::MapKey;
+    use proptest::prelude::*;
     use std::collections::BTreeMap;
     use tempfile::TempDir;
 
+    fn arb_map_key() -> impl Strategy<Value = MapKey> {
+        prop_oneof![
+            any::<i64>().prop_map(MapKey::Int),
+            any::<bool>().prop_map(MapKey::Bool),
+            ".*".prop_map(MapKey::String),
+        ]
+    }
+
+    fn arb_typed_value() -> impl Strategy<Value = TypedValue> {
+        let leaf = prop_oneof![
+            any::<i64>().prop_map(TypedValue::Int),
+            // NaN never compares equal, so stick to finite floats
+            (-1e12f64..1e12f64).prop_map(TypedValue::Float),
+            any::<bool>().prop_map(TypedValue::Bool),
+            ".*".prop_map(TypedValue::String),
+        ];
+        leaf.prop_recursive(4, 64, 8, |inner| {
+            prop::collection::btree_map(arb_map_key(), inner, 0..8).prop_map(TypedValue::Map)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_event_round_trip(seq: u64, ts: u64, event_type in ".*", payload in arb_typed_value()) {
+            let event = Event {
+                actor_id: None,
+                seq,
+                event_type,
+                payload,
+                ts,
+                attachments: vec![],
+            };
+            let decoded = Event::from_bytes(&event.to_bytes().unwrap()).unwrap();
+            prop_assert_eq!(decoded.seq, event.seq);
+            prop_assert_eq!(decoded.ts, event.ts);
+            prop_assert_eq!(decoded.event_type, event.event_type);
+            prop_assert_eq!(decoded.payload, event.payload);
+        }
+
+        #[test]
+        fn prop_snapshot_round_trip(seq: u64, ts: u64, state in arb_typed_value()) {
+            let snapshot = Snapshot { actor_id: None, seq, state, ts };
+            let decoded = Snapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap();
+            prop_assert_eq!(decoded.seq, snapshot.seq);
+            prop_assert_eq!(decoded.state, snapshot.state);
+        }
+
+        #[test]
+        fn prop_decode_events_never_panics(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+            let _ = Journal::decode_events(&bytes[..]);
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_length_prefix() {
+        let bytes = u32::MAX.to_le_bytes();
+        let err = Journal::decode_events(&bytes[..]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_decode_truncated_record_is_error() {
+        let event = Event::new(0, "Test".to_string(), TypedValue::Int(1));
+        let data = event.to_bytes().unwrap();
+        let mut bytes = (data.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&data[..data.len() / 2]);
+
+        assert!(Journal::decode_events(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn test_records_of_another_actor_are_refused() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let (id, other) = (ActorId::new(), ActorId::new());
+        let event = Event {
+            actor_id: Some(other.clone()),
+            ..Event::new(0, "A".to_string(), TypedValue::Int(1))
+        };
+        let err = journal.append(&id, &event).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        let snapshot = Snapshot {
+            actor_id: Some(other.clone()),
+            ..snapshot_at(0)
+        };
+        let err = journal.save_snapshot(&id, &snapshot).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(journal.read_events(&id).unwrap().is_empty());
+        assert!(journal.load_snapshot(&id).unwrap().is_none());
+
+        // Its own records are fine
+        journal.append(&other, &event).unwrap();
+        journal.save_snapshot(&other, &snapshot).unwrap();
+    }
+
+    #[test]
+    fn test_torn_group_is_dropped_whole() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let id = ActorId::new();
+        let event = |seq| Event::new(seq, "A".to_string(), TypedValue::Int(1));
+        journal.append(&id, &event(0)).unwrap();
+        journal.append_all(&id, &[event(1), event(2)]).unwrap();
+        assert_eq!(journal.read_events(&id).unwrap().len(), 3);
+
+        // Cut the write short inside the group's last length prefix
+        let path = journal.journal_path(&id);
+        let last = Event {
+            actor_id: Some(id.clone()),
+            ..event(2)
+        };
+        let len = fs::metadata(&path).unwrap().len();
+        let torn = len - 4 - last.to_bytes().unwrap().len() as u64 + 2;
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(torn)
+            .unwrap();
+
+        let seqs: Vec<u64> = journal
+            .read_events(&id)
+            .unwrap()
+            .iter()
+            .map(|e| e.seq)
+            .collect();
+        assert_eq!(seqs, [0]);
+        let report = journal.verify(&id).unwrap();
+        assert_eq!(report.events, 1);
+        assert!(matches!(
+            report.issues[..],
+            [verify::Issue::TornTail { .. }]
+        ));
+    }
+
+    fn snapshot_at(seq: u64) -> Snapshot {
+        Snapshot {
+            actor_id: None,
+            seq,
+            state: TypedValue::Int(1),
+            ts: 0,
+        }
+    }
+
+    #[test]
+    fn test_new_files_have_header() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let id = ActorId::new();
+
+        journal
+            .append(&id, &Event::new(0, "A".to_string(), TypedValue::Int(1)))
+            .unwrap();
+        journal.save_snapshot(&id, &snapshot_at(1)).unwrap();
+
+        let journal_bytes = fs::read(journal.journal_path(&id)).unwrap();
+        assert_eq!(journal_bytes[..4], JOURNAL_MAGIC);
+        assert_eq!(journal_bytes[4], FORMAT_VERSION);
+        let snapshot_bytes = fs::read(journal.snapshot_path(&id)).unwrap();
+        assert_eq!(snapshot_bytes[..4], SNAPSHOT_MAGIC);
+
+        assert_eq!(journal.read_events(&id).unwrap().len(), 1);
+        assert_eq!(journal.load_snapshot(&id).unwrap().unwrap().seq, 1);
+    }
+
+    #[test]
+    fn test_headerless_files_still_readable() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let id = ActorId::new();
+        journal.ensure_dir(&id).unwrap();
+
+        // Write version 0 files by hand, in 0.1.0's layout
+        let event = Event::new(0, "Legacy".to_string(), TypedValue::Int(1));
+        let mut legacy = vec![];
+        write_frame(&mut legacy, &event.to_record(0).unwrap()).unwrap();
+        fs::write(journal.journal_path(&id), legacy).unwrap();
+        let snapshot = snapshot_at(1);
+        let legacy = bincode::serialize(&(snapshot.seq, &snapshot.state, snapshot.ts)).unwrap();
+        fs::write(journal.snapshot_path(&id), legacy).unwrap();
+
+        // Appends to a headerless journal stay headerless, in that layout
+        journal
+            .append(&id, &Event::new(1, "New".to_string(), TypedValue::Int(2)))
+            .unwrap();
+        let data = fs::read(journal.journal_path(&id)).unwrap();
+        let first = read_frame(&mut &data[..]).unwrap().unwrap();
+        assert!(Event::from_record(&first, 0, &DecodeLimits::default()).is_ok());
+        let events = journal.read_events(&id).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_type, "Legacy");
+        assert_eq!(events[1].actor_id, None);
+        assert_eq!(journal.load_snapshot(&id).unwrap().unwrap().seq, 1);
+        let attached = Event::new(2, "Attached".to_string(), TypedValue::Int(3))
+            .with_attachment(BlobId::of(b"blob"));
+        assert!(journal.append(&id, &attached).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_header_rejected() {
+        let mut future = FileHeader::current().to_bytes(JOURNAL_MAGIC);
+        future[4] = FORMAT_VERSION + 1;
+        let err = Journal::decode_events(&future[..]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+
+        let compressed = FileHeader {
+            flags: FLAG_COMPRESSED,
+            ..FileHeader::current()
+        }
+        .to_bytes(SNAPSHOT_MAGIC);
+        let err = Journal::decode_snapshot(&compressed).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn test_concurrent_appends_do_not_interleave() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Arc::new(Journal::new(temp_dir.path()));
+        // A second journal on the same path, reached through another name
+        let other = Arc::new(Journal::new(temp_dir.path().join(".")));
+        let id = ActorId::new();
+        let payload = TypedValue::String("x".repeat(4096));
+
+        let writers: Vec<_> = (0..8)
+            .map(|n| {
+                let journal = if n % 2 == 0 { &journal } else { &other }.clone();
+                let (id, payload) = (id.clone(), payload.clone());
+                std::thread::spawn(move || {
+                    for seq in 0..100 {
+                        let event = Event::new(seq, "Concurrent".to_string(), payload.clone());
+                        journal.append(&id, &event).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let events = journal.read_events(&id).unwrap();
+        assert_eq!(events.len(), 800);
+        assert!(events.iter().all(|e| e.payload == payload));
+
+        // Released on unregister
+        let key = journal.lock_key(&id);
+        assert!(append_locks().lock().unwrap().contains_key(&key));
+        other.release(&id);
+        assert!(!append_locks().lock().unwrap().contains_key(&key));
+    }
+
     #[test]
     fn test_append_and_read_events() {
         let temp_dir = TempDir::new().unwrap();
@@ -280,6 +1958,7 @@ mod tests {
         assert_eq!(events.len(), 2);
         assert_eq!(events[0].event_type, "Deposit");
         assert_eq!(events[1].event_type, "Withdraw");
+        assert_eq!(events[0].actor_id, Some(actor_id.clone()));
     }
 
     #[test]
@@ -311,6 +1990,7 @@ mod tests {
         state.insert(MapKey::String("balance".to_string()), TypedValue::Int(500));
 
         let snapshot = Snapshot {
+            actor_id: None,
             seq: 10,
             state: TypedValue::Map(state),
             ts: 1234567890,
@@ -320,6 +2000,7 @@ mod tests {
 
         let loaded = journal.load_snapshot(&actor_id).unwrap().unwrap();
         assert_eq!(loaded.seq, 10);
+        assert_eq!(loaded.actor_id, Some(actor_id.clone()));
         if let TypedValue::Map(m) = &loaded.state {
             assert_eq!(m.get(&MapKey::String("balance".to_string())), Some(&TypedValue::Int(500)));
         } else {
@@ -339,6 +2020,114 @@ mod tests {
         assert!(journal.load_snapshot(&actor_id).unwrap().is_none());
     }
 
+    #[test]
+    fn test_alias_redirects_reads() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let (old, new) = (ActorId::new(), ActorId::new());
+
+        let event = Event::new(1, "Moved".to_string(), TypedValue::Int(1));
+        journal.append(&new, &event).unwrap();
+        journal.set_alias(&old, &new).unwrap();
+
+        assert_eq!(journal.resolve_alias(&old), new);
+        assert_eq!(journal.read_events(&old).unwrap()[0].event_type, "Moved");
+        assert_eq!(
+            journal.set_alias(&new, &old).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+
+        // A straggling write from the old actor stays in its own files
+        let stopped = Event::new(0, "Stopped".to_string(), TypedValue::Int(0));
+        journal.append(&old, &stopped).unwrap();
+        assert_eq!(journal.read_events(&new).unwrap().len(), 1);
+
+        // Survives reopening the journal
+        let reopened = Journal::new(temp_dir.path());
+        assert_eq!(reopened.alias_of(&old), Some(new.clone()));
+        assert!(reopened.remove_alias(&old).unwrap());
+        assert_eq!(reopened.read_events(&old).unwrap()[0].event_type, "Stopped");
+    }
+
+    #[test]
+    fn test_quota_compacts_before_refusing() {
+        let temp_dir = TempDir::new().unwrap();
+        let actor_id = ActorId::new();
+        let event = |seq| Event::new(seq, "Tick".to_string(), TypedValue::Int(seq as i64));
+
+        let unlimited = Journal::new(temp_dir.path());
+        for seq in 1..=3 {
+            unlimited.append(&actor_id, &event(seq)).unwrap();
+        }
+        let used = unlimited.disk_usage().unwrap();
+        let record = (used - HEADER_LEN as u64) / 3;
+
+        // One byte short of room for a fourth record
+        let journal = Journal::new(temp_dir.path()).with_quota(Quota {
+            max_total_bytes: Some(used + record - 1),
+            on_exceeded: QuotaAction::Compact,
+            ..Quota::default()
+        });
+        let err = journal.append(&actor_id, &event(4)).unwrap_err();
+        let exceeded = quota::exceeded(&err).unwrap();
+        assert_eq!(exceeded.scope, QuotaScope::Total);
+
+        // With a snapshot taken before seq 3, compaction frees room
+        journal.save_snapshot(&actor_id, &snapshot_at(3)).unwrap();
+        journal.append(&actor_id, &event(4)).unwrap();
+        let seqs: Vec<u64> = journal
+            .read_events(&actor_id)
+            .unwrap()
+            .iter()
+            .map(|e| e.seq)
+            .collect();
+        assert_eq!(seqs, [3, 4]);
+        assert_eq!(
+            journal.disk_usage().unwrap(),
+            journal.refresh_usage().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_blob_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+
+        let id = journal.put_blob(b"attachment bytes").unwrap();
+        assert_eq!(journal.put_blob(b"attachment bytes").unwrap(), id);
+        assert_eq!(id.as_str().len(), 64);
+        assert_eq!(id.to_string().parse::<BlobId>().unwrap(), id);
+
+        assert_eq!(journal.get_blob(&id).unwrap().unwrap(), b"attachment bytes");
+        assert!(journal.get_blob(&BlobId::of(b"other")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_collect_unreferenced_blobs() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let actor_id = ActorId::new();
+
+        let kept = journal.put_blob(b"kept").unwrap();
+        let orphan = journal.put_blob(b"orphan").unwrap();
+
+        // Not yet referenced, but inside the grace period
+        assert_eq!(journal.collect_blobs().unwrap(), 0);
+        let journal = journal.with_blob_grace(Duration::ZERO);
+
+        let event = Event::new(0, "Uploaded".to_string(), TypedValue::Bool(true))
+            .with_attachment(kept.clone());
+        journal.append(&actor_id, &event).unwrap();
+        assert_eq!(
+            journal.read_events(&actor_id).unwrap()[0].attachments,
+            vec![kept.clone()]
+        );
+
+        assert_eq!(journal.collect_blobs().unwrap(), 1);
+        assert!(journal.get_blob(&kept).unwrap().is_some());
+        assert!(journal.get_blob(&orphan).unwrap().is_none());
+    }
+
     #[test]
     fn test_debug_dump() {
         let temp_dir = TempDir::new().unwrap();
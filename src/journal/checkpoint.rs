@@ -0,0 +1,87 @@
+//! Checkpoint manifests
+//!
+//! Each manifest recorded by `ActorRuntime::checkpoint_all` is appended
+//! to `checkpoints.bin` under the journal path, using the journal's
+//! header and record framing:
+//!
+//! ```text
+//! [8: header, magic "SQCP"]
+//! [4: length][bincode Checkpoint]   (once per checkpoint)
+//! ```
+//!
+//! The checkpointed states themselves are bincode blobs in the blob store.
+
+use super::{read_frame, write_frame, BlobId, FileHeader, Journal};
+use crate::checkpoint::Checkpoint;
+use crate::serialize::TypedValue;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Magic bytes opening the checkpoint history
+pub const CHECKPOINT_MAGIC: [u8; 4] = *b"SQCP";
+
+/// File holding the checkpoint history, under the journal path
+const CHECKPOINTS_FILE: &str = "checkpoints.bin";
+
+fn invalid(e: bincode::Error) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+}
+
+impl Journal {
+    fn checkpoints_path(&self) -> PathBuf {
+        self.base_path.join(CHECKPOINTS_FILE)
+    }
+
+    /// Append a checkpoint manifest to the history
+    pub fn record_checkpoint(&self, checkpoint: &Checkpoint) -> std::io::Result<()> {
+        if let Some(memory) = &self.memory {
+            memory.lock().checkpoints.push(checkpoint.clone());
+            return Ok(());
+        }
+        fs::create_dir_all(&self.base_path)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.checkpoints_path())?;
+        let mut buf = vec![];
+        if file.metadata()?.len() == 0 {
+            buf.extend_from_slice(&FileHeader::current().to_bytes(CHECKPOINT_MAGIC));
+        }
+        write_frame(&mut buf, &bincode::serialize(checkpoint).map_err(invalid)?)?;
+        file.write_all(&buf)?;
+        self.adjust_usage(buf.len() as i64);
+        Ok(())
+    }
+
+    /// Store a checkpointed state in the blob store
+    pub fn put_checkpoint_state(&self, state: &TypedValue) -> std::io::Result<BlobId> {
+        self.put_blob(&bincode::serialize(state).map_err(invalid)?)
+    }
+
+    /// Load a checkpointed state from the blob store
+    pub fn get_checkpoint_state(&self, id: &BlobId) -> std::io::Result<Option<TypedValue>> {
+        self.get_blob(id)?
+            .map(|data| self.decode_limits.decode(&data, |state| state))
+            .transpose()
+    }
+
+    /// Every recorded checkpoint, oldest first
+    pub fn checkpoints(&self) -> std::io::Result<Vec<Checkpoint>> {
+        if let Some(memory) = &self.memory {
+            return Ok(memory.lock().checkpoints.clone());
+        }
+        let data = match fs::read(self.checkpoints_path()) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+        let mut reader = &data[..];
+        FileHeader::read(&mut reader, CHECKPOINT_MAGIC)?;
+        let mut checkpoints = vec![];
+        while let Some(record) = read_frame(&mut reader)? {
+            checkpoints.push(bincode::deserialize(&record).map_err(invalid)?);
+        }
+        Ok(checkpoints)
+    }
+}
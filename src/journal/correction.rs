@@ -0,0 +1,199 @@
+//! Corrections to journaled events
+//!
+//! Journals are append-only, so bad data is never fixed in place.
+//! `Journal::append_correction` appends a `Correction` event instead,
+//! naming the sequence number of the event it corrects and carrying the
+//! replacement:
+//!
+//! ```rust,ignore
+//! let fixed = Event::new(0, "Deposited".to_string(), TypedValue::Int(100));
+//! journal.append_correction(&account_id, 7, &fixed)?;
+//! ```
+//!
+//! Both records stay in the journal for auditing. Recovery and replay
+//! fold the journal through `apply_corrections`, which applies the
+//! replacement in place of the original (the latest correction wins) and
+//! skips the correction records themselves.
+//!
+//! A correction to an event the latest snapshot already covers discards
+//! that snapshot, so the next recovery replays from the start; an event
+//! compaction already dropped can't be corrected. Correct actors that
+//! aren't running: a running actor keeps its in-memory state until it is
+//! recovered again.
+
+use super::{Event, Journal};
+use crate::actor::ActorId;
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+/// Event type of a correction record
+pub const CORRECTION_EVENT: &str = "Correction";
+
+fn key(name: &str) -> TypedMapKey {
+    TypedMapKey::String(name.to_string())
+}
+
+fn invalid_input(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+/// The sequence number a correction record targets and its replacement
+/// event, or `None` if `event` isn't a correction
+pub fn correction_of(event: &Event) -> Option<(u64, Event)> {
+    if event.event_type != CORRECTION_EVENT {
+        return None;
+    }
+    let TypedValue::Map(fields) = &event.payload else {
+        return None;
+    };
+    let (Some(TypedValue::Int(target)), Some(TypedValue::String(event_type)), Some(payload)) = (
+        fields.get(&key("target")),
+        fields.get(&key("event_type")),
+        fields.get(&key("payload")),
+    ) else {
+        return None;
+    };
+    let replacement = Event {
+        actor_id: event.actor_id.clone(),
+        seq: *target as u64,
+        event_type: event_type.clone(),
+        payload: payload.clone(),
+        ts: event.ts,
+        attachments: event.attachments.clone(),
+    };
+    Some((*target as u64, replacement))
+}
+
+/// Point a correction record at event `target` instead
+pub(super) fn retarget(correction: &mut Event, target: u64) {
+    if let TypedValue::Map(fields) = &mut correction.payload {
+        fields.insert(key("target"), TypedValue::Int(target as i64));
+    }
+}
+
+/// Apply the corrections among `events` to the events they target,
+/// dropping the correction records
+pub fn apply_corrections(events: Vec<Event>) -> Vec<Event> {
+    let corrections: HashMap<u64, Event> = events.iter().filter_map(correction_of).collect();
+    events
+        .into_iter()
+        .filter(|event| event.event_type != CORRECTION_EVENT)
+        .map(|event| match corrections.get(&event.seq) {
+            Some(replacement) => replacement.clone(),
+            None => event,
+        })
+        .collect()
+}
+
+impl Journal {
+    /// Record a correction to the actor's event `target_seq`, returning the
+    /// correction record's sequence number
+    ///
+    /// Only `correction`'s type, payload and attachments are used. Fails
+    /// with `InvalidInput` if there is no such event (or it was compacted
+    /// away) or it is itself a correction. For an aliased actor the
+    /// correction goes to the journal the alias leads to.
+    pub fn append_correction(
+        &self,
+        actor_id: &ActorId,
+        target_seq: u64,
+        correction: &Event,
+    ) -> std::io::Result<u64> {
+        let actor_id = &self.resolve_alias(actor_id);
+        let events = self.read_events(actor_id)?;
+        let target = events.iter().find(|e| e.seq == target_seq).ok_or_else(|| {
+            invalid_input(format!(
+                "{} has no event {} to correct",
+                actor_id, target_seq
+            ))
+        })?;
+        if target.event_type == CORRECTION_EVENT {
+            return Err(invalid_input(format!(
+                "event {} of {} is a correction; correct the original event",
+                target_seq, actor_id
+            )));
+        }
+
+        let mut fields = BTreeMap::new();
+        fields.insert(key("target"), TypedValue::Int(target_seq as i64));
+        fields.insert(
+            key("event_type"),
+            TypedValue::String(correction.event_type.clone()),
+        );
+        fields.insert(key("payload"), correction.payload.clone());
+        let seq = events.iter().map(|e| e.seq + 1).max().unwrap_or(0);
+        let record = Event {
+            attachments: correction.attachments.clone(),
+            ..Event::new(seq, CORRECTION_EVENT.to_string(), TypedValue::Map(fields))
+        };
+        self.append(actor_id, &record)?;
+
+        if self
+            .load_snapshot(actor_id)?
+            .is_some_and(|s| s.seq > target_seq)
+        {
+            self.discard_snapshot(actor_id)?;
+        }
+        Ok(seq)
+    }
+
+    /// Remove the actor's snapshot, so recovery replays every event
+    fn discard_snapshot(&self, actor_id: &ActorId) -> std::io::Result<()> {
+        if let Some(memory) = &self.memory {
+            memory.lock().snapshots.remove(actor_id);
+            return Ok(());
+        }
+        let path = self.snapshot_path(actor_id);
+        let size = fs::metadata(&path)?.len();
+        fs::remove_file(path)?;
+        self.adjust_usage(-(size as i64));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::Snapshot;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_corrections_replace_their_target_on_replay() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let id = ActorId::new();
+        for (seq, amount) in [(0, 10), (1, 999), (2, 5)] {
+            let event = Event::new(seq, "Added".to_string(), TypedValue::Int(amount));
+            journal.append(&id, &event).unwrap();
+        }
+        let snapshot = Snapshot {
+            actor_id: None,
+            seq: 3,
+            state: TypedValue::Int(1014),
+            ts: 0,
+        };
+        journal.save_snapshot(&id, &snapshot).unwrap();
+
+        let fixed = Event::new(0, "Added".to_string(), TypedValue::Int(9));
+        assert_eq!(journal.append_correction(&id, 1, &fixed).unwrap(), 3);
+        assert!(journal.load_snapshot(&id).unwrap().is_none());
+        assert!(journal.append_correction(&id, 3, &fixed).is_err());
+        assert!(journal.append_correction(&id, 7, &fixed).is_err());
+
+        let events = journal.read_events(&id).unwrap();
+        assert_eq!(events.len(), 4, "the original is kept");
+        let applied: Vec<_> = apply_corrections(events)
+            .into_iter()
+            .map(|e| (e.seq, e.payload))
+            .collect();
+        assert_eq!(
+            applied,
+            [
+                (0, TypedValue::Int(10)),
+                (1, TypedValue::Int(9)),
+                (2, TypedValue::Int(5))
+            ]
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Golden journal fixtures
+//!
+//! A fixture is a small journal (events plus an optional snapshot)
+//! written to disk by one crate version and checked into the repository.
+//! Later versions must still read it back unchanged, which catches
+//! accidental on-disk format breaks in ordinary test runs.
+//!
+//! ```rust,ignore
+//! // Record once per release (e.g. with SEQ_ACTORS_BLESS=1)...
+//! fixtures::sample().write(&golden_dir.join("0.2.0"))?;
+//!
+//! // ...and assert every recorded version stays readable
+//! for dir in recorded_versions {
+//!     fixtures::sample().assert_readable(&dir);
+//! }
+//! ```
+//!
+//! Fixtures are written through `Journal`, so a fixture directory has the
+//! same layout as a journal base directory. `tests/fixtures/journals/0.1.0`
+//! predates both this module and file headers: it was written by 0.1.0
+//! itself and holds `legacy_sample`.
+
+use super::{BlobId, Event, Journal, Snapshot};
+use crate::actor::ActorId;
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Environment variable that asks fixture tests to re-record goldens
+pub const BLESS_ENV: &str = "SEQ_ACTORS_BLESS";
+
+/// Versions whose goldens were written in the headerless format, holding
+/// `legacy_sample` (never re-recorded)
+pub const LEGACY_VERSIONS: &[&str] = &["0.1.0"];
+
+/// One actor's journal contents
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixture {
+    pub actor_id: ActorId,
+    pub events: Vec<Event>,
+    pub snapshot: Option<Snapshot>,
+}
+
+impl Fixture {
+    /// Write this fixture as a journal under `dir`, replacing any previous
+    /// journal for the same actor
+    pub fn write(&self, dir: &Path) -> std::io::Result<()> {
+        let journal = Journal::new(dir);
+        let actor_dir = journal.actor_dir(&self.actor_id);
+        if actor_dir.exists() {
+            fs::remove_dir_all(&actor_dir)?;
+        }
+
+        for event in &self.events {
+            journal.append(&self.actor_id, event)?;
+        }
+        if let Some(snapshot) = &self.snapshot {
+            journal.save_snapshot(&self.actor_id, snapshot)?;
+        }
+        Ok(())
+    }
+
+    /// Read an actor's journal under `dir` back as a fixture
+    pub fn read(dir: &Path, actor_id: &ActorId) -> std::io::Result<Fixture> {
+        let journal = Journal::new(dir);
+        Ok(Fixture {
+            actor_id: actor_id.clone(),
+            events: journal.read_events(actor_id)?,
+            snapshot: journal.load_snapshot(actor_id)?,
+        })
+    }
+
+    /// Panic unless the journal under `dir` reads back as this fixture
+    pub fn assert_readable(&self, dir: &Path) {
+        let found = Fixture::read(dir, &self.actor_id)
+            .unwrap_or_else(|e| panic!("golden journal {} is unreadable: {}", dir.display(), e));
+        assert_eq!(
+            &found,
+            self,
+            "golden journal {} no longer decodes to the expected fixture",
+            dir.display()
+        );
+    }
+}
+
+/// The canonical fixture: every value kind, an attachment, and a snapshot
+///
+/// Everything is fixed (ids, timestamps), so the bytes written for a given
+/// format are stable. Never change this data; add a new fixture instead.
+pub fn sample() -> Fixture {
+    let actor_id = ActorId::from_uuid(Uuid::from_u128(0x5eac_7025_0000_4000_8000_0000_0000_0001));
+    let key = |k: &str| TypedMapKey::String(k.to_string());
+
+    let nested = TypedValue::Map(BTreeMap::from([
+        (TypedMapKey::Int(-1), TypedValue::Bool(false)),
+        (TypedMapKey::Bool(true), TypedValue::Float(2.5)),
+        (key("name"), TypedValue::String("golden".to_string())),
+    ]));
+    let event = |seq: u64, event_type: &str, payload: TypedValue| Event {
+        actor_id: Some(actor_id.clone()),
+        seq,
+        event_type: event_type.to_string(),
+        payload,
+        ts: 1_700_000_000_000 + seq,
+        attachments: vec![],
+    };
+
+    let events = vec![
+        event(0, "Opened", TypedValue::Int(i64::MAX)),
+        event(1, "Renamed", TypedValue::String("ünïcode ✓".to_string())),
+        event(
+            2,
+            "Nested",
+            TypedValue::Map(BTreeMap::from([(key("inner"), nested.clone())])),
+        ),
+        Event {
+            attachments: vec![BlobId::of(b"golden attachment")],
+            ..event(3, "Attached", TypedValue::Float(-0.125))
+        },
+    ];
+    let snapshot = Snapshot {
+        actor_id: Some(actor_id.clone()),
+        seq: 2,
+        state: nested,
+        ts: 1_700_000_000_100,
+    };
+
+    Fixture {
+        actor_id,
+        events,
+        snapshot: Some(snapshot),
+    }
+}
+
+/// `sample` as 0.1.0 could write it: without actor IDs on the event and
+/// snapshot records, or attachments
+pub fn legacy_sample() -> Fixture {
+    let Fixture {
+        actor_id,
+        events,
+        snapshot,
+    } = sample();
+    let events = events
+        .into_iter()
+        .map(|event| Event {
+            actor_id: None,
+            attachments: vec![],
+            ..event
+        })
+        .collect();
+    let snapshot = snapshot.map(|snapshot| Snapshot {
+        actor_id: None,
+        ..snapshot
+    });
+    Fixture {
+        actor_id,
+        events,
+        snapshot,
+    }
+}
+
+/// The fixture a golden recorded by `version` holds
+pub fn sample_for(version: &str) -> Fixture {
+    match LEGACY_VERSIONS.contains(&version) {
+        true => legacy_sample(),
+        false => sample(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_then_read() {
+        let temp_dir = TempDir::new().unwrap();
+        let fixture = sample();
+
+        fixture.write(temp_dir.path()).unwrap();
+        // Rewriting replaces rather than appends
+        fixture.write(temp_dir.path()).unwrap();
+        fixture.assert_readable(temp_dir.path());
+    }
+
+    /// Every recorded version under `tests/fixtures/journals/<version>` must
+    /// still decode. Run with `SEQ_ACTORS_BLESS=1` to record the current
+    /// version.
+    #[test]
+    fn test_golden_journals_readable() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/journals");
+        if std::env::var_os(BLESS_ENV).is_some() {
+            let version = env!("CARGO_PKG_VERSION");
+            assert!(
+                !LEGACY_VERSIONS.contains(&version),
+                "{} was released in the headerless format; bump the crate version to bless",
+                version
+            );
+            sample().write(&root.join(version)).unwrap();
+        }
+
+        let mut checked = vec![];
+        for version in fs::read_dir(&root).unwrap() {
+            let dir = version.unwrap().path();
+            let version = dir.file_name().unwrap().to_str().unwrap().to_string();
+            sample_for(&version).assert_readable(&dir);
+            checked.push(version);
+        }
+        assert!(checked.iter().any(|version| version == "0.1.0"));
+    }
+}
@@ -0,0 +1,369 @@
+//! Lazily decoded snapshots
+//!
+//! Decoding a snapshot builds its whole state at once; for a map of
+//! hundreds of megabytes that stalls whoever asked for it. With
+//! `Journal::with_lazy_snapshots`, snapshots of Map states are written in
+//! a keyed layout (`Codec::Keyed`) where every top-level entry is encoded
+//! on its own behind a length prefix:
+//!
+//! ```text
+//! [header, codec 1][4: meta length][bincode (actor_id, seq, ts)][8: entry count]
+//! [4: key length][bincode key][4: value length][bincode value]
+//! [4: key length][bincode key][4: value length][bincode value]
+//! ...
+//! ```
+//!
+//! `Journal::load_snapshot_lazy` indexes the entries without decoding
+//! them, and `LazySnapshot::get` decodes an entry the first time it is
+//! read. Only reads through a `LazySnapshot` are spared the rest of the
+//! state: loading the whole state (`into_snapshot`, `load_snapshot`, and
+//! so recovering an actor) still decodes every entry, one after another
+//! on the calling thread.
+//!
+//! ```rust,ignore
+//! let journal = Journal::new(path).with_lazy_snapshots(true);
+//! let snapshot = journal.load_snapshot_lazy(&id)?.unwrap();
+//! let users = snapshot.get(&TypedMapKey::String("users".to_string()))?;
+//! ```
+//!
+//! Other states are written in the default layout, and load whole. Each
+//! entry is bounded by the journal's `DecodeLimits` as a record of its
+//! own. Versions without keyed snapshots refuse them as an unknown codec.
+
+use super::limits::{DecodeLimits, LimitExceeded};
+use super::Snapshot;
+use crate::actor::ActorId;
+use crate::serialize::{TypedMapKey, TypedValue};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn encode<T: Serialize>(value: &T) -> std::io::Result<Vec<u8>> {
+    bincode::serialize(value).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+fn push_prefixed(body: &mut Vec<u8>, bytes: &[u8]) -> std::io::Result<()> {
+    let len = u32::try_from(bytes.len()).map_err(|_| invalid("snapshot entry over 4 GiB"))?;
+    body.extend_from_slice(&len.to_le_bytes());
+    body.extend_from_slice(bytes);
+    Ok(())
+}
+
+/// Encode a snapshot body in the keyed layout, or `None` if its state
+/// isn't a Map
+pub(crate) fn encode_keyed(snapshot: &Snapshot) -> Option<std::io::Result<Vec<u8>>> {
+    match &snapshot.state {
+        TypedValue::Map(entries) => Some(encode_entries(snapshot, entries)),
+        _ => None,
+    }
+}
+
+fn encode_entries(
+    snapshot: &Snapshot,
+    entries: &BTreeMap<TypedMapKey, TypedValue>,
+) -> std::io::Result<Vec<u8>> {
+    let mut body = vec![];
+    push_prefixed(
+        &mut body,
+        &encode(&(&snapshot.actor_id, snapshot.seq, snapshot.ts))?,
+    )?;
+    body.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+    for (key, value) in entries {
+        push_prefixed(&mut body, &encode(key)?)?;
+        push_prefixed(&mut body, &encode(value)?)?;
+    }
+    Ok(body)
+}
+
+/// Reads the keyed layout front to back
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn take(&mut self, len: usize) -> std::io::Result<Range<usize>> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| invalid("truncated keyed snapshot"))?;
+        let range = self.pos..end;
+        self.pos = end;
+        Ok(range)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> std::io::Result<[u8; N]> {
+        let range = self.take(N)?;
+        Ok(self.bytes[range].try_into().expect("range of N bytes"))
+    }
+
+    fn prefixed(&mut self) -> std::io::Result<Range<usize>> {
+        let len = u32::from_le_bytes(self.take_array()?);
+        self.take(len as usize)
+    }
+}
+
+/// One top-level entry of a keyed snapshot
+struct LazyEntry {
+    range: Range<usize>,
+    value: OnceLock<TypedValue>,
+}
+
+enum LazyState {
+    /// Decoded already (a snapshot in the default layout)
+    Whole(TypedValue),
+    Keyed {
+        bytes: Vec<u8>,
+        entries: BTreeMap<TypedMapKey, LazyEntry>,
+        /// Limits for one entry's value
+        limits: DecodeLimits,
+    },
+}
+
+/// A snapshot whose top-level state entries are decoded on access
+pub struct LazySnapshot {
+    pub actor_id: Option<ActorId>,
+    pub seq: u64,
+    pub ts: u64,
+    state: LazyState,
+}
+
+impl From<Snapshot> for LazySnapshot {
+    fn from(snapshot: Snapshot) -> Self {
+        LazySnapshot {
+            actor_id: snapshot.actor_id,
+            seq: snapshot.seq,
+            ts: snapshot.ts,
+            state: LazyState::Whole(snapshot.state),
+        }
+    }
+}
+
+fn decode_value(bytes: &[u8], limits: &DecodeLimits) -> std::io::Result<TypedValue> {
+    limits.decode(bytes, |value| value)
+}
+
+impl LazyEntry {
+    /// The value, decoding it unless it was read already
+    fn into_value(self, bytes: &[u8], limits: &DecodeLimits) -> std::io::Result<TypedValue> {
+        match self.value.into_inner() {
+            Some(value) => Ok(value),
+            None => decode_value(&bytes[self.range], limits),
+        }
+    }
+}
+
+/// Where a keyed snapshot's entries lie, with its metadata
+struct Index {
+    actor_id: Option<ActorId>,
+    seq: u64,
+    ts: u64,
+    entries: Vec<(TypedMapKey, Range<usize>)>,
+    /// Limits for one entry's value
+    limits: DecodeLimits,
+}
+
+/// Index a keyed snapshot whose body starts at `start` in `bytes`,
+/// decoding only the keys
+fn index(bytes: &[u8], start: usize, limits: &DecodeLimits) -> std::io::Result<Index> {
+    let mut cursor = Cursor { bytes, pos: start };
+    let meta = cursor.prefixed()?;
+    let (actor_id, seq, ts) = limits.decode_bounded(&bytes[meta])?;
+    let count = u64::from_le_bytes(cursor.take_array()?);
+    if count > limits.max_len as u64 {
+        return Err(LimitExceeded::TooLong {
+            len: count as usize,
+            limit: limits.max_len,
+        }
+        .into());
+    }
+    let mut entries = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let key = cursor.prefixed()?;
+        let key: TypedMapKey = limits.decode_bounded(&bytes[key])?;
+        entries.push((key, cursor.prefixed()?));
+    }
+    // The map itself is one level of the state's depth
+    let limits = DecodeLimits {
+        max_depth: limits.max_depth.saturating_sub(1),
+        ..*limits
+    };
+    Ok(Index {
+        actor_id,
+        seq,
+        ts,
+        entries,
+        limits,
+    })
+}
+
+/// Decode a keyed snapshot whose body starts at `start` in `bytes`, all
+/// at once and in place
+pub(crate) fn decode_keyed(
+    bytes: &[u8],
+    start: usize,
+    limits: &DecodeLimits,
+) -> std::io::Result<Snapshot> {
+    let index = index(bytes, start, limits)?;
+    let entries = index
+        .entries
+        .into_iter()
+        .map(|(key, range)| Ok((key, decode_value(&bytes[range], &index.limits)?)))
+        .collect::<std::io::Result<_>>()?;
+    Ok(Snapshot {
+        actor_id: index.actor_id,
+        seq: index.seq,
+        state: TypedValue::Map(entries),
+        ts: index.ts,
+    })
+}
+
+impl LazySnapshot {
+    /// Index a keyed snapshot whose body starts at `start` in `bytes`,
+    /// decoding only the keys
+    pub(crate) fn parse(
+        bytes: Vec<u8>,
+        start: usize,
+        limits: &DecodeLimits,
+    ) -> std::io::Result<Self> {
+        let index = index(&bytes, start, limits)?;
+        let entries = index
+            .entries
+            .into_iter()
+            .map(|(key, range)| {
+                let value = OnceLock::new();
+                (key, LazyEntry { range, value })
+            })
+            .collect();
+        Ok(LazySnapshot {
+            actor_id: index.actor_id,
+            seq: index.seq,
+            ts: index.ts,
+            state: LazyState::Keyed {
+                bytes,
+                entries,
+                limits: index.limits,
+            },
+        })
+    }
+
+    /// Keys of the state's top-level entries (none unless it is a Map)
+    pub fn keys(&self) -> Vec<&TypedMapKey> {
+        match &self.state {
+            LazyState::Whole(TypedValue::Map(entries)) => entries.keys().collect(),
+            LazyState::Whole(_) => vec![],
+            LazyState::Keyed { entries, .. } => entries.keys().collect(),
+        }
+    }
+
+    /// One top-level entry of the state, decoded on first access
+    pub fn get(&self, key: &TypedMapKey) -> std::io::Result<Option<&TypedValue>> {
+        let (bytes, entry, limits) = match &self.state {
+            LazyState::Whole(TypedValue::Map(entries)) => return Ok(entries.get(key)),
+            LazyState::Whole(_) => return Ok(None),
+            LazyState::Keyed {
+                bytes,
+                entries,
+                limits,
+            } => match entries.get(key) {
+                Some(entry) => (bytes, entry, limits),
+                None => return Ok(None),
+            },
+        };
+        if let Some(value) = entry.value.get() {
+            return Ok(Some(value));
+        }
+        let value = decode_value(&bytes[entry.range.clone()], limits)?;
+        Ok(Some(entry.value.get_or_init(|| value)))
+    }
+
+    /// Decode the whole state
+    ///
+    /// Entries not read yet are decoded in turn, on the calling thread.
+    pub fn into_snapshot(self) -> std::io::Result<Snapshot> {
+        let state = match self.state {
+            LazyState::Whole(state) => state,
+            LazyState::Keyed {
+                bytes,
+                entries,
+                limits,
+            } => TypedValue::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, entry)| Ok((key, entry.into_value(&bytes, &limits)?)))
+                    .collect::<std::io::Result<_>>()?,
+            ),
+        };
+        Ok(Snapshot {
+            actor_id: self.actor_id,
+            seq: self.seq,
+            state,
+            ts: self.ts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::Journal;
+    use tempfile::TempDir;
+
+    fn key(name: &str) -> TypedMapKey {
+        TypedMapKey::String(name.to_string())
+    }
+
+    #[test]
+    fn test_keyed_snapshot_decodes_entries_on_access() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path()).with_lazy_snapshots(true);
+        let id = ActorId::new();
+        let mut entries = BTreeMap::new();
+        for i in 0..100 {
+            entries.insert(key(&format!("k{}", i)), TypedValue::Int(i));
+        }
+        entries.insert(key("big"), TypedValue::String("x".repeat(10_000)));
+        let snapshot = Snapshot {
+            actor_id: None,
+            seq: 7,
+            state: TypedValue::Map(entries),
+            ts: 1,
+        };
+        journal.save_snapshot(&id, &snapshot).unwrap();
+
+        let lazy = journal.load_snapshot_lazy(&id).unwrap().unwrap();
+        assert_eq!((lazy.actor_id.as_ref(), lazy.seq), (Some(&id), 7));
+        assert_eq!(lazy.keys().len(), 101);
+        assert_eq!(lazy.get(&key("k42")).unwrap(), Some(&TypedValue::Int(42)));
+        assert_eq!(lazy.get(&key("missing")).unwrap(), None);
+
+        let loaded = journal.load_snapshot(&id).unwrap().unwrap();
+        assert_eq!(loaded.state, snapshot.state);
+        assert_eq!(lazy.into_snapshot().unwrap().state, snapshot.state);
+        let file = std::fs::read(journal.snapshot_path(&id)).unwrap();
+        assert_eq!(
+            Journal::decode_snapshot(&file).unwrap().state,
+            snapshot.state
+        );
+
+        // Non-map states keep the default layout
+        journal
+            .save_snapshot(
+                &id,
+                &Snapshot {
+                    state: TypedValue::Int(3),
+                    ..snapshot
+                },
+            )
+            .unwrap();
+        let lazy = journal.load_snapshot_lazy(&id).unwrap().unwrap();
+        assert!(lazy.keys().is_empty());
+        assert_eq!(lazy.into_snapshot().unwrap().state, TypedValue::Int(3));
+    }
+}
@@ -0,0 +1,511 @@
+//! Limits on decoded data
+//!
+//! Journal files, snapshots and offloaded messages may be corrupted, or
+//! come from somewhere untrusted (a copied journal, an archive). Decoding
+//! them is bounded by `DecodeLimits`:
+//!
+//! - `max_bytes` caps the size of one encoded record; bincode refuses to
+//!   read past it, so a corrupted length can't trigger a huge allocation
+//! - `max_depth` caps how deeply a payload or state nests maps and
+//!   variants; decoding stops once nesting goes past what the limit
+//!   allows, before a deeply nested record can exhaust the stack
+//! - `max_len` caps the entries of any one map or variant
+//!
+//! A record over a limit fails to decode with an `InvalidData` IO error
+//! carrying a typed `LimitExceeded` (see `exceeded`). The defaults are far
+//! above anything the runtime writes itself; `Journal::with_decode_limits`
+//! tightens them for a journal.
+//!
+//! ```rust,ignore
+//! let journal = Journal::new(path).with_decode_limits(DecodeLimits {
+//!     max_depth: 16,
+//!     ..DecodeLimits::default()
+//! });
+//! ```
+
+use super::MAX_RECORD_LEN;
+use crate::serialize::TypedValue;
+use bincode::Options;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Visitor};
+
+/// Bounds on what decoding a record may produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Deepest nesting of maps and variants (a scalar has depth 1)
+    pub max_depth: usize,
+    /// Most entries in one map or variant
+    pub max_len: usize,
+    /// Largest encoded record
+    pub max_bytes: u64,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        DecodeLimits {
+            max_depth: 128,
+            max_len: 1 << 20,
+            max_bytes: MAX_RECORD_LEN as u64,
+        }
+    }
+}
+
+/// A record refused because it exceeds a decode limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitExceeded {
+    TooLarge { limit: u64 },
+    TooDeep { limit: usize },
+    TooLong { len: usize, limit: usize },
+}
+
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitExceeded::TooLarge { limit } => {
+                write!(f, "record exceeds the decode limit of {} bytes", limit)
+            }
+            LimitExceeded::TooDeep { limit } => {
+                write!(f, "value nests deeper than the decode limit of {}", limit)
+            }
+            LimitExceeded::TooLong { len, limit } => {
+                write!(
+                    f,
+                    "collection of {} entries exceeds the decode limit of {}",
+                    len, limit
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+impl From<LimitExceeded> for std::io::Error {
+    fn from(exceeded: LimitExceeded) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, exceeded)
+    }
+}
+
+/// The decode limit error inside an IO error, if that's what it is
+pub fn exceeded(error: &std::io::Error) -> Option<&LimitExceeded> {
+    error.get_ref()?.downcast_ref()
+}
+
+/// Serde nesting levels one level of a `TypedValue` takes at most (a
+/// variant is an enum, its struct of fields and their sequence)
+const LEVELS_PER_DEPTH: usize = 3;
+
+/// Serde nesting levels allowed for the record around a value
+const RECORD_LEVELS: usize = 8;
+
+/// Message of the error the nesting guard stops decoding with
+const TOO_DEEP: &str = "record nests deeper than its decode limit";
+
+impl DecodeLimits {
+    /// Decode a bincode record, then check the value `value_of` picks
+    /// out of it
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        bytes: &[u8],
+        value_of: impl FnOnce(&T) -> &TypedValue,
+    ) -> std::io::Result<T> {
+        let decoded = self.decode_bounded(bytes)?;
+        self.check(value_of(&decoded))?;
+        Ok(decoded)
+    }
+
+    /// Decode a bincode record within `max_bytes`, nesting no deeper than
+    /// `max_depth` allows
+    ///
+    /// The nesting guard counts serde's levels, not the value's, so it is
+    /// looser than `check`; it keeps a corrupted record from recursing
+    /// without bound.
+    pub fn decode_bounded<T: DeserializeOwned>(&self, bytes: &[u8]) -> std::io::Result<T> {
+        if bytes.len() as u64 > self.max_bytes {
+            return Err(LimitExceeded::TooLarge {
+                limit: self.max_bytes,
+            }
+            .into());
+        }
+        // The options of `bincode::deserialize`, with a limit
+        let options = bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(self.max_bytes);
+        let mut deserializer = bincode::Deserializer::from_slice(bytes, options);
+        let levels = self
+            .max_depth
+            .saturating_mul(LEVELS_PER_DEPTH)
+            .saturating_add(RECORD_LEVELS);
+        T::deserialize(Nested {
+            inner: &mut deserializer,
+            levels,
+        })
+        .map_err(|e| match *e {
+            bincode::ErrorKind::SizeLimit => LimitExceeded::TooLarge {
+                limit: self.max_bytes,
+            }
+            .into(),
+            bincode::ErrorKind::Custom(ref msg) if msg == TOO_DEEP => LimitExceeded::TooDeep {
+                limit: self.max_depth,
+            }
+            .into(),
+            _ => std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        })
+    }
+
+    /// Check a value's depth and collection lengths
+    pub fn check(&self, value: &TypedValue) -> Result<(), LimitExceeded> {
+        let mut pending = vec![(value, 1)];
+        while let Some((value, depth)) = pending.pop() {
+            if depth > self.max_depth {
+                return Err(LimitExceeded::TooDeep {
+                    limit: self.max_depth,
+                });
+            }
+            let len = match value {
+                TypedValue::Map(fields) => {
+                    pending.extend(fields.values().map(|v| (v, depth + 1)));
+                    fields.len()
+                }
+                TypedValue::Variant { fields, .. } => {
+                    pending.extend(fields.iter().map(|v| (v, depth + 1)));
+                    fields.len()
+                }
+                _ => 0,
+            };
+            if len > self.max_len {
+                return Err(LimitExceeded::TooLong {
+                    len,
+                    limit: self.max_len,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A deserializer refusing to nest more than `levels` deeper
+///
+/// Every sequence, map, enum, option and newtype a visitor enters takes a
+/// level; the accessors handed to the visitor pass what's left on.
+struct Nested<D> {
+    inner: D,
+    levels: usize,
+}
+
+/// A visitor counting the level it enters
+struct NestedVisitor<V> {
+    inner: V,
+    levels: usize,
+}
+
+/// A seed deserializing with what's left of the levels
+struct NestedSeed<S> {
+    inner: S,
+    levels: usize,
+}
+
+/// Sequence, map, enum and variant access within the levels left
+struct NestedAccess<A> {
+    inner: A,
+    levels: usize,
+}
+
+impl<V> NestedVisitor<V> {
+    /// Levels left below this one, or the error ending decoding
+    fn enter<E: de::Error>(&self) -> Result<usize, E> {
+        self.levels
+            .checked_sub(1)
+            .ok_or_else(|| E::custom(TOO_DEEP))
+    }
+}
+
+macro_rules! forward_nested {
+    ($($method:ident($($arg:ident: $ty:ty),*))*) => {
+        $(
+            fn $method<V: Visitor<'de>>(
+                self,
+                $($arg: $ty,)*
+                visitor: V,
+            ) -> Result<V::Value, Self::Error> {
+                let visitor = NestedVisitor {
+                    inner: visitor,
+                    levels: self.levels,
+                };
+                self.inner.$method($($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, D: de::Deserializer<'de>> de::Deserializer<'de> for Nested<D> {
+    type Error = D::Error;
+
+    forward_nested! {
+        deserialize_any() deserialize_bool() deserialize_i8() deserialize_i16()
+        deserialize_i32() deserialize_i64() deserialize_i128() deserialize_u8()
+        deserialize_u16() deserialize_u32() deserialize_u64() deserialize_u128()
+        deserialize_f32() deserialize_f64() deserialize_char() deserialize_str()
+        deserialize_string() deserialize_bytes() deserialize_byte_buf()
+        deserialize_option() deserialize_unit() deserialize_seq() deserialize_map()
+        deserialize_identifier() deserialize_ignored_any()
+        deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str)
+        deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize)
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+macro_rules! forward_visit {
+    ($($method:ident($ty:ty))*) => {
+        $(
+            fn $method<E: de::Error>(self, v: $ty) -> Result<Self::Value, E> {
+                self.inner.$method(v)
+            }
+        )*
+    };
+}
+
+impl<'de, V: Visitor<'de>> Visitor<'de> for NestedVisitor<V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.expecting(f)
+    }
+
+    forward_visit! {
+        visit_bool(bool) visit_i8(i8) visit_i16(i16) visit_i32(i32) visit_i64(i64)
+        visit_i128(i128) visit_u8(u8) visit_u16(u16) visit_u32(u32) visit_u64(u64)
+        visit_u128(u128) visit_f32(f32) visit_f64(f64) visit_char(char)
+        visit_str(&str) visit_borrowed_str(&'de str) visit_string(String)
+        visit_bytes(&[u8]) visit_borrowed_bytes(&'de [u8]) visit_byte_buf(Vec<u8>)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.inner.visit_none()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.inner.visit_unit()
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, inner: D) -> Result<Self::Value, D::Error> {
+        let levels = self.enter()?;
+        self.inner.visit_some(Nested { inner, levels })
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        inner: D,
+    ) -> Result<Self::Value, D::Error> {
+        let levels = self.enter()?;
+        self.inner.visit_newtype_struct(Nested { inner, levels })
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, inner: A) -> Result<Self::Value, A::Error> {
+        let levels = self.enter()?;
+        self.inner.visit_seq(NestedAccess { inner, levels })
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(self, inner: A) -> Result<Self::Value, A::Error> {
+        let levels = self.enter()?;
+        self.inner.visit_map(NestedAccess { inner, levels })
+    }
+
+    fn visit_enum<A: de::EnumAccess<'de>>(self, inner: A) -> Result<Self::Value, A::Error> {
+        let levels = self.enter()?;
+        self.inner.visit_enum(NestedAccess { inner, levels })
+    }
+}
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for NestedSeed<S> {
+    type Value = S::Value;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, inner: D) -> Result<S::Value, D::Error> {
+        self.inner.deserialize(Nested {
+            inner,
+            levels: self.levels,
+        })
+    }
+}
+
+impl<A> NestedAccess<A> {
+    fn seed<S>(&self, inner: S) -> NestedSeed<S> {
+        NestedSeed {
+            inner,
+            levels: self.levels,
+        }
+    }
+
+    fn visitor<V>(&self, inner: V) -> NestedVisitor<V> {
+        NestedVisitor {
+            inner,
+            levels: self.levels,
+        }
+    }
+}
+
+impl<'de, A: de::SeqAccess<'de>> de::SeqAccess<'de> for NestedAccess<A> {
+    type Error = A::Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, A::Error> {
+        let seed = self.seed(seed);
+        self.inner.next_element_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, A: de::MapAccess<'de>> de::MapAccess<'de> for NestedAccess<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, A::Error> {
+        let seed = self.seed(seed);
+        self.inner.next_key_seed(seed)
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value, A::Error> {
+        let seed = self.seed(seed);
+        self.inner.next_value_seed(seed)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, A: de::EnumAccess<'de>> de::EnumAccess<'de> for NestedAccess<A> {
+    type Error = A::Error;
+    type Variant = NestedAccess<A::Variant>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Self::Variant), A::Error> {
+        let levels = self.levels;
+        let (value, inner) = self.inner.variant_seed(NestedSeed {
+            inner: seed,
+            levels,
+        })?;
+        Ok((value, NestedAccess { inner, levels }))
+    }
+}
+
+impl<'de, A: de::VariantAccess<'de>> de::VariantAccess<'de> for NestedAccess<A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        self.inner.unit_variant()
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, A::Error> {
+        let seed = self.seed(seed);
+        self.inner.newtype_variant_seed(seed)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, A::Error> {
+        let visitor = self.visitor(visitor);
+        self.inner.tuple_variant(len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        let visitor = self.visitor(visitor);
+        self.inner.struct_variant(fields, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::Event;
+
+    fn nested(depth: usize) -> TypedValue {
+        (1..depth).fold(TypedValue::Int(0), |inner, _| TypedValue::Variant {
+            tag: 0,
+            fields: vec![inner],
+        })
+    }
+
+    #[test]
+    fn test_limits_refuse_oversized_records() {
+        let limits = DecodeLimits {
+            max_depth: 4,
+            max_len: 3,
+            max_bytes: 256,
+        };
+        let decode = |payload: TypedValue| {
+            let bytes = Event::new(0, "E".to_string(), payload).to_bytes().unwrap();
+            limits.decode(&bytes, |event: &Event| &event.payload)
+        };
+
+        assert!(decode(nested(4)).is_ok());
+        let err = decode(nested(5)).unwrap_err();
+        assert_eq!(exceeded(&err), Some(&LimitExceeded::TooDeep { limit: 4 }));
+        let wide = TypedValue::Variant {
+            tag: 0,
+            fields: vec![TypedValue::Int(1); 4],
+        };
+        let err = decode(wide).unwrap_err();
+        assert_eq!(
+            exceeded(&err),
+            Some(&LimitExceeded::TooLong { len: 4, limit: 3 })
+        );
+        let err = decode(TypedValue::String("x".repeat(300))).unwrap_err();
+        assert_eq!(
+            exceeded(&err),
+            Some(&LimitExceeded::TooLarge { limit: 256 })
+        );
+
+        // A corrupted length prefix is refused rather than allocated
+        let mut bytes = Event::new(0, "E".to_string(), TypedValue::Int(1))
+            .to_bytes()
+            .unwrap();
+        bytes[9..17].copy_from_slice(&u64::MAX.to_le_bytes());
+        let err = limits
+            .decode(&bytes, |event: &Event| &event.payload)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_nesting_refused_while_decoding() {
+        let limits = DecodeLimits {
+            max_depth: 4,
+            ..DecodeLimits::default()
+        };
+        // Encoding (and dropping) a value this deep takes a large stack
+        let bytes = std::thread::Builder::new()
+            .stack_size(256 << 20)
+            .spawn(|| bincode::serialize(&nested(20_000)).unwrap())
+            .unwrap()
+            .join()
+            .unwrap();
+
+        // Decoding it whole would overflow a small one
+        let err = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(move || limits.decode_bounded::<TypedValue>(&bytes).unwrap_err())
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(exceeded(&err), Some(&LimitExceeded::TooDeep { limit: 4 }));
+    }
+}
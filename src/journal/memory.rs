@@ -0,0 +1,97 @@
+//! In-memory journal storage
+//!
+//! A journal created with `Journal::in_memory` keeps events, snapshots,
+//! aliases, singletons, blobs, rollout histories and checkpoint manifests
+//! in process memory and never touches the filesystem: nothing survives
+//! the process, and no directory is created. It is meant for tests, demos and pure-compute
+//! jobs (see `RuntimeConfig::in_memory`).
+//!
+//! Reads and writes behave as they do on disk, with these exceptions:
+//!
+//! - Operations that only make sense for files fail with `Unsupported`:
+//!   offloading messages, crash dumps, `verify`, `repair` and persistent
+//!   subscriptions
+//! - Quotas are not enforced, and `disk_usage` is always 0
+
+use super::{BlobId, Event, Snapshot};
+use crate::actor::ActorId;
+use crate::checkpoint::Checkpoint;
+use crate::rollout::RolloutDecision;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+
+#[derive(Default)]
+pub(super) struct MemoryData {
+    pub(super) events: HashMap<ActorId, Vec<Event>>,
+    pub(super) snapshots: HashMap<ActorId, Snapshot>,
+    pub(super) aliases: HashMap<ActorId, ActorId>,
+    pub(super) singletons: HashMap<String, ActorId>,
+    /// Blob contents, with when each was last stored
+    pub(super) blobs: HashMap<BlobId, (Vec<u8>, SystemTime)>,
+    pub(super) rollouts: HashMap<String, Vec<RolloutDecision>>,
+    pub(super) checkpoints: Vec<Checkpoint>,
+}
+
+/// Journal contents held in memory
+#[derive(Default)]
+pub(super) struct MemoryStore(Mutex<MemoryData>);
+
+impl MemoryStore {
+    pub(super) fn lock(&self) -> MutexGuard<'_, MemoryData> {
+        self.0.lock().expect("memory journal lock poisoned")
+    }
+}
+
+/// The error for file-only operations on an in-memory journal
+pub(super) fn unsupported(operation: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("{} is not supported by an in-memory journal", operation),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::actor::ActorId;
+    use crate::journal::{Event, Journal, Snapshot};
+    use crate::serialize::TypedValue;
+
+    #[test]
+    fn test_in_memory_journal() {
+        let journal = Journal::in_memory().with_blob_grace(std::time::Duration::ZERO);
+        let (id, alias) = (ActorId::new(), ActorId::new());
+        for seq in 0..3 {
+            let event = Event::new(seq, "Tick".to_string(), TypedValue::Int(seq as i64));
+            journal.append(&id, &event).unwrap();
+        }
+        journal.set_alias(&alias, &id).unwrap();
+        let events = journal.read_events(&alias).unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].actor_id, Some(id.clone()));
+
+        let snapshot = Snapshot {
+            actor_id: None,
+            seq: 2,
+            state: TypedValue::Int(1),
+            ts: 0,
+        };
+        journal.save_snapshot(&id, &snapshot).unwrap();
+        assert_eq!(journal.load_snapshot(&id).unwrap().unwrap().seq, 2);
+        assert!(journal.compact(&id).unwrap() > 0);
+        assert_eq!(journal.read_events(&id).unwrap().len(), 1);
+        assert_eq!(journal.actor_ids().unwrap(), {
+            let mut ids = vec![id.clone(), alias.clone()];
+            ids.sort_by_key(|id| id.0);
+            ids
+        });
+
+        let blob = journal.put_blob(b"attachment").unwrap();
+        assert_eq!(journal.get_blob(&blob).unwrap().unwrap(), b"attachment");
+        assert_eq!(journal.collect_blobs().unwrap(), 1);
+
+        let err = journal.verify(&id).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+        assert!(journal.offload_message(&TypedValue::Int(1)).is_err());
+    }
+}
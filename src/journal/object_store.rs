@@ -0,0 +1,215 @@
+//! Snapshots and archives in an object store (feature `object-store`)
+//!
+//! `ObjectStoreBackend` keeps an actor's long-term history in S3, GCS,
+//! Azure or any other `object_store::ObjectStore`, while the hot end of
+//! the journal stays on local disk. Objects live under a prefix:
+//!
+//! ```text
+//! {prefix}/{actor_id}/snapshot.bin
+//! {prefix}/{actor_id}/archive/{first_seq}-{last_seq}.sqar
+//! ```
+//!
+//! Snapshots use the same encoding as `snapshot.bin` on disk, archives the
+//! segment format of `archive` (seqs zero-padded so keys list in order).
+//! `offload` moves everything a local snapshot covers into the store and
+//! compacts it out of the journal:
+//!
+//! ```rust,ignore
+//! let s3 = AmazonS3Builder::from_env().with_bucket_name("history").build()?;
+//! let backend = ObjectStoreBackend::new(Arc::new(s3), "prod/actors")?;
+//! backend.offload(runtime.journal(), &actor_id)?;
+//! ```
+//!
+//! The object store API is async; the backend drives it on a private
+//! tokio runtime, so its methods block and must not be called from
+//! inside another tokio runtime.
+
+use super::archive::ArchiveInfo;
+use super::limits::DecodeLimits;
+use super::{Event, FileHeader, Journal, Snapshot, SNAPSHOT_MAGIC};
+use crate::actor::ActorId;
+use object_store::path::Path;
+use object_store::{ObjectStore, PutPayload};
+use std::sync::Arc;
+
+/// Snapshot and archive storage in an object store
+pub struct ObjectStoreBackend {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+    runtime: tokio::runtime::Runtime,
+    decode_limits: DecodeLimits,
+}
+
+impl ObjectStoreBackend {
+    /// Store objects in `store` under `prefix`
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: &str) -> std::io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(ObjectStoreBackend {
+            store,
+            prefix: Path::from(prefix),
+            runtime,
+            decode_limits: DecodeLimits::default(),
+        })
+    }
+
+    /// Bound what reading snapshots and archives back may decode (see
+    /// `limits`)
+    pub fn with_decode_limits(mut self, limits: DecodeLimits) -> Self {
+        self.decode_limits = limits;
+        self
+    }
+
+    fn actor_path(&self, actor_id: &ActorId) -> Path {
+        self.prefix.child(actor_id.as_str())
+    }
+
+    fn archive_dir(&self, actor_id: &ActorId) -> Path {
+        self.actor_path(actor_id).child("archive")
+    }
+
+    fn put(&self, path: &Path, data: Vec<u8>) -> std::io::Result<()> {
+        self.runtime
+            .block_on(self.store.put(path, PutPayload::from(data)))?;
+        Ok(())
+    }
+
+    /// Contents of an object, or `None` if it doesn't exist
+    fn get(&self, path: &Path) -> std::io::Result<Option<Vec<u8>>> {
+        self.runtime.block_on(async {
+            match self.store.get(path).await {
+                Ok(result) => Ok(Some(result.bytes().await?.to_vec())),
+                Err(object_store::Error::NotFound { .. }) => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+
+    /// Upload a snapshot, replacing the actor's previous one
+    pub fn put_snapshot(&self, actor_id: &ActorId, snapshot: &Snapshot) -> std::io::Result<()> {
+        let snapshot = Snapshot {
+            actor_id: Some(actor_id.clone()),
+            ..snapshot.clone()
+        };
+        let mut data = FileHeader::current().to_bytes(SNAPSHOT_MAGIC).to_vec();
+        data.extend_from_slice(&snapshot.to_bytes()?);
+        self.put(&self.actor_path(actor_id).child("snapshot.bin"), data)
+    }
+
+    /// Download the actor's snapshot, if one was uploaded
+    pub fn get_snapshot(&self, actor_id: &ActorId) -> std::io::Result<Option<Snapshot>> {
+        self.get(&self.actor_path(actor_id).child("snapshot.bin"))?
+            .map(|data| Journal::decode_snapshot_within(&data, &self.decode_limits))
+            .transpose()
+    }
+
+    /// Upload the actor's journaled events with `seq < before_seq` as an
+    /// archive segment
+    ///
+    /// Returns `None` (uploading nothing) if there are no such events.
+    pub fn archive(
+        &self,
+        journal: &Journal,
+        actor_id: &ActorId,
+        before_seq: u64,
+    ) -> std::io::Result<Option<ArchiveInfo>> {
+        let mut segment = vec![];
+        let info = journal.archive(actor_id, before_seq, &mut segment)?;
+        let (Some(first), Some(last)) = (info.first_seq, info.last_seq) else {
+            return Ok(None);
+        };
+        let name = format!("{:020}-{:020}.sqar", first, last);
+        self.put(&self.archive_dir(actor_id).child(name), segment)?;
+        Ok(Some(info))
+    }
+
+    /// Every archived event for the actor, in seq order
+    pub fn read_archives(&self, actor_id: &ActorId) -> std::io::Result<Vec<Event>> {
+        let dir = self.archive_dir(actor_id);
+        let listing = self
+            .runtime
+            .block_on(self.store.list_with_delimiter(Some(&dir)))?;
+        let mut paths: Vec<Path> = listing.objects.into_iter().map(|o| o.location).collect();
+        paths.sort();
+
+        let mut events: Vec<Event> = vec![];
+        for path in paths {
+            let Some(data) = self.get(&path)? else {
+                continue;
+            };
+            let archive = Journal::read_archive_within(&data[..], &self.decode_limits)?;
+            // Segments archived twice overlap; keep each seq once
+            let after = events.last().map(|e| e.seq);
+            events.extend(
+                archive
+                    .events
+                    .into_iter()
+                    .filter(|e| after.is_none_or(|after| e.seq > after)),
+            );
+        }
+        Ok(events)
+    }
+
+    /// Move the history covered by the actor's local snapshot to the
+    /// store: upload the snapshot, archive the events before it, and
+    /// compact them out of the local journal
+    ///
+    /// Returns the archived segment's info (`None` if there was no local
+    /// snapshot or nothing to archive).
+    pub fn offload(
+        &self,
+        journal: &Journal,
+        actor_id: &ActorId,
+    ) -> std::io::Result<Option<ArchiveInfo>> {
+        let Some(snapshot) = journal.load_snapshot(actor_id)? else {
+            return Ok(None);
+        };
+        self.put_snapshot(actor_id, &snapshot)?;
+        let info = self.archive(journal, actor_id, snapshot.seq)?;
+        journal.compact(actor_id)?;
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialize::TypedValue;
+    use object_store::memory::InMemory;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_offload_to_object_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let backend = ObjectStoreBackend::new(Arc::new(InMemory::new()), "actors").unwrap();
+        let actor_id = ActorId::new();
+
+        let tick = |seq| Event::new(seq, "Tick".to_string(), TypedValue::Int(seq as i64));
+        for seq in 0..3 {
+            journal.append(&actor_id, &tick(seq)).unwrap();
+        }
+        let snapshot = Snapshot {
+            actor_id: None,
+            seq: 2,
+            state: TypedValue::Int(1),
+            ts: 0,
+        };
+        journal.save_snapshot(&actor_id, &snapshot).unwrap();
+        assert_eq!(backend.get_snapshot(&actor_id).unwrap(), None);
+
+        let info = backend.offload(&journal, &actor_id).unwrap().unwrap();
+        assert_eq!((info.first_seq, info.last_seq), (Some(0), Some(1)));
+        assert_eq!(journal.read_events(&actor_id).unwrap().len(), 1);
+        let uploaded = backend.get_snapshot(&actor_id).unwrap().unwrap();
+        assert_eq!((uploaded.seq, uploaded.state), (2, TypedValue::Int(1)));
+
+        // A later segment continues the history
+        journal.append(&actor_id, &tick(3)).unwrap();
+        backend.archive(&journal, &actor_id, 4).unwrap();
+        let archived = backend.read_archives(&actor_id).unwrap();
+        let seqs: Vec<u64> = archived.iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, [0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,86 @@
+//! Disk quotas for a journal base path
+//!
+//! A `Quota` caps the bytes a journal may use, in total under its base
+//! path and/or per actor (journal plus snapshot). `Journal::append` checks
+//! the quota before writing. If the record would go over:
+//!
+//! - `QuotaAction::Refuse` fails the append with a `QuotaExceeded` error
+//! - `QuotaAction::Compact` first compacts the appending actor's journal
+//!   (dropping events its latest snapshot already covers) and only fails
+//!   if that didn't free enough
+//!
+//! `QuotaExceeded` travels inside the `std::io::Error` returned by the
+//! journal (see `exceeded`), and surfaces from the runtime as
+//! `RuntimeError::QuotaExceeded`.
+//!
+//! Total usage is measured once by walking the base path, then kept up to
+//! date by the journal's own writes. Call `Journal::refresh_usage` after
+//! changing files behind its back.
+
+use crate::actor::ActorId;
+
+/// What to do when an append would exceed the quota
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuotaAction {
+    /// Fail the append
+    #[default]
+    Refuse,
+    /// Compact the actor's journal, then fail if still over
+    Compact,
+}
+
+/// Byte limits for a journal
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Quota {
+    /// Bytes under the base path (None = unlimited)
+    pub max_total_bytes: Option<u64>,
+    /// Bytes per actor, journal plus snapshot (None = unlimited)
+    pub max_actor_bytes: Option<u64>,
+    pub on_exceeded: QuotaAction,
+}
+
+/// Which limit was hit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaScope {
+    Total,
+    Actor,
+}
+
+/// An append refused because it would exceed the quota
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaExceeded {
+    /// Actor whose append was refused
+    pub actor_id: ActorId,
+    pub scope: QuotaScope,
+    /// The limit that would have been exceeded
+    pub limit: u64,
+    /// Bytes in use, before the refused record
+    pub used: u64,
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let scope = match self.scope {
+            QuotaScope::Total => "journal",
+            QuotaScope::Actor => "actor",
+        };
+        write!(
+            f,
+            "{} quota of {} bytes exceeded appending for {} ({} bytes used)",
+            scope, self.limit, self.actor_id, self.used
+        )
+    }
+}
+
+impl std::error::Error for QuotaExceeded {}
+
+impl From<QuotaExceeded> for std::io::Error {
+    fn from(e: QuotaExceeded) -> Self {
+        std::io::Error::new(std::io::ErrorKind::StorageFull, e)
+    }
+}
+
+/// The quota error inside a journal IO error, if that's what it is
+pub fn exceeded(error: &std::io::Error) -> Option<&QuotaExceeded> {
+    error.get_ref()?.downcast_ref()
+}
@@ -0,0 +1,370 @@
+//! Repairing inconsistent journals
+//!
+//! Bugs and manual edits can leave a journal whose sequence numbers repeat
+//! or run backwards, which `Journal::verify` reports and recovery folds in
+//! blindly. `Journal::repair` rewrites the journal under one of three
+//! policies:
+//!
+//! - `Renumber`: keep every event, renumbering them consecutively in file
+//!   order from the first event's seq. Corrections (see `correction`)
+//!   follow the event they target to its new seq; a journal where that
+//!   event can't be told apart (its seq repeats before the correction, or
+//!   no event before it has that seq) is refused with `InvalidData`
+//! - `DropDuplicates`: drop each event whose seq isn't past the last one
+//!   kept
+//! - `Quarantine`: like `DropDuplicates`, but move those events to a
+//!   sidecar journal, `{actor_dir}/quarantine.bin`, for inspection
+//!   (readable with `Journal::decode_events`)
+//!
+//! Under every policy, records that can't be decoded, and anything after a
+//! framing error, are discarded. Each change is appended to
+//! `{actor_dir}/repair.log` and returned in the `RepairLog`.
+//!
+//! Renumbering moves events relative to the snapshot's seq, so check the
+//! result with `Journal::verify` before restarting the actor.
+
+use super::correction::{correction_of, retarget};
+use super::verify::{read_records, Issue};
+use super::{write_frame, Event, FileHeader, Journal, JOURNAL_MAGIC};
+use crate::actor::ActorId;
+use crate::runtime::now_millis;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+/// Sidecar journal holding quarantined events
+const QUARANTINE_FILE: &str = "quarantine.bin";
+
+/// Text log of every repair made to an actor's journal
+const REPAIR_LOG_FILE: &str = "repair.log";
+
+/// How `Journal::repair` resolves out-of-order and duplicate events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairPolicy {
+    Renumber,
+    DropDuplicates,
+    Quarantine,
+}
+
+/// One change made by `Journal::repair`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairAction {
+    /// An event's seq was changed
+    Renumbered { from: u64, to: u64 },
+    /// The correction record `seq` was pointed at its target's new seq
+    Retargeted { seq: u64, from: u64, to: u64 },
+    /// An out-of-order or duplicate event was dropped
+    Dropped { seq: u64 },
+    /// An out-of-order or duplicate event was moved to the sidecar
+    Quarantined { seq: u64 },
+    /// Unreadable bytes were left out of the rewritten journal
+    Discarded(Issue),
+}
+
+impl fmt::Display for RepairAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepairAction::Renumbered { from, to } => {
+                write!(f, "renumbered event {} to {}", from, to)
+            }
+            RepairAction::Retargeted { seq, from, to } => {
+                write!(
+                    f,
+                    "retargeted correction {} from event {} to {}",
+                    seq, from, to
+                )
+            }
+            RepairAction::Dropped { seq } => write!(f, "dropped event {}", seq),
+            RepairAction::Quarantined { seq } => write!(f, "quarantined event {}", seq),
+            RepairAction::Discarded(issue) => write!(f, "discarded {}", issue),
+        }
+    }
+}
+
+/// What `Journal::repair` did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairLog {
+    pub actor_id: ActorId,
+    pub policy: RepairPolicy,
+    /// Changes in journal order; empty if the journal needed none
+    pub actions: Vec<RepairAction>,
+}
+
+impl Journal {
+    /// Rewrite an actor's journal so its sequence numbers strictly
+    /// increase, resolving anomalies by `policy`
+    ///
+    /// The journal is left untouched if nothing needs repairing.
+    pub fn repair(&self, actor_id: &ActorId, policy: RepairPolicy) -> std::io::Result<RepairLog> {
+        self.require_files("repair")?;
+        let lock = self.append_lock(&self.resolve_alias(actor_id));
+        let _guard = lock.lock().expect("append lock poisoned");
+
+        let mut log = RepairLog {
+            actor_id: actor_id.clone(),
+            policy,
+            actions: vec![],
+        };
+        let path = self.journal_path(actor_id);
+        if !path.exists() {
+            return Ok(log);
+        }
+        let data = fs::read(&path)?;
+
+        let mut kept: Vec<Event> = vec![];
+        let mut quarantined = vec![];
+        // Renumbering: the new seqs of the events kept so far, by old seq
+        let mut renumbered: HashMap<u64, Vec<u64>> = HashMap::new();
+        for record in read_records(&data).1 {
+            let mut event = match record {
+                Ok(event) => event,
+                Err(issue) => {
+                    log.actions.push(RepairAction::Discarded(issue));
+                    continue;
+                }
+            };
+            let Some(previous) = kept.last().map(|e| e.seq) else {
+                renumbered.entry(event.seq).or_default().push(event.seq);
+                kept.push(event);
+                continue;
+            };
+            if event.seq > previous && policy != RepairPolicy::Renumber {
+                kept.push(event);
+                continue;
+            }
+            match policy {
+                RepairPolicy::Renumber => {
+                    let old = event.seq;
+                    if old != previous + 1 {
+                        log.actions.push(RepairAction::Renumbered {
+                            from: old,
+                            to: previous + 1,
+                        });
+                        event.seq = previous + 1;
+                    }
+                    match correction_of(&event) {
+                        Some((target, _)) => {
+                            let to = match renumbered.get(&target).map(Vec::as_slice) {
+                                Some(&[to]) => to,
+                                _ => {
+                                    return Err(std::io::Error::new(
+                                        std::io::ErrorKind::InvalidData,
+                                        format!(
+                                            "correction {} of {} targets event {}, which can't be \
+                                             told apart for renumbering",
+                                            old, actor_id, target
+                                        ),
+                                    ))
+                                }
+                            };
+                            if to != target {
+                                log.actions.push(RepairAction::Retargeted {
+                                    seq: event.seq,
+                                    from: target,
+                                    to,
+                                });
+                                retarget(&mut event, to);
+                            }
+                        }
+                        None => renumbered.entry(old).or_default().push(event.seq),
+                    }
+                    kept.push(event);
+                }
+                RepairPolicy::DropDuplicates => {
+                    log.actions.push(RepairAction::Dropped { seq: event.seq });
+                }
+                RepairPolicy::Quarantine => {
+                    log.actions
+                        .push(RepairAction::Quarantined { seq: event.seq });
+                    quarantined.push(event);
+                }
+            }
+        }
+        if log.actions.is_empty() {
+            return Ok(log);
+        }
+
+        if !quarantined.is_empty() {
+            self.append_quarantine(actor_id, &quarantined)?;
+        }
+
+        let mut rewritten = FileHeader::current().to_bytes(JOURNAL_MAGIC).to_vec();
+        for event in &kept {
+            write_frame(&mut rewritten, &event.to_bytes()?)?;
+        }
+        // Write then rename so a crash leaves either journal intact
+        let tmp = path.with_extension("bin.repair");
+        fs::write(&tmp, &rewritten)?;
+        fs::rename(tmp, &path)?;
+        self.adjust_usage(rewritten.len() as i64 - data.len() as i64);
+
+        self.write_repair_log(&log)?;
+        Ok(log)
+    }
+
+    /// Add events to the actor's quarantine sidecar
+    fn append_quarantine(&self, actor_id: &ActorId, events: &[Event]) -> std::io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.actor_dir(actor_id).join(QUARANTINE_FILE))?;
+        let mut buf = vec![];
+        if file.metadata()?.len() == 0 {
+            buf.extend_from_slice(&FileHeader::current().to_bytes(JOURNAL_MAGIC));
+        }
+        for event in events {
+            write_frame(&mut buf, &event.to_bytes()?)?;
+        }
+        file.write_all(&buf)?;
+        self.adjust_usage(buf.len() as i64);
+        Ok(())
+    }
+
+    /// Append a repair's actions to the actor's repair log
+    fn write_repair_log(&self, log: &RepairLog) -> std::io::Result<()> {
+        let mut text = String::new();
+        let ts = now_millis();
+        for action in &log.actions {
+            text.push_str(&format!("{} {:?}: {}\n", ts, log.policy, action));
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.actor_dir(&log.actor_id).join(REPAIR_LOG_FILE))?;
+        file.write_all(text.as_bytes())?;
+        self.adjust_usage(text.len() as i64);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialize::TypedValue;
+    use tempfile::TempDir;
+
+    /// An actor whose journal holds events with `seqs`, in that order
+    fn journal_with(journal: &Journal, seqs: &[u64]) -> ActorId {
+        let actor_id = ActorId::new();
+        for &seq in seqs {
+            let event = Event::new(seq, "Tick".to_string(), TypedValue::Int(seq as i64));
+            journal.append(&actor_id, &event).unwrap();
+        }
+        actor_id
+    }
+
+    fn seqs(events: &[Event]) -> Vec<u64> {
+        events.iter().map(|e| e.seq).collect()
+    }
+
+    #[test]
+    fn test_repair_policies() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let anomalies = [0, 1, 1, 3, 2];
+
+        let renumbered = journal_with(&journal, &anomalies);
+        let log = journal.repair(&renumbered, RepairPolicy::Renumber).unwrap();
+        assert_eq!(
+            log.actions,
+            [
+                RepairAction::Renumbered { from: 1, to: 2 },
+                RepairAction::Renumbered { from: 2, to: 4 },
+            ]
+        );
+        assert_eq!(
+            seqs(&journal.read_events(&renumbered).unwrap()),
+            [0, 1, 2, 3, 4]
+        );
+
+        let dropped = journal_with(&journal, &anomalies);
+        let log = journal
+            .repair(&dropped, RepairPolicy::DropDuplicates)
+            .unwrap();
+        assert_eq!(
+            log.actions,
+            [
+                RepairAction::Dropped { seq: 1 },
+                RepairAction::Dropped { seq: 2 },
+            ]
+        );
+        assert_eq!(seqs(&journal.read_events(&dropped).unwrap()), [0, 1, 3]);
+
+        let quarantined = journal_with(&journal, &anomalies);
+        journal
+            .repair(&quarantined, RepairPolicy::Quarantine)
+            .unwrap();
+        assert_eq!(seqs(&journal.read_events(&quarantined).unwrap()), [0, 1, 3]);
+        let sidecar = fs::read(journal.actor_dir(&quarantined).join(QUARANTINE_FILE)).unwrap();
+        assert_eq!(seqs(&Journal::decode_events(&sidecar[..]).unwrap()), [1, 2]);
+        let text = fs::read_to_string(journal.actor_dir(&quarantined).join(REPAIR_LOG_FILE));
+        assert!(text.unwrap().ends_with("Quarantine: quarantined event 2\n"));
+
+        // Nothing left to repair
+        let again = journal.repair(&renumbered, RepairPolicy::Renumber).unwrap();
+        assert!(again.actions.is_empty());
+        assert!(journal.verify(&renumbered).unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_renumber_retargets_corrections() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let fixed = Event::new(0, "Tick".to_string(), TypedValue::Int(-1));
+
+        let actor_id = journal_with(&journal, &[0, 0, 1]);
+        assert_eq!(journal.append_correction(&actor_id, 1, &fixed).unwrap(), 2);
+        let log = journal.repair(&actor_id, RepairPolicy::Renumber).unwrap();
+        assert_eq!(
+            log.actions,
+            [
+                RepairAction::Renumbered { from: 0, to: 1 },
+                RepairAction::Renumbered { from: 1, to: 2 },
+                RepairAction::Renumbered { from: 2, to: 3 },
+                RepairAction::Retargeted {
+                    seq: 3,
+                    from: 1,
+                    to: 2
+                },
+            ]
+        );
+        let events =
+            crate::journal::correction::apply_corrections(journal.read_events(&actor_id).unwrap());
+        let payloads: Vec<_> = events.iter().map(|e| e.payload.clone()).collect();
+        assert_eq!(
+            payloads,
+            [TypedValue::Int(0), TypedValue::Int(0), TypedValue::Int(-1)]
+        );
+
+        // A correction to a repeated seq could mean either event
+        let ambiguous = journal_with(&journal, &[0, 0, 1]);
+        journal.append_correction(&ambiguous, 0, &fixed).unwrap();
+        let before = fs::read(journal.journal_path(&ambiguous)).unwrap();
+        let err = journal
+            .repair(&ambiguous, RepairPolicy::Renumber)
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(fs::read(journal.journal_path(&ambiguous)).unwrap(), before);
+    }
+
+    #[test]
+    fn test_repair_discards_torn_tail() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let actor_id = journal_with(&journal, &[0, 1]);
+        let path = journal.journal_path(&actor_id);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[9, 0]).unwrap();
+
+        let log = journal
+            .repair(&actor_id, RepairPolicy::DropDuplicates)
+            .unwrap();
+        assert!(matches!(
+            log.actions[..],
+            [RepairAction::Discarded(Issue::TornTail { .. })]
+        ));
+        assert!(journal.verify(&actor_id).unwrap().is_ok());
+        assert_eq!(seqs(&journal.read_events(&actor_id).unwrap()), [0, 1]);
+    }
+}
@@ -0,0 +1,86 @@
+//! Rollout decision history
+//!
+//! Each promotion or rollback made by a `crate::rollout::Rollout` is
+//! appended to `rollouts/{name}.bin` under the journal path, using the
+//! journal's header and record framing:
+//!
+//! ```text
+//! [8: header, magic "SQRO"]
+//! [4: length][bincode RolloutDecision]   (once per decision)
+//! ```
+
+use super::{check_name, read_frame, write_frame, FileHeader, Journal};
+use crate::rollout::RolloutDecision;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Magic bytes opening a rollout history
+pub const ROLLOUT_MAGIC: [u8; 4] = *b"SQRO";
+
+/// Directory holding rollout histories, under the journal path
+const ROLLOUTS_DIR: &str = "rollouts";
+
+fn invalid(e: bincode::Error) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+}
+
+impl Journal {
+    fn rollout_path(&self, name: &str) -> PathBuf {
+        self.base_path
+            .join(ROLLOUTS_DIR)
+            .join(format!("{}.bin", name))
+    }
+
+    /// Append a decision to the named rollout's history
+    ///
+    /// Fails with `InvalidInput` if `name` isn't a plain file name.
+    pub fn record_rollout(&self, name: &str, decision: &RolloutDecision) -> std::io::Result<()> {
+        check_name("rollout", name)?;
+        if let Some(memory) = &self.memory {
+            let mut memory = memory.lock();
+            memory
+                .rollouts
+                .entry(name.to_string())
+                .or_default()
+                .push(decision.clone());
+            return Ok(());
+        }
+        let path = self.rollout_path(name);
+        fs::create_dir_all(path.parent().expect("rollout path has a parent"))?;
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut buf = vec![];
+        if file.metadata()?.len() == 0 {
+            buf.extend_from_slice(&FileHeader::current().to_bytes(ROLLOUT_MAGIC));
+        }
+        write_frame(&mut buf, &bincode::serialize(decision).map_err(invalid)?)?;
+        file.write_all(&buf)?;
+        self.adjust_usage(buf.len() as i64);
+        Ok(())
+    }
+
+    /// Every decision made for the named rollout, oldest first
+    pub fn rollout_history(&self, name: &str) -> std::io::Result<Vec<RolloutDecision>> {
+        check_name("rollout", name)?;
+        if let Some(memory) = &self.memory {
+            return Ok(memory
+                .lock()
+                .rollouts
+                .get(name)
+                .cloned()
+                .unwrap_or_default());
+        }
+        let data = match fs::read(self.rollout_path(name)) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+        let mut reader = &data[..];
+        FileHeader::read(&mut reader, ROLLOUT_MAGIC)?;
+        let mut decisions = vec![];
+        while let Some(record) = read_frame(&mut reader)? {
+            decisions.push(bincode::deserialize(&record).map_err(invalid)?);
+        }
+        Ok(decisions)
+    }
+}
@@ -0,0 +1,128 @@
+//! Journal statistics
+//!
+//! `Journal::stats` summarizes one actor's persisted history (how many
+//! events, which sequence and time range they cover, how much disk they
+//! take, and how stale the snapshot is) for capacity planning.
+//! `Journal::stats_all` does the same for every actor under the base path.
+
+use super::Journal;
+use crate::actor::ActorId;
+use crate::runtime::now_millis;
+use std::fs;
+use std::time::Duration;
+
+/// Summary of one actor's journal and snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalStats {
+    pub actor_id: ActorId,
+    /// Events currently in the journal (after any compaction)
+    pub event_count: u64,
+    pub first_seq: Option<u64>,
+    pub last_seq: Option<u64>,
+    /// Unix timestamp (milliseconds) of the first event
+    pub first_ts: Option<u64>,
+    /// Unix timestamp (milliseconds) of the last event
+    pub last_ts: Option<u64>,
+    pub journal_bytes: u64,
+    pub snapshot_bytes: u64,
+    /// Sequence number the snapshot was taken at
+    pub snapshot_seq: Option<u64>,
+    /// Time since the snapshot was taken
+    pub snapshot_age: Option<Duration>,
+    /// Journal files holding the events (0 or 1 until journals are
+    /// segmented)
+    pub segments: u32,
+}
+
+/// Size of a file, 0 if it doesn't exist
+fn file_len(path: &std::path::Path) -> std::io::Result<u64> {
+    match fs::metadata(path) {
+        Ok(meta) => Ok(meta.len()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+impl Journal {
+    /// Statistics for one actor (all zero / `None` if it has no journal)
+    pub fn stats(&self, actor_id: &ActorId) -> std::io::Result<JournalStats> {
+        let events = self.read_events(actor_id)?;
+        let snapshot = self.load_snapshot(actor_id)?;
+        let journal_bytes = file_len(&self.journal_path(actor_id))?;
+
+        Ok(JournalStats {
+            actor_id: actor_id.clone(),
+            event_count: events.len() as u64,
+            first_seq: events.first().map(|e| e.seq),
+            last_seq: events.last().map(|e| e.seq),
+            first_ts: events.first().map(|e| e.ts),
+            last_ts: events.last().map(|e| e.ts),
+            journal_bytes,
+            snapshot_bytes: file_len(&self.snapshot_path(actor_id))?,
+            snapshot_seq: snapshot.as_ref().map(|s| s.seq),
+            snapshot_age: snapshot
+                .map(|s| Duration::from_millis(now_millis().saturating_sub(s.ts))),
+            segments: u32::from(journal_bytes > 0),
+        })
+    }
+
+    /// Statistics for every actor under the base path
+    ///
+    /// Aliased IDs are skipped; their history is reported under the actor
+    /// they point to.
+    pub fn stats_all(&self) -> std::io::Result<Vec<JournalStats>> {
+        self.actor_ids()?
+            .into_iter()
+            .filter(|id| self.alias_of(id).is_none())
+            .map(|id| self.stats(&id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::{Event, Snapshot};
+    use crate::serialize::TypedValue;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_stats() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let (actor_id, empty) = (ActorId::new(), ActorId::new());
+
+        for seq in 0..3 {
+            let mut event = Event::new(seq, "Tick".to_string(), TypedValue::Int(0));
+            event.ts = 1_000 + seq;
+            journal.append(&actor_id, &event).unwrap();
+        }
+        let snapshot = Snapshot {
+            actor_id: None,
+            seq: 3,
+            state: TypedValue::Int(3),
+            ts: now_millis(),
+        };
+        journal.save_snapshot(&actor_id, &snapshot).unwrap();
+
+        let stats = journal.stats(&actor_id).unwrap();
+        assert_eq!(stats.event_count, 3);
+        assert_eq!((stats.first_seq, stats.last_seq), (Some(0), Some(2)));
+        assert_eq!((stats.first_ts, stats.last_ts), (Some(1_000), Some(1_002)));
+        assert!(stats.journal_bytes > 0 && stats.snapshot_bytes > 0);
+        assert_eq!(stats.snapshot_seq, Some(3));
+        assert!(stats.snapshot_age.unwrap() < Duration::from_secs(60));
+        assert_eq!(stats.segments, 1);
+
+        let none = journal.stats(&empty).unwrap();
+        assert_eq!(
+            (none.event_count, none.segments, none.snapshot_seq),
+            (0, 0, None)
+        );
+
+        journal.set_alias(&empty, &actor_id).unwrap();
+        let all = journal.stats_all().unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!((&all[0].actor_id, all[0].event_count), (&actor_id, 3));
+    }
+}
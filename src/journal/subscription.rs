@@ -0,0 +1,430 @@
+//! Persistent subscriptions for external consumers
+//!
+//! A persistent subscription reads new events from every actor under the
+//! journal's base path and remembers, on disk, how far its consumer has
+//! acknowledged. An exporter process that restarts opens the subscription
+//! under the same name and resumes exactly where it left off:
+//!
+//! ```rust,ignore
+//! let journal = Journal::new("./actors");
+//! let mut sub = journal.subscribe_persistent("exporter", StartFrom::Beginning)?;
+//! loop {
+//!     for event in sub.poll()? {
+//!         publish(&event)?;
+//!         sub.ack(&event)?;
+//!     }
+//! }
+//! ```
+//!
+//! Delivery is at-least-once: events polled but not acknowledged before a
+//! restart are delivered again. Checkpoints live in
+//! `{base_path}/subscriptions/{name}.bin` and record, per actor, the next
+//! sequence number to deliver.
+//!
+//! A subscription remembers where it stopped reading each journal file,
+//! so a poll reads only what was appended since (the whole file again
+//! only after it was compacted or repaired).
+
+use super::{check_name, Journal, ReadOffset};
+use crate::actor::ActorId;
+use crate::journal::Event;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where a subscription without a checkpoint starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StartFrom {
+    /// Every event already journaled
+    #[default]
+    Beginning,
+    /// Only events journaled after the subscription is created
+    Now,
+}
+
+/// Next sequence number to deliver, per actor
+type Positions = HashMap<ActorId, u64>;
+
+/// A subscription set aside between uses, to resume without rereading
+/// its checkpoint or the journals it has read
+#[derive(Debug, Clone)]
+pub(crate) struct Progress {
+    name: String,
+    acked: Positions,
+    delivered: Positions,
+    offsets: HashMap<ActorId, ReadOffset>,
+}
+
+/// A named, checkpointed reader over every actor's journal
+pub struct PersistentSubscription<'a> {
+    journal: &'a Journal,
+    name: String,
+    /// Acknowledged positions, as persisted
+    acked: Positions,
+    /// Positions already returned by `poll`
+    delivered: Positions,
+    /// Where the last poll stopped reading each actor's journal
+    offsets: HashMap<ActorId, ReadOffset>,
+}
+
+impl Journal {
+    fn subscriptions_dir(&self) -> PathBuf {
+        self.base_path.join("subscriptions")
+    }
+
+    fn checkpoint_path(&self, name: &str) -> PathBuf {
+        self.subscriptions_dir().join(format!("{}.bin", name))
+    }
+
+    /// Open the persistent subscription `name`, resuming from its
+    /// checkpoint if it has one
+    ///
+    /// `from` only applies the first time a subscription is opened.
+    /// Fails with `InvalidInput` if `name` isn't a plain file name.
+    pub fn subscribe_persistent(
+        &self,
+        name: &str,
+        from: StartFrom,
+    ) -> std::io::Result<PersistentSubscription<'_>> {
+        check_name("subscription", name)?;
+        self.require_files("persistent subscriptions")?;
+
+        let path = self.checkpoint_path(name);
+        if path.exists() {
+            let acked = read_checkpoint(&path)?;
+            return Ok(PersistentSubscription {
+                journal: self,
+                name: name.to_string(),
+                delivered: acked.clone(),
+                acked,
+                offsets: HashMap::new(),
+            });
+        }
+
+        let mut positions = Positions::new();
+        if from == StartFrom::Now {
+            for id in self.subscribed_ids()? {
+                if let Some(last) = self.read_events(&id)?.last() {
+                    positions.insert(id, last.seq + 1);
+                }
+            }
+        }
+        let sub = PersistentSubscription {
+            journal: self,
+            name: name.to_string(),
+            acked: positions.clone(),
+            delivered: positions,
+            offsets: HashMap::new(),
+        };
+        sub.save()?;
+        Ok(sub)
+    }
+
+    /// Pick up a subscription set aside with `PersistentSubscription::suspend`
+    pub(crate) fn resume_subscription(&self, progress: Progress) -> PersistentSubscription<'_> {
+        PersistentSubscription {
+            journal: self,
+            name: progress.name,
+            acked: progress.acked,
+            delivered: progress.delivered,
+            offsets: progress.offsets,
+        }
+    }
+
+    /// Actors whose events subscriptions read (aliases are skipped: their
+    /// events live under the actor they point to)
+    fn subscribed_ids(&self) -> std::io::Result<Vec<ActorId>> {
+        Ok(self
+            .actor_ids()?
+            .into_iter()
+            .filter(|id| self.alias_of(id).is_none())
+            .collect())
+    }
+
+    /// Lowest position any subscription has acknowledged for `actor` (0
+    /// if there are no subscriptions yet)
+    ///
+    /// Compaction keeps the outbox intents from there on.
+    pub(crate) fn acknowledged_from(&self, actor: &ActorId) -> std::io::Result<u64> {
+        let entries = match fs::read_dir(self.subscriptions_dir()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let mut lowest: Option<u64> = None;
+        for entry in entries {
+            let path = entry?.path();
+            // Skip checkpoints being written
+            if path.extension() != Some("bin".as_ref()) {
+                continue;
+            }
+            let next = read_checkpoint(&path)?.get(actor).copied().unwrap_or(0);
+            lowest = Some(lowest.map_or(next, |lowest| lowest.min(next)));
+        }
+        Ok(lowest.unwrap_or(0))
+    }
+
+    /// Remove a persistent subscription's checkpoint, returning whether it
+    /// existed
+    pub fn remove_subscription(&self, name: &str) -> std::io::Result<bool> {
+        match fs::remove_file(self.checkpoint_path(name)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn read_checkpoint(path: &Path) -> std::io::Result<Positions> {
+    bincode::deserialize(&fs::read(path)?)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+impl PersistentSubscription<'_> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Events journaled since the last poll, by actor and then in
+    /// sequence order
+    ///
+    /// Every event carries its `actor_id`.
+    pub fn poll(&mut self) -> std::io::Result<Vec<Event>> {
+        let mut events = vec![];
+        for id in self.journal.subscribed_ids()? {
+            let next = self.delivered.get(&id).copied().unwrap_or(0);
+            let (read, offset) = self
+                .journal
+                .read_events_from(&id, self.offsets.get(&id).copied())?;
+            match offset {
+                Some(offset) => self.offsets.insert(id.clone(), offset),
+                None => self.offsets.remove(&id),
+            };
+            let new: Vec<Event> = read
+                .into_iter()
+                .filter(|e| e.seq >= next)
+                .map(|mut e| {
+                    e.actor_id.get_or_insert_with(|| id.clone());
+                    e
+                })
+                .collect();
+            if let Some(last) = new.last() {
+                self.delivered.insert(id, last.seq + 1);
+            }
+            events.extend(new);
+        }
+        Ok(events)
+    }
+
+    /// Acknowledge `event` and every earlier event from the same actor,
+    /// persisting the checkpoint
+    pub fn ack(&mut self, event: &Event) -> std::io::Result<()> {
+        let Some(id) = &event.actor_id else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "acknowledged event has no actor_id",
+            ));
+        };
+        self.ack_through(id, event.seq)
+    }
+
+    /// Acknowledge `actor`'s events up to and including `seq`
+    pub fn ack_through(&mut self, actor: &ActorId, seq: u64) -> std::io::Result<()> {
+        let next = self.acked.entry(actor.clone()).or_insert(0);
+        if seq < *next {
+            return Ok(());
+        }
+        *next = seq + 1;
+        let delivered = self.delivered.entry(actor.clone()).or_insert(0);
+        *delivered = (*delivered).max(seq + 1);
+        self.save()
+    }
+
+    /// Next sequence number to deliver for `actor`, as acknowledged
+    pub fn position(&self, actor: &ActorId) -> u64 {
+        self.acked.get(actor).copied().unwrap_or(0)
+    }
+
+    /// Forget unacknowledged deliveries, so the next poll redelivers them
+    pub fn rewind(&mut self) {
+        // Journals with nothing to redeliver are still read from where
+        // the last poll stopped
+        for (id, delivered) in &self.delivered {
+            if self.acked.get(id) != Some(delivered) {
+                self.offsets.remove(id);
+            }
+        }
+        self.delivered = self.acked.clone();
+    }
+
+    /// Set the subscription aside, keeping where it stopped reading
+    pub(crate) fn suspend(self) -> Progress {
+        Progress {
+            name: self.name,
+            acked: self.acked,
+            delivered: self.delivered,
+            offsets: self.offsets,
+        }
+    }
+
+    /// Write the checkpoint (write then rename, so it's never partial)
+    fn save(&self) -> std::io::Result<()> {
+        let dir = self.journal.subscriptions_dir();
+        fs::create_dir_all(&dir)?;
+        let data = bincode::serialize(&self.acked)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let path = self.journal.checkpoint_path(&self.name);
+        let tmp = dir.join(format!("{}.tmp", self.name));
+        fs::write(&tmp, data)?;
+        fs::rename(tmp, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialize::TypedValue;
+    use tempfile::TempDir;
+
+    fn append(journal: &Journal, id: &ActorId, seq: u64) {
+        let event = Event::new(seq, "Tick".to_string(), TypedValue::Int(seq as i64));
+        journal.append(id, &event).unwrap();
+    }
+
+    #[test]
+    fn test_resumes_from_checkpoint() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let (a, b) = (ActorId::new(), ActorId::new());
+        for seq in 0..3 {
+            append(&journal, &a, seq);
+        }
+        append(&journal, &b, 0);
+
+        let mut sub = journal
+            .subscribe_persistent("exporter", StartFrom::Beginning)
+            .unwrap();
+        let events = sub.poll().unwrap();
+        assert_eq!(events.len(), 4);
+        assert!(sub.poll().unwrap().is_empty());
+
+        // Only a's first two events make it out before the "crash"
+        let acked = events
+            .iter()
+            .find(|e| e.actor_id.as_ref() == Some(&a) && e.seq == 1)
+            .unwrap();
+        sub.ack(acked).unwrap();
+        drop(sub);
+
+        append(&journal, &a, 3);
+        let mut sub = journal
+            .subscribe_persistent("exporter", StartFrom::Now)
+            .unwrap();
+        assert_eq!(sub.position(&a), 2);
+        let mut redelivered: Vec<_> = sub
+            .poll()
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.actor_id.unwrap() == a, e.seq))
+            .collect();
+        redelivered.sort();
+        assert_eq!(redelivered, [(false, 0), (true, 2), (true, 3)]);
+    }
+
+    #[test]
+    fn test_poll_keeps_sequence_order_across_rewrites() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let id = ActorId::new();
+        // The clock moved back between the two events
+        for (seq, ts) in [(0, 100), (1, 50)] {
+            let event = Event {
+                ts,
+                ..Event::new(seq, "Tick".to_string(), TypedValue::Int(0))
+            };
+            journal.append(&id, &event).unwrap();
+        }
+
+        let mut sub = journal
+            .subscribe_persistent("ordered", StartFrom::Beginning)
+            .unwrap();
+        let seqs = |events: Vec<Event>| events.iter().map(|e| e.seq).collect::<Vec<_>>();
+        assert_eq!(seqs(sub.poll().unwrap()), [0, 1]);
+        append(&journal, &id, 2);
+        assert_eq!(seqs(sub.poll().unwrap()), [2]);
+
+        // Compaction rewrites the file under the subscription
+        let snapshot = crate::journal::Snapshot {
+            actor_id: None,
+            seq: 2,
+            state: TypedValue::Int(0),
+            ts: 0,
+        };
+        journal.save_snapshot(&id, &snapshot).unwrap();
+        assert!(journal.compact(&id).unwrap() > 0);
+        append(&journal, &id, 3);
+        assert_eq!(seqs(sub.poll().unwrap()), [3]);
+    }
+
+    #[test]
+    fn test_compaction_keeps_unacknowledged_intents() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let id = ActorId::new();
+        append(&journal, &id, 0);
+        let intent = Event::new(
+            1,
+            crate::outbox::INTENT_EVENT.to_string(),
+            TypedValue::Int(0),
+        );
+        journal.append(&id, &intent).unwrap();
+        append(&journal, &id, 2);
+        let snapshot = crate::journal::Snapshot {
+            actor_id: None,
+            seq: 3,
+            state: TypedValue::Int(0),
+            ts: 0,
+        };
+        journal.save_snapshot(&id, &snapshot).unwrap();
+        let seqs = |journal: &Journal| {
+            let events = journal.read_events(&id).unwrap();
+            events.iter().map(|e| e.seq).collect::<Vec<_>>()
+        };
+
+        // Nothing has acknowledged the intent yet
+        journal.compact(&id).unwrap();
+        assert_eq!(seqs(&journal), [1, 2]);
+
+        let mut sub = journal
+            .subscribe_persistent("outbox", StartFrom::Beginning)
+            .unwrap();
+        sub.ack_through(&id, 1).unwrap();
+        journal.compact(&id).unwrap();
+        assert!(seqs(&journal).is_empty());
+    }
+
+    #[test]
+    fn test_start_from_now_skips_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let id = ActorId::new();
+        append(&journal, &id, 0);
+
+        let mut sub = journal
+            .subscribe_persistent("live", StartFrom::Now)
+            .unwrap();
+        assert!(sub.poll().unwrap().is_empty());
+        append(&journal, &id, 1);
+        let events = sub.poll().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].seq, 1);
+
+        sub.rewind();
+        assert_eq!(sub.poll().unwrap().len(), 1);
+
+        assert!(journal
+            .subscribe_persistent("../escape", StartFrom::Now)
+            .is_err());
+        assert!(journal.remove_subscription("live").unwrap());
+    }
+}
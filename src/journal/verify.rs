@@ -0,0 +1,441 @@
+//! Journal integrity checks
+//!
+//! `Journal::verify` reads one actor's journal and snapshot the way
+//! recovery would, but keeps going past problems and reports every one it
+//! finds:
+//!
+//! - the file header and the framing of each record (length prefixes in
+//!   bounds, no record cut short)
+//! - that every record decodes, belongs to the actor, and has a sequence
+//!   number one past the record before it
+//! - that the blobs events attach are present and match their content
+//!   address (records themselves carry no checksum)
+//! - that the snapshot decodes and the journal covers it: no events
+//!   missing between the snapshot and the journal, and the snapshot not
+//!   ahead of the journal
+//!
+//! The `seq-journal verify` command runs it over a journal directory.
+
+use super::limits::DecodeLimits;
+use super::{BlobId, Event, FileHeader, Journal, CONTINUES, JOURNAL_MAGIC, MAX_RECORD_LEN};
+use crate::actor::ActorId;
+use std::fmt;
+use std::fs;
+
+/// A problem `Journal::verify` found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// The journal file couldn't be read or its header is unsupported
+    Header(String),
+    /// A length prefix is out of bounds or its record is cut short;
+    /// nothing after `offset` can be read
+    Framing { offset: u64, error: String },
+    /// The file ends partway through a length prefix, or before the last
+    /// record of a group written together (an interrupted write, which
+    /// recovery ignores)
+    TornTail { offset: u64 },
+    /// A record doesn't decode as an event
+    Decode { offset: u64, error: String },
+    /// An event records a different actor
+    WrongActor { seq: u64, actor_id: ActorId },
+    /// An event's seq isn't one past the previous event's
+    Sequence { seq: u64, previous: u64 },
+    /// An attached blob is missing
+    MissingBlob { seq: u64, blob: BlobId },
+    /// An attached blob's content doesn't match its address
+    CorruptBlob { seq: u64, blob: BlobId },
+    /// The snapshot file couldn't be read or decoded
+    Snapshot(String),
+    /// Events between the snapshot and the first journaled event are
+    /// missing
+    SnapshotGap { snapshot_seq: u64, first_seq: u64 },
+    /// The snapshot was taken after the last journaled event
+    SnapshotAhead {
+        snapshot_seq: u64,
+        last_seq: Option<u64>,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::Header(error) => write!(f, "unreadable header: {}", error),
+            Issue::Framing { offset, error } => {
+                write!(f, "bad framing at byte {}: {}", offset, error)
+            }
+            Issue::TornTail { offset } => write!(f, "torn write at byte {}", offset),
+            Issue::Decode { offset, error } => {
+                write!(f, "undecodable record at byte {}: {}", offset, error)
+            }
+            Issue::WrongActor { seq, actor_id } => {
+                write!(f, "event {} belongs to actor {}", seq, actor_id)
+            }
+            Issue::Sequence { seq, previous } => {
+                write!(f, "event {} follows event {}", seq, previous)
+            }
+            Issue::MissingBlob { seq, blob } => {
+                write!(f, "event {} attaches missing blob {}", seq, blob)
+            }
+            Issue::CorruptBlob { seq, blob } => {
+                write!(f, "event {} attaches corrupt blob {}", seq, blob)
+            }
+            Issue::Snapshot(error) => write!(f, "unreadable snapshot: {}", error),
+            Issue::SnapshotGap {
+                snapshot_seq,
+                first_seq,
+            } => write!(
+                f,
+                "snapshot at {} but the journal starts at event {}",
+                snapshot_seq, first_seq
+            ),
+            Issue::SnapshotAhead {
+                snapshot_seq,
+                last_seq,
+            } => match last_seq {
+                Some(last) => write!(
+                    f,
+                    "snapshot at {} is past the last event {}",
+                    snapshot_seq, last
+                ),
+                None => write!(f, "snapshot at {} but the journal is empty", snapshot_seq),
+            },
+        }
+    }
+}
+
+/// What `Journal::verify` found for one actor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub actor_id: ActorId,
+    /// Header of the journal file (`None` if there is no journal or the
+    /// header is unreadable)
+    pub header: Option<FileHeader>,
+    /// Events decoded
+    pub events: u64,
+    pub first_seq: Option<u64>,
+    pub last_seq: Option<u64>,
+    /// Sequence number the snapshot was taken at
+    pub snapshot_seq: Option<u64>,
+    pub issues: Vec<Issue>,
+}
+
+impl VerifyReport {
+    /// No issues were found
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl Journal {
+    /// Check an actor's journal and snapshot for damage
+    ///
+    /// Fails only if the files can't be read at all; everything wrong with
+    /// their contents is listed in the report.
+    pub fn verify(&self, actor_id: &ActorId) -> std::io::Result<VerifyReport> {
+        self.require_files("verify")?;
+        let owner = self.resolve_alias(actor_id);
+        let mut report = VerifyReport {
+            actor_id: actor_id.clone(),
+            header: None,
+            events: 0,
+            first_seq: None,
+            last_seq: None,
+            snapshot_seq: None,
+            issues: vec![],
+        };
+
+        let path = self.journal_path(actor_id);
+        if path.exists() {
+            let data = fs::read(path)?;
+            let (header, records) = read_records(&data);
+            report.header = header;
+            for record in records {
+                match record {
+                    Ok(event) => self.verify_event(&owner, &event, &mut report)?,
+                    Err(issue) => report.issues.push(issue),
+                }
+            }
+        }
+
+        match self.load_snapshot(actor_id) {
+            Ok(Some(snapshot)) => {
+                report.snapshot_seq = Some(snapshot.seq);
+                verify_snapshot(snapshot.seq, &mut report);
+            }
+            Ok(None) => {}
+            Err(e) => report.issues.push(Issue::Snapshot(e.to_string())),
+        }
+        Ok(report)
+    }
+
+    /// `verify` for every actor under the base path, skipping aliases
+    pub fn verify_all(&self) -> std::io::Result<Vec<VerifyReport>> {
+        self.actor_ids()?
+            .into_iter()
+            .filter(|id| self.alias_of(id).is_none())
+            .map(|id| self.verify(&id))
+            .collect()
+    }
+
+    /// Check one decoded event against the ones before it
+    fn verify_event(
+        &self,
+        owner: &ActorId,
+        event: &Event,
+        report: &mut VerifyReport,
+    ) -> std::io::Result<()> {
+        if let Some(actor_id) = event.actor_id.as_ref().filter(|id| *id != owner) {
+            report.issues.push(Issue::WrongActor {
+                seq: event.seq,
+                actor_id: actor_id.clone(),
+            });
+        }
+        if let Some(previous) = report.last_seq {
+            if event.seq != previous + 1 {
+                report.issues.push(Issue::Sequence {
+                    seq: event.seq,
+                    previous,
+                });
+            }
+        }
+        for blob in &event.attachments {
+            let issue = match self.get_blob(blob)? {
+                None => Issue::MissingBlob {
+                    seq: event.seq,
+                    blob: blob.clone(),
+                },
+                Some(data) if BlobId::of(&data) != *blob => Issue::CorruptBlob {
+                    seq: event.seq,
+                    blob: blob.clone(),
+                },
+                Some(_) => continue,
+            };
+            report.issues.push(issue);
+        }
+
+        report.events += 1;
+        report.first_seq.get_or_insert(event.seq);
+        report.last_seq = Some(event.seq);
+        Ok(())
+    }
+}
+
+/// Walk the records of a journal file, in order, decoding each or saying
+/// what is wrong with it
+///
+/// Also returns the file's header (`None` for a legacy file, or if the
+/// header is unreadable).
+pub(super) fn read_records(data: &[u8]) -> (Option<FileHeader>, Vec<Result<Event, Issue>>) {
+    let mut reader = data;
+    let (header, body) = match FileHeader::read(&mut reader, JOURNAL_MAGIC) {
+        Ok((header, _)) if header.version == 0 => (None, data),
+        Ok((header, _)) => (Some(header), reader),
+        Err(e) => return (None, vec![Err(Issue::Header(e.to_string()))]),
+    };
+
+    let mut records = vec![];
+    // Where the group being read started, as (record index, offset)
+    let mut group = None;
+    let mut pos = 0;
+    while pos < body.len() {
+        let offset = (data.len() - body.len() + pos) as u64;
+        let Some(prefix) = body.get(pos..pos + 4) else {
+            break;
+        };
+        let prefix = u32::from_le_bytes(prefix.try_into().expect("4 bytes"));
+        let len = (prefix & !CONTINUES) as usize;
+        let record = body.get(pos + 4..pos + 4 + len);
+        let record = match record {
+            Some(record) if len <= MAX_RECORD_LEN => record,
+            _ => {
+                let error = match len > MAX_RECORD_LEN {
+                    true => format!("length {} exceeds max record length", len),
+                    false => format!("record of {} bytes cut short", len),
+                };
+                // The group this record closes can't be complete either
+                if let Some((start, _)) = group.take() {
+                    records.truncate(start);
+                }
+                records.push(Err(Issue::Framing { offset, error }));
+                return (header, records);
+            }
+        };
+        pos += 4 + len;
+        match prefix & CONTINUES != 0 {
+            true => group = group.or(Some((records.len(), offset))),
+            false => group = None,
+        }
+
+        let version = header.map_or(0, |header| header.version);
+        let decoded = Event::from_record(record, version, &DecodeLimits::default());
+        records.push(decoded.map_err(|e| Issue::Decode {
+            offset,
+            error: e.to_string(),
+        }));
+    }
+    if pos < body.len() || group.is_some() {
+        // Recovery drops a group whose write didn't finish as a whole
+        let offset = match group {
+            Some((start, offset)) => {
+                records.truncate(start);
+                offset
+            }
+            None => (data.len() - body.len() + pos) as u64,
+        };
+        records.push(Err(Issue::TornTail { offset }));
+    }
+    (header, records)
+}
+
+/// Check that the journal covers a snapshot taken at `snapshot_seq`
+fn verify_snapshot(snapshot_seq: u64, report: &mut VerifyReport) {
+    match (report.first_seq, report.last_seq) {
+        (Some(first), _) if first > snapshot_seq => report.issues.push(Issue::SnapshotGap {
+            snapshot_seq,
+            first_seq: first,
+        }),
+        (_, Some(last)) if last + 1 >= snapshot_seq => {}
+        // No events: the initial state, or compaction dropped every event
+        // the snapshot covers
+        (_, None) => {}
+        (_, last_seq) => report.issues.push(Issue::SnapshotAhead {
+            snapshot_seq,
+            last_seq,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::Snapshot;
+    use crate::serialize::TypedValue;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn tick(seq: u64) -> Event {
+        Event::new(seq, "Tick".to_string(), TypedValue::Int(seq as i64))
+    }
+
+    #[test]
+    fn test_verify_clean_journal() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let actor_id = ActorId::new();
+
+        let blob = journal.put_blob(b"attachment").unwrap();
+        journal
+            .append(&actor_id, &tick(0).with_attachment(blob))
+            .unwrap();
+        journal.append_all(&actor_id, &[tick(1), tick(2)]).unwrap();
+        let snapshot = Snapshot {
+            actor_id: None,
+            seq: 3,
+            state: TypedValue::Int(3),
+            ts: 0,
+        };
+        journal.save_snapshot(&actor_id, &snapshot).unwrap();
+
+        let report = journal.verify(&actor_id).unwrap();
+        assert!(report.is_ok(), "{:?}", report.issues);
+        assert_eq!(report.header, Some(FileHeader::current()));
+        assert_eq!(
+            (report.events, report.first_seq, report.last_seq),
+            (3, Some(0), Some(2))
+        );
+        assert_eq!(report.snapshot_seq, Some(3));
+        assert_eq!(journal.verify_all().unwrap(), [report]);
+    }
+
+    #[test]
+    fn test_verify_reports_damage() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let actor_id = ActorId::new();
+
+        let missing = BlobId::of(b"never stored");
+        journal.append(&actor_id, &tick(0)).unwrap();
+        journal
+            .append(&actor_id, &tick(2).with_attachment(missing.clone()))
+            .unwrap();
+        journal.append(&actor_id, &tick(2)).unwrap();
+        let snapshot = Snapshot {
+            actor_id: None,
+            seq: 9,
+            state: TypedValue::Int(0),
+            ts: 0,
+        };
+        journal.save_snapshot(&actor_id, &snapshot).unwrap();
+
+        // A record that doesn't decode, then half a length prefix
+        let path = journal.journal_path(&actor_id);
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[1, 0, 0, 0, 0xff, 7, 0]).unwrap();
+        let end = fs::metadata(&path).unwrap().len();
+
+        let report = journal.verify(&actor_id).unwrap();
+        assert!(
+            matches!(
+                &report.issues[..],
+                [
+                    Issue::Sequence { seq: 2, previous: 0 },
+                    Issue::MissingBlob { seq: 2, .. },
+                    Issue::Sequence { seq: 2, previous: 2 },
+                    Issue::Decode { offset, .. },
+                    Issue::TornTail { offset: torn },
+                    Issue::SnapshotAhead { snapshot_seq: 9, last_seq: Some(2) },
+                ] if *offset == end - 7 && *torn == end - 2
+            ),
+            "{:?}",
+            report.issues
+        );
+        assert_eq!(
+            report.issues[1].to_string(),
+            format!("event 2 attaches missing blob {}", missing)
+        );
+        assert_eq!(report.events, 3);
+    }
+
+    #[test]
+    fn test_verify_compacted_journal() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let actor_id = ActorId::new();
+        journal.append_all(&actor_id, &[tick(0), tick(1)]).unwrap();
+        let snapshot = Snapshot {
+            actor_id: None,
+            seq: 2,
+            state: TypedValue::Int(1),
+            ts: 0,
+        };
+        journal.save_snapshot(&actor_id, &snapshot).unwrap();
+        assert!(journal.compact(&actor_id).unwrap() > 0);
+
+        // The idle actor's journal is empty, and still covers the snapshot
+        let report = journal.verify(&actor_id).unwrap();
+        assert!(report.is_ok(), "{:?}", report.issues);
+        assert_eq!((report.events, report.snapshot_seq), (0, Some(2)));
+    }
+
+    #[test]
+    fn test_verify_reports_bad_framing() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let actor_id = ActorId::new();
+        journal.append(&actor_id, &tick(0)).unwrap();
+
+        let path = journal.journal_path(&actor_id);
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[100, 0, 0, 0, 1, 2]).unwrap();
+
+        let offset = fs::metadata(&path).unwrap().len() - 6;
+        let report = journal.verify(&actor_id).unwrap();
+        assert_eq!(
+            report.issues,
+            [Issue::Framing {
+                offset,
+                error: "record of 100 bytes cut short".to_string(),
+            }]
+        );
+    }
+}
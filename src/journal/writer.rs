@@ -0,0 +1,249 @@
+//! Dedicated journal writer thread
+//!
+//! `JournalWriter` moves journal appends onto one background thread that
+//! receives requests over a channel, so disk latency is paid off the
+//! threads running behaviors. How long a caller waits depends on the
+//! `Durability` it asks for:
+//!
+//! - `Async`: return as soon as the request is queued. Write errors are
+//!   reported by the next `flush`.
+//! - `Written`: wait until the record has been written to the file.
+//! - `Synced`: also wait for the file to be synced to disk.
+//!
+//! Requests are handled in order, so records for one actor land in the
+//! order they were appended.
+//!
+//! Waiting for an ack on a seq-runtime strand yields to its scheduler
+//! instead of blocking the thread (see `crate::strand`).
+
+use super::{Event, Journal};
+use crate::actor::ActorId;
+use crate::strand;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// How durable an append must be before the caller continues
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Durability {
+    /// Queued for writing
+    Async,
+    /// Written to the journal file
+    #[default]
+    Written,
+    /// Written and synced to disk
+    Synced,
+}
+
+type Ack = Sender<std::io::Result<()>>;
+
+enum Request {
+    Append {
+        actor_id: ActorId,
+        events: Vec<Event>,
+        sync: bool,
+        ack: Option<Ack>,
+    },
+    Flush(Ack),
+}
+
+/// Handle to the journal writer thread
+///
+/// Dropping the handle finishes queued writes and stops the thread.
+pub struct JournalWriter {
+    requests: Option<Sender<Request>>,
+    /// First error from an `Async` append, reported by `flush`
+    async_error: Arc<Mutex<Option<std::io::Error>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl JournalWriter {
+    /// Start a writer thread appending to `journal`
+    pub fn spawn(journal: Arc<Journal>) -> Self {
+        let (requests, receiver) = mpsc::channel();
+        let async_error = Arc::new(Mutex::new(None));
+
+        let thread = {
+            let async_error = async_error.clone();
+            std::thread::Builder::new()
+                .name("seq-actors-journal".to_string())
+                .spawn(move || run(&journal, receiver, &async_error))
+                .expect("failed to spawn journal writer thread")
+        };
+
+        JournalWriter {
+            requests: Some(requests),
+            async_error,
+            thread: Some(thread),
+        }
+    }
+
+    /// Append an event, waiting as long as `durability` requires
+    pub fn append(
+        &self,
+        actor_id: &ActorId,
+        event: &Event,
+        durability: Durability,
+    ) -> std::io::Result<()> {
+        self.append_all(actor_id, std::slice::from_ref(event), durability)
+    }
+
+    /// Append events in a single write (see `Journal::append_all`),
+    /// waiting as long as `durability` requires
+    pub fn append_all(
+        &self,
+        actor_id: &ActorId,
+        events: &[Event],
+        durability: Durability,
+    ) -> std::io::Result<()> {
+        let (ack, done) = match durability {
+            Durability::Async => (None, None),
+            Durability::Written | Durability::Synced => {
+                let (ack, done) = mpsc::channel();
+                (Some(ack), Some(done))
+            }
+        };
+
+        self.send(Request::Append {
+            actor_id: actor_id.clone(),
+            events: events.to_vec(),
+            sync: durability == Durability::Synced,
+            ack,
+        })?;
+        match done {
+            Some(done) => strand::recv(&done).map_err(|_| stopped())?,
+            None => Ok(()),
+        }
+    }
+
+    /// Wait for every queued append to be written
+    ///
+    /// Returns the first error from an `Async` append since the last flush.
+    pub fn flush(&self) -> std::io::Result<()> {
+        let (ack, done) = mpsc::channel();
+        self.send(Request::Flush(ack))?;
+        strand::recv(&done).map_err(|_| stopped())??;
+
+        match self
+            .async_error
+            .lock()
+            .expect("writer error lock poisoned")
+            .take()
+        {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn send(&self, request: Request) -> std::io::Result<()> {
+        self.requests
+            .as_ref()
+            .expect("requests sender is only taken on drop")
+            .send(request)
+            .map_err(|_| stopped())
+    }
+}
+
+impl Drop for JournalWriter {
+    fn drop(&mut self) {
+        // Closing the channel ends the thread once the queue drains
+        self.requests.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn stopped() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::BrokenPipe,
+        "journal writer thread stopped",
+    )
+}
+
+/// Writer thread body
+fn run(
+    journal: &Journal,
+    requests: Receiver<Request>,
+    async_error: &Mutex<Option<std::io::Error>>,
+) {
+    for request in requests {
+        match request {
+            Request::Append {
+                actor_id,
+                events,
+                sync,
+                ack,
+            } => {
+                let mut result = journal.append_all(&actor_id, &events);
+                if sync && result.is_ok() {
+                    result = journal.sync(&actor_id);
+                }
+                match ack {
+                    Some(ack) => {
+                        let _ = ack.send(result);
+                    }
+                    None => {
+                        if let Err(e) = result {
+                            async_error
+                                .lock()
+                                .expect("writer error lock poisoned")
+                                .get_or_insert(e);
+                        }
+                    }
+                }
+            }
+            Request::Flush(ack) => {
+                let _ = ack.send(Ok(()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialize::TypedValue;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_appends_in_order_for_each_durability() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Arc::new(Journal::new(temp_dir.path()));
+        let writer = JournalWriter::spawn(journal.clone());
+        let id = ActorId::new();
+
+        let modes = [Durability::Async, Durability::Written, Durability::Synced];
+        for seq in 0..30 {
+            let event = Event::new(seq, "E".to_string(), TypedValue::Int(seq as i64));
+            writer.append(&id, &event, modes[seq as usize % 3]).unwrap();
+        }
+        writer.flush().unwrap();
+
+        let seqs: Vec<u64> = journal
+            .read_events(&id)
+            .unwrap()
+            .iter()
+            .map(|e| e.seq)
+            .collect();
+        assert_eq!(seqs, (0..30).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_async_error_reported_by_flush() {
+        let temp_dir = TempDir::new().unwrap();
+        let writer = JournalWriter::spawn(Arc::new(Journal::new(temp_dir.path())));
+
+        let oversized = Event::new(
+            0,
+            "Huge".to_string(),
+            TypedValue::String("x".repeat(super::super::MAX_RECORD_LEN + 1)),
+        );
+        writer
+            .append(&ActorId::new(), &oversized, Durability::Async)
+            .unwrap();
+
+        assert!(writer.flush().is_err());
+        assert!(writer.flush().is_ok());
+    }
+}
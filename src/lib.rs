@@ -8,7 +8,50 @@
 //! - **Actors**: Isolated units with identity, state (Map), and behavior (Quotation)
 //! - **Messages**: Variants sent between actors
 //! - **Journal**: Binary event log for persistence and recovery
-//! - **Supervisor**: Manages actor lifecycle and failure recovery
+//! - **Supervisor**: Manages actor lifecycle and failure recovery, with
+//!   restart limits and backoff configured per behavior
+//! - **Outbox**: Executes side effects journaled with the state change that
+//!   caused them, at least once
+//! - **Session**: Records runtime inputs for deterministic replay
+//! - **Replay**: Steps through an actor's events to watch its state evolve
+//! - **Testkit**: Drives behaviors with scripted messages in unit tests
+//! - **Watchdog**: Flags behaviors blocking the scheduler past a threshold
+//! - **Audit**: Runtime reconfiguration journaled under a reserved actor
+//! - **Throughput history**: Per-actor message counts journaled
+//!   periodically, for history without a metrics store
+//! - **Checkpoints**: Consistent snapshots of every actor at once, and restores
+//! - **System actor**: Lifecycle events sent to actors that subscribe to a
+//!   reserved ID, for monitoring written in Seq
+//! - **Rollouts**: Split traffic between two behavior versions, promoting or
+//!   rolling back by failure rate
+//! - **Shadow actors**: Try a new behavior on mirrored live traffic, isolated
+//!   from replies and the journal
+//! - **Sandbox**: Per-behavior deny-lists of the builtins actors may call
+//! - **Load shedding**: Low-priority actors reject non-critical messages
+//!   while the runtime is overloaded
+//! - **Message expiry**: Stale queued messages are dropped instead of handled
+//! - **State size limits**: Actors whose state outgrows a cap are reported
+//!   or passivated with a final snapshot
+//! - **Affinity**: Pins actors to pools handled on dedicated threads
+//! - **Storage tiers**: Per-actor journal roots, or no storage at all
+//! - **Event validation**: Checks that reject emitted events before they
+//!   are journaled, failing the message
+//! - **Timeouts**: Per-message deadlines, cancelled cooperatively
+//! - **State deltas**: Structural diffs of large states journaled in place
+//!   of events, applied on recovery
+//! - **Preloading**: Journals of hot actors read ahead in the background
+//!   at startup
+//! - **Reference data**: Actor states published for lock-free reads by any
+//!   actor, without messaging
+//! - **Delivery groups**: Competing consumers sharing a group's messages,
+//!   one member per message
+//! - **GenServer**: OTP-style calls with timeouts and casts, with call
+//!   results journaled
+//! - **Fairness**: Weighted scheduling between groups of actors
+//! - **Tokio bridge**: Spawn, send, ask and drive actors from tokio tasks
+//!   (feature `tokio-bridge`)
+//! - **Object store**: Snapshots and archived history in S3/GCS (feature
+//!   `object-store`)
 //!
 //! # Serialization
 //!
@@ -31,17 +74,111 @@
 //! ```
 
 pub mod actor;
+pub mod affinity;
+pub mod audit;
+pub mod auth;
+pub mod behavior;
 pub mod builtins;
+pub mod checkpoint;
+pub mod crash;
+pub mod delivery;
+pub mod delta;
+pub mod dispatch;
+pub mod error;
+pub mod expiry;
+pub mod fairness;
 pub mod ffi;
+pub mod fsm;
+pub mod genserver;
+pub mod health;
+pub mod interceptor;
 pub mod journal;
+pub mod lifecycle;
+pub mod message;
+pub mod metrics;
+pub mod observer;
+pub mod outbox;
+pub mod pool;
+pub mod preload;
+pub mod ratelimit;
+pub mod refdata;
+pub mod replay;
+pub mod reply;
+pub mod rollout;
 pub mod runtime;
+pub mod sandbox;
 pub mod serialize;
+pub mod session;
+pub mod shedding;
+pub mod state_size;
+pub mod strand;
+pub mod supervision;
+pub mod system;
+pub mod testkit;
+pub mod throughput;
+pub mod tier;
+pub mod timeout;
+#[cfg(feature = "tokio-bridge")]
+pub mod tokio_bridge;
+pub mod validate;
+pub mod watchdog;
+pub mod workflow;
 
 // Re-exports
-pub use actor::{Actor, ActorId, ActorRef};
-pub use builtins::compiler_config;
-pub use journal::{Event, Journal, Snapshot};
-pub use runtime::{ActorRuntime, Mailbox, RuntimeConfig};
+pub use actor::{Actor, ActorHandle, ActorId, ActorRef, IdScheme};
+pub use affinity::PoolWorker;
+pub use audit::{ConfigChange, ConfigTarget};
+pub use auth::{AuthPolicy, Operation, Principal};
+pub use behavior::{Behavior, BehaviorContext, BehaviorError};
+pub use builtins::{
+    builtin_effects, compiler_config, compiler_config_with, BuiltinGroup, BuiltinOptions,
+    StackEffect,
+};
+pub use checkpoint::{Checkpoint, CheckpointEntry};
+pub use crash::{CrashDump, PanicPolicy};
+pub use delivery::DeliveryStrategy;
+pub use delta::StateDelta;
+pub use dispatch::Dispatch;
+pub use error::RuntimeError;
+pub use expiry::ExpirySweeper;
+pub use fairness::GroupUsage;
+pub use fsm::Fsm;
+pub use genserver::{CallResult, GenServer, GenServerBehavior};
+pub use health::{ActorDump, HealthReport, MessageCounts};
+pub use interceptor::Interceptor;
+pub use journal::archive::{Archive, ArchiveInfo};
+pub use journal::lazy::LazySnapshot;
+pub use journal::limits::{DecodeLimits, LimitExceeded};
+pub use journal::repair::{RepairLog, RepairPolicy};
+pub use journal::stats::JournalStats;
+pub use journal::subscription::{PersistentSubscription, StartFrom};
+pub use journal::verify::VerifyReport;
+pub use journal::writer::Durability;
+pub use journal::{BlobId, Event, Journal, Snapshot};
+pub use lifecycle::{Handoff, StopReason};
+pub use message::TypedMessage;
+pub use metrics::MetricsSnapshot;
+pub use observer::{DropReason, RuntimeObserver};
+pub use outbox::{Intent, Outbox, OutboxWorker};
+pub use preload::Preload;
+pub use ratelimit::RateLimit;
+pub use replay::ReplaySession;
+pub use reply::{Reply, ReplyToken};
+pub use rollout::{Rollout, RolloutDecision, RolloutPolicy, RolloutStatus};
+pub use runtime::{
+    coroutine_name, global_runtime, install_global, ActorRuntime, BlockedActor, CoroutineInfo,
+    CoroutineStatus, Envelope, Mailbox, Payload, RuntimeConfig,
+};
+pub use sandbox::Sandbox;
+pub use shedding::{LoadShedding, Pressure, PressureMonitor};
+pub use state_size::{StateLimit, StateLimitAction};
+pub use supervision::{Backoff, Supervision};
+pub use system::{SubscriptionFilter, SystemEvent};
+pub use throughput::{ThroughputRecord, ThroughputRecorder};
+pub use tier::StorageTier;
+pub use validate::{EventRejected, EventValidator, ForbiddenFields, MaxEventSize};
+pub use watchdog::Watchdog;
+pub use workflow::{Step, Workflow};
 
 // Serialization re-exports from seq-runtime
 pub use serialize::{MapKey, SerializeError, TypedMapKey, TypedValue, ValueSerialize};
@@ -0,0 +1,174 @@
+//! Stop reasons and Down notifications
+//!
+//! Every actor that terminates journals one final `ActorStopped` event
+//! recording why it stopped, after any events from its `on_stop` hook.
+//! Actors monitoring it (`ActorRuntime::monitor`) are then sent a tagged
+//! `Down` message:
+//!
+//! ```text
+//! { tag: "Down", fields: { actor: "<id>", reason: "error", error: "..." } }
+//! ```
+//!
+//! `reason` is the `StopReason::code`; `error` is only present for
+//! `StopReason::Error`.
+//!
+//! A stopped actor normally handles what is already queued before it
+//! terminates, and a killed or unregistered one drops it. With a `Handoff`
+//! (`ActorRuntime::set_handoff`) the queue is passed on instead, as soon as
+//! the actor is stopped, killed or unregistered: to a successor, which
+//! gets each message with its original sender and reply channel, or to
+//! the dead letters.
+
+use crate::actor::ActorId;
+use crate::message::TypedMessage;
+use crate::serialize::{TypedMapKey, TypedValue};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Event type of the final event journaled for a terminated actor
+pub const STOPPED_EVENT: &str = "ActorStopped";
+
+/// Tag of the message sent to monitoring actors
+pub const DOWN_MESSAGE: &str = "Down";
+
+/// Where an actor's queued messages go when it stops (see module docs)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Handoff {
+    /// Forward them to another actor; any it can't accept are dead letters
+    Successor(ActorId),
+    /// Report them as dead letters rather than drops
+    DeadLetters,
+}
+
+/// Why an actor stopped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StopReason {
+    /// Asked to stop, and finished its queued messages
+    Normal,
+    /// Stopped with its queued messages dropped
+    Killed,
+    /// Stopped by its supervisor (e.g. as part of a restart)
+    SupervisorDirective,
+    /// Stopped because of a failure
+    Error(String),
+    /// Stopped with a final snapshot because its state outgrew its limit
+    /// (see `crate::state_size`)
+    Passivated,
+}
+
+fn key(name: &str) -> TypedMapKey {
+    TypedMapKey::String(name.to_string())
+}
+
+impl StopReason {
+    /// Stable code written to the journal and Down messages
+    pub fn code(&self) -> &'static str {
+        match self {
+            StopReason::Normal => "normal",
+            StopReason::Killed => "killed",
+            StopReason::SupervisorDirective => "supervisor",
+            StopReason::Error(_) => "error",
+            StopReason::Passivated => "passivated",
+        }
+    }
+
+    /// A clean stop rather than a crash or kill
+    pub fn is_normal(&self) -> bool {
+        matches!(self, StopReason::Normal | StopReason::Passivated)
+    }
+
+    /// Map of `reason` (the code) and, for errors, `error`
+    pub fn to_value(&self) -> TypedValue {
+        let mut fields = BTreeMap::new();
+        fields.insert(key("reason"), TypedValue::String(self.code().to_string()));
+        if let StopReason::Error(message) = self {
+            fields.insert(key("error"), TypedValue::String(message.clone()));
+        }
+        TypedValue::Map(fields)
+    }
+
+    /// Inverse of `to_value`
+    pub fn from_value(value: &TypedValue) -> Option<Self> {
+        let TypedValue::Map(fields) = value else {
+            return None;
+        };
+        let Some(TypedValue::String(code)) = fields.get(&key("reason")) else {
+            return None;
+        };
+        match code.as_str() {
+            "normal" => Some(StopReason::Normal),
+            "killed" => Some(StopReason::Killed),
+            "supervisor" => Some(StopReason::SupervisorDirective),
+            "passivated" => Some(StopReason::Passivated),
+            "error" => match fields.get(&key("error")) {
+                Some(TypedValue::String(message)) => Some(StopReason::Error(message.clone())),
+                _ => Some(StopReason::Error(String::new())),
+            },
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopReason::Error(message) => write!(f, "error: {}", message),
+            _ => f.write_str(self.code()),
+        }
+    }
+}
+
+/// The `Down` message telling a monitor that `actor` stopped
+pub fn down_message(actor: &ActorId, reason: &StopReason) -> TypedValue {
+    let TypedValue::Map(mut fields) = reason.to_value() else {
+        unreachable!("StopReason::to_value is a Map");
+    };
+    fields.insert(key("actor"), TypedValue::String(actor.to_string()));
+    TypedMessage::new(DOWN_MESSAGE, TypedValue::Map(fields)).into()
+}
+
+/// Parse a `Down` message into the stopped actor and its reason
+pub fn parse_down(msg: &TypedValue) -> Option<(ActorId, StopReason)> {
+    let message = TypedMessage::try_from(msg).ok()?;
+    if message.tag != DOWN_MESSAGE {
+        return None;
+    }
+    let TypedValue::Map(fields) = &message.fields else {
+        return None;
+    };
+    let Some(TypedValue::String(actor)) = fields.get(&key("actor")) else {
+        return None;
+    };
+    Some((
+        actor.parse().ok()?,
+        StopReason::from_value(&message.fields)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reason_round_trip() {
+        for reason in [
+            StopReason::Normal,
+            StopReason::Killed,
+            StopReason::SupervisorDirective,
+            StopReason::Error("disk full".to_string()),
+        ] {
+            assert_eq!(StopReason::from_value(&reason.to_value()), Some(reason));
+        }
+    }
+
+    #[test]
+    fn test_down_message_round_trip() {
+        let id = ActorId::new();
+        let reason = StopReason::Error("boom".to_string());
+        let msg = down_message(&id, &reason);
+
+        assert_eq!(crate::message::tag_of(&msg), Some(DOWN_MESSAGE));
+        assert_eq!(parse_down(&msg), Some((id, reason)));
+        assert_eq!(parse_down(&TypedValue::String("Ping".to_string())), None);
+    }
+}
@@ -0,0 +1,117 @@
+//! Tagged messages
+//!
+//! Messages are conceptually variants: a tag naming what happened
+//! ("Deposit", "Withdraw") plus its fields. On the Rust side a message
+//! travels as a plain `TypedValue`, so `TypedMessage` converts between the
+//! two representations:
+//!
+//! - a String is a bare tag with no fields (`"Ping"`)
+//! - a Map with a String `"tag"` key is a tag plus the value under
+//!   `"fields"` (an empty Map if absent)
+//!
+//! The tag doubles as the journaled event type: `BehaviorContext::append`
+//! journals a tagged message under its own tag.
+
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::collections::BTreeMap;
+
+/// Event type used for journaled values that carry no tag
+pub const UNTAGGED_EVENT: &str = "Event";
+
+/// A message split into its tag and fields
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedMessage {
+    pub tag: String,
+    pub fields: TypedValue,
+}
+
+fn key(name: &str) -> TypedMapKey {
+    TypedMapKey::String(name.to_string())
+}
+
+impl TypedMessage {
+    pub fn new(tag: impl Into<String>, fields: TypedValue) -> Self {
+        TypedMessage {
+            tag: tag.into(),
+            fields,
+        }
+    }
+
+    /// A message with a tag and no fields
+    pub fn bare(tag: impl Into<String>) -> Self {
+        Self::new(tag, TypedValue::Map(BTreeMap::new()))
+    }
+}
+
+/// Tag of a message value, if it has one
+pub fn tag_of(msg: &TypedValue) -> Option<&str> {
+    match msg {
+        TypedValue::String(tag) => Some(tag),
+        TypedValue::Map(fields) => match fields.get(&key("tag")) {
+            Some(TypedValue::String(tag)) => Some(tag),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Fields of a message value (an empty Map for a bare tag)
+///
+/// `None` if the value is not a tagged message.
+pub fn payload_of(msg: &TypedValue) -> Option<TypedValue> {
+    TypedMessage::try_from(msg).ok().map(|m| m.fields)
+}
+
+impl TryFrom<&TypedValue> for TypedMessage {
+    type Error = TypedValue;
+
+    /// Fails (returning the value) if it carries no tag
+    fn try_from(value: &TypedValue) -> Result<Self, Self::Error> {
+        match value {
+            TypedValue::String(tag) => Ok(TypedMessage::bare(tag.clone())),
+            TypedValue::Map(fields) => match fields.get(&key("tag")) {
+                Some(TypedValue::String(tag)) => Ok(TypedMessage::new(
+                    tag.clone(),
+                    fields
+                        .get(&key("fields"))
+                        .cloned()
+                        .unwrap_or_else(|| TypedValue::Map(BTreeMap::new())),
+                )),
+                _ => Err(value.clone()),
+            },
+            _ => Err(value.clone()),
+        }
+    }
+}
+
+impl From<TypedMessage> for TypedValue {
+    fn from(msg: TypedMessage) -> Self {
+        TypedValue::Map(BTreeMap::from([
+            (key("tag"), TypedValue::String(msg.tag)),
+            (key("fields"), msg.fields),
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_bare_tags() {
+        let deposit = TypedMessage::new("Deposit", TypedValue::Int(100));
+        let value = TypedValue::from(deposit.clone());
+        assert_eq!(tag_of(&value), Some("Deposit"));
+        assert_eq!(payload_of(&value), Some(TypedValue::Int(100)));
+        assert_eq!(TypedMessage::try_from(&value), Ok(deposit));
+
+        let ping = TypedValue::String("Ping".to_string());
+        assert_eq!(
+            TypedMessage::try_from(&ping),
+            Ok(TypedMessage::bare("Ping"))
+        );
+
+        assert_eq!(tag_of(&TypedValue::Int(1)), None);
+        assert!(TypedMessage::try_from(&TypedValue::Map(BTreeMap::new())).is_err());
+    }
+}
@@ -0,0 +1,80 @@
+//! Runtime metrics
+//!
+//! Aggregate counters maintained by `ActorRuntime`. Take a point-in-time
+//! copy with `ActorRuntime::metrics()`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Live counters (updated lock-free by the runtime)
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    pub(crate) messages_processed: AtomicU64,
+    pub(crate) behavior_failures: AtomicU64,
+    pub(crate) blocked_detected: AtomicU64,
+    pub(crate) rate_limited: AtomicU64,
+    pub(crate) shed: AtomicU64,
+    pub(crate) expired: AtomicU64,
+    pub(crate) dropped: AtomicU64,
+    pub(crate) dead_letters: AtomicU64,
+    pub(crate) self_send_loops: AtomicU64,
+    pub(crate) state_limit_exceeded: AtomicU64,
+    pub(crate) builtins_denied: AtomicU64,
+    pub(crate) events_rejected: AtomicU64,
+    pub(crate) timed_out: AtomicU64,
+}
+
+impl Metrics {
+    pub(crate) fn incr(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            messages_processed: self.messages_processed.load(Ordering::Relaxed),
+            behavior_failures: self.behavior_failures.load(Ordering::Relaxed),
+            blocked_detected: self.blocked_detected.load(Ordering::Relaxed),
+            rate_limited: self.rate_limited.load(Ordering::Relaxed),
+            shed: self.shed.load(Ordering::Relaxed),
+            expired: self.expired.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            dead_letters: self.dead_letters.load(Ordering::Relaxed),
+            self_send_loops: self.self_send_loops.load(Ordering::Relaxed),
+            state_limit_exceeded: self.state_limit_exceeded.load(Ordering::Relaxed),
+            builtins_denied: self.builtins_denied.load(Ordering::Relaxed),
+            events_rejected: self.events_rejected.load(Ordering::Relaxed),
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Point-in-time copy of the runtime counters
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// Messages handled successfully
+    pub messages_processed: u64,
+    /// Messages the behavior rejected
+    pub behavior_failures: u64,
+    /// Handlings the watchdog flagged as blocked
+    pub blocked_detected: u64,
+    /// Sends rejected by a rate limit
+    pub rate_limited: u64,
+    /// Sends rejected by load shedding
+    pub shed: u64,
+    /// Queued messages dropped after outliving their TTL or maximum age
+    pub expired: u64,
+    /// Queued messages dropped without being handled (includes
+    /// `rate_limited`, `shed` and `expired`)
+    pub dropped: u64,
+    /// Sends to unknown or stopped actors, and messages nothing handled
+    pub dead_letters: u64,
+    /// Self-send chains that reached the loop threshold
+    pub self_send_loops: u64,
+    /// Actor states that grew past their `StateLimit`
+    pub state_limit_exceeded: u64,
+    /// Builtin calls refused by an actor's sandbox
+    pub builtins_denied: u64,
+    /// Messages failed because a validator rejected an emitted event
+    pub events_rejected: u64,
+    /// Messages failed for running past their timeout
+    pub timed_out: u64,
+}
@@ -0,0 +1,77 @@
+//! Runtime observer callbacks
+//!
+//! Host applications implement `RuntimeObserver` to be told about notable
+//! runtime events as they happen. Every callback has a no-op default, so
+//! implementors only override what they care about. Callbacks run on the
+//! thread that detected the event and should return quickly.
+
+use crate::actor::ActorId;
+use crate::lifecycle::StopReason;
+use crate::shedding::Pressure;
+use crate::state_size::StateLimit;
+use std::time::Duration;
+
+/// Why a queued message was dropped without being handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropReason {
+    /// The recipient's rate limit rejected it
+    RateLimited,
+    /// The recipient was shedding load
+    Shed,
+    /// The recipient was unregistered with the message still queued
+    Unregistered,
+    /// The recipient was killed with the message still queued
+    Killed,
+    /// The message outlived its TTL or the recipient's maximum message age
+    /// (see `crate::expiry`)
+    Expired,
+    /// Writing the send to the recorded session failed (see
+    /// `crate::session`), so it wasn't queued
+    TraceFailed,
+}
+
+/// Receives runtime event callbacks
+pub trait RuntimeObserver: Send + Sync {
+    /// A message handling has been running longer than the watchdog
+    /// threshold (reported once per handling)
+    fn on_blocked(&self, _id: &ActorId, _behavior: &str, _elapsed: Duration) {}
+
+    /// A message of `size` bytes was queued, leaving `depth` queued
+    fn on_enqueue(&self, _to: &ActorId, _size: usize, _depth: usize) {}
+
+    /// A message was taken for handling after waiting `waited` in the inbox
+    fn on_dequeue(&self, _id: &ActorId, _size: usize, _waited: Duration) {}
+
+    /// A message for a known actor was dropped without being handled
+    fn on_drop(&self, _to: &ActorId, _size: usize, _reason: DropReason) {}
+
+    /// A message was sent to an actor that is unknown or stopped, or its
+    /// behavior had nothing to handle it with
+    fn on_dead_letter(&self, _to: &ActorId, _size: usize) {}
+
+    /// An actor has handled `chain` self-sent messages in a row, reaching
+    /// the configured loop threshold (reported once per chain)
+    fn on_self_send_loop(&self, _id: &ActorId, _chain: u32) {}
+
+    /// An actor terminated (its final event and snapshot are written)
+    fn on_stopped(&self, _id: &ActorId, _reason: &StopReason) {}
+
+    /// Load shedding started or stopped (`pressure.shedding` says which)
+    fn on_load_shedding(&self, _pressure: &Pressure) {}
+
+    /// An actor's state grew to `size` bytes, past its limit (reported
+    /// when it crosses the limit, not again until it drops back under)
+    fn on_state_limit(&self, _id: &ActorId, _size: usize, _limit: &StateLimit) {}
+
+    /// An actor called a builtin its sandbox denies
+    fn on_builtin_denied(&self, _id: &ActorId, _word: &str) {}
+
+    /// An actor's behavior panicked handling a message (reported before
+    /// its crash dump is written; see `crate::crash`)
+    fn on_panic(&self, _id: &ActorId, _behavior: &str, _message: &str) {}
+
+    /// The process is about to abort after a panic in `id`
+    /// (`crate::crash::PanicPolicy::AbortProcess`; the journal is flushed
+    /// already)
+    fn on_abort(&self, _id: &ActorId) {}
+}
@@ -0,0 +1,440 @@
+//! Transactional side effects (the outbox pattern)
+//!
+//! A behavior shouldn't call out to the world while handling a message: if
+//! it crashes after the call but before its events are journaled, the
+//! effect happened without the state change that caused it (or the other
+//! way round). Instead it records an `Intent`:
+//!
+//! ```rust,ignore
+//! ctx.emit("OrderPlaced", order.clone());
+//! ctx.outbox(Intent::new("http", receipt_request));
+//! ctx.outbox(Intent::send(&shipping, ship_msg));
+//! ```
+//!
+//! Intents are journaled as `OutboxIntent` events in the same write as the
+//! handling's other events, so they exist exactly when the state change
+//! does. An `Outbox` then executes them from the journal through handlers
+//! registered per target, acknowledging each once it succeeds (tracked by
+//! a persistent subscription, see `journal::subscription`). Delivery is
+//! at-least-once: an intent whose handler fails, or that was executed
+//! just before a crash, is retried, so handlers should be idempotent.
+//! Later intents from the same actor wait, keeping each actor's effects in
+//! order.
+//!
+//! Intents for the `actor` target (`Intent::send`) are delivered by the
+//! runtime itself. Intents journaled in a named storage tier (see
+//! `crate::tier`) are delivered too, acknowledged in that tier's journal.
+//! Nothing is delivered with journaling disabled.
+
+use crate::actor::ActorId;
+use crate::error::RuntimeError;
+use crate::journal::subscription::{PersistentSubscription, Progress, StartFrom};
+use crate::runtime::ActorRuntime;
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Event type journaled for an intent
+pub const INTENT_EVENT: &str = "OutboxIntent";
+
+/// Target of intents that message another actor
+pub const ACTOR_TARGET: &str = "actor";
+
+/// Subscription name used by `Outbox::new`
+pub const DEFAULT_NAME: &str = "outbox";
+
+/// A side effect to execute once its handling is journaled
+#[derive(Debug, Clone, PartialEq)]
+pub struct Intent {
+    /// Handler to execute it with
+    pub target: String,
+    pub payload: TypedValue,
+}
+
+fn key(name: &str) -> TypedMapKey {
+    TypedMapKey::String(name.to_string())
+}
+
+impl Intent {
+    pub fn new(target: impl Into<String>, payload: TypedValue) -> Self {
+        Intent {
+            target: target.into(),
+            payload,
+        }
+    }
+
+    /// Send `msg` to actor `to`
+    pub fn send(to: &ActorId, msg: TypedValue) -> Self {
+        let mut fields = BTreeMap::new();
+        fields.insert(key("to"), TypedValue::String(to.to_string()));
+        fields.insert(key("msg"), msg);
+        Intent::new(ACTOR_TARGET, TypedValue::Map(fields))
+    }
+
+    /// Map of `target` and `payload`, as journaled
+    pub fn to_value(&self) -> TypedValue {
+        let mut fields = BTreeMap::new();
+        fields.insert(key("target"), TypedValue::String(self.target.clone()));
+        fields.insert(key("payload"), self.payload.clone());
+        TypedValue::Map(fields)
+    }
+
+    /// Inverse of `to_value`
+    pub fn from_value(value: &TypedValue) -> Option<Self> {
+        let TypedValue::Map(fields) = value else {
+            return None;
+        };
+        let Some(TypedValue::String(target)) = fields.get(&key("target")) else {
+            return None;
+        };
+        Some(Intent::new(
+            target.clone(),
+            fields.get(&key("payload"))?.clone(),
+        ))
+    }
+
+    /// Recipient and message of an `Intent::send`
+    fn as_send(&self) -> Option<(ActorId, TypedValue)> {
+        let TypedValue::Map(fields) = &self.payload else {
+            return None;
+        };
+        let Some(TypedValue::String(to)) = fields.get(&key("to")) else {
+            return None;
+        };
+        Some((to.parse().ok()?, fields.get(&key("msg"))?.clone()))
+    }
+}
+
+/// Executes intents for one target, returning an error to retry later
+pub type IntentHandler = dyn Fn(&Intent) -> Result<(), String> + Send + Sync;
+
+/// An intent whose delivery failed (it will be retried)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailedIntent {
+    pub actor_id: ActorId,
+    /// Sequence number of its `OutboxIntent` event
+    pub seq: u64,
+    pub intent: Intent,
+    pub error: String,
+}
+
+/// Outcome of one delivery pass
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutboxReport {
+    pub delivered: usize,
+    pub failed: Vec<FailedIntent>,
+}
+
+/// Delivers journaled intents
+pub struct Outbox {
+    runtime: Weak<ActorRuntime>,
+    /// Name of the subscription tracking acknowledgements
+    name: String,
+    handlers: HashMap<String, Arc<IntentHandler>>,
+    /// Each journal's subscription between passes, by storage tier
+    progress: Mutex<HashMap<Option<String>, Progress>>,
+}
+
+impl Outbox {
+    /// Outbox for `runtime`'s journal, tracked as `DEFAULT_NAME`
+    pub fn new(runtime: &Arc<ActorRuntime>) -> Self {
+        Outbox {
+            runtime: Arc::downgrade(runtime),
+            name: DEFAULT_NAME.to_string(),
+            handlers: HashMap::new(),
+            progress: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Track acknowledgements under another subscription name (to run
+    /// separate outboxes over one journal)
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Execute intents for `target` with `handler`
+    pub fn handler<F>(mut self, target: impl Into<String>, handler: F) -> Self
+    where
+        F: Fn(&Intent) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.handlers.insert(target.into(), Arc::new(handler));
+        self
+    }
+
+    /// Deliver every unacknowledged intent once
+    pub fn deliver_pending(&self) -> Result<OutboxReport, RuntimeError> {
+        match self.runtime.upgrade() {
+            Some(runtime) => self.deliver_with(&runtime),
+            None => Ok(OutboxReport::default()),
+        }
+    }
+
+    fn deliver_with(&self, runtime: &ActorRuntime) -> Result<OutboxReport, RuntimeError> {
+        runtime.flush_journal()?;
+        let mut progress = self.progress.lock().expect("outbox progress lock poisoned");
+        let mut report = OutboxReport::default();
+        for (tier, journal) in runtime.all_journals() {
+            // Pick up where the last pass stopped reading, retrying the
+            // intents it left unacknowledged
+            let tier = tier.map(str::to_string);
+            let mut sub = match progress.remove(&tier) {
+                Some(progress) => journal.resume_subscription(progress),
+                None => journal.subscribe_persistent(&self.name, StartFrom::Beginning)?,
+            };
+            sub.rewind();
+            self.deliver_from(runtime, &mut sub, &mut report)?;
+            progress.insert(tier, sub.suspend());
+        }
+        Ok(report)
+    }
+
+    /// Deliver the intents `sub` has yet to acknowledge
+    fn deliver_from(
+        &self,
+        runtime: &ActorRuntime,
+        sub: &mut PersistentSubscription<'_>,
+        report: &mut OutboxReport,
+    ) -> Result<(), RuntimeError> {
+        // Actors with a failed intent: their later intents wait
+        let mut blocked = HashSet::new();
+        // Last non-intent event seen per actor, acknowledged at the end
+        let mut passed = HashMap::new();
+        for event in sub.poll()? {
+            let actor_id = event
+                .actor_id
+                .clone()
+                .expect("subscriptions stamp every event's actor_id");
+            if blocked.contains(&actor_id) {
+                continue;
+            }
+            let intent = match event.event_type.as_str() {
+                INTENT_EVENT => Intent::from_value(&event.payload),
+                _ => None,
+            };
+            let Some(intent) = intent else {
+                passed.insert(actor_id, event.seq);
+                continue;
+            };
+
+            match self.execute(runtime, &intent) {
+                Ok(()) => {
+                    sub.ack_through(&actor_id, event.seq)?;
+                    passed.remove(&actor_id);
+                    report.delivered += 1;
+                }
+                Err(error) => {
+                    blocked.insert(actor_id.clone());
+                    report.failed.push(FailedIntent {
+                        actor_id,
+                        seq: event.seq,
+                        intent,
+                        error,
+                    });
+                }
+            }
+        }
+        for (actor_id, seq) in passed {
+            sub.ack_through(&actor_id, seq)?;
+        }
+        Ok(())
+    }
+
+    fn execute(&self, runtime: &ActorRuntime, intent: &Intent) -> Result<(), String> {
+        if let Some(handler) = self.handlers.get(&intent.target) {
+            return handler(intent);
+        }
+        if intent.target == ACTOR_TARGET {
+            let (to, msg) = intent
+                .as_send()
+                .ok_or_else(|| "malformed actor intent".to_string())?;
+            return runtime.send(&to, msg).map_err(|e| e.to_string());
+        }
+        Err(format!("no handler for target {:?}", intent.target))
+    }
+
+    /// Deliver on a background thread, every `interval`
+    pub fn spawn(self, interval: Duration) -> OutboxWorker {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            std::thread::Builder::new()
+                .name("seq-actors-outbox".to_string())
+                .spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        let Some(runtime) = self.runtime.upgrade() else {
+                            break;
+                        };
+                        let report = self.deliver_with(&runtime)?;
+                        drop(runtime);
+                        if report.delivered == 0 {
+                            std::thread::park_timeout(interval);
+                        }
+                    }
+                    Ok(())
+                })
+                .expect("failed to spawn outbox thread")
+        };
+
+        OutboxWorker {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Background thread delivering an outbox's intents
+///
+/// Failed intents are retried on later passes. Stops when dropped, when
+/// the runtime is dropped, or at the first journal error.
+pub struct OutboxWorker {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Result<(), RuntimeError>>>,
+}
+
+impl OutboxWorker {
+    /// Stop the worker, returning the error that ended it early, if any
+    pub fn stop(mut self) -> Result<(), RuntimeError> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> Result<(), RuntimeError> {
+        self.stop.store(true, Ordering::Relaxed);
+        match self.thread.take() {
+            Some(thread) => {
+                thread.thread().unpark();
+                thread.join().expect("outbox thread panicked")
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for OutboxWorker {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavior::{BehaviorContext, BehaviorError};
+    use crate::runtime::RuntimeConfig;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_intents_delivered_at_least_once_in_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = Arc::new(ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            ..RuntimeConfig::default()
+        }));
+        runtime.register_behavior(
+            "inbox",
+            |_ctx: &mut BehaviorContext, _state: &TypedValue, msg: &TypedValue| {
+                Ok::<_, BehaviorError>(msg.clone())
+            },
+        );
+        let inbox = runtime.spawn("inbox").unwrap();
+        runtime.register_behavior("orders", {
+            let inbox = inbox.clone();
+            move |ctx: &mut BehaviorContext, _state: &TypedValue, msg: &TypedValue| {
+                ctx.emit("OrderPlaced", msg.clone());
+                ctx.outbox(Intent::send(&inbox, msg.clone()));
+                ctx.outbox(Intent::new("http", msg.clone()));
+                Ok::<_, BehaviorError>(msg.clone())
+            }
+        });
+        let orders = runtime.spawn("orders").unwrap();
+        runtime.send(&orders, TypedValue::Int(7)).unwrap();
+        runtime.run_until_idle().unwrap();
+
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let posted = Arc::new(Mutex::new(vec![]));
+        let outbox = Outbox::new(&runtime).handler("http", {
+            let (attempts, posted) = (attempts.clone(), posted.clone());
+            move |intent: &Intent| {
+                if attempts.fetch_add(1, Ordering::Relaxed) == 0 {
+                    return Err("connection refused".to_string());
+                }
+                posted.lock().unwrap().push(intent.payload.clone());
+                Ok(())
+            }
+        });
+
+        let first = outbox.deliver_pending().unwrap();
+        assert_eq!(first.delivered, 1);
+        assert_eq!(first.failed.len(), 1);
+        assert_eq!(first.failed[0].error, "connection refused");
+        runtime.run_until_idle().unwrap();
+        assert_eq!(runtime.read_state(&inbox).unwrap(), TypedValue::Int(7));
+
+        // Only the failed intent is retried
+        assert_eq!(outbox.deliver_pending().unwrap().delivered, 1);
+        assert_eq!(*posted.lock().unwrap(), [TypedValue::Int(7)]);
+        assert_eq!(outbox.deliver_pending().unwrap(), OutboxReport::default());
+
+        // Acknowledgements survive a restart of the outbox
+        let restarted = Outbox::new(&runtime).handler("http", |_: &Intent| Ok(()));
+        assert_eq!(restarted.deliver_pending().unwrap().delivered, 0);
+
+        runtime.unregister_actor(&orders);
+        runtime.unregister_actor(&inbox);
+    }
+
+    #[test]
+    fn test_intents_delivered_from_storage_tiers() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = Arc::new(ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().join("default"),
+            storage_tiers: [("fast".to_string(), temp_dir.path().join("fast"))].into(),
+            ..RuntimeConfig::default()
+        }));
+        runtime.register_behavior(
+            "notifier",
+            |ctx: &mut BehaviorContext, _state: &TypedValue, msg: &TypedValue| {
+                ctx.outbox(Intent::new("mail", msg.clone()));
+                Ok::<_, BehaviorError>(msg.clone())
+            },
+        );
+        let hot = runtime
+            .spawn_in_tier("notifier", crate::tier::StorageTier::named("fast"))
+            .unwrap();
+        let cold = runtime.spawn("notifier").unwrap();
+        runtime.send(&hot, TypedValue::Int(1)).unwrap();
+        runtime.send(&cold, TypedValue::Int(2)).unwrap();
+        runtime.run_until_idle().unwrap();
+
+        let sent = Arc::new(Mutex::new(vec![]));
+        let outbox = Outbox::new(&runtime).handler("mail", {
+            let sent = sent.clone();
+            move |intent: &Intent| {
+                sent.lock().unwrap().push(intent.payload.clone());
+                Ok(())
+            }
+        });
+        assert_eq!(outbox.deliver_pending().unwrap().delivered, 2);
+        assert_eq!(outbox.deliver_pending().unwrap().delivered, 0);
+        sent.lock().unwrap().sort_by_key(|v| format!("{:?}", v));
+        assert_eq!(
+            *sent.lock().unwrap(),
+            [TypedValue::Int(1), TypedValue::Int(2)]
+        );
+
+        runtime.unregister_actor(&hot);
+        runtime.unregister_actor(&cold);
+    }
+
+    #[test]
+    fn test_intent_round_trip() {
+        let intent = Intent::send(&ActorId::new(), TypedValue::Int(1));
+        assert_eq!(Intent::from_value(&intent.to_value()), Some(intent));
+        assert_eq!(Intent::from_value(&TypedValue::Int(1)), None);
+    }
+}
@@ -0,0 +1,193 @@
+//! Free lists for hot-path allocations
+//!
+//! Every builtin call pops its arguments off the Seq stack and pushes its
+//! results, one heap node per value. A `NodePool` keeps popped nodes
+//! around so the next push reuses one instead of going back to the
+//! allocator. The FFI layer keeps one pool per thread (see
+//! `ffi::pool_stats`), so free lists need no locking.
+//!
+//! Envelopes don't need a pool of their own: they live by value in each
+//! actor's inbox `VecDeque`, whose buffer is reused from message to
+//! message.
+//!
+//! A pool only reuses and frees nodes a pool allocated: nodes move
+//! between threads with the stacks they're on, so the ones pools own are
+//! tracked process-wide. A node allocated elsewhere (by seq-runtime) has
+//! its value moved out and is otherwise left alone, since its allocator
+//! is not ours to call.
+
+use std::collections::HashSet;
+use std::mem::MaybeUninit;
+use std::sync::{Mutex, OnceLock};
+
+/// Default number of free nodes a pool keeps
+pub const DEFAULT_POOL_CAPACITY: usize = 1024;
+
+/// Shards of the set of owned nodes, to spread out lock contention
+const OWNED_SHARDS: usize = 16;
+
+/// Addresses of the nodes pools have allocated and not freed
+fn owned_shard(node: usize) -> &'static Mutex<HashSet<usize>> {
+    static OWNED: OnceLock<Vec<Mutex<HashSet<usize>>>> = OnceLock::new();
+    let shards = OWNED.get_or_init(|| (0..OWNED_SHARDS).map(|_| Mutex::default()).collect());
+    // Nodes are at least word-aligned, so skip the low bits
+    &shards[(node >> 4) % OWNED_SHARDS]
+}
+
+fn lock_owned(node: usize) -> std::sync::MutexGuard<'static, HashSet<usize>> {
+    owned_shard(node).lock().expect("owned nodes lock poisoned")
+}
+
+/// Allocation counters for a pool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Allocations served from the free list
+    pub reused: u64,
+    /// Allocations that went to the allocator
+    pub allocated: u64,
+    /// Nodes freed back to the allocator because the pool was full
+    pub released: u64,
+    /// Recycled nodes left alone because no pool allocated them
+    pub foreign: u64,
+    /// Nodes currently on the free list
+    pub free: usize,
+}
+
+/// A bounded free list of heap nodes holding a `T`
+pub struct NodePool<T> {
+    free: Vec<*mut T>,
+    capacity: usize,
+    stats: PoolStats,
+}
+
+impl<T> NodePool<T> {
+    /// A pool that keeps at most `capacity` free nodes
+    pub fn new(capacity: usize) -> Self {
+        NodePool {
+            free: Vec::with_capacity(capacity),
+            capacity,
+            stats: PoolStats::default(),
+        }
+    }
+
+    /// A node holding `value`, reused if one is free
+    ///
+    /// The node must be handed back with `recycle`.
+    pub fn alloc(&mut self, value: T) -> *mut T {
+        match self.free.pop() {
+            Some(node) => {
+                self.stats.reused += 1;
+                // Safety: free nodes are live allocations whose value was
+                // moved out by `recycle`
+                unsafe { node.write(value) };
+                node
+            }
+            None => {
+                self.stats.allocated += 1;
+                let node = Box::into_raw(Box::new(value));
+                lock_owned(node as usize).insert(node as usize);
+                node
+            }
+        }
+    }
+
+    /// Move the value out of `node`, keeping the node for reuse if a
+    /// pool allocated it
+    ///
+    /// # Safety
+    ///
+    /// `node` must point to an initialized value and not be used by the
+    /// caller afterwards.
+    pub unsafe fn recycle(&mut self, node: *mut T) -> T {
+        let value = node.read();
+        if !lock_owned(node as usize).contains(&(node as usize)) {
+            self.stats.foreign += 1;
+        } else if self.free.len() < self.capacity {
+            self.free.push(node);
+        } else {
+            self.stats.released += 1;
+            release(node);
+        }
+        value
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            free: self.free.len(),
+            ..self.stats
+        }
+    }
+}
+
+impl<T> Default for NodePool<T> {
+    fn default() -> Self {
+        NodePool::new(DEFAULT_POOL_CAPACITY)
+    }
+}
+
+impl<T> Drop for NodePool<T> {
+    fn drop(&mut self) {
+        for node in self.free.drain(..) {
+            // Safety: free nodes were allocated by a pool and hold no value
+            unsafe { release(node) };
+        }
+    }
+}
+
+/// Free an empty node a pool allocated
+///
+/// # Safety
+///
+/// `node` must come from `NodePool::alloc` and its value must have been
+/// moved out.
+unsafe fn release<T>(node: *mut T) {
+    lock_owned(node as usize).remove(&(node as usize));
+    // Only the memory goes: the value was moved out
+    drop(Box::from_raw(node.cast::<MaybeUninit<T>>()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reuses_recycled_nodes() {
+        let mut pool = NodePool::new(1);
+        let a = pool.alloc(String::from("a"));
+        let b = pool.alloc(String::from("b"));
+        assert_eq!(unsafe { pool.recycle(a) }, "a");
+        assert_eq!(unsafe { pool.recycle(b) }, "b");
+
+        // Only one node fits on the free list; the other went back
+        let c = pool.alloc(String::from("c"));
+        assert_eq!(c, a);
+        assert_eq!(
+            pool.stats(),
+            PoolStats {
+                reused: 1,
+                allocated: 2,
+                released: 1,
+                foreign: 0,
+                free: 0,
+            }
+        );
+        assert_eq!(unsafe { pool.recycle(c) }, "c");
+    }
+
+    #[test]
+    fn test_foreign_nodes_are_not_kept() {
+        let mut pool = NodePool::new(4);
+        let foreign = Box::into_raw(Box::new(String::from("theirs")));
+        assert_eq!(unsafe { pool.recycle(foreign) }, "theirs");
+        assert_eq!(pool.stats().foreign, 1);
+        assert_eq!(pool.stats().free, 0);
+
+        // The next allocation is a node of the pool's own
+        let ours = pool.alloc(String::from("ours"));
+        assert_ne!(pool.stats().allocated, 0);
+        assert_eq!(unsafe { pool.recycle(ours) }, "ours");
+        assert_eq!(pool.stats().free, 1);
+        // Still the caller's to free
+        drop(unsafe { Box::from_raw(foreign.cast::<MaybeUninit<String>>()) });
+    }
+}
@@ -0,0 +1,187 @@
+//! Cold-start preloading
+//!
+//! Recovering an actor reads and decodes its snapshot and every event
+//! after it, so after a restart the first message to each actor pays that
+//! cost. `RuntimeConfig::preload` does the reading ahead of time, on a
+//! background thread started with the runtime:
+//!
+//! ```rust,ignore
+//! let config = RuntimeConfig {
+//!     // or Preload::Actors(vec![hot_id, other_hot_id])
+//!     preload: Some(Preload::TouchedWithin(Duration::from_secs(6 * 3600))),
+//!     ..RuntimeConfig::default()
+//! };
+//! let runtime = ActorRuntime::new(config);
+//! runtime.wait_for_preload(Duration::from_secs(10));
+//! ```
+//!
+//! `TouchedWithin` picks the actors whose journal or snapshot file was
+//! modified within the window. Spawning a preloaded actor takes its
+//! snapshot and events from memory; only folding them through the
+//! behavior is left, since the journal doesn't know an actor's behavior.
+//!
+//! Preloading covers the default storage tier. An actor written to after
+//! it was preloaded (by the runtime, before it was spawned) is read from
+//! the journal again; write other actors' journals directly only before
+//! the runtime starts.
+
+use crate::actor::ActorId;
+use crate::journal::{Event, Journal, Snapshot};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Which actors `RuntimeConfig::preload` reads ahead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Preload {
+    /// These actors
+    Actors(Vec<ActorId>),
+    /// Every actor whose journal was written within the duration
+    TouchedWithin(Duration),
+}
+
+/// An actor's journal, read ahead of its spawn
+#[derive(Debug, Clone)]
+pub(crate) struct Preloaded {
+    pub(crate) snapshot: Option<Snapshot>,
+    /// Events from the snapshot's sequence number on
+    pub(crate) events: Vec<Event>,
+}
+
+#[derive(Default)]
+struct Entries {
+    preloaded: HashMap<ActorId, Preloaded>,
+    /// Actors spawned or written to since startup: never preloaded again
+    claimed: HashSet<ActorId>,
+    done: bool,
+}
+
+/// Journals read ahead, shared with the preloading thread
+#[derive(Default)]
+pub(crate) struct PreloadCache {
+    entries: Mutex<Entries>,
+    finished: Condvar,
+}
+
+impl PreloadCache {
+    /// Start reading `preload`'s actors from `journal` in the background
+    pub(crate) fn spawn(journal: Arc<Journal>, preload: Preload) -> Arc<Self> {
+        let cache = Arc::new(PreloadCache::default());
+        let worker = cache.clone();
+        std::thread::Builder::new()
+            .name("seq-actors-preload".to_string())
+            .spawn(move || {
+                worker.load(&journal, preload);
+                worker.lock().done = true;
+                worker.finished.notify_all();
+            })
+            .expect("failed to spawn preload thread");
+        cache
+    }
+
+    /// A cache with nothing to preload
+    pub(crate) fn empty() -> Arc<Self> {
+        let cache = PreloadCache::default();
+        cache.lock().done = true;
+        Arc::new(cache)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries> {
+        self.entries.lock().expect("preload cache lock poisoned")
+    }
+
+    fn load(&self, journal: &Journal, preload: Preload) {
+        let ids = match preload {
+            Preload::Actors(ids) => ids,
+            Preload::TouchedWithin(window) => {
+                let since = SystemTime::now()
+                    .checked_sub(window)
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                journal.modified_since(since).unwrap_or_default()
+            }
+        };
+        for id in ids {
+            if self.lock().claimed.contains(&id) {
+                continue;
+            }
+            // Unreadable journals are left for spawn to report
+            let Ok(snapshot) = journal.load_snapshot(&id) else {
+                continue;
+            };
+            let from_seq = snapshot.as_ref().map(|s| s.seq).unwrap_or(0);
+            let Ok(events) = journal.read_events(&id) else {
+                continue;
+            };
+            let events = events.into_iter().filter(|e| e.seq >= from_seq).collect();
+            let mut entries = self.lock();
+            if !entries.claimed.contains(&id) {
+                entries.preloaded.insert(id, Preloaded { snapshot, events });
+            }
+        }
+    }
+
+    /// Take an actor's preloaded journal, if it has one
+    pub(crate) fn take(&self, id: &ActorId) -> Option<Preloaded> {
+        let mut entries = self.lock();
+        entries.claimed.insert(id.clone());
+        entries.preloaded.remove(id)
+    }
+
+    /// Drop an actor's preloaded journal, now out of date
+    pub(crate) fn invalidate(&self, id: &ActorId) {
+        self.take(id);
+    }
+
+    /// Actors preloaded and not spawned yet
+    pub(crate) fn len(&self) -> usize {
+        self.lock().preloaded.len()
+    }
+
+    /// Wait up to `timeout` for preloading to finish
+    pub(crate) fn wait(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut entries = self.lock();
+        while !entries.done {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return false;
+            }
+            entries = self
+                .finished
+                .wait_timeout(entries, left)
+                .expect("preload cache lock poisoned")
+                .0;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialize::TypedValue;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_preloads_recently_touched_actors() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = Arc::new(Journal::new(temp_dir.path()));
+        let (hot, claimed) = (ActorId::new(), ActorId::new());
+        for id in [&hot, &claimed] {
+            let event = Event::new(0, "Added".to_string(), TypedValue::Int(1));
+            journal.append(id, &event).unwrap();
+        }
+
+        let cache = PreloadCache::spawn(journal.clone(), Preload::TouchedWithin(Duration::ZERO));
+        assert!(cache.wait(Duration::from_secs(5)));
+        assert_eq!(cache.len(), 0, "nothing was touched in an empty window");
+
+        let cache = PreloadCache::empty();
+        cache.invalidate(&claimed);
+        cache.load(&journal, Preload::TouchedWithin(Duration::from_secs(3600)));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.take(&hot).unwrap().events.len(), 1);
+        assert!(cache.take(&hot).is_none());
+        assert!(cache.take(&claimed).is_none());
+    }
+}
@@ -0,0 +1,122 @@
+//! Token-bucket rate limiting
+//!
+//! An actor with a `RateLimit` accepts at most `per_second` messages per
+//! second on average, with bursts of up to `burst`. Sends past the limit
+//! are rejected with `RuntimeError::RateLimited` so callers see
+//! backpressure instead of the inbox growing without bound.
+//!
+//! TODO: Per-peer limits once the remote transport exists.
+
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// Rate limit for messages accepted by an actor
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Sustained messages per second
+    pub per_second: f64,
+    /// Messages accepted back-to-back from a full bucket
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Limit to `per_second`, allowing a burst of one second's worth
+    pub fn per_second(per_second: u32) -> Self {
+        RateLimit {
+            per_second: per_second as f64,
+            burst: per_second.max(1),
+        }
+    }
+}
+
+/// Token bucket enforcing a `RateLimit`
+#[derive(Debug, Clone)]
+pub(crate) struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// Start with a full bucket
+    pub(crate) fn new(limit: RateLimit, now: Instant) -> Self {
+        TokenBucket {
+            limit,
+            tokens: limit.burst as f64,
+            refilled_at: now,
+        }
+    }
+
+    /// Take one token if available
+    pub(crate) fn try_acquire(&mut self, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst as f64);
+        self.refilled_at = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Give back a token taken for a message that wasn't queued after all
+    pub(crate) fn release(&mut self) {
+        self.tokens = (self.tokens + 1.0).min(self.limit.burst as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_burst_then_refill() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(
+            RateLimit {
+                per_second: 10.0,
+                burst: 3,
+            },
+            start,
+        );
+
+        assert!(bucket.try_acquire(start));
+        assert!(bucket.try_acquire(start));
+        assert!(bucket.try_acquire(start));
+        assert!(!bucket.try_acquire(start));
+
+        // 100ms at 10/s refills one token
+        let later = start + Duration::from_millis(100);
+        assert!(bucket.try_acquire(later));
+        assert!(!bucket.try_acquire(later));
+
+        // Never refills past the burst size
+        let much_later = later + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert!(bucket.try_acquire(much_later));
+        }
+        assert!(!bucket.try_acquire(much_later));
+    }
+
+    #[test]
+    fn test_release_returns_a_token() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(RateLimit::per_second(1), start);
+
+        assert!(bucket.try_acquire(start));
+        bucket.release();
+        assert!(bucket.try_acquire(start));
+        assert!(!bucket.try_acquire(start));
+
+        // Never past the burst size
+        bucket.release();
+        bucket.release();
+        assert!(bucket.try_acquire(start));
+        assert!(!bucket.try_acquire(start));
+    }
+}
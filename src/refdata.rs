@@ -0,0 +1,104 @@
+//! Reference data actors
+//!
+//! Hot lookup tables (currency rates, feature flags, routing tables) are
+//! read by many actors and written rarely. Asking the owning actor for
+//! every lookup costs a request/reply hop each time. Publishing the actor
+//! as reference data instead makes its state readable by anyone, without
+//! messaging:
+//!
+//! ```rust,ignore
+//! let rates = runtime.spawn("rates")?;
+//! runtime.publish_reference("rates", &rates)?;
+//!
+//! // anywhere, from any actor
+//! let eur = runtime.reference_get("rates", &TypedMapKey::String("EUR".to_string()));
+//! ```
+//!
+//! The runtime keeps the actor's latest state as an immutable
+//! `Arc<TypedValue>`, replaced whenever a handled message changes it;
+//! `reference_data` hands out a clone of the `Arc`, so readers never wait
+//! on the actor and never see half an update. Updates still go through
+//! the actor as messages, and are journaled as usual. Unregistering the
+//! actor withdraws its data.
+//!
+//! Rust only for now: the proposed Seq word `ref-data-get ( Name Key --
+//! Value )` waits on the value bridge (see DESIGN.md, Proposed Builtins).
+
+use crate::actor::ActorId;
+use crate::serialize::TypedValue;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Published reference data
+#[derive(Default)]
+pub(crate) struct ReferenceData {
+    /// Name each publishing actor is published under
+    names: HashMap<ActorId, String>,
+    /// Latest state, by name
+    published: HashMap<String, Arc<TypedValue>>,
+}
+
+impl ReferenceData {
+    /// Publish `id`'s state under `name`, replacing whoever published it
+    /// before
+    pub(crate) fn publish(&mut self, name: &str, id: &ActorId, state: TypedValue) {
+        self.names.retain(|_, published| published != name);
+        self.names.insert(id.clone(), name.to_string());
+        self.published.insert(name.to_string(), Arc::new(state));
+    }
+
+    /// Stop publishing under `name`
+    pub(crate) fn withdraw(&mut self, name: &str) {
+        self.names.retain(|_, published| published != name);
+        self.published.remove(name);
+    }
+
+    /// Stop publishing `id`'s state
+    pub(crate) fn withdraw_actor(&mut self, id: &ActorId) {
+        if let Some(name) = self.names.remove(id) {
+            self.published.remove(&name);
+        }
+    }
+
+    /// Name `id` publishes under, if any
+    pub(crate) fn name_of(&self, id: &ActorId) -> Option<&str> {
+        self.names.get(id).map(String::as_str)
+    }
+
+    /// Replace the state published under `name`
+    pub(crate) fn update(&mut self, name: &str, state: TypedValue) {
+        self.published.insert(name.to_string(), Arc::new(state));
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<Arc<TypedValue>> {
+        self.published.get(name).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_replaces_and_withdraws() {
+        let (first, second) = (ActorId::new(), ActorId::new());
+        let mut data = ReferenceData::default();
+        data.publish("rates", &first, TypedValue::Int(1));
+        let held = data.get("rates").unwrap();
+
+        data.publish("rates", &second, TypedValue::Int(2));
+        assert_eq!(data.name_of(&first), None);
+        assert_eq!(*held, TypedValue::Int(1), "readers keep what they took");
+        data.update("rates", TypedValue::Int(3));
+        assert_eq!(*data.get("rates").unwrap(), TypedValue::Int(3));
+
+        data.withdraw_actor(&first);
+        assert!(data.get("rates").is_some());
+        data.withdraw_actor(&second);
+        assert!(data.get("rates").is_none());
+
+        data.publish("flags", &first, TypedValue::Bool(true));
+        data.withdraw("flags");
+        assert_eq!(data.name_of(&first), None);
+    }
+}
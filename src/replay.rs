@@ -0,0 +1,319 @@
+//! Event-by-event replay for debugging
+//!
+//! `ReplaySession` folds an actor's journaled events through its
+//! behavior's reducer (`Behavior::apply_event`; journaled state deltas are
+//! applied as they are, see `crate::delta`) one at a time, exposing the
+//! state before and after each event. Stepping until the state first
+//! looks wrong pinpoints the event that introduced a bug:
+//!
+//! ```rust,ignore
+//! let mut session = runtime.replay_session(&account_id, "account")?;
+//! let culprit = session.run_until(|step| balance(&step.after) < 0);
+//! ```
+//!
+//! With `throttle`, each step waits out a minimum interval first, so the
+//! state can be watched evolving at a readable pace. `debugger` wraps a
+//! session in an interactive command loop.
+
+pub mod debugger;
+
+use crate::actor::ActorId;
+use crate::behavior::Behavior;
+use crate::delta;
+use crate::journal::correction::apply_corrections;
+use crate::journal::{Event, Journal};
+use crate::serialize::TypedValue;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// One applied event
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayStep {
+    /// Position of the event in the session (0-based)
+    pub index: usize,
+    pub event: Event,
+    /// State before the event was applied
+    pub before: TypedValue,
+    /// State after
+    pub after: TypedValue,
+}
+
+/// Steps through an actor's events with its reducer
+pub struct ReplaySession {
+    behavior: Arc<dyn Behavior>,
+    events: Vec<Event>,
+    /// State before the first event
+    initial: TypedValue,
+    state: TypedValue,
+    /// Events applied so far
+    position: usize,
+    /// Minimum time between steps
+    throttle: Option<Duration>,
+    last_step: Option<Instant>,
+}
+
+impl ReplaySession {
+    /// Replay `events` starting from `initial`
+    pub fn new(behavior: Arc<dyn Behavior>, initial: TypedValue, events: Vec<Event>) -> Self {
+        ReplaySession {
+            behavior,
+            events,
+            state: initial.clone(),
+            initial,
+            position: 0,
+            throttle: None,
+            last_step: None,
+        }
+    }
+
+    /// Replay an actor's journal
+    ///
+    /// Starts from the behavior's initial state with every event, with
+    /// corrections applied (see `crate::journal::correction`). If the
+    /// journal was compacted (its first events are gone), starts from the
+    /// snapshot instead, with the events after it.
+    pub fn from_journal(
+        journal: &Journal,
+        actor_id: &ActorId,
+        behavior: Arc<dyn Behavior>,
+    ) -> std::io::Result<Self> {
+        let events = apply_corrections(journal.read_events(actor_id)?);
+        let compacted = events.first().is_some_and(|e| e.seq > 0);
+        let snapshot = match compacted {
+            true => journal.load_snapshot(actor_id)?,
+            false => None,
+        };
+
+        Ok(match snapshot {
+            Some(snapshot) => {
+                let events = events
+                    .into_iter()
+                    .filter(|e| e.seq >= snapshot.seq)
+                    .collect();
+                Self::new(behavior, snapshot.state, events)
+            }
+            None => {
+                let initial = behavior.initial_state();
+                Self::new(behavior, initial, events)
+            }
+        })
+    }
+
+    /// Wait at least `per_event` between steps
+    pub fn throttle(mut self, per_event: Duration) -> Self {
+        self.throttle = Some(per_event);
+        self
+    }
+
+    /// Apply the next event, or `None` at the end
+    pub fn step(&mut self) -> Option<ReplayStep> {
+        let event = self.events.get(self.position)?.clone();
+        if let (Some(interval), Some(last)) = (self.throttle, self.last_step) {
+            let elapsed = last.elapsed();
+            if elapsed < interval {
+                std::thread::sleep(interval - elapsed);
+            }
+        }
+        self.last_step = Some(Instant::now());
+
+        let before = std::mem::replace(&mut self.state, TypedValue::Bool(false));
+        self.state = delta::fold(&before, &event, |s, e| self.behavior.apply_event(s, e));
+        let step = ReplayStep {
+            index: self.position,
+            event,
+            before,
+            after: self.state.clone(),
+        };
+        self.position += 1;
+        Some(step)
+    }
+
+    /// Step until `found` matches a step, returning that step
+    ///
+    /// `None` if the events run out first.
+    pub fn run_until(&mut self, mut found: impl FnMut(&ReplayStep) -> bool) -> Option<ReplayStep> {
+        while let Some(step) = self.step() {
+            if found(&step) {
+                return Some(step);
+            }
+        }
+        None
+    }
+
+    /// Move to just before event `position` (clamped to the end), by
+    /// replaying from the start
+    ///
+    /// Seeking ignores the throttle.
+    pub fn seek(&mut self, position: usize) {
+        let position = position.min(self.events.len());
+        if position < self.position {
+            self.state = self.initial.clone();
+            self.position = 0;
+        }
+        while self.position < position {
+            let event = &self.events[self.position];
+            self.state = delta::fold(&self.state, event, |s, e| self.behavior.apply_event(s, e));
+            self.position += 1;
+        }
+    }
+
+    /// Back to before the first event
+    pub fn reset(&mut self) {
+        self.seek(0);
+    }
+
+    /// State after the events applied so far
+    pub fn state(&self) -> &TypedValue {
+        &self.state
+    }
+
+    /// Number of events applied so far
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Every event in the session
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// The event `step` would apply next
+    pub fn peek(&self) -> Option<&Event> {
+        self.events.get(self.position)
+    }
+}
+
+impl Iterator for ReplaySession {
+    type Item = ReplayStep;
+
+    fn next(&mut self) -> Option<ReplayStep> {
+        self.step()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavior::{BehaviorContext, BehaviorError};
+
+    /// Sums Int event payloads
+    struct Summing;
+
+    impl Behavior for Summing {
+        fn handle(
+            &self,
+            _ctx: &mut BehaviorContext,
+            state: &TypedValue,
+            _msg: &TypedValue,
+        ) -> Result<TypedValue, BehaviorError> {
+            Ok(state.clone())
+        }
+
+        fn initial_state(&self) -> TypedValue {
+            TypedValue::Int(0)
+        }
+
+        fn apply_event(&self, state: &TypedValue, event: &Event) -> TypedValue {
+            match (state, &event.payload) {
+                (TypedValue::Int(total), TypedValue::Int(n)) => TypedValue::Int(total + n),
+                _ => state.clone(),
+            }
+        }
+    }
+
+    fn events(amounts: &[i64]) -> Vec<Event> {
+        amounts
+            .iter()
+            .enumerate()
+            .map(|(seq, n)| Event::new(seq as u64, "Added".to_string(), TypedValue::Int(*n)))
+            .collect()
+    }
+
+    #[test]
+    fn test_step_and_find_culprit() {
+        let mut session = ReplaySession::new(
+            Arc::new(Summing),
+            TypedValue::Int(0),
+            events(&[5, 3, -10, 4]),
+        );
+
+        let first = session.step().unwrap();
+        assert_eq!(
+            (first.before, first.after),
+            (TypedValue::Int(0), TypedValue::Int(5))
+        );
+
+        let culprit = session
+            .run_until(|step| matches!(step.after, TypedValue::Int(n) if n < 0))
+            .unwrap();
+        assert_eq!(culprit.index, 2);
+        assert_eq!(culprit.event.payload, TypedValue::Int(-10));
+        assert_eq!(session.state(), &TypedValue::Int(-2));
+
+        assert_eq!(session.by_ref().count(), 1);
+        assert!(session.step().is_none());
+    }
+
+    #[test]
+    fn test_seek_backward() {
+        let mut session =
+            ReplaySession::new(Arc::new(Summing), TypedValue::Int(0), events(&[1, 2, 3]));
+        session.seek(3);
+        assert_eq!(session.state(), &TypedValue::Int(6));
+        session.seek(1);
+        assert_eq!(
+            (session.position(), session.state()),
+            (1, &TypedValue::Int(1))
+        );
+        assert_eq!(session.peek().unwrap().payload, TypedValue::Int(2));
+        session.reset();
+        assert_eq!(session.state(), &TypedValue::Int(0));
+    }
+
+    #[test]
+    fn test_throttle_spaces_steps() {
+        let mut session =
+            ReplaySession::new(Arc::new(Summing), TypedValue::Int(0), events(&[1, 1]))
+                .throttle(Duration::from_millis(20));
+        let start = Instant::now();
+        while session.step().is_some() {}
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_from_compacted_journal_starts_at_snapshot() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let actor_id = ActorId::new();
+        for event in events(&[1, 2, 3]).into_iter().skip(1) {
+            journal.append(&actor_id, &event).unwrap();
+        }
+        let snapshot = crate::journal::Snapshot {
+            actor_id: None,
+            seq: 1,
+            state: TypedValue::Int(1),
+            ts: 0,
+        };
+        journal.save_snapshot(&actor_id, &snapshot).unwrap();
+
+        let session = ReplaySession::from_journal(&journal, &actor_id, Arc::new(Summing)).unwrap();
+        assert_eq!(session.last().unwrap().after, TypedValue::Int(6));
+    }
+
+    #[test]
+    fn test_from_journal_applies_corrections() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let journal = Journal::new(temp_dir.path());
+        let actor_id = ActorId::new();
+        for event in events(&[1, 500, 3]) {
+            journal.append(&actor_id, &event).unwrap();
+        }
+        let fixed = Event::new(0, "Added".to_string(), TypedValue::Int(2));
+        journal.append_correction(&actor_id, 1, &fixed).unwrap();
+
+        let mut session =
+            ReplaySession::from_journal(&journal, &actor_id, Arc::new(Summing)).unwrap();
+        assert_eq!(session.by_ref().count(), 3);
+        assert_eq!(session.state(), &TypedValue::Int(6));
+    }
+}
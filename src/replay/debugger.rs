@@ -0,0 +1,400 @@
+//! Interactive time-travel debugger
+//!
+//! `Debugger` drives a `ReplaySession` from line commands: step forward
+//! and backward, jump to a position, break on event types, and diff the
+//! state between two positions. `run` reads commands from any `BufRead`
+//! and writes to any `Write`, so a host binary with its behaviors
+//! registered can offer it on a terminal:
+//!
+//! ```rust,ignore
+//! let session = runtime.replay_session(&id, "account")?;
+//! Debugger::new(session).run(std::io::stdin().lock(), std::io::stdout())?;
+//! ```
+//!
+//! Positions count applied events: position 0 is the state before the
+//! first event, position n the state after the n-th.
+
+use super::{ReplaySession, ReplayStep};
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::collections::BTreeSet;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+const HELP: &str = "\
+commands:
+  n, next            apply the next event
+  b, back            step back one event
+  g, goto <pos>      jump to a position
+  c, continue        run to the next breakpoint (or the end)
+  s, state           show the current state
+  e, event           show the next event
+  break <type>       break before events of this type
+  unbreak <type>     remove a breakpoint
+  diff <a> <b>       compare the states at two positions
+  h, help            show this help
+  q, quit            leave the debugger";
+
+/// One debugger command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Next,
+    Back,
+    Goto(usize),
+    Continue,
+    State,
+    Event,
+    Break(String),
+    Unbreak(String),
+    Diff(usize, usize),
+    Help,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let position = |word: &str| {
+            word.parse::<usize>()
+                .map_err(|_| format!("not a position: {}", word))
+        };
+        match words.as_slice() {
+            ["n" | "next"] => Ok(Command::Next),
+            ["b" | "back"] => Ok(Command::Back),
+            ["g" | "goto", pos] => Ok(Command::Goto(position(pos)?)),
+            ["c" | "continue"] => Ok(Command::Continue),
+            ["s" | "state"] => Ok(Command::State),
+            ["e" | "event"] => Ok(Command::Event),
+            ["break", event_type] => Ok(Command::Break(event_type.to_string())),
+            ["unbreak", event_type] => Ok(Command::Unbreak(event_type.to_string())),
+            ["diff", a, b] => Ok(Command::Diff(position(a)?, position(b)?)),
+            ["h" | "help"] => Ok(Command::Help),
+            ["q" | "quit"] => Ok(Command::Quit),
+            _ => Err(format!("unknown command: {} (try help)", line.trim())),
+        }
+    }
+}
+
+/// One difference between two states
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateChange {
+    Added(TypedMapKey, TypedValue),
+    Removed(TypedMapKey, TypedValue),
+    Changed(TypedMapKey, TypedValue, TypedValue),
+    /// The states aren't both Maps and differ as a whole
+    Replaced(TypedValue, TypedValue),
+}
+
+/// Top-level differences from `before` to `after`
+pub fn diff_states(before: &TypedValue, after: &TypedValue) -> Vec<StateChange> {
+    let (TypedValue::Map(old), TypedValue::Map(new)) = (before, after) else {
+        return match before == after {
+            true => vec![],
+            false => vec![StateChange::Replaced(before.clone(), after.clone())],
+        };
+    };
+
+    let mut changes = vec![];
+    for (key, value) in old {
+        match new.get(key) {
+            None => changes.push(StateChange::Removed(key.clone(), value.clone())),
+            Some(next) if next != value => changes.push(StateChange::Changed(
+                key.clone(),
+                value.clone(),
+                next.clone(),
+            )),
+            Some(_) => {}
+        }
+    }
+    for (key, value) in new {
+        if !old.contains_key(key) {
+            changes.push(StateChange::Added(key.clone(), value.clone()));
+        }
+    }
+    changes
+}
+
+/// Time-travel debugger over a replay session
+pub struct Debugger {
+    session: ReplaySession,
+    breakpoints: BTreeSet<String>,
+}
+
+impl Debugger {
+    pub fn new(session: ReplaySession) -> Self {
+        Debugger {
+            session,
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    /// The underlying session
+    pub fn session(&self) -> &ReplaySession {
+        &self.session
+    }
+
+    /// Apply the next event
+    pub fn forward(&mut self) -> Option<ReplayStep> {
+        self.session.step()
+    }
+
+    /// Undo the last event, returning false at the start
+    pub fn back(&mut self) -> bool {
+        let position = self.session.position();
+        if position == 0 {
+            return false;
+        }
+        self.session.seek(position - 1);
+        true
+    }
+
+    /// Break before events of `event_type`
+    pub fn break_on(&mut self, event_type: impl Into<String>) {
+        self.breakpoints.insert(event_type.into());
+    }
+
+    /// Remove a breakpoint, returning whether it was set
+    pub fn clear_break(&mut self, event_type: &str) -> bool {
+        self.breakpoints.remove(event_type)
+    }
+
+    /// Step until the next event is a breakpoint type (or the end)
+    ///
+    /// Always applies at least one event, so repeated calls move from one
+    /// breakpoint to the next. Returns the number of events applied.
+    pub fn continue_to_breakpoint(&mut self) -> usize {
+        let mut applied = 0;
+        while self.session.step().is_some() {
+            applied += 1;
+            match self.session.peek() {
+                Some(next) if self.breakpoints.contains(&next.event_type) => break,
+                Some(_) => {}
+                None => break,
+            }
+        }
+        applied
+    }
+
+    /// State at `position`, leaving the current position unchanged
+    pub fn state_at(&mut self, position: usize) -> TypedValue {
+        let current = self.session.position();
+        self.session.seek(position);
+        let state = self.session.state().clone();
+        self.session.seek(current);
+        state
+    }
+
+    /// Differences between the states at two positions
+    pub fn diff(&mut self, from: usize, to: usize) -> Vec<StateChange> {
+        let before = self.state_at(from);
+        let after = self.state_at(to);
+        diff_states(&before, &after)
+    }
+
+    /// Read commands from `input` until it ends or `quit`
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> std::io::Result<()> {
+        writeln!(output, "type help for commands")?;
+        self.write_position(&mut output)?;
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match line.parse::<Command>() {
+                Ok(Command::Quit) => break,
+                Ok(command) => self.execute(command, &mut output)?,
+                Err(message) => writeln!(output, "{}", message)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Run one command, writing its result
+    fn execute(&mut self, command: Command, output: &mut impl Write) -> std::io::Result<()> {
+        match command {
+            Command::Next => match self.forward() {
+                Some(step) => writeln!(
+                    output,
+                    "{} -> {}",
+                    step.event.to_debug_string(),
+                    step.after.to_debug_string()
+                )?,
+                None => writeln!(output, "at the end")?,
+            },
+            Command::Back => match self.back() {
+                true => self.write_position(output)?,
+                false => writeln!(output, "at the start")?,
+            },
+            Command::Goto(position) => {
+                self.session.seek(position);
+                self.write_position(output)?;
+            }
+            Command::Continue => {
+                let applied = self.continue_to_breakpoint();
+                writeln!(output, "applied {} events", applied)?;
+                self.write_position(output)?;
+            }
+            Command::State => writeln!(output, "{}", self.session.state().to_debug_string())?,
+            Command::Event => match self.session.peek() {
+                Some(event) => writeln!(output, "{}", event.to_debug_string())?,
+                None => writeln!(output, "at the end")?,
+            },
+            Command::Break(event_type) => {
+                writeln!(output, "breaking before {}", event_type)?;
+                self.break_on(event_type);
+            }
+            Command::Unbreak(event_type) => match self.clear_break(&event_type) {
+                true => writeln!(output, "removed breakpoint {}", event_type)?,
+                false => writeln!(output, "no breakpoint {}", event_type)?,
+            },
+            Command::Diff(from, to) => {
+                let changes = self.diff(from, to);
+                if changes.is_empty() {
+                    writeln!(output, "no changes")?;
+                }
+                for change in changes {
+                    match change {
+                        StateChange::Added(key, value) => {
+                            writeln!(output, "+ {:?}: {}", key, value.to_debug_string())?
+                        }
+                        StateChange::Removed(key, value) => {
+                            writeln!(output, "- {:?}: {}", key, value.to_debug_string())?
+                        }
+                        StateChange::Changed(key, old, new) => writeln!(
+                            output,
+                            "~ {:?}: {} -> {}",
+                            key,
+                            old.to_debug_string(),
+                            new.to_debug_string()
+                        )?,
+                        StateChange::Replaced(old, new) => writeln!(
+                            output,
+                            "~ {} -> {}",
+                            old.to_debug_string(),
+                            new.to_debug_string()
+                        )?,
+                    }
+                }
+            }
+            Command::Help => writeln!(output, "{}", HELP)?,
+            Command::Quit => {}
+        }
+        Ok(())
+    }
+
+    fn write_position(&self, output: &mut impl Write) -> std::io::Result<()> {
+        writeln!(
+            output,
+            "at position {} of {}",
+            self.session.position(),
+            self.session.events().len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavior::{Behavior, BehaviorContext, BehaviorError};
+    use crate::journal::Event;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    fn key(name: &str) -> TypedMapKey {
+        TypedMapKey::String(name.to_string())
+    }
+
+    /// Each event's payload is stored under its event type
+    struct Recording;
+
+    impl Behavior for Recording {
+        fn handle(
+            &self,
+            _ctx: &mut BehaviorContext,
+            state: &TypedValue,
+            _msg: &TypedValue,
+        ) -> Result<TypedValue, BehaviorError> {
+            Ok(state.clone())
+        }
+
+        fn apply_event(&self, state: &TypedValue, event: &Event) -> TypedValue {
+            let TypedValue::Map(fields) = state else {
+                return state.clone();
+            };
+            let mut fields = fields.clone();
+            fields.insert(key(&event.event_type), event.payload.clone());
+            TypedValue::Map(fields)
+        }
+    }
+
+    fn debugger(types: &[&str]) -> Debugger {
+        let events = types
+            .iter()
+            .enumerate()
+            .map(|(seq, t)| Event::new(seq as u64, t.to_string(), TypedValue::Int(seq as i64)))
+            .collect();
+        let initial = TypedValue::Map(BTreeMap::new());
+        Debugger::new(ReplaySession::new(Arc::new(Recording), initial, events))
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!("n".parse(), Ok(Command::Next));
+        assert_eq!("goto 3".parse(), Ok(Command::Goto(3)));
+        assert_eq!("diff 1 4".parse(), Ok(Command::Diff(1, 4)));
+        assert_eq!(
+            "break Deposit".parse(),
+            Ok(Command::Break("Deposit".to_string()))
+        );
+        assert!("goto x".parse::<Command>().is_err());
+        assert!("frobnicate".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn test_step_back_and_breakpoints() {
+        let mut debugger = debugger(&["Open", "Deposit", "Withdraw", "Deposit"]);
+        debugger.forward();
+        debugger.forward();
+        assert!(debugger.back());
+        assert_eq!(debugger.session().position(), 1);
+
+        debugger.break_on("Deposit");
+        assert_eq!(debugger.continue_to_breakpoint(), 2);
+        assert_eq!(debugger.session().peek().unwrap().event_type, "Deposit");
+        assert_eq!(debugger.continue_to_breakpoint(), 1);
+        assert!(debugger.session().peek().is_none());
+    }
+
+    #[test]
+    fn test_diff_between_positions() {
+        let mut debugger = debugger(&["Open", "Deposit", "Deposit"]);
+        assert_eq!(
+            debugger.diff(1, 3),
+            vec![StateChange::Added(key("Deposit"), TypedValue::Int(2))]
+        );
+        assert_eq!(
+            debugger.diff(2, 3),
+            vec![StateChange::Changed(
+                key("Deposit"),
+                TypedValue::Int(1),
+                TypedValue::Int(2)
+            )]
+        );
+        assert_eq!(debugger.session().position(), 0);
+    }
+
+    #[test]
+    fn test_run_reads_commands_until_quit() {
+        let mut debugger = debugger(&["Open", "Deposit"]);
+        let mut output = vec![];
+        debugger
+            .run(&b"next\nbogus\ngoto 2\nquit\nnext\n"[..], &mut output)
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("unknown command: bogus"));
+        assert!(output.contains("at position 2 of 2"));
+        assert_eq!(debugger.session().position(), 2);
+    }
+}
@@ -0,0 +1,265 @@
+//! Replies to `ask`
+//!
+//! `ActorRuntime::ask` queues a message with a reply slot attached and
+//! returns a `Reply`. The behavior answers with `BehaviorContext::reply`;
+//! if it fails, or finishes without replying, or the message is dropped,
+//! the `Reply` resolves to an error instead of hanging.
+//!
+//! A behavior that can't answer within one message turn (it needs another
+//! actor's response first) calls `BehaviorContext::defer_reply` instead.
+//! That returns a `ReplyToken` it can keep in its state or pass along in
+//! messages; whoever holds the token later answers with
+//! `BehaviorContext::fulfill` or `ActorRuntime::fulfill`. A deferred reply
+//! that is never fulfilled leaves the asker to time out.
+//!
+//! Hosts wait for a reply either by blocking (`Reply::wait`) or, with the
+//! `async-bridge` feature, by awaiting it: `Reply` is then a `Future`, so
+//! async servers can wait without tying up a thread.
+
+use crate::actor::ActorId;
+use crate::error::RuntimeError;
+use crate::serialize::TypedValue;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+type Outcome = Result<TypedValue, RuntimeError>;
+
+#[derive(Default)]
+struct SlotState {
+    outcome: Option<Outcome>,
+    /// Set once an outcome has been delivered (even if already taken)
+    completed: bool,
+    #[cfg(feature = "async-bridge")]
+    waker: Option<std::task::Waker>,
+}
+
+#[derive(Default)]
+struct Slot {
+    state: Mutex<SlotState>,
+    ready: Condvar,
+}
+
+impl Slot {
+    /// Deliver an outcome; later ones are ignored
+    fn complete(&self, outcome: Outcome) {
+        let mut state = self.state.lock().expect("reply slot lock poisoned");
+        if state.completed {
+            return;
+        }
+        state.completed = true;
+        state.outcome = Some(outcome);
+        #[cfg(feature = "async-bridge")]
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        self.ready.notify_all();
+    }
+}
+
+/// Sending half, carried by the envelope
+///
+/// Resolves the reply with `RuntimeError::NoReply` when dropped unanswered.
+pub(crate) struct ReplySender {
+    slot: Arc<Slot>,
+    actor: ActorId,
+}
+
+impl std::fmt::Debug for ReplySender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ReplySender({})", self.actor)
+    }
+}
+
+impl ReplySender {
+    pub(crate) fn send(&self, outcome: Outcome) {
+        self.slot.complete(outcome);
+    }
+}
+
+impl Drop for ReplySender {
+    fn drop(&mut self) {
+        self.slot
+            .complete(Err(RuntimeError::NoReply(self.actor.clone())));
+    }
+}
+
+/// Handle to a deferred reply
+///
+/// Travels as a `TypedValue` string (`to_value` / `from_value`), so it can
+/// be kept in actor state or sent in messages.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReplyToken(uuid::Uuid);
+
+/// Prefix of a token's string form
+const TOKEN_PREFIX: &str = "reply:";
+
+impl ReplyToken {
+    pub(crate) fn new() -> Self {
+        ReplyToken(uuid::Uuid::new_v4())
+    }
+
+    pub fn to_value(&self) -> TypedValue {
+        TypedValue::String(format!("{}{}", TOKEN_PREFIX, self.0))
+    }
+
+    /// Inverse of `to_value`
+    pub fn from_value(value: &TypedValue) -> Option<Self> {
+        let TypedValue::String(s) = value else {
+            return None;
+        };
+        s.strip_prefix(TOKEN_PREFIX)?.parse().ok().map(ReplyToken)
+    }
+}
+
+/// Pending answer to an `ask`
+pub struct Reply {
+    slot: Arc<Slot>,
+    actor: ActorId,
+}
+
+impl Reply {
+    /// Create a reply and the sender that resolves it
+    pub(crate) fn channel(actor: ActorId) -> (Arc<ReplySender>, Reply) {
+        let slot = Arc::new(Slot::default());
+        let sender = Arc::new(ReplySender {
+            slot: slot.clone(),
+            actor: actor.clone(),
+        });
+        (sender, Reply { slot, actor })
+    }
+
+    /// A reply that has already failed (the ask never reached the actor)
+    #[cfg(feature = "async-bridge")]
+    pub(crate) fn failed(actor: ActorId, error: RuntimeError) -> Reply {
+        let slot = Arc::new(Slot::default());
+        slot.complete(Err(error));
+        Reply { slot, actor }
+    }
+
+    /// Block until the actor replies, or fail with `AskTimeout`
+    pub fn wait(self, timeout: Duration) -> Result<TypedValue, RuntimeError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.slot.state.lock().expect("reply slot lock poisoned");
+        loop {
+            if let Some(outcome) = state.outcome.take() {
+                return outcome;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RuntimeError::AskTimeout(self.actor.clone()));
+            }
+            state = self
+                .slot
+                .ready
+                .wait_timeout(state, deadline - now)
+                .expect("reply slot lock poisoned")
+                .0;
+        }
+    }
+}
+
+#[cfg(feature = "async-bridge")]
+impl std::future::Future for Reply {
+    type Output = Result<TypedValue, RuntimeError>;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let mut state = self.slot.state.lock().expect("reply slot lock poisoned");
+        match state.outcome.take() {
+            Some(outcome) => std::task::Poll::Ready(outcome),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                std::task::Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dropped_sender_resolves_no_reply() {
+        let id = ActorId::new();
+        let (sender, reply) = Reply::channel(id.clone());
+        drop(sender);
+        assert!(matches!(
+            reply.wait(Duration::from_secs(1)),
+            Err(RuntimeError::NoReply(actor)) if actor == id
+        ));
+    }
+
+    #[test]
+    fn test_first_outcome_wins() {
+        let (sender, reply) = Reply::channel(ActorId::new());
+        sender.send(Ok(TypedValue::Int(1)));
+        sender.send(Ok(TypedValue::Int(2)));
+        drop(sender);
+        assert_eq!(
+            reply.wait(Duration::from_secs(1)).unwrap(),
+            TypedValue::Int(1)
+        );
+    }
+
+    #[test]
+    fn test_wait_times_out() {
+        let (_sender, reply) = Reply::channel(ActorId::new());
+        assert!(matches!(
+            reply.wait(Duration::from_millis(10)),
+            Err(RuntimeError::AskTimeout(_))
+        ));
+    }
+
+    /// Minimal executor: poll on the current thread, parking between wakes
+    #[cfg(feature = "async-bridge")]
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        use std::task::{Context, Poll, Wake, Waker};
+
+        struct ThreadWaker(std::thread::Thread);
+        impl Wake for ThreadWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    #[cfg(feature = "async-bridge")]
+    #[test]
+    fn test_await_reply_from_other_thread() {
+        let (sender, reply) = Reply::channel(ActorId::new());
+        let answer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            sender.send(Ok(TypedValue::Int(7)));
+        });
+        assert_eq!(block_on(reply).unwrap(), TypedValue::Int(7));
+        answer.join().unwrap();
+    }
+
+    #[cfg(feature = "async-bridge")]
+    #[test]
+    fn test_ask_async_without_global_runtime() {
+        use crate::actor::ActorRef;
+
+        if crate::runtime::global_runtime().is_some() {
+            return;
+        }
+        let actor = ActorRef::new(ActorId::new());
+        assert!(matches!(
+            block_on(actor.ask_async(TypedValue::Int(1))),
+            Err(RuntimeError::NoGlobalRuntime)
+        ));
+    }
+}
@@ -0,0 +1,372 @@
+//! Blue/green behavior rollouts
+//!
+//! A `Rollout` moves traffic from a running actor (blue) to one running a
+//! new behavior version (green) a share at a time. Messages sent through
+//! it are split by `RolloutPolicy::green_percent`; both actors keep
+//! handling them as usual. Once green has handled `min_messages`, their
+//! failure rates since the rollout started are compared:
+//!
+//! - green's is within `tolerance` of blue's: promote, all traffic to green
+//! - otherwise: roll back, all traffic to blue
+//!
+//! With `RolloutPolicy::auto` the rollout decides by itself on the next
+//! `send` or `check`; otherwise `verdict` only reports what the policy
+//! recommends and the host calls `promote` or `roll_back`. Either way each
+//! decision is journaled under the rollout's name for audit
+//! (`Journal::rollout_history`). Deciding doesn't stop either actor.
+//!
+//! ```rust,ignore
+//! let green = runtime.spawn("pricing-v2")?;
+//! let rollout = Rollout::start(&runtime, "pricing-v2", blue, green, RolloutPolicy {
+//!     green_percent: 10,
+//!     ..RolloutPolicy::default()
+//! })?;
+//! rollout.send(&runtime, quote_request)?;
+//! ```
+
+use crate::actor::ActorId;
+use crate::error::RuntimeError;
+use crate::health::MessageCounts;
+use crate::runtime::{now_millis, ActorRuntime};
+use crate::serialize::TypedValue;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// How a rollout splits traffic and decides
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RolloutPolicy {
+    /// Share of messages sent to green, in percent (0 to 100)
+    pub green_percent: u32,
+    /// Messages green must handle before a verdict
+    pub min_messages: u64,
+    /// How far green's failure rate may exceed blue's and still be
+    /// promoted (0.01 = one percentage point)
+    pub tolerance: f64,
+    /// Promote or roll back as soon as there is a verdict
+    pub auto: bool,
+}
+
+impl Default for RolloutPolicy {
+    fn default() -> Self {
+        RolloutPolicy {
+            green_percent: 10,
+            min_messages: 100,
+            tolerance: 0.01,
+            auto: true,
+        }
+    }
+}
+
+/// Where a rollout stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RolloutStatus {
+    /// Traffic is split between blue and green
+    Running,
+    /// All traffic goes to green
+    Promoted,
+    /// All traffic goes to blue
+    RolledBack,
+}
+
+/// A journaled promotion or rollback
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RolloutDecision {
+    /// Unix timestamp (milliseconds)
+    pub ts: u64,
+    pub blue: ActorId,
+    pub green: ActorId,
+    /// `Promoted` or `RolledBack`
+    pub status: RolloutStatus,
+    /// Made by the policy rather than by the host
+    pub automatic: bool,
+    /// Counts since the rollout started, when the decision was made
+    pub blue_messages: MessageCounts,
+    pub green_messages: MessageCounts,
+}
+
+/// Traffic split between two actors, decided by failure rates
+pub struct Rollout {
+    name: String,
+    blue: ActorId,
+    green: ActorId,
+    policy: RolloutPolicy,
+    /// Counts when the rollout started, for blue and green
+    baseline: (MessageCounts, MessageCounts),
+    /// Messages sent through the rollout
+    sent: AtomicU64,
+    status: Mutex<RolloutStatus>,
+}
+
+impl Rollout {
+    /// Start splitting traffic between `blue` and `green`
+    ///
+    /// `name` keys the decision history in the journal, so it must be a
+    /// plain file name.
+    pub fn start(
+        runtime: &ActorRuntime,
+        name: &str,
+        blue: ActorId,
+        green: ActorId,
+        policy: RolloutPolicy,
+    ) -> Result<Self, RuntimeError> {
+        crate::journal::check_name("rollout", name)?;
+        let counts = |id: &ActorId| {
+            runtime
+                .message_counts(id)
+                .ok_or_else(|| RuntimeError::ActorNotFound(id.clone()))
+        };
+        let baseline = (counts(&blue)?, counts(&green)?);
+        Ok(Rollout {
+            name: name.to_string(),
+            blue,
+            green,
+            policy,
+            baseline,
+            sent: AtomicU64::new(0),
+            status: Mutex::new(RolloutStatus::Running),
+        })
+    }
+
+    pub fn status(&self) -> RolloutStatus {
+        *self.status.lock().expect("rollout status lock poisoned")
+    }
+
+    /// Actor the next message goes to
+    ///
+    /// While running, green gets every message that takes the running
+    /// green share past a whole percent, spreading its share evenly.
+    pub fn target(&self) -> &ActorId {
+        match self.status() {
+            RolloutStatus::Promoted => &self.green,
+            RolloutStatus::RolledBack => &self.blue,
+            RolloutStatus::Running => {
+                let n = self.sent.fetch_add(1, Ordering::Relaxed);
+                let percent = self.policy.green_percent.min(100) as u64;
+                if (n + 1) * percent / 100 > n * percent / 100 {
+                    &self.green
+                } else {
+                    &self.blue
+                }
+            }
+        }
+    }
+
+    /// Send a message to blue or green, deciding first if the policy is
+    /// automatic and has a verdict
+    pub fn send(&self, runtime: &ActorRuntime, msg: TypedValue) -> Result<(), RuntimeError> {
+        self.check(runtime)?;
+        runtime.send(self.target(), msg)
+    }
+
+    /// Counts for blue and green since the rollout started
+    pub fn counts(&self, runtime: &ActorRuntime) -> (MessageCounts, MessageCounts) {
+        let since = |id: &ActorId, baseline: &MessageCounts| {
+            runtime
+                .message_counts(id)
+                .map(|counts| counts.since(baseline))
+                .unwrap_or_default()
+        };
+        (
+            since(&self.blue, &self.baseline.0),
+            since(&self.green, &self.baseline.1),
+        )
+    }
+
+    /// What the policy recommends now: `Promoted`, `RolledBack`, or
+    /// `None` until green has handled enough messages
+    pub fn verdict(&self, runtime: &ActorRuntime) -> Option<RolloutStatus> {
+        let (blue, green) = self.counts(runtime);
+        if green.handled < self.policy.min_messages {
+            return None;
+        }
+        if green.failure_rate() <= blue.failure_rate() + self.policy.tolerance {
+            Some(RolloutStatus::Promoted)
+        } else {
+            Some(RolloutStatus::RolledBack)
+        }
+    }
+
+    /// Apply the verdict if the policy is automatic and the rollout is
+    /// still running, returning the decision made
+    pub fn check(&self, runtime: &ActorRuntime) -> Result<Option<RolloutDecision>, RuntimeError> {
+        if !self.policy.auto || self.status() != RolloutStatus::Running {
+            return Ok(None);
+        }
+        let Some(status) = self.verdict(runtime) else {
+            return Ok(None);
+        };
+        let mut current = self.status.lock().expect("rollout status lock poisoned");
+        // A concurrent sender may have decided since the status was read
+        if *current != RolloutStatus::Running {
+            return Ok(None);
+        }
+        self.decide(runtime, &mut current, status, true).map(Some)
+    }
+
+    /// Send all traffic to green
+    pub fn promote(&self, runtime: &ActorRuntime) -> Result<RolloutDecision, RuntimeError> {
+        let mut current = self.status.lock().expect("rollout status lock poisoned");
+        self.decide(runtime, &mut current, RolloutStatus::Promoted, false)
+    }
+
+    /// Send all traffic back to blue
+    pub fn roll_back(&self, runtime: &ActorRuntime) -> Result<RolloutDecision, RuntimeError> {
+        let mut current = self.status.lock().expect("rollout status lock poisoned");
+        self.decide(runtime, &mut current, RolloutStatus::RolledBack, false)
+    }
+
+    /// Journal a decision and switch to it, with the status lock held
+    fn decide(
+        &self,
+        runtime: &ActorRuntime,
+        current: &mut RolloutStatus,
+        status: RolloutStatus,
+        automatic: bool,
+    ) -> Result<RolloutDecision, RuntimeError> {
+        let (blue_messages, green_messages) = self.counts(runtime);
+        let decision = RolloutDecision {
+            ts: now_millis(),
+            blue: self.blue.clone(),
+            green: self.green.clone(),
+            status,
+            automatic,
+            blue_messages,
+            green_messages,
+        };
+        runtime.journal().record_rollout(&self.name, &decision)?;
+        *current = status;
+        Ok(decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavior::{BehaviorContext, BehaviorError};
+    use crate::runtime::RuntimeConfig;
+    use tempfile::TempDir;
+
+    /// Runtime with a working `stable` behavior and a `broken` one that
+    /// rejects negative numbers
+    fn runtime(temp_dir: &TempDir) -> ActorRuntime {
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            ..RuntimeConfig::default()
+        });
+        runtime.register_behavior(
+            "stable",
+            |_ctx: &mut BehaviorContext, _state: &TypedValue, msg: &TypedValue| {
+                Ok::<_, BehaviorError>(msg.clone())
+            },
+        );
+        runtime.register_behavior(
+            "broken",
+            |_ctx: &mut BehaviorContext, _state: &TypedValue, msg: &TypedValue| match msg {
+                TypedValue::Int(n) if *n < 0 => Err(BehaviorError::new("negative")),
+                _ => Ok(msg.clone()),
+            },
+        );
+        runtime
+    }
+
+    #[test]
+    fn test_rollout_promotes_healthy_green() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = runtime(&temp_dir);
+        let blue = runtime.spawn("stable").unwrap();
+        let green = runtime.spawn("broken").unwrap();
+        let policy = RolloutPolicy {
+            green_percent: 25,
+            min_messages: 3,
+            ..RolloutPolicy::default()
+        };
+        let rollout = Rollout::start(&runtime, "v2", blue.clone(), green.clone(), policy).unwrap();
+
+        // Every fourth message goes to green
+        let targets: Vec<bool> = (0..8).map(|_| rollout.target() == &green).collect();
+        assert_eq!(targets.iter().filter(|g| **g).count(), 2);
+        assert!(targets[3] && targets[7]);
+
+        for _ in 0..13 {
+            rollout.send(&runtime, TypedValue::Int(1)).unwrap();
+            runtime.run_until_idle().unwrap();
+        }
+        assert_eq!(rollout.status(), RolloutStatus::Promoted);
+        assert_eq!(rollout.target(), &green);
+
+        let history = runtime.journal().rollout_history("v2").unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(history[0].automatic);
+        assert_eq!(history[0].green_messages.handled, 3);
+
+        runtime.unregister_actor(&blue);
+        runtime.unregister_actor(&green);
+    }
+
+    #[test]
+    fn test_concurrent_checks_decide_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = runtime(&temp_dir);
+        let blue = runtime.spawn("stable").unwrap();
+        let green = runtime.spawn("stable").unwrap();
+        let policy = RolloutPolicy {
+            green_percent: 100,
+            min_messages: 1,
+            ..RolloutPolicy::default()
+        };
+        let rollout = Rollout::start(&runtime, "v5", blue.clone(), green.clone(), policy).unwrap();
+        rollout.send(&runtime, TypedValue::Int(1)).unwrap();
+        runtime.run_until_idle().unwrap();
+
+        let decided = std::thread::scope(|scope| {
+            let checks: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| rollout.check(&runtime).unwrap()))
+                .collect();
+            checks
+                .into_iter()
+                .filter_map(|check| check.join().unwrap())
+                .count()
+        });
+        assert_eq!(decided, 1);
+        assert_eq!(rollout.status(), RolloutStatus::Promoted);
+        assert_eq!(runtime.journal().rollout_history("v5").unwrap().len(), 1);
+
+        runtime.unregister_actor(&blue);
+        runtime.unregister_actor(&green);
+    }
+
+    #[test]
+    fn test_rollout_verdict_rolls_back_failing_green() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = runtime(&temp_dir);
+        let blue = runtime.spawn("stable").unwrap();
+        let green = runtime.spawn("broken").unwrap();
+        let policy = RolloutPolicy {
+            green_percent: 50,
+            min_messages: 2,
+            auto: false,
+            ..RolloutPolicy::default()
+        };
+        let rollout = Rollout::start(&runtime, "v3", blue.clone(), green.clone(), policy).unwrap();
+
+        for _ in 0..4 {
+            rollout.send(&runtime, TypedValue::Int(-1)).unwrap();
+        }
+        runtime.run_until_idle().unwrap();
+        let (blue_counts, green_counts) = rollout.counts(&runtime);
+        assert_eq!((blue_counts.failed, green_counts.failed), (0, 2));
+        assert_eq!(rollout.verdict(&runtime), Some(RolloutStatus::RolledBack));
+        // Not automatic: nothing changes until the host decides
+        assert_eq!(rollout.status(), RolloutStatus::Running);
+
+        let decision = rollout.roll_back(&runtime).unwrap();
+        assert!(!decision.automatic);
+        assert_eq!(rollout.target(), &blue);
+        assert_eq!(runtime.journal().rollout_history("v3").unwrap(), [decision]);
+        assert!(Rollout::start(&runtime, "../v4", blue.clone(), green.clone(), policy).is_err());
+
+        runtime.unregister_actor(&blue);
+        runtime.unregister_actor(&green);
+    }
+}
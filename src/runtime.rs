@@ -37,12 +37,51 @@
 //! 5. Behavior quotation executed: (State, Msg) → State'
 //! 6. State updated, loop continues
 
-use crate::actor::ActorId;
+use crate::actor::{Actor, ActorHandle, ActorId, IdScheme};
+use crate::audit::{ConfigChange, ConfigTarget, CONFIG_CHANGED};
+use crate::auth::{AuthPolicy, GrantPolicy, Operation, Principal};
+use crate::behavior::{handle_message_checked, handle_stop, Behavior, BehaviorError};
+use crate::checkpoint::{restored_payload, Checkpoint, CheckpointEntry, RESTORED_EVENT};
+use crate::crash::{catch_panic, failure_payload, CrashDump, Panic, PanicPolicy, FAILURE_EVENT};
+use crate::delivery::{DeliveryGroup, DeliveryStrategy};
+use crate::delta;
+use crate::error::RuntimeError;
+use crate::fairness::{GroupCounters, GroupUsage, DEFAULT_GROUP, DEFAULT_WEIGHT};
+use crate::health::{ActorDump, ActorHealth, HealthReport, JournalHealth, MessageCounts};
+use crate::interceptor::{Intercepted, Interceptor};
+use crate::journal::correction::apply_corrections;
+use crate::journal::limits::DecodeLimits;
+use crate::journal::quota::{self, Quota};
+use crate::journal::writer::{Durability, JournalWriter};
 use crate::journal::{Event, Journal, Snapshot};
-use crate::serialize::TypedValue;
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::RwLock;
+use crate::lifecycle::{down_message, Handoff, StopReason, STOPPED_EVENT};
+use crate::metrics::{Metrics, MetricsSnapshot};
+use crate::observer::{DropReason, RuntimeObserver};
+use crate::preload::{Preload, PreloadCache};
+use crate::ratelimit::{RateLimit, TokenBucket};
+use crate::refdata::ReferenceData;
+use crate::replay::ReplaySession;
+use crate::reply::{Reply, ReplySender, ReplyToken};
+use crate::sandbox::Sandbox;
+use crate::serialize::{TypedMapKey, TypedValue};
+use crate::session::{SessionRecorder, TraceRecord};
+use crate::shedding::{LoadShedding, Pressure};
+use crate::state_size::{state_size, StateLimit, StateLimitAction};
+use crate::strand;
+use crate::supervision::Supervision;
+use crate::system::{SubscriptionFilter, SystemEvent};
+use crate::throughput::{ThroughputLog, ThroughputRecord, METRICS_RECORDED};
+use crate::tier::StorageTier;
+use crate::timeout::{timed_out_payload, TIMED_OUT_EVENT};
+use crate::validate::{EventRejected, EventValidator};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+/// Channel ID used by mailboxes that have no Seq channel
+const LOCAL_CHANNEL: i64 = -1;
 
 /// Actor mailbox - wraps a channel ID for type safety
 #[derive(Debug, Clone, Copy)]
@@ -56,77 +95,449 @@ impl Mailbox {
         Mailbox { channel_id }
     }
 
+    /// Mailbox for an actor dispatched by `ActorRuntime` from Rust
+    ///
+    /// Messages are queued in the runtime rather than a Seq channel.
+    pub fn local() -> Self {
+        Mailbox {
+            channel_id: LOCAL_CHANNEL,
+        }
+    }
+
     pub fn channel_id(&self) -> i64 {
         self.channel_id
     }
+
+    /// Whether this mailbox is queued in the runtime (no Seq channel)
+    pub fn is_local(&self) -> bool {
+        self.channel_id == LOCAL_CHANNEL
+    }
+}
+
+/// Message payload held by an envelope
+#[derive(Debug, Clone)]
+pub enum Payload {
+    /// Message held in the inbox
+    Inline(TypedValue),
+    /// Message written under the journal path because it exceeded
+    /// `RuntimeConfig::max_message_size`; rehydrated on receive
+    Offloaded(Arc<OffloadedMessage>),
+    /// Message queued with `ActorRuntime::send_shared`, possibly in several
+    /// inboxes at once; never offloaded
+    Shared(Arc<TypedValue>),
+}
+
+/// The file of an offloaded message, removed once no envelope holds it
+///
+/// Copies of an envelope (a shadow's, say) share the file, so it goes
+/// when the last of them is handled or dropped.
+#[derive(Debug)]
+pub struct OffloadedMessage(PathBuf);
+
+impl OffloadedMessage {
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for OffloadedMessage {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Where a queued message came from, which decides whether it is traced
+/// (see `session`) and must pass admission
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Origin {
+    /// Sent by a behavior or queued by the runtime itself
+    Runtime,
+    /// Sent by the host: traced while a session is recorded
+    Host,
+    /// A traced host send being replayed, which was admitted once already
+    Replayed,
+}
+
+/// A message waiting in an actor's inbox
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    /// Sending actor, if the message came from another actor
+    pub sender: Option<ActorId>,
+    /// Message payload
+    pub payload: Payload,
+    /// Serialized size of the message in bytes, measured on enqueue only
+    /// if something reads it (`RuntimeConfig::max_message_size`, a load
+    /// shedding byte limit, or an observer); 0 otherwise
+    pub size: usize,
+    /// Unix timestamp (milliseconds) when the message was enqueued
+    pub enqueued_at: u64,
+    /// Unix timestamp (milliseconds) after which the message is dropped
+    /// instead of handled (see `expiry`)
+    pub expires_at: Option<u64>,
+    /// Where to send the answer, for messages sent with `ask`
+    pub(crate) reply_to: Option<Arc<ReplySender>>,
+}
+
+impl Envelope {
+    pub fn new(sender: Option<ActorId>, msg: TypedValue) -> Self {
+        Envelope {
+            sender,
+            payload: Payload::Inline(msg),
+            size: 0,
+            enqueued_at: now_millis(),
+            expires_at: None,
+            reply_to: None,
+        }
+    }
+
+    /// Envelope for a message shared with other inboxes instead of copied
+    pub fn shared(sender: Option<ActorId>, msg: Arc<TypedValue>) -> Self {
+        Envelope {
+            sender,
+            payload: Payload::Shared(msg),
+            size: 0,
+            enqueued_at: now_millis(),
+            expires_at: None,
+            reply_to: None,
+        }
+    }
+
+    /// Set `size`, unless it is known already
+    fn measure(&mut self) {
+        let msg = match &self.payload {
+            _ if self.size > 0 => return,
+            Payload::Inline(msg) => msg,
+            Payload::Shared(msg) => msg.as_ref(),
+            // Measured before it was offloaded
+            Payload::Offloaded(_) => return,
+        };
+        self.size = bincode::serialized_size(msg)
+            .map(|n| n as usize)
+            .unwrap_or(0);
+    }
+
+    /// Whether the message is past its TTL, or older than `max_age`, at
+    /// `now` (Unix milliseconds)
+    fn is_expired(&self, now: u64, max_age: Option<Duration>) -> bool {
+        self.expires_at.is_some_and(|at| now > at)
+            || max_age
+                .is_some_and(|age| now.saturating_sub(self.enqueued_at) > age.as_millis() as u64)
+    }
+
+    /// Copy for a shadow (see `ActorRuntime::spawn_shadow`), without the
+    /// reply channel; an offloaded payload's file is shared
+    fn mirror(&self) -> Envelope {
+        Envelope {
+            reply_to: None,
+            ..self.clone()
+        }
+    }
+}
+
+/// Runtime-side actor cell: state plus pending messages
+struct ActorCell {
+    /// Actor state; `None` while a message is being handled
+    actor: Option<Actor>,
+    /// Messages waiting to be handled
+    inbox: VecDeque<Envelope>,
+    /// Stopped, drained, `on_stop` run, and snapshot flushed
+    terminated: bool,
+    /// Unix timestamp (milliseconds) the last message finished
+    last_processed: Option<u64>,
+    /// When the in-flight message handling started
+    handling_since: Option<Instant>,
+    /// The watchdog already reported the in-flight handling
+    blocked_reported: bool,
+    /// Limits messages accepted by `enqueue`
+    rate_limit: Option<TokenBucket>,
+    /// Self-sent messages handled in a row
+    self_send_chain: u32,
+    /// Why the actor was stopped (set once, by the first stop)
+    stop_reason: Option<StopReason>,
+    /// Actors to send `Down` to when this one terminates
+    monitors: Vec<ActorId>,
+    /// Pool the actor is pinned to (None = the shared run loop)
+    pool: Option<String>,
+    /// Scheduling group within its run loop
+    group: String,
+    /// Thread handling the in-flight message
+    handling_thread: Option<String>,
+    /// Times the actor was restarted after a panic
+    restarts: u32,
+    /// Restart backoff: the actor isn't handled again before this
+    paused_until: Option<Instant>,
+    /// Overrides `RuntimeConfig::panic_policy` for this actor
+    panic_policy: Option<PanicPolicy>,
+    /// Messages older than this are dropped instead of handled
+    max_message_age: Option<Duration>,
+    /// Deadline for handling one message
+    message_timeout: Option<Duration>,
+    /// Builtins the actor may not call
+    sandbox: Option<Sandbox>,
+    /// Cap on the state size, checked after every message
+    state_limit: Option<StateLimit>,
+    /// State size in bytes when last measured
+    state_size: Option<usize>,
+    /// Messages handled so far
+    messages: MessageCounts,
+    /// Where queued messages go when the actor stops
+    handoff: Option<Handoff>,
+    /// When the actor was spawned (or recovered) in this runtime
+    spawned_at: Instant,
+}
+
+/// Name identifying an actor's coroutine in diagnostics: `behavior:id`
+pub fn coroutine_name(id: &ActorId, behavior: &str) -> String {
+    format!("{}:{}", behavior, id)
+}
+
+/// What an actor's coroutine is doing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoroutineStatus {
+    /// Waiting for messages
+    Idle,
+    /// Has queued messages but isn't handling one
+    Ready,
+    /// Handling a message
+    Running {
+        /// Thread it is running on
+        thread: String,
+        /// How long the handling has been running
+        elapsed: Duration,
+    },
+    /// Stopped, with queued messages left to drain
+    Stopping,
+    Terminated,
+}
+
+/// One entry of `ActorRuntime::dump_coroutines`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoroutineInfo {
+    /// See `coroutine_name`
+    pub name: String,
+    pub id: ActorId,
+    pub behavior: String,
+    pub status: CoroutineStatus,
+    /// Messages waiting in the inbox
+    pub queued: usize,
+    pub pool: Option<String>,
+}
+
+/// Actors of one scheduling group, as seen by a run loop
+struct SchedulingGroup {
+    weight: u32,
+    ids: Vec<ActorId>,
+    /// Index of the actor the next slice starts at
+    next: usize,
+}
+
+/// What one `run_slice` did
+#[derive(Default)]
+struct Slice {
+    handled: usize,
+    /// Some message was handled or failed
+    progressed: bool,
+}
+
+/// A message handling the watchdog found running too long
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockedActor {
+    pub id: ActorId,
+    pub behavior: String,
+    /// How long the handling has been running
+    pub elapsed: Duration,
+}
+
+/// Name of the current thread, or its ID if unnamed
+fn thread_label() -> String {
+    let thread = std::thread::current();
+    match thread.name() {
+        Some(name) => name.to_string(),
+        None => format!("{:?}", thread.id()),
+    }
+}
+
+/// Current Unix time in milliseconds
+pub(crate) fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
 }
 
 /// Actor entry in the registry
 #[derive(Debug)]
 struct ActorEntry {
+    /// Interned handle used on the Seq stack
+    handle: ActorHandle,
     /// Mailbox for sending messages to this actor
     mailbox: Mailbox,
     /// Behavior name (quotation to execute)
     /// Used when dispatching messages to run the actor's behavior
-    #[allow(dead_code)]
     behavior: String,
     /// Whether actor is running
     running: bool,
 }
 
+/// Number of registry shards (must be a power of two)
+///
+/// Actors are spread across shards by ActorId so that sends to different
+/// actors rarely contend on the same lock.
+const REGISTRY_SHARDS: usize = 64;
+
 /// Global actor registry
 ///
 /// Maps ActorId → ActorEntry (mailbox, behavior, status)
-/// Thread-safe for access from multiple coroutines.
+/// Thread-safe for access from multiple coroutines. The map is split into
+/// `REGISTRY_SHARDS` independently locked shards keyed by ActorId.
+///
+/// Registration also interns a numeric `ActorHandle` for the actor; the
+/// reverse handle → ActorId map is sharded the same way.
 pub(crate) struct ActorRegistry {
-    actors: RwLock<HashMap<ActorId, ActorEntry>>,
+    shards: Vec<RwLock<HashMap<ActorId, ActorEntry>>>,
+    handles: Vec<RwLock<HashMap<ActorHandle, ActorId>>>,
+    next_handle: AtomicU64,
 }
 
 impl ActorRegistry {
     fn new() -> Self {
         ActorRegistry {
-            actors: RwLock::new(HashMap::new()),
+            shards: (0..REGISTRY_SHARDS)
+                .map(|_| RwLock::new(HashMap::new()))
+                .collect(),
+            handles: (0..REGISTRY_SHARDS)
+                .map(|_| RwLock::new(HashMap::new()))
+                .collect(),
+            next_handle: AtomicU64::new(1),
         }
     }
 
-    /// Register a new actor
-    pub(crate) fn register(&self, id: ActorId, mailbox: Mailbox, behavior: String) {
-        let mut actors = self.actors.write().expect("registry write lock poisoned");
+    /// Select the shard owning an actor
+    fn shard(&self, id: &ActorId) -> &RwLock<HashMap<ActorId, ActorEntry>> {
+        let (hi, lo) = id.0.as_u64_pair();
+        &self.shards[((hi ^ lo) as usize) & (REGISTRY_SHARDS - 1)]
+    }
+
+    /// Select the handle shard owning a handle
+    fn handle_shard(&self, handle: ActorHandle) -> &RwLock<HashMap<ActorHandle, ActorId>> {
+        &self.handles[(handle.0 as usize) & (REGISTRY_SHARDS - 1)]
+    }
+
+    /// Register a new actor, returning its interned handle
+    ///
+    /// Re-registering an existing actor keeps its handle.
+    pub(crate) fn register(&self, id: ActorId, mailbox: Mailbox, behavior: String) -> ActorHandle {
+        let mut actors = self
+            .shard(&id)
+            .write()
+            .expect("registry write lock poisoned");
+        let handle = match actors.get(&id) {
+            Some(existing) => existing.handle,
+            None => {
+                let handle = ActorHandle(self.next_handle.fetch_add(1, Ordering::Relaxed));
+                self.handle_shard(handle)
+                    .write()
+                    .expect("registry handle lock poisoned")
+                    .insert(handle, id.clone());
+                handle
+            }
+        };
         actors.insert(
             id,
             ActorEntry {
+                handle,
                 mailbox,
                 behavior,
                 running: true,
             },
         );
+        handle
+    }
+
+    /// Get the interned handle for a registered actor
+    pub(crate) fn handle_of(&self, id: &ActorId) -> Option<ActorHandle> {
+        let actors = self.shard(id).read().expect("registry read lock poisoned");
+        actors.get(id).map(|e| e.handle)
+    }
+
+    /// Resolve a handle back to its ActorId
+    pub(crate) fn resolve(&self, handle: ActorHandle) -> Option<ActorId> {
+        let handles = self
+            .handle_shard(handle)
+            .read()
+            .expect("registry handle lock poisoned");
+        handles.get(&handle).cloned()
     }
 
     /// Get mailbox for an actor
-    fn get_mailbox(&self, id: &ActorId) -> Option<Mailbox> {
-        let actors = self.actors.read().expect("registry read lock poisoned");
+    pub(crate) fn get_mailbox(&self, id: &ActorId) -> Option<Mailbox> {
+        let actors = self.shard(id).read().expect("registry read lock poisoned");
         actors.get(id).map(|e| e.mailbox)
     }
 
     /// Mark actor as stopped
-    fn mark_stopped(&self, id: &ActorId) {
-        let mut actors = self.actors.write().expect("registry write lock poisoned");
+    pub(crate) fn mark_stopped(&self, id: &ActorId) {
+        let mut actors = self
+            .shard(id)
+            .write()
+            .expect("registry write lock poisoned");
         if let Some(entry) = actors.get_mut(id) {
             entry.running = false;
         }
     }
 
     /// Remove actor from registry
-    fn unregister(&self, id: &ActorId) {
-        let mut actors = self.actors.write().expect("registry write lock poisoned");
-        actors.remove(id);
+    pub(crate) fn unregister(&self, id: &ActorId) {
+        let mut actors = self
+            .shard(id)
+            .write()
+            .expect("registry write lock poisoned");
+        if let Some(entry) = actors.remove(id) {
+            self.handle_shard(entry.handle)
+                .write()
+                .expect("registry handle lock poisoned")
+                .remove(&entry.handle);
+        }
+    }
+
+    /// Get an actor's behavior name
+    pub(crate) fn behavior_of(&self, id: &ActorId) -> Option<String> {
+        let actors = self.shard(id).read().expect("registry read lock poisoned");
+        actors.get(id).map(|e| e.behavior.clone())
     }
 
     /// Check if actor exists and is running
-    fn is_running(&self, id: &ActorId) -> bool {
-        let actors = self.actors.read().expect("registry read lock poisoned");
+    pub(crate) fn is_running(&self, id: &ActorId) -> bool {
+        let actors = self.shard(id).read().expect("registry read lock poisoned");
         actors.get(id).is_some_and(|e| e.running)
     }
+
+    /// Running actors and their handles, shard by shard (each shard is
+    /// consistent, the whole list isn't a point-in-time snapshot)
+    pub(crate) fn running(&self) -> Vec<(ActorId, ActorHandle)> {
+        self.shards
+            .iter()
+            .flat_map(|shard| {
+                let actors = shard.read().expect("registry read lock poisoned");
+                actors
+                    .iter()
+                    .filter(|(_, e)| e.running)
+                    .map(|(id, e)| (id.clone(), e.handle))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Number of running actors
+    pub(crate) fn running_count(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| {
+                let actors = shard.read().expect("registry read lock poisoned");
+                actors.values().filter(|e| e.running).count()
+            })
+            .sum()
+    }
 }
 
 // Global registry instance (pub(crate) for FFI access)
@@ -143,6 +554,70 @@ pub struct RuntimeConfig {
     pub journaling_enabled: bool,
     /// Snapshot interval (events between snapshots)
     pub snapshot_interval: u64,
+    /// Largest message (serialized bytes) kept in an inbox; larger
+    /// messages are offloaded to disk until received (None = no limit)
+    pub max_message_size: Option<usize>,
+    /// How long an actor may sit on queued messages without handling any
+    /// before `health()` reports it stuck
+    pub stuck_actor_threshold: Duration,
+    /// How durable an event must be before `persist_event` returns
+    pub durability: Durability,
+    /// Consecutive self-sent messages an actor may handle before it is
+    /// reported as looping (None = never report)
+    pub self_send_loop_threshold: Option<u32>,
+    /// Disk limits for `journal_path` (None = unlimited)
+    pub quota: Option<Quota>,
+    /// Bounds on what reading the journals may decode
+    pub decode_limits: DecodeLimits,
+    /// Yield to the scheduler after this many builtin calls by actors on
+    /// one thread (see `ffi`, "Yielding"; None = only on `actor-yield`)
+    pub auto_yield_every: Option<u32>,
+    /// Where shadow actors journal their events (None =
+    /// `{journal_path}/shadows`; see `ActorRuntime::spawn_shadow`)
+    pub shadow_journal_path: Option<PathBuf>,
+    /// Most recent events included in a crash dump when a behavior panics
+    pub crash_dump_events: usize,
+    /// What happens to an actor whose behavior panics (see
+    /// `ActorRuntime::set_panic_policy` to override per actor)
+    pub panic_policy: PanicPolicy,
+    /// Supervision settings by behavior name, applied on spawn
+    pub supervision: HashMap<String, Supervision>,
+    /// Reject messages to low-priority actors under pressure (None = never)
+    pub load_shedding: Option<LoadShedding>,
+    /// Builtin deny-lists by behavior name, applied on spawn (see
+    /// `sandbox`)
+    pub sandboxes: HashMap<String, Sandbox>,
+    /// Drop queued messages older than this instead of handling them (see
+    /// `expiry`; None = messages only expire by their own TTL)
+    pub max_message_age: Option<Duration>,
+    /// Fail handlings that run longer than this (see `timeout`; None = no
+    /// limit)
+    pub message_timeout: Option<Duration>,
+    /// Scheduling weight of each actor group in the run loops (see
+    /// `fairness`; unlisted groups weigh `DEFAULT_WEIGHT`)
+    pub group_weights: HashMap<String, u32>,
+    /// Cap on each actor's state size (see `state_size`; None = states
+    /// aren't measured)
+    pub state_limit: Option<StateLimit>,
+    /// How IDs of spawned actors are generated
+    pub id_scheme: IdScheme,
+    /// Roots of the named storage tiers (see `tier`)
+    pub storage_tiers: HashMap<String, PathBuf>,
+    /// Storage tiers by behavior name, applied on spawn
+    pub behavior_tiers: HashMap<String, StorageTier>,
+    /// Behaviors whose actors journal state deltas instead of the events
+    /// they emit (see `delta`)
+    pub delta_behaviors: HashSet<String>,
+    /// Keep every journal in memory, never touching the filesystem (see
+    /// `journal::memory`); `journal_path` and tier roots are ignored
+    pub in_memory: bool,
+    /// Actors whose journals are read ahead in the background at startup
+    /// (see `preload`)
+    pub preload: Option<Preload>,
+    /// Write Map snapshots in the keyed layout, so
+    /// `Journal::load_snapshot_lazy` can decode them entry by entry
+    /// (recovery still decodes the whole state; see `journal::lazy`)
+    pub lazy_snapshots: bool,
 }
 
 impl Default for RuntimeConfig {
@@ -151,23 +626,236 @@ impl Default for RuntimeConfig {
             journal_path: PathBuf::from("./actors"),
             journaling_enabled: true,
             snapshot_interval: 100,
+            max_message_size: None,
+            stuck_actor_threshold: Duration::from_secs(30),
+            durability: Durability::Written,
+            self_send_loop_threshold: Some(10_000),
+            quota: None,
+            decode_limits: DecodeLimits::default(),
+            auto_yield_every: None,
+            shadow_journal_path: None,
+            crash_dump_events: 20,
+            panic_policy: PanicPolicy::Resume,
+            supervision: HashMap::new(),
+            load_shedding: None,
+            group_weights: HashMap::new(),
+            sandboxes: HashMap::new(),
+            max_message_age: None,
+            message_timeout: None,
+            state_limit: None,
+            id_scheme: IdScheme::Random,
+            storage_tiers: HashMap::new(),
+            behavior_tiers: HashMap::new(),
+            delta_behaviors: HashSet::new(),
+            in_memory: false,
+            preload: None,
+            lazy_snapshots: false,
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// Configuration for an ephemeral runtime that leaves nothing on disk
+    ///
+    /// Events and snapshots are journaled in memory, so recovery, replay
+    /// and restarts work within the process. Crash dumps are skipped, and
+    /// sends over `max_message_size` fail, since offloading needs a file.
+    pub fn in_memory() -> Self {
+        RuntimeConfig {
+            in_memory: true,
+            ..RuntimeConfig::default()
         }
     }
 }
 
+/// A named storage tier's journal and its writer thread
+struct TierJournal {
+    journal: Arc<Journal>,
+    writer: JournalWriter,
+}
+
 /// Actor runtime state
 ///
 /// Manages the lifecycle of all actors in the system.
+///
+/// Actors spawned from Rust with `spawn` are dispatched by the runtime
+/// itself: messages queue in a local inbox and `process_next` /
+/// `run_until_idle` run them through the actor's registered `Behavior`.
 pub struct ActorRuntime {
     config: RuntimeConfig,
-    journal: Journal,
+    journal: Arc<Journal>,
+    /// Background thread appending events to `journal`
+    writer: JournalWriter,
+    /// Behaviors available to `spawn`, by name
+    behaviors: RwLock<HashMap<String, Arc<dyn Behavior>>>,
+    /// Interceptor chains, by behavior name
+    interceptors: RwLock<HashMap<String, Vec<Arc<dyn Interceptor>>>>,
+    /// Checks on emitted events, in registration order
+    validators: RwLock<Vec<Arc<dyn EventValidator>>>,
+    /// Pools new actors are pinned to, by behavior name
+    behavior_pools: RwLock<HashMap<String, String>>,
+    /// Scheduling groups new actors join, by behavior name
+    behavior_groups: RwLock<HashMap<String, String>>,
+    /// Processing used per scheduling group
+    group_counters: Mutex<HashMap<String, GroupCounters>>,
+    /// Actors dispatched by this runtime
+    cells: RwLock<HashMap<ActorId, Arc<Mutex<ActorCell>>>>,
+    /// Active session recorder, if recording
+    recorder: Mutex<Option<SessionRecorder>>,
+    /// Paired with `stop_signal` to wake `wait_for_stop` callers
+    stop_lock: Mutex<()>,
+    /// Notified whenever an actor terminates
+    stop_signal: Condvar,
+    /// Paired with `idle_signal` to wake `with_idle_cell` callers
+    idle_lock: Mutex<()>,
+    /// Notified when an actor goes idle while someone waits for it
+    idle_signal: Condvar,
+    /// Callers waiting in `with_idle_cell`
+    idle_waiters: AtomicUsize,
+    /// Aggregate counters
+    metrics: Metrics,
+    /// Host callback for runtime events
+    observer: RwLock<Option<Arc<dyn RuntimeObserver>>>,
+    /// Decides what operator-facing callers may do (see `auth`)
+    auth_policy: RwLock<Arc<dyn AuthPolicy>>,
+    /// `ask`s whose replies were deferred, by token
+    deferred_replies: Mutex<HashMap<ReplyToken, Arc<ReplySender>>>,
+    /// When the runtime was created
+    started: Instant,
+    /// Actors spawned with `spawn_singleton`, by name (also guards
+    /// against two spawns of the same singleton racing)
+    singletons: Mutex<HashMap<String, ActorId>>,
+    /// The last `check_pressure` found the runtime over its thresholds
+    shedding: AtomicBool,
+    /// Journal for shadow actors, kept apart from `journal`
+    shadow_journal: Journal,
+    /// Shadow actors, mapped to the actor they shadow
+    shadows: RwLock<HashMap<ActorId, ActorId>>,
+    /// Next seq in the configuration audit journal (loaded on first use)
+    config_seq: Mutex<Option<u64>>,
+    /// Throughput recorded so far (see `throughput`)
+    throughput: Mutex<ThroughputLog>,
+    /// Set while a checkpoint is taken: no message handling starts
+    quiesced: AtomicBool,
+    /// Held by the one checkpoint being taken
+    checkpointing: Mutex<()>,
+    /// Actors subscribed to `ActorId::SYSTEM`, with their filters (see
+    /// `system`)
+    system_subscribers: RwLock<Vec<(ActorId, SubscriptionFilter)>>,
+    /// Delivery groups by name (see `delivery`)
+    delivery_groups: Mutex<HashMap<String, DeliveryGroup>>,
+    /// Journals of the named storage tiers (see `tier`)
+    tier_journals: HashMap<String, TierJournal>,
+    /// Actors stored outside the default tier
+    actor_tiers: RwLock<HashMap<ActorId, StorageTier>>,
+    /// Journals read ahead of spawn (see `preload`)
+    preloaded: Arc<PreloadCache>,
+    /// States published as reference data (see `refdata`)
+    reference_data: RwLock<ReferenceData>,
+}
+
+// Runtime used by FFI builtins that need more than the registry
+static GLOBAL_RUNTIME: OnceLock<ActorRuntime> = OnceLock::new();
+
+/// Install the process-wide runtime used by FFI builtins
+///
+/// Returns the runtime back if one is already installed.
+pub fn install_global(runtime: ActorRuntime) -> Result<&'static ActorRuntime, Box<ActorRuntime>> {
+    GLOBAL_RUNTIME.set(runtime).map_err(Box::new)?;
+    Ok(GLOBAL_RUNTIME.get().expect("just installed"))
+}
+
+/// Get the process-wide runtime, if installed
+pub fn global_runtime() -> Option<&'static ActorRuntime> {
+    GLOBAL_RUNTIME.get()
 }
 
 impl ActorRuntime {
     /// Create a new actor runtime
     pub fn new(config: RuntimeConfig) -> Self {
-        let journal = Journal::new(&config.journal_path);
-        ActorRuntime { config, journal }
+        let open = |path: &Path| {
+            let journal = if config.in_memory {
+                Journal::in_memory()
+            } else {
+                Journal::new(path)
+            };
+            journal
+                .with_decode_limits(config.decode_limits)
+                .with_lazy_snapshots(config.lazy_snapshots)
+        };
+        let journal = open(&config.journal_path);
+        let shadow_journal = open(&match &config.shadow_journal_path {
+            Some(path) => path.clone(),
+            None => config.journal_path.join("shadows"),
+        });
+        let with_quota = |journal: Journal| {
+            Arc::new(match &config.quota {
+                Some(quota) => journal.with_quota(quota.clone()),
+                None => journal,
+            })
+        };
+        let journal = with_quota(journal);
+        let tier_journals = config
+            .storage_tiers
+            .iter()
+            .map(|(name, root)| {
+                let journal = with_quota(open(root));
+                let writer = JournalWriter::spawn(journal.clone());
+                (name.clone(), TierJournal { journal, writer })
+            })
+            .collect();
+        let preloaded = match &config.preload {
+            Some(preload) => PreloadCache::spawn(journal.clone(), preload.clone()),
+            None => PreloadCache::empty(),
+        };
+        ActorRuntime {
+            config,
+            writer: JournalWriter::spawn(journal.clone()),
+            journal,
+            behaviors: RwLock::new(HashMap::new()),
+            interceptors: RwLock::new(HashMap::new()),
+            validators: RwLock::new(Vec::new()),
+            behavior_pools: RwLock::new(HashMap::new()),
+            behavior_groups: RwLock::new(HashMap::new()),
+            group_counters: Mutex::new(HashMap::new()),
+            cells: RwLock::new(HashMap::new()),
+            recorder: Mutex::new(None),
+            stop_lock: Mutex::new(()),
+            stop_signal: Condvar::new(),
+            idle_lock: Mutex::new(()),
+            idle_signal: Condvar::new(),
+            idle_waiters: AtomicUsize::new(0),
+            metrics: Metrics::default(),
+            observer: RwLock::new(None),
+            auth_policy: RwLock::new(Arc::new(GrantPolicy::new())),
+            deferred_replies: Mutex::new(HashMap::new()),
+            started: Instant::now(),
+            singletons: Mutex::new(HashMap::new()),
+            shedding: AtomicBool::new(false),
+            shadow_journal,
+            shadows: RwLock::new(HashMap::new()),
+            config_seq: Mutex::new(None),
+            throughput: Mutex::new(ThroughputLog::default()),
+            quiesced: AtomicBool::new(false),
+            checkpointing: Mutex::new(()),
+            system_subscribers: RwLock::new(vec![]),
+            delivery_groups: Mutex::new(HashMap::new()),
+            tier_journals,
+            actor_tiers: RwLock::new(HashMap::new()),
+            preloaded,
+            reference_data: RwLock::new(ReferenceData::default()),
+        }
+    }
+
+    /// Wait up to `timeout` for `RuntimeConfig::preload` to finish reading
+    /// ahead; true once it has (or if nothing is preloaded)
+    pub fn wait_for_preload(&self, timeout: Duration) -> bool {
+        self.preloaded.wait(timeout)
+    }
+
+    /// Actors preloaded and not spawned yet
+    pub fn preloaded_actors(&self) -> usize {
+        self.preloaded.len()
     }
 
     /// Create with default configuration
@@ -176,13 +864,76 @@ impl ActorRuntime {
     }
 
     /// Get reference to journal
+    ///
+    /// With `Durability::Async`, call `flush_journal` first to read
+    /// events that may still be queued.
     pub fn journal(&self) -> &Journal {
         &self.journal
     }
 
+    /// Journal shadow actors write their events to (see `spawn_shadow`)
+    pub fn shadow_journal(&self) -> &Journal {
+        &self.shadow_journal
+    }
+
+    /// Journal of a named storage tier (see `tier`)
+    pub fn tier_journal(&self, name: &str) -> Option<&Journal> {
+        self.tier_journals
+            .get(name)
+            .map(|tier| tier.journal.as_ref())
+    }
+
+    /// The default journal and each named tier's, by tier name (`None`
+    /// for the default)
+    pub(crate) fn all_journals(&self) -> impl Iterator<Item = (Option<&str>, &Journal)> {
+        let tiers = self
+            .tier_journals
+            .iter()
+            .map(|(name, tier)| (Some(name.as_str()), tier.journal.as_ref()));
+        std::iter::once((None, self.journal.as_ref())).chain(tiers)
+    }
+
+    /// The storage tier an actor was spawned in
+    pub fn tier_of(&self, id: &ActorId) -> StorageTier {
+        self.actor_tiers
+            .read()
+            .expect("actor tiers read lock poisoned")
+            .get(id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Journal and writer an actor is stored with (`None` if it is
+    /// memory-only)
+    fn storage(&self, id: &ActorId) -> Option<(&Arc<Journal>, &JournalWriter)> {
+        match self.tier_of(id) {
+            StorageTier::Default => Some((&self.journal, &self.writer)),
+            StorageTier::Named(name) => {
+                let tier = self
+                    .tier_journals
+                    .get(&name)
+                    .expect("tier checked on spawn");
+                Some((&tier.journal, &tier.writer))
+            }
+            StorageTier::MemoryOnly => None,
+        }
+    }
+
     /// Register an actor (called after coroutine spawned)
-    pub fn register_actor(&self, id: ActorId, mailbox: Mailbox, behavior: String) {
-        REGISTRY.register(id, mailbox, behavior);
+    ///
+    /// Returns the actor's interned handle.
+    pub fn register_actor(&self, id: ActorId, mailbox: Mailbox, behavior: String) -> ActorHandle {
+        REGISTRY.register(id, mailbox, behavior)
+    }
+
+    /// Get the interned handle for a registered actor
+    pub fn handle_of(&self, id: &ActorId) -> Option<ActorHandle> {
+        REGISTRY.handle_of(id)
+    }
+
+    /// Resolve an interned handle to its ActorId
+    pub fn resolve_handle(&self, handle: ActorHandle) -> Option<ActorId> {
+        REGISTRY.resolve(handle)
     }
 
     /// Get mailbox for sending to an actor
@@ -195,160 +946,5171 @@ impl ActorRuntime {
         REGISTRY.is_running(id)
     }
 
+    /// Number of running actors in the registry
+    ///
+    /// The registry is process-wide, so this counts actors registered
+    /// through any runtime (and by FFI `actor-spawn`).
+    pub fn actor_count(&self) -> usize {
+        REGISTRY.running_count()
+    }
+
+    /// IDs of the running actors in the registry (see `actor_count`)
+    pub fn actor_ids(&self) -> Vec<ActorId> {
+        REGISTRY.running().into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// Time since the runtime was created
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
     /// Mark actor as stopped
+    ///
+    /// The actor stops accepting messages immediately but still handles
+    /// the ones already queued, unless it has a `Handoff`. Once its inbox
+    /// is empty it runs `on_stop`, journals an `ActorStopped` event,
+    /// flushes a final snapshot, and terminates (see `wait_for_stop`).
     pub fn stop_actor(&self, id: &ActorId) {
-        REGISTRY.mark_stopped(id);
+        self.stop_actor_with(id, StopReason::Normal);
     }
 
-    /// Unregister actor (cleanup)
-    pub fn unregister_actor(&self, id: &ActorId) {
-        REGISTRY.unregister(id);
+    /// Stop an actor, recording why
+    ///
+    /// Like `stop_actor`; the reason is journaled and sent to monitors.
+    /// Only the first stop's reason counts.
+    pub fn stop_actor_with(&self, id: &ActorId, reason: StopReason) {
+        // Trace IO failures must not prevent a stop
+        let _ = self.record(match reason {
+            StopReason::Normal => TraceRecord::Stop { id: id.clone() },
+            _ => TraceRecord::StopWith {
+                id: id.clone(),
+                reason: reason.clone(),
+            },
+        });
+        let handed_off = self.cell(id).and_then(|cell| {
+            let mut cell = cell.lock().expect("actor cell lock poisoned");
+            cell.stop_reason.get_or_insert(reason);
+            let handoff = cell.handoff.clone()?;
+            Some((handoff, std::mem::take(&mut cell.inbox)))
+        });
+        REGISTRY.mark_stopped(id);
+        if let Some((handoff, inbox)) = handed_off {
+            self.hand_off(id, &handoff, inbox);
+        }
+        // Termination errors resurface from wait_for_stop
+        let _ = self.try_terminate(id);
     }
 
-    /// Recover actor state from journal
+    /// Stop an actor without handling its queued messages
     ///
-    /// Returns (state, sequence_number) or None if no persisted state
-    pub fn recover_state(&self, id: &ActorId) -> std::io::Result<Option<(TypedValue, u64)>> {
-        // Try to load snapshot first
-        if let Some(snapshot) = self.journal.load_snapshot(id)? {
-            // Replay events after snapshot
-            let events = self.journal.read_events_after(id, snapshot.seq)?;
-
-            if events.is_empty() {
-                return Ok(Some((snapshot.state, snapshot.seq)));
-            }
+    /// Queued messages are dropped; a message already being handled
+    /// finishes first. The stop reason is `StopReason::Killed`.
+    pub fn kill_actor(&self, id: &ActorId) {
+        self.kill_with(id, StopReason::Killed);
+    }
 
-            // TODO: Replay events to rebuild state
-            // For now, just return snapshot state
-            let final_seq = events.last().map(|e| e.seq).unwrap_or(snapshot.seq);
-            Ok(Some((snapshot.state, final_seq)))
-        } else {
-            // No snapshot, replay all events
-            let events = self.journal.read_events(id)?;
+    /// Drop an actor's queued messages and stop it with `reason`
+    fn kill_with(&self, id: &ActorId, reason: StopReason) {
+        let inbox = self.cell(id).map(|cell| {
+            let mut cell = cell.lock().expect("actor cell lock poisoned");
+            cell.stop_reason.get_or_insert(reason.clone());
+            (cell.handoff.clone(), std::mem::take(&mut cell.inbox))
+        });
+        if let Some((handoff, inbox)) = inbox {
+            self.hand_off_or_drop(id, handoff, inbox, DropReason::Killed);
+        }
+        self.stop_actor_with(id, reason);
+    }
 
-            if events.is_empty() {
-                return Ok(None);
+    /// Pass a stopping actor's queued messages to its handoff
+    fn hand_off(&self, id: &ActorId, handoff: &Handoff, inbox: VecDeque<Envelope>) {
+        for envelope in inbox {
+            match handoff {
+                // Failed forwards are counted as dead letters by enqueue
+                Handoff::Successor(successor) => {
+                    let _ = self.enqueue(successor, envelope);
+                }
+                Handoff::DeadLetters => {
+                    Metrics::incr(&self.metrics.dead_letters);
+                    self.observe(|o| o.on_dead_letter(id, envelope.size));
+                }
             }
+        }
+    }
 
-            // TODO: Replay events to rebuild state
-            // For now, return empty map
-            let final_seq = events.last().map(|e| e.seq).unwrap_or(0);
-            Ok(Some((TypedValue::Map(std::collections::BTreeMap::new()), final_seq)))
+    /// Pass queued messages to the handoff, or drop them for `reason` if
+    /// there is none
+    fn hand_off_or_drop(
+        &self,
+        id: &ActorId,
+        handoff: Option<Handoff>,
+        inbox: VecDeque<Envelope>,
+        reason: DropReason,
+    ) {
+        match handoff {
+            Some(handoff) => self.hand_off(id, &handoff, inbox),
+            None => {
+                for envelope in inbox {
+                    self.drop_message(id, envelope, reason);
+                }
+            }
         }
     }
 
-    /// Persist an event to the journal
-    pub fn persist_event(&self, id: &ActorId, event: &Event) -> std::io::Result<()> {
-        if self.config.journaling_enabled {
-            self.journal.append(id, event)?;
+    /// Pass an actor's queued messages on when it stops, instead of
+    /// handling (or dropping) them (`None` restores the default; see
+    /// `lifecycle`)
+    pub fn set_handoff(&self, id: &ActorId, handoff: Option<Handoff>) -> Result<(), RuntimeError> {
+        self.reconfigure("handoff", id, handoff, |cell, handoff| {
+            cell.handoff = handoff
+        })
+    }
+
+    /// Override the runtime's panic policy for one actor (`None` restores
+    /// the default)
+    pub fn set_panic_policy(
+        &self,
+        id: &ActorId,
+        policy: Option<PanicPolicy>,
+    ) -> Result<(), RuntimeError> {
+        self.reconfigure("panic_policy", id, policy, |cell, policy| {
+            cell.panic_policy = policy
+        })
+    }
+
+    /// Why an actor was stopped (`None` while it is running)
+    pub fn stop_reason(&self, id: &ActorId) -> Option<StopReason> {
+        let cell = self.cell(id)?;
+        let cell = cell.lock().expect("actor cell lock poisoned");
+        cell.stop_reason.clone()
+    }
+
+    /// Redirect `old` to `new`, persisted under the journal path
+    ///
+    /// Messages sent to `old` while it isn't running here are delivered to
+    /// `new`, and journal reads for `old` return `new`'s history.
+    pub fn alias(&self, old: &ActorId, new: &ActorId) -> Result<(), RuntimeError> {
+        // Refused before it is audited, like the journal would refuse it
+        if self.journal.resolve_alias(new) == *old {
+            return Err(RuntimeError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("aliasing {} to {} would form a cycle", old, new),
+            )));
         }
+        self.record_config("alias", ConfigTarget::Actor(old.clone()), new)?;
+        self.journal.set_alias(old, new)?;
         Ok(())
     }
 
-    /// Save a snapshot
-    pub fn save_snapshot(&self, id: &ActorId, state: &TypedValue, seq: u64) -> std::io::Result<()> {
-        if self.config.journaling_enabled {
-            let snapshot = Snapshot {
-                seq,
-                state: state.clone(),
-                ts: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .map(|d| d.as_millis() as u64)
-                    .unwrap_or(0),
-            };
-            self.journal.save_snapshot(id, &snapshot)?;
-        }
+    /// Journal a change to one of an actor's settings, then apply it
+    /// (see `audit`)
+    ///
+    /// The actor's cell stays locked meanwhile, so concurrent changes are
+    /// recorded in the order they take effect.
+    fn reconfigure<T: std::fmt::Debug>(
+        &self,
+        setting: &str,
+        id: &ActorId,
+        value: T,
+        apply: impl FnOnce(&mut ActorCell, T),
+    ) -> Result<(), RuntimeError> {
+        let cell = self
+            .cell(id)
+            .ok_or_else(|| RuntimeError::ActorNotFound(id.clone()))?;
+        let mut cell = cell.lock().expect("actor cell lock poisoned");
+        self.record_config(setting, ConfigTarget::Actor(id.clone()), &value)?;
+        apply(&mut cell, value);
         Ok(())
     }
-}
 
-// Thread-local storage for current actor context
-thread_local! {
-    static CURRENT_ACTOR_ID: std::cell::RefCell<Option<ActorId>> = const { std::cell::RefCell::new(None) };
-}
+    /// Journal a configuration change to `ActorId::CONFIG` (see `audit`)
+    fn record_config(
+        &self,
+        setting: &str,
+        target: ConfigTarget,
+        value: &impl std::fmt::Debug,
+    ) -> Result<(), RuntimeError> {
+        if !self.config.journaling_enabled {
+            return Ok(());
+        }
+        let mut next = self.config_seq.lock().expect("config seq lock poisoned");
+        let seq = match *next {
+            Some(seq) => seq,
+            None => {
+                let events = self.journal.read_events(&ActorId::CONFIG)?;
+                events.last().map_or(0, |e| e.seq + 1)
+            }
+        };
+        let payload = ConfigChange::payload(setting, &target, format!("{:?}", value));
+        let event = Event::new(seq, CONFIG_CHANGED.to_string(), payload);
+        self.journal.append(&ActorId::CONFIG, &event)?;
+        *next = Some(seq + 1);
+        Ok(())
+    }
 
-/// Set the current actor ID (called when entering actor coroutine)
-pub fn set_current_actor(id: ActorId) {
-    CURRENT_ACTOR_ID.with(|cell| {
-        *cell.borrow_mut() = Some(id);
-    });
-}
+    /// Configuration changes recorded so far, oldest first (see `audit`)
+    pub fn config_history(&self) -> Result<Vec<ConfigChange>, RuntimeError> {
+        let events = self.journal.read_events(&ActorId::CONFIG)?;
+        Ok(events.iter().filter_map(ConfigChange::from_event).collect())
+    }
 
-/// Get the current actor ID (for actor-self builtin)
-pub fn get_current_actor() -> Option<ActorId> {
-    CURRENT_ACTOR_ID.with(|cell| cell.borrow().clone())
-}
+    /// Journal what every actor handled since the last call, as a
+    /// `MetricsRecorded` event under `ActorId::SYSTEM` (see `throughput`)
+    ///
+    /// Returns the event's seq, or `None` if no actor handled anything or
+    /// journaling is disabled.
+    pub fn record_throughput(&self) -> Result<Option<u64>, RuntimeError> {
+        if !self.config.journaling_enabled {
+            return Ok(None);
+        }
+        let current: Vec<(ActorId, MessageCounts)> = self
+            .cells
+            .read()
+            .expect("cells read lock poisoned")
+            .iter()
+            .map(|(id, cell)| {
+                (
+                    id.clone(),
+                    cell.lock().expect("actor cell lock poisoned").messages,
+                )
+            })
+            .collect();
+        let mut log = self
+            .throughput
+            .lock()
+            .expect("throughput log lock poisoned");
+        let actors = log.since_last(&current);
+        if actors.is_empty() {
+            return Ok(None);
+        }
+        let seq = match log.next_seq {
+            Some(seq) => seq,
+            None => {
+                let events = self.journal.read_events(&ActorId::SYSTEM)?;
+                events.last().map_or(0, |e| e.seq + 1)
+            }
+        };
+        let payload = ThroughputRecord::payload(&actors);
+        let event = Event::new(seq, METRICS_RECORDED.to_string(), payload);
+        self.journal.append(&ActorId::SYSTEM, &event)?;
+        log.next_seq = Some(seq + 1);
+        log.recorded(current);
+        Ok(Some(seq))
+    }
 
-/// Clear the current actor ID (called when exiting actor coroutine)
-pub fn clear_current_actor() {
-    CURRENT_ACTOR_ID.with(|cell| {
-        *cell.borrow_mut() = None;
-    });
-}
+    /// Throughput recorded so far, oldest first (see `throughput`)
+    pub fn throughput_history(&self) -> Result<Vec<ThroughputRecord>, RuntimeError> {
+        let events = self.journal.read_events(&ActorId::SYSTEM)?;
+        Ok(events
+            .iter()
+            .filter_map(ThroughputRecord::from_event)
+            .collect())
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
+    /// Send `watcher` a `Down` message when `target` terminates
+    ///
+    /// If `target` already terminated, the `Down` is sent right away.
+    pub fn monitor(&self, watcher: &ActorId, target: &ActorId) -> Result<(), RuntimeError> {
+        let cell = self
+            .cell(target)
+            .ok_or_else(|| RuntimeError::ActorNotFound(target.clone()))?;
+        let terminated = {
+            let mut cell = cell.lock().expect("actor cell lock poisoned");
+            if !cell.terminated {
+                cell.monitors.push(watcher.clone());
+                return Ok(());
+            }
+            cell.stop_reason.clone().unwrap_or(StopReason::Normal)
+        };
+        self.send_down(watcher, target, &terminated);
+        Ok(())
+    }
 
-    #[test]
-    fn test_registry_operations() {
-        let id = ActorId::new();
-        let mailbox = Mailbox::new(42);
+    /// Stop monitoring `target`
+    pub fn demonitor(&self, watcher: &ActorId, target: &ActorId) {
+        if let Some(cell) = self.cell(target) {
+            let mut cell = cell.lock().expect("actor cell lock poisoned");
+            cell.monitors.retain(|m| m != watcher);
+        }
+    }
 
-        REGISTRY.register(id.clone(), mailbox, "test-behavior".to_string());
+    /// Send `subscriber` the runtime's lifecycle events (see `system`)
+    pub fn subscribe_system(&self, subscriber: &ActorId) {
+        self.subscribe_system_filtered(subscriber, SubscriptionFilter::default());
+    }
 
-        assert!(REGISTRY.is_running(&id));
-        assert_eq!(REGISTRY.get_mailbox(&id).unwrap().channel_id(), 42);
+    /// Send `subscriber` the lifecycle events `filter` lets through,
+    /// replacing its filter if it is already subscribed
+    pub fn subscribe_system_filtered(&self, subscriber: &ActorId, filter: SubscriptionFilter) {
+        let mut subscribers = self
+            .system_subscribers
+            .write()
+            .expect("system subscribers write lock poisoned");
+        match subscribers.iter_mut().find(|(s, _)| s == subscriber) {
+            Some((_, existing)) => *existing = filter,
+            None => subscribers.push((subscriber.clone(), filter)),
+        }
+    }
 
-        REGISTRY.mark_stopped(&id);
-        assert!(!REGISTRY.is_running(&id));
+    pub fn unsubscribe_system(&self, subscriber: &ActorId) {
+        self.system_subscribers
+            .write()
+            .expect("system subscribers write lock poisoned")
+            .retain(|(s, _)| s != subscriber);
+    }
 
-        REGISTRY.unregister(&id);
-        assert!(REGISTRY.get_mailbox(&id).is_none());
+    /// Send an event to every system subscriber whose filter lets it
+    /// through, except the actor it is about, from `ActorId::SYSTEM`
+    /// (failed sends are dead letters)
+    pub fn publish_system(&self, event: &SystemEvent) {
+        let msg = event.to_message();
+        let subscribers: Vec<ActorId> = self
+            .system_subscribers
+            .read()
+            .expect("system subscribers read lock poisoned")
+            .iter()
+            .filter(|(s, filter)| event.actor() != Some(s) && filter.matches(&msg))
+            .map(|(s, _)| s.clone())
+            .collect();
+        for subscriber in subscribers {
+            let envelope = Envelope::new(Some(ActorId::SYSTEM), msg.clone());
+            let _ = self.enqueue(&subscriber, envelope);
+        }
     }
 
-    #[test]
-    fn test_runtime_creation() {
-        let temp_dir = TempDir::new().unwrap();
-        let config = RuntimeConfig {
-            journal_path: temp_dir.path().to_path_buf(),
-            journaling_enabled: true,
-            snapshot_interval: 100,
-        };
+    /// Tell a monitor that `target` stopped (failed sends are dead letters)
+    fn send_down(&self, watcher: &ActorId, target: &ActorId, reason: &StopReason) {
+        let envelope = Envelope::new(Some(target.clone()), down_message(target, reason));
+        let _ = self.enqueue(watcher, envelope);
+    }
 
-        let runtime = ActorRuntime::new(config);
-        assert!(runtime.config.journaling_enabled);
+    /// Whether an actor has fully terminated
+    pub fn is_terminated(&self, id: &ActorId) -> bool {
+        self.cell(id)
+            .is_some_and(|cell| cell.lock().expect("actor cell lock poisoned").terminated)
     }
 
-    #[test]
-    fn test_current_actor_thread_local() {
-        let id = ActorId::new();
+    /// Block until a stopped actor has fully terminated
+    ///
+    /// Once the actor is stopped, helps drain the messages still queued
+    /// for it (unless it is pinned, which leaves them to its pool), then
+    /// waits for its `on_stop` hook and final snapshot. A running actor's
+    /// messages are left to its run loop. Returns `Ok(false)` if the actor
+    /// hasn't terminated when the timeout elapses (for example because it
+    /// was never stopped). Under `strand::yielding` the wait yields the
+    /// strand rather than blocking its thread.
+    pub fn wait_for_stop(&self, id: &ActorId, timeout: Duration) -> Result<bool, RuntimeError> {
+        let deadline = Instant::now() + timeout;
 
-        assert!(get_current_actor().is_none());
+        loop {
+            // Help drain the inbox rather than waiting for another dispatcher
+            let helped = self.drains_on_wait(id)
+                && match self.process_next(id) {
+                    Ok(handled) => handled,
+                    Err(e) if e.is_message_failure() => true,
+                    Err(e) => return Err(e),
+                };
 
-        set_current_actor(id.clone());
-        assert_eq!(get_current_actor().unwrap(), id);
+            if !helped && self.try_terminate(id)? {
+                return Ok(true);
+            }
 
-        clear_current_actor();
-        assert!(get_current_actor().is_none());
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(self.is_terminated(id));
+            }
+            if helped {
+                continue;
+            }
+
+            let guard = self.stop_lock.lock().expect("stop lock poisoned");
+            if self.is_terminated(id) {
+                return Ok(true);
+            }
+            // Wake periodically: another thread may finish draining first
+            let wait = (deadline - now).min(Duration::from_millis(10));
+            drop(
+                strand::wait_timeout(&self.stop_lock, guard, &self.stop_signal, wait)
+                    .expect("stop lock poisoned"),
+            );
+        }
     }
 
-    #[test]
-    fn test_recover_empty_state() {
-        let temp_dir = TempDir::new().unwrap();
+    /// Whether `wait_for_stop` may handle an actor's queued messages
+    /// itself: only once it is stopped, and never if it is pinned
+    fn drains_on_wait(&self, id: &ActorId) -> bool {
+        self.cell(id).is_some_and(|cell| {
+            let cell = cell.lock().expect("actor cell lock poisoned");
+            cell.stop_reason.is_some() && cell.pool.is_none()
+        })
+    }
+
+    /// Terminate a stopped actor if its inbox is drained
+    ///
+    /// Returns whether the actor is now terminated.
+    fn try_terminate(&self, id: &ActorId) -> Result<bool, RuntimeError> {
+        let Some(cell) = self.cell(id) else {
+            return Ok(false);
+        };
+        if REGISTRY.is_running(id) {
+            return Ok(false);
+        }
+
+        let (mut actor, reason) = {
+            let mut cell = cell.lock().expect("actor cell lock poisoned");
+            if cell.terminated {
+                return Ok(true);
+            }
+            if cell.actor.is_none() || !cell.inbox.is_empty() {
+                return Ok(false);
+            }
+            let reason = cell.stop_reason.get_or_insert(StopReason::Normal).clone();
+            (cell.actor.take().expect("checked above"), reason)
+        };
+
+        let result = self.finish_actor(&mut actor, &reason);
+
+        let monitors = {
+            let mut cell = cell.lock().expect("actor cell lock poisoned");
+            cell.actor = Some(actor);
+            cell.terminated = result.is_ok();
+            if result.is_ok() {
+                std::mem::take(&mut cell.monitors)
+            } else {
+                vec![]
+            }
+        };
+        self.signal_idle();
+        if result.is_ok() {
+            {
+                let _guard = self.stop_lock.lock().expect("stop lock poisoned");
+                self.stop_signal.notify_all();
+            }
+            self.observe(|o| o.on_stopped(id, &reason));
+            for watcher in &monitors {
+                self.send_down(watcher, id, &reason);
+            }
+            self.publish_system(&SystemEvent::ActorStopped {
+                actor: id.clone(),
+                reason: reason.clone(),
+            });
+        }
+
+        result.map(|()| true)
+    }
+
+    /// Run the stop hook, journal the stop reason, and flush the final
+    /// snapshot
+    fn finish_actor(&self, actor: &mut Actor, reason: &StopReason) -> Result<(), RuntimeError> {
+        if let Some(behavior) = self.behavior(&actor.behavior) {
+            for event in handle_stop(behavior.as_ref(), actor) {
+                self.persist_event(&actor.id, &event)?;
+            }
+        }
+        let mut stopped = Event::new(
+            actor.next_sequence(),
+            STOPPED_EVENT.to_string(),
+            reason.to_value(),
+        );
+        stopped.actor_id = Some(actor.id.clone());
+        self.persist_event(&actor.id, &stopped)?;
+        self.save_snapshot(&actor.id, &actor.state, actor.sequence)?;
+        Ok(())
+    }
+
+    /// Unregister actor (cleanup)
+    ///
+    /// Messages still queued for the actor are dropped.
+    pub fn unregister_actor(&self, id: &ActorId) {
+        REGISTRY.unregister(id);
+        for (_, journal) in self.all_journals() {
+            journal.release(id);
+        }
+        self.shadows
+            .write()
+            .expect("shadows write lock poisoned")
+            .remove(id);
+        self.unsubscribe_system(id);
+        self.leave_delivery_groups(id);
+        self.reference_data
+            .write()
+            .expect("reference data write lock poisoned")
+            .withdraw_actor(id);
+        self.actor_tiers
+            .write()
+            .expect("actor tiers write lock poisoned")
+            .remove(id);
+        let removed = self
+            .cells
+            .write()
+            .expect("cells write lock poisoned")
+            .remove(id);
+
+        if let Some(cell) = removed {
+            let (handoff, inbox) = {
+                let mut cell = cell.lock().expect("actor cell lock poisoned");
+                (cell.handoff.clone(), std::mem::take(&mut cell.inbox))
+            };
+            self.hand_off_or_drop(id, handoff, inbox, DropReason::Unregistered);
+        }
+    }
+
+    /// Register a behavior that `spawn` can refer to by name
+    pub fn register_behavior(&self, name: impl Into<String>, behavior: impl Behavior + 'static) {
+        let mut behaviors = self
+            .behaviors
+            .write()
+            .expect("behaviors write lock poisoned");
+        behaviors.insert(name.into(), Arc::new(behavior));
+    }
+
+    /// Wrap every message handled by `behavior` in an interceptor
+    ///
+    /// Interceptors run in the order they were added. They apply to actors
+    /// already running the behavior, from their next message on.
+    pub fn add_interceptor(&self, behavior: &str, interceptor: impl Interceptor + 'static) {
+        self.interceptors
+            .write()
+            .expect("interceptors write lock poisoned")
+            .entry(behavior.to_string())
+            .or_default()
+            .push(Arc::new(interceptor));
+    }
+
+    /// Check every event behaviors emit before it is journaled (see
+    /// `validate`)
+    pub fn add_event_validator(&self, validator: impl EventValidator + 'static) {
+        self.validators
+            .write()
+            .expect("validators write lock poisoned")
+            .push(Arc::new(validator));
+    }
+
+    /// Run the validators over a message's events
+    fn validate_events(&self, id: &ActorId, events: &[Event]) -> Result<(), RuntimeError> {
+        let validators = self
+            .validators
+            .read()
+            .expect("validators read lock poisoned");
+        for event in events {
+            for validator in validators.iter() {
+                if let Err(reason) = validator.validate(id, event) {
+                    Metrics::incr(&self.metrics.events_rejected);
+                    return Err(RuntimeError::EventRejected(EventRejected {
+                        actor: id.clone(),
+                        event_type: event.event_type.clone(),
+                        seq: event.seq,
+                        reason,
+                    }));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Look up a registered behavior, wrapped in its interceptors
+    fn behavior(&self, name: &str) -> Option<Arc<dyn Behavior>> {
+        let behaviors = self.behaviors.read().expect("behaviors read lock poisoned");
+        let inner = behaviors.get(name).cloned()?;
+
+        let interceptors = self
+            .interceptors
+            .read()
+            .expect("interceptors read lock poisoned");
+        match interceptors.get(name) {
+            Some(chain) => Some(Arc::new(Intercepted {
+                inner,
+                chain: chain.clone(),
+            })),
+            None => Some(inner),
+        }
+    }
+
+    /// Look up an actor's cell
+    fn cell(&self, id: &ActorId) -> Option<Arc<Mutex<ActorCell>>> {
+        let cells = self.cells.read().expect("cells read lock poisoned");
+        cells.get(id).cloned()
+    }
+
+    /// Spawn a new actor dispatched by this runtime
+    pub fn spawn(&self, behavior: &str) -> Result<ActorId, RuntimeError> {
+        self.spawn_with_id(self.new_actor_id(), behavior)
+    }
+
+    /// A fresh ID in the configured `id_scheme`
+    pub fn new_actor_id(&self) -> ActorId {
+        ActorId::generate(self.config.id_scheme)
+    }
+
+    /// Spawn an actor pinned to a pool (see `pin`)
+    pub fn spawn_pinned(&self, behavior: &str, pool: &str) -> Result<ActorId, RuntimeError> {
+        let id = self.spawn(behavior)?;
+        self.pin(&id, Some(pool))?;
+        Ok(id)
+    }
+
+    /// Spawn an actor stored in `tier` rather than its behavior's
+    pub fn spawn_in_tier(
+        &self,
+        behavior: &str,
+        tier: StorageTier,
+    ) -> Result<ActorId, RuntimeError> {
+        self.spawn_with_id_in_tier(self.new_actor_id(), behavior, tier)
+    }
+
+    /// Spawn an actor with a specific ID, recovering any persisted state
+    ///
+    /// The actor is pinned to its behavior's pool, if it has one, and
+    /// joins its behavior's scheduling group and storage tier.
+    pub fn spawn_with_id(&self, id: ActorId, behavior: &str) -> Result<ActorId, RuntimeError> {
+        let tier = self.config.behavior_tiers.get(behavior).cloned();
+        self.spawn_with_id_in_tier(id, behavior, tier.unwrap_or_default())
+    }
+
+    /// Spawn an actor with a specific ID in `tier`, recovering any state
+    /// persisted there
+    pub fn spawn_with_id_in_tier(
+        &self,
+        id: ActorId,
+        behavior: &str,
+        tier: StorageTier,
+    ) -> Result<ActorId, RuntimeError> {
+        let pool = self
+            .behavior_pools
+            .read()
+            .expect("behavior pools read lock poisoned")
+            .get(behavior)
+            .cloned();
+        let group = self
+            .behavior_groups
+            .read()
+            .expect("behavior groups read lock poisoned")
+            .get(behavior)
+            .cloned()
+            .unwrap_or_else(|| DEFAULT_GROUP.to_string());
+        let Some(handler) = self.behavior(behavior) else {
+            return Err(RuntimeError::UnknownBehavior(behavior.to_string()));
+        };
+        if let StorageTier::Named(name) = &tier {
+            if !self.tier_journals.contains_key(name) {
+                return Err(RuntimeError::UnknownTier(name.clone()));
+            }
+        }
+        {
+            let mut tiers = self
+                .actor_tiers
+                .write()
+                .expect("actor tiers write lock poisoned");
+            match tier {
+                StorageTier::Default => tiers.remove(&id),
+                tier => tiers.insert(id.clone(), tier),
+            };
+        }
+
+        let (state, seq) = match self.recover(&id, Some(handler.as_ref()))? {
+            Some(recovered) => recovered,
+            None => (handler.initial_state(), 0),
+        };
+        let actor = Actor::with_state(id.clone(), behavior.to_string(), state, seq);
+
+        self.record(TraceRecord::Spawn {
+            id: id.clone(),
+            behavior: behavior.to_string(),
+        })?;
+
+        let cell = ActorCell {
+            actor: Some(actor),
+            inbox: VecDeque::new(),
+            terminated: false,
+            last_processed: None,
+            handling_since: None,
+            blocked_reported: false,
+            rate_limit: None,
+            self_send_chain: 0,
+            stop_reason: None,
+            monitors: vec![],
+            pool,
+            group,
+            handling_thread: None,
+            panic_policy: self.supervision(behavior).and_then(|s| s.panic_policy),
+            restarts: 0,
+            paused_until: None,
+            max_message_age: self.config.max_message_age,
+            message_timeout: self.config.message_timeout,
+            sandbox: self.config.sandboxes.get(behavior).cloned(),
+            state_limit: self.config.state_limit,
+            state_size: None,
+            messages: MessageCounts::default(),
+            handoff: None,
+            spawned_at: Instant::now(),
+        };
+        self.cells
+            .write()
+            .expect("cells write lock poisoned")
+            .insert(id.clone(), Arc::new(Mutex::new(cell)));
+        REGISTRY.register(id.clone(), Mailbox::local(), behavior.to_string());
+        self.publish_system(&SystemEvent::ActorSpawned {
+            actor: id.clone(),
+            behavior: behavior.to_string(),
+        });
+
+        Ok(id)
+    }
+
+    /// Spawn a shadow of `primary` running `behavior`, to try a new
+    /// behavior version against live traffic
+    ///
+    /// The shadow starts from a copy of the primary's state and inbox,
+    /// taken between two of its messages, and is sent a copy of every
+    /// message the primary accepts from then on. Nothing it
+    /// does is visible outside it: its replies are discarded (including
+    /// deferred ones it fulfills), and its events go to `shadow_journal`
+    /// instead of the runtime's journal. It is never snapshotted. Compare
+    /// its state and events with the primary's to validate the new
+    /// behavior, then stop it like any other actor.
+    ///
+    /// Shadows are run by the same run loops as everything else. To bound
+    /// what the experiment costs, give the shadow a rate limit
+    /// (`set_rate_limit`); messages are not mirrored at all while the
+    /// runtime is shedding load. Mirrored messages a shadow rejects are
+    /// dropped without affecting the primary.
+    pub fn spawn_shadow(&self, primary: &ActorId, behavior: &str) -> Result<ActorId, RuntimeError> {
+        if self.cell(primary).is_none() {
+            return Err(RuntimeError::ActorNotFound(primary.clone()));
+        }
+        let id = self.spawn_with_id(self.new_actor_id(), behavior)?;
+        let shadow = self.cell(&id).expect("just spawned");
+        // Registered under the primary's cell lock, which `enqueue` holds
+        // to mirror a message: each one is either already in the copied
+        // inbox or mirrored after it
+        let registered = self.with_idle_cell(primary, |primary_cell| {
+            let mut cell = shadow.lock().expect("actor cell lock poisoned");
+            let actor = cell.actor.as_mut().expect("just spawned");
+            let copied = primary_cell.actor.as_ref().expect("idle actor");
+            actor.state = copied.state.clone();
+            actor.sequence = copied.sequence;
+            cell.inbox
+                .extend(primary_cell.inbox.iter().map(Envelope::mirror));
+            self.shadows
+                .write()
+                .expect("shadows write lock poisoned")
+                .insert(id.clone(), primary.clone());
+        });
+        if let Err(e) = registered {
+            self.unregister_actor(&id);
+            return Err(e);
+        }
+        Ok(id)
+    }
+
+    /// Shadows currently mirroring `primary`'s messages
+    pub fn shadows_of(&self, primary: &ActorId) -> Vec<ActorId> {
+        let shadows = self.shadows.read().expect("shadows read lock poisoned");
+        shadows
+            .iter()
+            .filter(|(_, p)| *p == primary)
+            .map(|(shadow, _)| shadow.clone())
+            .collect()
+    }
+
+    fn is_shadow(&self, id: &ActorId) -> bool {
+        let shadows = self.shadows.read().expect("shadows read lock poisoned");
+        shadows.contains_key(id)
+    }
+
+    /// A copy of `envelope` for each of `to`'s shadows (see
+    /// `Envelope::mirror`)
+    ///
+    /// Called with `to`'s cell locked, as the envelope is queued.
+    fn mirrored(&self, to: &ActorId, envelope: &Envelope) -> Vec<(ActorId, Envelope)> {
+        if self.shedding.load(Ordering::Relaxed) {
+            return vec![];
+        }
+        self.shadows_of(to)
+            .into_iter()
+            .map(|shadow| (shadow, envelope.mirror()))
+            .collect()
+    }
+
+    /// Spawn the one actor known as `name` on this runtime, or get the
+    /// one already running
+    ///
+    /// Later calls return the running singleton's ID whatever `behavior`
+    /// they pass. With journaling on, the ID is recorded in the journal
+    /// (see `Journal::singleton`), so after a restart the first call
+    /// brings the singleton back with the same ID and recovered state; the
+    /// same happens if it was stopped. Fails with `ActorStopped` while a
+    /// stopped singleton is still draining, and with `InvalidInput` if
+    /// `name` isn't a plain file name.
+    pub fn spawn_singleton(&self, name: &str, behavior: &str) -> Result<ActorId, RuntimeError> {
+        crate::journal::check_name("singleton", name)?;
+        let mut singletons = self.singletons.lock().expect("singletons lock poisoned");
+        let known = match singletons.get(name) {
+            Some(id) => Some(id.clone()),
+            None if self.config.journaling_enabled => self.journal.singleton(name),
+            None => None,
+        };
+
+        if let Some(id) = &known {
+            if let Some(cell) = self.cell(id) {
+                if REGISTRY.is_running(id) {
+                    return Ok(id.clone());
+                }
+                if !cell.lock().expect("actor cell lock poisoned").terminated {
+                    return Err(RuntimeError::ActorStopped(id.clone()));
+                }
+            }
+        }
+
+        let id = self.spawn_with_id(known.unwrap_or_default(), behavior)?;
+        if self.config.journaling_enabled {
+            self.journal.set_singleton(name, &id)?;
+        }
+        singletons.insert(name.to_string(), id.clone());
+        Ok(id)
+    }
+
+    /// Send a message to an actor dispatched by this runtime
+    pub fn send(&self, to: &ActorId, msg: TypedValue) -> Result<(), RuntimeError> {
+        self.enqueue_from(Origin::Host, to, Envelope::new(None, msg))
+    }
+
+    /// Queue a traced host send again, bypassing the rate limit and load
+    /// shedding that admitted it in the recorded run
+    pub(crate) fn replay_send(&self, to: &ActorId, msg: TypedValue) -> Result<(), RuntimeError> {
+        self.enqueue_from(Origin::Replayed, to, Envelope::new(None, msg))
+    }
+
+    /// Add an actor to a delivery group (see `delivery`)
+    pub fn join_delivery_group(&self, group: &str, id: &ActorId) {
+        let mut groups = self
+            .delivery_groups
+            .lock()
+            .expect("delivery groups lock poisoned");
+        let members = &mut groups.entry(group.to_string()).or_default().members;
+        if !members.contains(id) {
+            members.push(id.clone());
+        }
+    }
+
+    pub fn leave_delivery_group(&self, group: &str, id: &ActorId) {
+        let mut groups = self
+            .delivery_groups
+            .lock()
+            .expect("delivery groups lock poisoned");
+        if let Some(group) = groups.get_mut(group) {
+            group.members.retain(|m| m != id);
+        }
+    }
+
+    fn leave_delivery_groups(&self, id: &ActorId) {
+        let mut groups = self
+            .delivery_groups
+            .lock()
+            .expect("delivery groups lock poisoned");
+        for group in groups.values_mut() {
+            group.members.retain(|m| m != id);
+        }
+    }
+
+    /// Choose how a delivery group picks members (round-robin by default)
+    pub fn set_delivery_strategy(
+        &self,
+        group: &str,
+        strategy: DeliveryStrategy,
+    ) -> Result<(), RuntimeError> {
+        let mut groups = self
+            .delivery_groups
+            .lock()
+            .expect("delivery groups lock poisoned");
+        let target = ConfigTarget::DeliveryGroup(group.to_string());
+        self.record_config("delivery_strategy", target, &strategy)?;
+        groups.entry(group.to_string()).or_default().strategy = strategy;
+        Ok(())
+    }
+
+    /// Members of a delivery group, in the order they joined
+    pub fn delivery_group_members(&self, group: &str) -> Vec<ActorId> {
+        let groups = self
+            .delivery_groups
+            .lock()
+            .expect("delivery groups lock poisoned");
+        groups
+            .get(group)
+            .map(|g| g.members.clone())
+            .unwrap_or_default()
+    }
+
+    /// Send a message to one running member of a delivery group, returning
+    /// the member chosen
+    pub fn send_to_group(&self, group: &str, msg: TypedValue) -> Result<ActorId, RuntimeError> {
+        let member = {
+            let mut groups = self
+                .delivery_groups
+                .lock()
+                .expect("delivery groups lock poisoned");
+            groups.get_mut(group).and_then(|g| {
+                g.pick(|id| {
+                    let cell = self.cell(id).filter(|_| REGISTRY.is_running(id))?;
+                    let queued = cell.lock().expect("actor cell lock poisoned").inbox.len();
+                    Some(queued)
+                })
+            })
+        };
+        let member = member.ok_or_else(|| RuntimeError::NoGroupMember(group.to_string()))?;
+        self.send(&member, msg)?;
+        Ok(member)
+    }
+
+    /// Publish an actor's state as reference data under `name` (see
+    /// `refdata`)
+    ///
+    /// The current state is published right away, and again whenever a
+    /// handled message changes it. An actor publishes under one name; a
+    /// name taken by another actor moves to this one.
+    pub fn publish_reference(&self, name: &str, id: &ActorId) -> Result<(), RuntimeError> {
+        let state = self.read_state(id)?;
+        let mut data = self
+            .reference_data
+            .write()
+            .expect("reference data write lock poisoned");
+        data.withdraw_actor(id);
+        data.publish(name, id, state);
+        Ok(())
+    }
+
+    /// Stop publishing reference data under `name`
+    pub fn withdraw_reference(&self, name: &str) {
+        self.reference_data
+            .write()
+            .expect("reference data write lock poisoned")
+            .withdraw(name);
+    }
+
+    /// The state published under `name`, shared rather than copied
+    pub fn reference_data(&self, name: &str) -> Option<Arc<TypedValue>> {
+        self.reference_data
+            .read()
+            .expect("reference data read lock poisoned")
+            .get(name)
+    }
+
+    /// One entry of the Map state published under `name`
+    pub fn reference_get(&self, name: &str, key: &TypedMapKey) -> Option<TypedValue> {
+        match &*self.reference_data(name)? {
+            TypedValue::Map(entries) => entries.get(key).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Republish a reference data actor's state if it changed
+    fn refresh_reference(&self, id: &ActorId, previous: &TypedValue, state: &TypedValue) {
+        let name = self
+            .reference_data
+            .read()
+            .expect("reference data read lock poisoned")
+            .name_of(id)
+            .map(str::to_string);
+        if let Some(name) = name.filter(|_| previous != state) {
+            self.reference_data
+                .write()
+                .expect("reference data write lock poisoned")
+                .update(&name, state.clone());
+        }
+    }
+
+    /// Send a message that is dropped instead of handled if it is still
+    /// queued after `ttl` (see `expiry`)
+    pub fn send_with_ttl(
+        &self,
+        to: &ActorId,
+        msg: TypedValue,
+        ttl: Duration,
+    ) -> Result<(), RuntimeError> {
+        let envelope = Envelope::new(None, msg);
+        let envelope = Envelope {
+            expires_at: Some(envelope.enqueued_at + ttl.as_millis() as u64),
+            ..envelope
+        };
+        self.enqueue_from(Origin::Host, to, envelope)
+    }
+
+    /// Send a message without copying it
+    ///
+    /// The same `Arc` can be sent to any number of actors on this runtime:
+    /// their inboxes share one copy of the value, which is freed once the
+    /// last of them has handled it. Behaviors only ever see the message by
+    /// reference, so one that wants to change it clones it first; the copy
+    /// happens in that receiver alone. Worth it for large Maps and Lists
+    /// fanned out to many actors. Shared messages are never offloaded, so
+    /// `RuntimeConfig::max_message_size` doesn't apply to them.
+    pub fn send_shared(&self, to: &ActorId, msg: Arc<TypedValue>) -> Result<(), RuntimeError> {
+        self.enqueue_from(Origin::Host, to, Envelope::shared(None, msg))
+    }
+
+    /// Send many messages to one actor
+    ///
+    /// Equivalent to calling `send` for each message in order, but looks
+    /// the actor up and locks its inbox once for the whole batch. Messages
+    /// its rate limit rejects are dropped (and reported as with `send`);
+    /// returns how many were queued. If tracing the session (see
+    /// `start_recording`) fails partway, the messages before the failure
+    /// stay queued and the rest aren't sent; the error is returned only if
+    /// none was queued.
+    pub fn send_batch(&self, to: &ActorId, msgs: Vec<TypedValue>) -> Result<usize, RuntimeError> {
+        let envelopes = msgs
+            .into_iter()
+            .map(|msg| Envelope::new(None, msg))
+            .collect();
+        self.enqueue_batch(to, envelopes)
+    }
+
+    /// Send a message and get a handle to the behavior's reply
+    ///
+    /// The behavior answers with `BehaviorContext::reply`. Someone must
+    /// still drive the actor (`process_next` / `run_until_idle`).
+    pub fn ask(&self, to: &ActorId, msg: TypedValue) -> Result<Reply, RuntimeError> {
+        let (reply_to, reply) = Reply::channel(to.clone());
+        let envelope = Envelope {
+            reply_to: Some(reply_to),
+            ..Envelope::new(None, msg)
+        };
+        self.enqueue_from(Origin::Host, to, envelope)?;
+        Ok(reply)
+    }
+
+    /// Restrict the builtins an actor may call (`None` allows all of them)
+    pub fn set_sandbox(&self, id: &ActorId, sandbox: Option<Sandbox>) -> Result<(), RuntimeError> {
+        self.reconfigure("sandbox", id, sandbox, |cell, sandbox| {
+            cell.sandbox = sandbox
+        })
+    }
+
+    /// Whether an actor may call the builtin `word` (see `sandbox`)
+    ///
+    /// Actors this runtime doesn't know are not sandboxed.
+    pub fn check_builtin(&self, id: &ActorId, word: &str) -> Result<(), RuntimeError> {
+        let allowed = self.cell(id).is_none_or(|cell| {
+            let cell = cell.lock().expect("actor cell lock poisoned");
+            cell.sandbox.as_ref().is_none_or(|s| s.allows(word))
+        });
+        if allowed {
+            return Ok(());
+        }
+        Metrics::incr(&self.metrics.builtins_denied);
+        self.observe(|o| o.on_builtin_denied(id, word));
+        Err(RuntimeError::BuiltinDenied {
+            actor: id.clone(),
+            word: word.to_string(),
+        })
+    }
+
+    /// Set the policy operator-facing calls are checked against
+    pub fn set_auth_policy(&self, policy: impl AuthPolicy + 'static) {
+        *self.auth_policy.write().expect("auth policy lock poisoned") = Arc::new(policy);
+    }
+
+    /// Whether `principal` may perform `op` (see `auth`)
+    ///
+    /// Until `set_auth_policy` is called, every operation is denied.
+    pub fn authorize(&self, principal: &Principal, op: Operation) -> Result<(), RuntimeError> {
+        let policy = self
+            .auth_policy
+            .read()
+            .expect("auth policy lock poisoned")
+            .clone();
+        if policy.authorize(principal, &op) {
+            return Ok(());
+        }
+        Err(RuntimeError::Unauthorized {
+            principal: principal.clone(),
+            op,
+        })
+    }
+
+    /// `health`, for a caller outside the host process
+    pub fn health_as(&self, principal: &Principal) -> Result<HealthReport, RuntimeError> {
+        self.authorize(principal, Operation::Health)?;
+        Ok(self.health())
+    }
+
+    /// `dump_actor`, for a caller outside the host process
+    pub fn dump_actor_as(
+        &self,
+        principal: &Principal,
+        id: &ActorId,
+    ) -> Result<ActorDump, RuntimeError> {
+        self.authorize(principal, Operation::Inspect(id.clone()))?;
+        self.dump_actor(id)
+    }
+
+    /// `send`, for a caller outside the host process
+    pub fn send_as(
+        &self,
+        principal: &Principal,
+        to: &ActorId,
+        msg: TypedValue,
+    ) -> Result<(), RuntimeError> {
+        self.authorize(principal, Operation::Send(to.clone()))?;
+        self.send(to, msg)
+    }
+
+    /// `spawn`, for a caller outside the host process
+    pub fn spawn_as(&self, principal: &Principal, behavior: &str) -> Result<ActorId, RuntimeError> {
+        self.authorize(principal, Operation::Spawn(behavior.to_string()))?;
+        self.spawn(behavior)
+    }
+
+    /// `stop_actor`, for a caller outside the host process
+    pub fn stop_actor_as(&self, principal: &Principal, id: &ActorId) -> Result<(), RuntimeError> {
+        self.authorize(principal, Operation::Stop(id.clone()))?;
+        self.stop_actor(id);
+        Ok(())
+    }
+
+    /// Drop an actor's queued messages once they are older than `max_age`
+    /// (`None` = no maximum; see `expiry`)
+    pub fn set_max_message_age(
+        &self,
+        id: &ActorId,
+        max_age: Option<Duration>,
+    ) -> Result<(), RuntimeError> {
+        self.reconfigure("max_message_age", id, max_age, |cell, max_age| {
+            cell.max_message_age = max_age
+        })
+    }
+
+    /// Fail an actor's handlings that run longer than `timeout` (`None` =
+    /// no limit; see `timeout`)
+    pub fn set_message_timeout(
+        &self,
+        id: &ActorId,
+        timeout: Option<Duration>,
+    ) -> Result<(), RuntimeError> {
+        self.reconfigure("message_timeout", id, timeout, |cell, timeout| {
+            cell.message_timeout = timeout
+        })
+    }
+
+    /// Cap an actor's state size (`None` stops measuring it)
+    pub fn set_state_limit(
+        &self,
+        id: &ActorId,
+        limit: Option<StateLimit>,
+    ) -> Result<(), RuntimeError> {
+        self.reconfigure("state_limit", id, limit, |cell, limit| {
+            cell.state_limit = limit
+        })
+    }
+
+    /// Limit how many messages per second an actor accepts
+    ///
+    /// `None` removes the limit. Sends past the limit fail with
+    /// `RuntimeError::RateLimited`.
+    pub fn set_rate_limit(
+        &self,
+        id: &ActorId,
+        limit: Option<RateLimit>,
+    ) -> Result<(), RuntimeError> {
+        let cell = self
+            .cell(id)
+            .ok_or_else(|| RuntimeError::ActorNotFound(id.clone()))?;
+        // Traced before it is audited, and applied only once it is both.
+        // The recorder stays locked until then, so sends traced after the
+        // change are queued after it (lock order: recorder, then cell).
+        let mut recorder = self.recorder.lock().expect("recorder lock poisoned");
+        if let Some(session) = recorder.as_mut() {
+            session.record(&TraceRecord::RateLimit {
+                id: id.clone(),
+                limit,
+            })?;
+        }
+        self.record_config("rate_limit", ConfigTarget::Actor(id.clone()), &limit)?;
+        cell.lock().expect("actor cell lock poisoned").rate_limit =
+            limit.map(|limit| TokenBucket::new(limit, Instant::now()));
+        Ok(())
+    }
+
+    /// Answer a deferred reply (see `BehaviorContext::defer_reply`)
+    ///
+    /// Returns false if no reply is pending for the token (it was already
+    /// fulfilled, or the deferred message wasn't an `ask`).
+    pub fn fulfill(&self, token: &ReplyToken, value: TypedValue) -> bool {
+        let pending = self
+            .deferred_replies
+            .lock()
+            .expect("deferred replies lock poisoned")
+            .remove(token);
+        match pending {
+            Some(reply_to) => {
+                reply_to.send(Ok(value));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Run `f` on an actor between message handlings
+    ///
+    /// Waits out an in-flight message first (yielding under
+    /// `strand::yielding`); the cell stays locked while `f` runs, so no
+    /// new message starts meanwhile.
+    fn with_idle_actor<T>(
+        &self,
+        id: &ActorId,
+        f: impl FnOnce(&mut Actor) -> T,
+    ) -> Result<T, RuntimeError> {
+        self.with_idle_cell(id, |cell| f(cell.actor.as_mut().expect("idle actor")))
+    }
+
+    /// Run `f` on an actor's cell between message handlings (see
+    /// `with_idle_actor`)
+    fn with_idle_cell<T>(
+        &self,
+        id: &ActorId,
+        f: impl FnOnce(&mut ActorCell) -> T,
+    ) -> Result<T, RuntimeError> {
+        let cell = self
+            .cell(id)
+            .ok_or_else(|| RuntimeError::ActorNotFound(id.clone()))?;
+        {
+            let mut cell = cell.lock().expect("actor cell lock poisoned");
+            if cell.actor.is_some() {
+                return Ok(f(&mut cell));
+            }
+        }
+
+        let mut guard = self.idle_lock.lock().expect("idle lock poisoned");
+        self.idle_waiters.fetch_add(1, Ordering::SeqCst);
+        loop {
+            // Checked with `idle_lock` held: an actor going idle after this
+            // check can't be notified before we wait
+            let mut cell = cell.lock().expect("actor cell lock poisoned");
+            if cell.actor.is_some() {
+                self.idle_waiters.fetch_sub(1, Ordering::SeqCst);
+                drop(guard);
+                return Ok(f(&mut cell));
+            }
+            drop(cell);
+            guard = strand::wait_timeout(
+                &self.idle_lock,
+                guard,
+                &self.idle_signal,
+                Duration::from_millis(100),
+            )
+            .expect("idle lock poisoned");
+        }
+    }
+
+    /// Wake `with_idle_cell` callers after putting an actor back in its
+    /// cell (and unlocking it)
+    fn signal_idle(&self) {
+        if self.idle_waiters.load(Ordering::SeqCst) > 0 {
+            let _guard = self.idle_lock.lock().expect("idle lock poisoned");
+            self.idle_signal.notify_all();
+        }
+    }
+
+    /// Snapshot an actor's state now, rather than waiting for the snapshot
+    /// interval
+    ///
+    /// For business boundaries such as end of day or the end of a batch.
+    /// Waits for an in-flight message to finish first. Returns the
+    /// snapshot's sequence number.
+    pub fn snapshot_now(&self, id: &ActorId) -> Result<u64, RuntimeError> {
+        let saved = self.with_idle_actor(id, |actor| {
+            self.save_snapshot(id, &actor.state, actor.sequence)
+                .map(|()| actor.sequence)
+        })?;
+        Ok(saved?)
+    }
+
+    /// Snapshot every running actor at one consistent point and journal
+    /// the manifest (see `checkpoint`)
+    ///
+    /// Message handling pauses until it returns. Actors that don't
+    /// journal (shadows, memory-only tiers, journaling disabled) are left
+    /// out.
+    pub fn checkpoint_all(&self) -> Result<Checkpoint, RuntimeError> {
+        let _checkpointing = self
+            .checkpointing
+            .lock()
+            .expect("checkpointing lock poisoned");
+        self.quiesced.store(true, Ordering::SeqCst);
+        let checkpoint = self.checkpoint_quiesced();
+        self.quiesced.store(false, Ordering::SeqCst);
+        checkpoint
+    }
+
+    fn checkpoint_quiesced(&self) -> Result<Checkpoint, RuntimeError> {
+        let ids: Vec<ActorId> = self
+            .cells
+            .read()
+            .expect("cells read lock poisoned")
+            .keys()
+            .filter(|id| REGISTRY.is_running(id) && self.journals(id))
+            .cloned()
+            .collect();
+        let mut actors = vec![];
+        for id in ids {
+            let entry = self.with_idle_actor(&id, |actor| {
+                self.save_snapshot(&id, &actor.state, actor.sequence)?;
+                Ok::<_, std::io::Error>(CheckpointEntry {
+                    actor: id.clone(),
+                    behavior: actor.behavior.clone(),
+                    seq: actor.sequence,
+                    state: self.journal.put_checkpoint_state(&actor.state)?,
+                })
+            })?;
+            actors.push(entry?);
+        }
+        actors.sort_by_key(|entry| entry.actor.0);
+        // Snapshots are written directly; the events before them may not be
+        self.flush_journal()?;
+        let checkpoint = Checkpoint {
+            ts: now_millis(),
+            actors,
+        };
+        if self.config.journaling_enabled {
+            self.journal.record_checkpoint(&checkpoint)?;
+        }
+        Ok(checkpoint)
+    }
+
+    /// Roll every actor in `checkpoint` back to its checkpointed state
+    /// (see `checkpoint`)
+    ///
+    /// Message handling pauses until it returns. Actors in the manifest
+    /// that aren't running are spawned again first.
+    pub fn restore_checkpoint(&self, checkpoint: &Checkpoint) -> Result<(), RuntimeError> {
+        let _checkpointing = self
+            .checkpointing
+            .lock()
+            .expect("checkpointing lock poisoned");
+        self.quiesced.store(true, Ordering::SeqCst);
+        let restored = checkpoint
+            .actors
+            .iter()
+            .try_for_each(|entry| self.restore_entry(checkpoint, entry));
+        self.quiesced.store(false, Ordering::SeqCst);
+        restored
+    }
+
+    fn restore_entry(
+        &self,
+        checkpoint: &Checkpoint,
+        entry: &CheckpointEntry,
+    ) -> Result<(), RuntimeError> {
+        let id = &entry.actor;
+        let state = self
+            .journal
+            .get_checkpoint_state(&entry.state)?
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("checkpointed state of {} is missing", id),
+                )
+            })?;
+        if self.cell(id).is_none() {
+            self.spawn_with_id(id.clone(), &entry.behavior)?;
+        }
+        let restored = self.with_idle_actor(id, |actor| {
+            let seq = actor.next_sequence();
+            let payload = restored_payload(checkpoint, entry);
+            self.persist_event(id, &Event::new(seq, RESTORED_EVENT.to_string(), payload))?;
+            self.save_snapshot(id, &state, actor.sequence)?;
+            self.refresh_reference(id, &actor.state, &state);
+            actor.state = state;
+            Ok::<_, std::io::Error>(())
+        })?;
+        Ok(restored?)
+    }
+
+    /// Consistent copy of an actor's current state, without messaging it
+    ///
+    /// The copy is taken between message handlings (waiting for an
+    /// in-flight message to finish), so it never reflects half a turn. For
+    /// dashboards and monitoring that shouldn't enqueue query messages.
+    pub fn read_state(&self, id: &ActorId) -> Result<TypedValue, RuntimeError> {
+        self.with_idle_actor(id, |actor| actor.state.clone())
+    }
+
+    /// One top-level entry of an actor's Map state, read like `read_state`
+    ///
+    /// Copies only that entry, so reading a small part of a very large
+    /// state doesn't copy all of it. `None` if the state isn't a Map or has
+    /// no such key.
+    pub fn read_state_key(
+        &self,
+        id: &ActorId,
+        key: &TypedMapKey,
+    ) -> Result<Option<TypedValue>, RuntimeError> {
+        self.with_idle_actor(id, |actor| match &actor.state {
+            TypedValue::Map(entries) => entries.get(key).cloned(),
+            _ => None,
+        })
+    }
+
+    /// State, mailbox depth and bookkeeping of one actor, for a debug
+    /// console
+    ///
+    /// Taken between message handlings, like `read_state`.
+    pub fn dump_actor(&self, id: &ActorId) -> Result<ActorDump, RuntimeError> {
+        self.with_idle_cell(id, |cell| {
+            let actor = cell.actor.as_ref().expect("idle actor");
+            ActorDump {
+                id: id.clone(),
+                behavior: actor.behavior.clone(),
+                state: actor.state.clone(),
+                mailbox_depth: cell.inbox.len(),
+                seq: actor.sequence,
+                uptime: cell.spawned_at.elapsed(),
+                restarts: cell.restarts,
+            }
+        })
+    }
+
+    /// Copy of an actor's current state
+    ///
+    /// `None` if the actor is unknown or is handling a message right now.
+    pub(crate) fn peek_state(&self, id: &ActorId) -> Option<TypedValue> {
+        let cell = self.cell(id)?;
+        let cell = cell.lock().expect("actor cell lock poisoned");
+        cell.actor.as_ref().map(|actor| actor.state.clone())
+    }
+
+    /// Queue an envelope in an actor's inbox
+    ///
+    /// Messages over `max_message_size` are offloaded to disk first.
+    fn enqueue(&self, to: &ActorId, envelope: Envelope) -> Result<(), RuntimeError> {
+        self.enqueue_from(Origin::Runtime, to, envelope)
+    }
+
+    /// `enqueue` a message from `origin`
+    ///
+    /// A host send is traced once it is admitted, under the inbox lock so
+    /// the trace has it before the delivery that handles it.
+    fn enqueue_from(
+        &self,
+        origin: Origin,
+        requested: &ActorId,
+        mut envelope: Envelope,
+    ) -> Result<(), RuntimeError> {
+        if self.measures_messages() {
+            envelope.measure();
+        }
+        let to = &self.route(requested);
+        let cell = match self.cell(to) {
+            Some(cell) if REGISTRY.is_running(to) => cell,
+            found => {
+                Metrics::incr(&self.metrics.dead_letters);
+                self.observe(|o| o.on_dead_letter(to, envelope.size));
+                return Err(match found {
+                    Some(_) => RuntimeError::ActorStopped(to.clone()),
+                    None => RuntimeError::ActorNotFound(to.clone()),
+                });
+            }
+        };
+
+        if origin != Origin::Replayed {
+            if let Some(shedding) = self.shedding_for(to) {
+                if !shedding.accepts(&envelope) {
+                    Metrics::incr(&self.metrics.shed);
+                    self.drop_message(to, envelope, DropReason::Shed);
+                    return Err(RuntimeError::Overloaded(to.clone()));
+                }
+            }
+        }
+
+        let traced = match origin {
+            Origin::Host => self.traced_send(requested, &envelope),
+            _ => None,
+        };
+        self.offload_if_large(&mut envelope)?;
+
+        let size = envelope.size;
+        // Held from the trace write until the message is queued, so sends
+        // are queued in the order they were traced
+        let mut recorder = traced
+            .is_some()
+            .then(|| self.recorder.lock().expect("recorder lock poisoned"));
+        // The rate limit is checked last, and its token refunded if the
+        // trace write fails, so a message that isn't queued after all
+        // doesn't use up its quota
+        if origin != Origin::Replayed && !Self::take_token(&cell) {
+            drop(recorder);
+            Metrics::incr(&self.metrics.rate_limited);
+            self.drop_message(to, envelope, DropReason::RateLimited);
+            return Err(RuntimeError::RateLimited(to.clone()));
+        }
+        // Written with the cell unlocked, so a slow trace doesn't hold up
+        // the actor
+        let written = match (recorder.as_mut().and_then(|r| r.as_mut()), &traced) {
+            (Some(session), Some(record)) => session.record(record),
+            _ => Ok(()),
+        };
+        if let Err(e) = written {
+            drop(recorder);
+            Self::refund_tokens(&cell, 1);
+            self.drop_message(to, envelope, DropReason::TraceFailed);
+            return Err(e.into());
+        }
+        let (depth, mirrors) = {
+            let mut cell = cell.lock().expect("actor cell lock poisoned");
+            let mirrors = self.mirrored(to, &envelope);
+            cell.inbox.push_back(envelope);
+            (cell.inbox.len(), mirrors)
+        };
+        drop(recorder);
+        self.observe(|o| o.on_enqueue(to, size, depth));
+        for (shadow, envelope) in mirrors {
+            // A shadow's failures never reach the primary's senders
+            let _ = self.enqueue(&shadow, envelope);
+        }
+        Ok(())
+    }
+
+    /// Queue host sends in an actor's inbox as one batch, returning how
+    /// many the rate limit let through
+    ///
+    /// A failure to trace a send stops the batch there: what was traced
+    /// before is queued and counted, that send and the rest are dropped
+    /// (`DropReason::TraceFailed`) with their rate-limit tokens refunded,
+    /// and the error is returned only if nothing was queued.
+    fn enqueue_batch(
+        &self,
+        requested: &ActorId,
+        mut envelopes: Vec<Envelope>,
+    ) -> Result<usize, RuntimeError> {
+        if self.measures_messages() {
+            envelopes.iter_mut().for_each(Envelope::measure);
+        }
+        let to = &self.route(requested);
+        let cell = match self.cell(to) {
+            Some(cell) if REGISTRY.is_running(to) => cell,
+            found => {
+                for envelope in &envelopes {
+                    Metrics::incr(&self.metrics.dead_letters);
+                    self.observe(|o| o.on_dead_letter(to, envelope.size));
+                }
+                return Err(match found {
+                    Some(_) => RuntimeError::ActorStopped(to.clone()),
+                    None => RuntimeError::ActorNotFound(to.clone()),
+                });
+            }
+        };
+
+        let (mut envelopes, shed): (Vec<_>, Vec<_>) = match self.shedding_for(to) {
+            Some(shedding) => envelopes.into_iter().partition(|e| shedding.accepts(e)),
+            None => (envelopes, vec![]),
+        };
+        for envelope in shed {
+            Metrics::incr(&self.metrics.shed);
+            self.drop_message(to, envelope, DropReason::Shed);
+        }
+        let traced: Vec<_> = envelopes
+            .iter()
+            .map(|e| self.traced_send(requested, e))
+            .collect();
+        for envelope in &mut envelopes {
+            self.offload_if_large(envelope)?;
+        }
+
+        // Held from the trace writes until the batch is queued, as in
+        // `enqueue_from`
+        let mut recorder = traced
+            .iter()
+            .any(Option::is_some)
+            .then(|| self.recorder.lock().expect("recorder lock poisoned"));
+        let mut allowed = Vec::with_capacity(envelopes.len());
+        let mut limited = vec![];
+        {
+            let mut cell = cell.lock().expect("actor cell lock poisoned");
+            let now = Instant::now();
+            for (envelope, traced) in envelopes.into_iter().zip(traced) {
+                match &mut cell.rate_limit {
+                    Some(bucket) if !bucket.try_acquire(now) => limited.push(envelope),
+                    _ => allowed.push((envelope, traced)),
+                }
+            }
+        }
+
+        // Written with the cell unlocked, as in `enqueue_from`
+        let mut session = recorder.as_mut().and_then(|recorder| recorder.as_mut());
+        let mut allowed = allowed.into_iter();
+        let mut traced_ok = vec![];
+        let mut untraced = vec![];
+        let mut failed = None;
+        for (envelope, traced) in allowed.by_ref() {
+            if let (Some(session), Some(record)) = (session.as_deref_mut(), &traced) {
+                if let Err(e) = session.record(record) {
+                    failed = Some(RuntimeError::from(e));
+                    untraced.push(envelope);
+                    break;
+                }
+            }
+            traced_ok.push(envelope);
+        }
+        untraced.extend(allowed.map(|(envelope, _)| envelope));
+        Self::refund_tokens(&cell, untraced.len());
+
+        let mut queued = Vec::with_capacity(traced_ok.len());
+        let mut mirrors = vec![];
+        let depth = {
+            let mut cell = cell.lock().expect("actor cell lock poisoned");
+            for envelope in traced_ok {
+                queued.push(envelope.size);
+                mirrors.extend(self.mirrored(to, &envelope));
+                cell.inbox.push_back(envelope);
+            }
+            cell.inbox.len()
+        };
+        drop(recorder);
+
+        for envelope in limited {
+            Metrics::incr(&self.metrics.rate_limited);
+            self.drop_message(to, envelope, DropReason::RateLimited);
+        }
+        for envelope in untraced {
+            self.drop_message(to, envelope, DropReason::TraceFailed);
+        }
+        let first_depth = depth - queued.len();
+        for (i, size) in queued.iter().enumerate() {
+            self.observe(|o| o.on_enqueue(to, *size, first_depth + i + 1));
+        }
+        for (shadow, envelope) in mirrors {
+            let _ = self.enqueue(&shadow, envelope);
+        }
+        match failed {
+            Some(e) if queued.is_empty() => Err(e),
+            _ => Ok(queued.len()),
+        }
+    }
+
+    /// Take a token from the actor's rate limit for one message (true
+    /// without a limit)
+    fn take_token(cell: &Mutex<ActorCell>) -> bool {
+        cell.lock()
+            .expect("actor cell lock poisoned")
+            .rate_limit
+            .as_mut()
+            .is_none_or(|bucket| bucket.try_acquire(Instant::now()))
+    }
+
+    /// Give back the tokens taken for `count` messages that weren't
+    /// queued after all
+    fn refund_tokens(cell: &Mutex<ActorCell>, count: usize) {
+        if count == 0 {
+            return;
+        }
+        if let Some(bucket) = &mut cell.lock().expect("actor cell lock poisoned").rate_limit {
+            (0..count).for_each(|_| bucket.release());
+        }
+    }
+
+    /// Whether envelopes need their `size` measured: something reads it
+    fn measures_messages(&self) -> bool {
+        self.config.max_message_size.is_some()
+            || self
+                .config
+                .load_shedding
+                .as_ref()
+                .is_some_and(|shedding| shedding.max_queued_bytes.is_some())
+            || self.observer().is_some()
+    }
+
+    /// Write an envelope's message to disk if it is over
+    /// `max_message_size`
+    fn offload_if_large(&self, envelope: &mut Envelope) -> Result<(), RuntimeError> {
+        let Some(max) = self.config.max_message_size else {
+            return Ok(());
+        };
+        if let Payload::Inline(msg) = &envelope.payload {
+            if envelope.size > max {
+                let path = self.journal.offload_message(msg)?;
+                envelope.payload = Payload::Offloaded(Arc::new(OffloadedMessage(path)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Load shedding settings, if `to` is a low-priority actor and the
+    /// runtime is currently shedding
+    fn shedding_for(&self, to: &ActorId) -> Option<&LoadShedding> {
+        if !self.shedding.load(Ordering::Relaxed) {
+            return None;
+        }
+        let shedding = self.config.load_shedding.as_ref()?;
+        let behavior = REGISTRY.behavior_of(to)?;
+        shedding
+            .low_priority
+            .contains(&behavior)
+            .then_some(shedding)
+    }
+
+    /// Where messages for `to` go: `to` itself while it is running here,
+    /// otherwise whatever it is aliased to
+    fn route(&self, to: &ActorId) -> ActorId {
+        if self.cell(to).is_some() && REGISTRY.is_running(to) {
+            return to.clone();
+        }
+        self.journal.resolve_alias(to)
+    }
+
+    /// Discard a message that will never be handled
+    fn drop_message(&self, to: &ActorId, envelope: Envelope, reason: DropReason) {
+        Metrics::incr(&self.metrics.dropped);
+        if reason == DropReason::Expired {
+            Metrics::incr(&self.metrics.expired);
+        }
+        // Dropping an offloaded message removes its file
+        self.observe(|o| o.on_drop(to, envelope.size, reason));
+    }
+
+    /// Get an envelope's message, reading it back from disk if offloaded
+    fn rehydrate(&self, payload: Payload) -> std::io::Result<Arc<TypedValue>> {
+        match payload {
+            Payload::Inline(msg) => Ok(Arc::new(msg)),
+            Payload::Offloaded(file) => self.journal.read_offloaded(file.path()).map(Arc::new),
+            Payload::Shared(msg) => Ok(msg),
+        }
+    }
+
+    /// Handle the next queued message for an actor
+    ///
+    /// Returns `Ok(false)` if there was nothing to do (empty inbox, or the
+    /// actor is already handling a message). A behavior failure, or a
+    /// failure to journal its events, consumes the message and leaves the
+    /// actor's state unchanged.
+    pub fn process_next(&self, id: &ActorId) -> Result<bool, RuntimeError> {
+        let cell = self
+            .cell(id)
+            .ok_or_else(|| RuntimeError::ActorNotFound(id.clone()))?;
+
+        // Take the actor out of its cell so the behavior runs unlocked
+        let mut expired = vec![];
+        let (mut actor, mut envelope, state_limit, timeout) = {
+            let mut cell = cell.lock().expect("actor cell lock poisoned");
+            if cell.actor.is_none() {
+                return Ok(false);
+            }
+            if cell
+                .paused_until
+                .is_some_and(|until| Instant::now() < until)
+            {
+                return Ok(false);
+            }
+            if self.quiesced.load(Ordering::SeqCst) {
+                return Ok(false);
+            }
+            let now = now_millis();
+            let max_age = cell.max_message_age;
+            while cell
+                .inbox
+                .front()
+                .is_some_and(|e| e.is_expired(now, max_age))
+            {
+                expired.extend(cell.inbox.pop_front());
+            }
+            let Some(envelope) = cell.inbox.pop_front() else {
+                drop(cell);
+                for envelope in expired {
+                    self.drop_message(id, envelope, DropReason::Expired);
+                }
+                return Ok(false);
+            };
+            cell.handling_since = Some(Instant::now());
+            cell.handling_thread = Some(thread_label());
+            cell.blocked_reported = false;
+            if envelope.sender.as_ref() == Some(id) {
+                cell.self_send_chain += 1;
+            } else {
+                cell.self_send_chain = 0;
+            }
+            let chain = cell.self_send_chain;
+            if Some(chain) == self.config.self_send_loop_threshold {
+                Metrics::incr(&self.metrics.self_send_loops);
+                self.observe(|o| o.on_self_send_loop(id, chain));
+            }
+            let state_limit = cell.state_limit;
+            let timeout = cell.message_timeout;
+            (
+                cell.actor.take().expect("checked above"),
+                envelope,
+                state_limit,
+                timeout,
+            )
+        };
+        for envelope in expired {
+            self.drop_message(id, envelope, DropReason::Expired);
+        }
+        self.observe(|o| {
+            let waited = now_millis().saturating_sub(envelope.enqueued_at);
+            o.on_dequeue(id, envelope.size, Duration::from_millis(waited))
+        });
+
+        let reply_to = envelope.reply_to.take();
+        // Set if the behavior panicked, to the policy that applies
+        let mut panicked = None;
+        set_current_actor(id.clone());
+        let result = self
+            .record(TraceRecord::Deliver { to: id.clone() })
+            .and_then(|()| {
+                let behavior = self
+                    .behavior(&actor.behavior)
+                    .ok_or_else(|| RuntimeError::UnknownBehavior(actor.behavior.clone()))?;
+                let size = envelope.size;
+                let msg = self.rehydrate(envelope.payload)?;
+                let sequence = actor.sequence;
+                let deadline = timeout.map(|timeout| Instant::now() + timeout);
+                crate::timeout::arm(deadline);
+                let outcome = catch_panic(|| {
+                    let sender = envelope.sender.clone();
+                    handle_message_checked(behavior.as_ref(), &mut actor, &msg, sender, |events| {
+                        match timeout.filter(|_| crate::timeout::cancelled()) {
+                            Some(timeout) => Err(RuntimeError::TimedOut {
+                                actor: id.clone(),
+                                timeout,
+                            }),
+                            None => self.validate_events(id, events),
+                        }
+                    })
+                });
+                crate::timeout::arm(None);
+                let expired = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+                let handled = match outcome {
+                    // Includes a behavior that failed because it was cancelled
+                    Ok(Err(RuntimeError::Behavior(_) | RuntimeError::TimedOut { .. }))
+                        if expired =>
+                    {
+                        let timeout = timeout.expect("expired without a timeout");
+                        return Err(self.timed_out(&mut actor, timeout));
+                    }
+                    Ok(handled) => handled?,
+                    Err(panic) => {
+                        let error = self.behavior_panicked(&mut actor, &msg, panic);
+                        let policy = self.panic_policy_after(&cell, &actor.behavior);
+                        if policy == PanicPolicy::RestartActor {
+                            self.restart(&mut actor, behavior.as_ref())?;
+                        }
+                        panicked = Some(policy);
+                        return Err(error);
+                    }
+                };
+                let events = self.journaled_events(&mut actor, handled.events, &handled.previous);
+                if let Err(e) = self.persist_events(id, &events) {
+                    // Nothing was journaled, so nothing happened
+                    actor.state = handled.previous;
+                    actor.sequence = sequence;
+                    return Err(e.into());
+                }
+                self.refresh_reference(id, &handled.previous, &actor.state);
+                if handled.dead_letter {
+                    Metrics::incr(&self.metrics.dead_letters);
+                    self.observe(|o| o.on_dead_letter(id, size));
+                }
+                let answered = handled.reply.is_some() || handled.deferred.is_some();
+                if let (Some(reply_to), Some(value)) = (&reply_to, handled.reply) {
+                    reply_to.send(Ok(value));
+                }
+                if let (Some(reply_to), Some(token)) = (&reply_to, handled.deferred) {
+                    self.deferred_replies
+                        .lock()
+                        .expect("deferred replies lock poisoned")
+                        .insert(token, reply_to.clone());
+                }
+                if !self.is_shadow(id) {
+                    for (token, value) in handled.fulfilled {
+                        self.fulfill(&token, value);
+                    }
+                    // The asker waits on the first forward unless already answered
+                    let mut pending = reply_to.clone().filter(|_| !answered);
+                    for (to, msg) in handled.forwards {
+                        let forwarded = Envelope {
+                            expires_at: envelope.expires_at,
+                            reply_to: pending.take(),
+                            ..Envelope::new(envelope.sender.clone(), msg)
+                        };
+                        // Failures are reported as dead letters or drops by enqueue
+                        let _ = self.enqueue(&to, forwarded);
+                    }
+                }
+                Ok(handled
+                    .self_sends
+                    .into_iter()
+                    .map(|msg| Envelope::new(Some(id.clone()), msg))
+                    .collect::<Vec<_>>())
+            });
+        clear_current_actor();
+
+        let running = REGISTRY.is_running(id);
+        let size = state_limit
+            .filter(|_| result.is_ok())
+            .map(|_| state_size(&actor.state));
+        let over_limit = {
+            let mut cell = cell.lock().expect("actor cell lock poisoned");
+            cell.actor = Some(actor);
+            cell.last_processed = Some(now_millis());
+            let busy = cell
+                .handling_since
+                .take()
+                .map(|t| t.elapsed())
+                .unwrap_or_default();
+            cell.handling_thread = None;
+            let mut counters = self
+                .group_counters
+                .lock()
+                .expect("group counters lock poisoned");
+            let counters = counters.entry(cell.group.clone()).or_default();
+            counters.handled += 1;
+            counters.busy += busy;
+            cell.messages.handled += 1;
+            cell.messages.failed += result.is_err() as u64;
+            let under = |size: usize, limit: StateLimit| size <= limit.max_bytes;
+            let crossed = match (size, state_limit) {
+                (Some(size), Some(limit)) if !under(size, limit) => cell
+                    .state_size
+                    .is_none_or(|before| under(before, limit))
+                    .then_some((size, limit)),
+                _ => None,
+            };
+            cell.state_size = size.or(cell.state_size);
+            crossed
+        };
+        self.signal_idle();
+        if let Some((size, limit)) = over_limit {
+            Metrics::incr(&self.metrics.state_limit_exceeded);
+            self.observe(|o| o.on_state_limit(id, size, &limit));
+        }
+        let result = result.map(|self_sends| {
+            Metrics::incr(&self.metrics.messages_processed);
+            for envelope in self_sends {
+                if running {
+                    // Straight into our own inbox: no registry routing needed
+                    let mut cell = cell.lock().expect("actor cell lock poisoned");
+                    cell.inbox.push_back(envelope);
+                } else {
+                    Metrics::incr(&self.metrics.dead_letters);
+                    self.observe(|o| o.on_dead_letter(id, envelope.size));
+                }
+            }
+        });
+        if let Err(RuntimeError::Behavior(e)) = &result {
+            Metrics::incr(&self.metrics.behavior_failures);
+            if let Some(reply_to) = &reply_to {
+                reply_to.send(Err(RuntimeError::Behavior(e.clone())));
+            }
+        }
+        let failed = match &result {
+            Err(RuntimeError::EventRejected(e)) => Some(RuntimeError::EventRejected(e.clone())),
+            Err(RuntimeError::TimedOut { actor, timeout }) => Some(RuntimeError::TimedOut {
+                actor: actor.clone(),
+                timeout: *timeout,
+            }),
+            _ => None,
+        };
+        if let (Some(e), Some(reply_to)) = (failed, &reply_to) {
+            reply_to.send(Err(e));
+        }
+        let result = match (panicked, result) {
+            (Some(PanicPolicy::StopActor), Err(e)) => {
+                self.kill_with(id, StopReason::Error(e.to_string()));
+                Err(e)
+            }
+            (Some(PanicPolicy::EscalateToSupervisor), Err(RuntimeError::Behavior(e))) => {
+                self.kill_with(id, StopReason::Error(e.to_string()));
+                Err(RuntimeError::Escalated(id.clone(), e))
+            }
+            (Some(PanicPolicy::AbortProcess), _) => {
+                let _ = self.flush_journal();
+                self.observe(|o| o.on_abort(id));
+                std::process::abort();
+            }
+            (_, result) => result,
+        };
+        if !running {
+            self.try_terminate(id)?;
+        } else if over_limit.is_some_and(|(_, l)| l.action == StateLimitAction::Passivate) {
+            self.stop_actor_with(id, StopReason::Passivated);
+        }
+        result.map(|()| true)
+    }
+
+    /// What to journal for a handling that emitted `events`: the events
+    /// themselves, or for a delta behavior the change from `previous`
+    /// followed by the events `delta::keeps`
+    fn journaled_events(
+        &self,
+        actor: &mut Actor,
+        events: Vec<Event>,
+        previous: &TypedValue,
+    ) -> Vec<Event> {
+        if !self.config.delta_behaviors.contains(&actor.behavior) {
+            return events;
+        }
+        actor.sequence -= events.len() as u64;
+        let delta = delta::diff(previous, &actor.state).map(|delta| delta.to_event(0));
+        let kept = events.into_iter().filter(delta::keeps);
+        delta
+            .into_iter()
+            .chain(kept)
+            .map(|mut event| {
+                event.seq = actor.next_sequence();
+                event.actor_id = Some(actor.id.clone());
+                event
+            })
+            .collect()
+    }
+
+    /// Pin an actor to a pool, or unpin it with `None`
+    ///
+    /// Pinned actors are skipped by `run_until_idle` and only handled by
+    /// `run_pool_until_idle` for their pool (typically from a dedicated
+    /// `PoolWorker` thread), so latency-sensitive actors don't queue
+    /// behind bulk work.
+    pub fn pin(&self, id: &ActorId, pool: Option<&str>) -> Result<(), RuntimeError> {
+        self.reconfigure("pool", id, pool, |cell, pool| {
+            cell.pool = pool.map(str::to_string)
+        })
+    }
+
+    /// Pin actors spawned with `behavior` from now on to `pool`
+    pub fn pin_behavior(&self, behavior: &str, pool: &str) -> Result<(), RuntimeError> {
+        let mut pools = self
+            .behavior_pools
+            .write()
+            .expect("behavior pools write lock poisoned");
+        let target = ConfigTarget::Behavior(behavior.to_string());
+        self.record_config("pool", target, &pool)?;
+        pools.insert(behavior.to_string(), pool.to_string());
+        Ok(())
+    }
+
+    /// Pool an actor is pinned to
+    pub fn pool_of(&self, id: &ActorId) -> Option<String> {
+        let cell = self.cell(id)?;
+        let cell = cell.lock().expect("actor cell lock poisoned");
+        cell.pool.clone()
+    }
+
+    /// Put an actor in a scheduling group (see `fairness`)
+    pub fn set_group(&self, id: &ActorId, group: &str) -> Result<(), RuntimeError> {
+        self.reconfigure("group", id, group, |cell, group| {
+            cell.group = group.to_string()
+        })
+    }
+
+    /// Put actors spawned with `behavior` from now on in `group`
+    pub fn group_behavior(&self, behavior: &str, group: &str) -> Result<(), RuntimeError> {
+        let mut groups = self
+            .behavior_groups
+            .write()
+            .expect("behavior groups write lock poisoned");
+        let target = ConfigTarget::Behavior(behavior.to_string());
+        self.record_config("group", target, &group)?;
+        groups.insert(behavior.to_string(), group.to_string());
+        Ok(())
+    }
+
+    /// Scheduling group an actor belongs to
+    pub fn group_of(&self, id: &ActorId) -> Option<String> {
+        let cell = self.cell(id)?;
+        let cell = cell.lock().expect("actor cell lock poisoned");
+        Some(cell.group.clone())
+    }
+
+    fn group_weight(&self, group: &str) -> u32 {
+        let weight = self.config.group_weights.get(group).copied();
+        weight.unwrap_or(DEFAULT_WEIGHT).max(1)
+    }
+
+    /// Processing used by each scheduling group so far, by group name
+    pub fn group_usage(&self) -> Vec<GroupUsage> {
+        let counters = self
+            .group_counters
+            .lock()
+            .expect("group counters lock poisoned");
+        let total: Duration = counters.values().map(|c| c.busy).sum();
+        let mut usage: Vec<GroupUsage> = counters
+            .iter()
+            .map(|(group, counters)| GroupUsage {
+                group: group.clone(),
+                weight: self.group_weight(group),
+                handled: counters.handled,
+                busy: counters.busy,
+                utilization: match total.is_zero() {
+                    true => 0.0,
+                    false => counters.busy.as_secs_f64() / total.as_secs_f64(),
+                },
+            })
+            .collect();
+        usage.sort_by(|a, b| a.group.cmp(&b.group));
+        usage
+    }
+
+    /// Process messages until every unpinned actor's inbox is empty
+    ///
+    /// Scheduling groups share the loop by weight (see `fairness`).
+    /// Behavior failures are skipped (the failing message is consumed);
+    /// other errors stop the loop. Returns the number of messages handled.
+    pub fn run_until_idle(&self) -> Result<usize, RuntimeError> {
+        self.run_pool(None)
+    }
+
+    /// `run_until_idle` for the actors pinned to `pool`
+    pub fn run_pool_until_idle(&self, pool: &str) -> Result<usize, RuntimeError> {
+        self.run_pool(Some(pool))
+    }
+
+    fn run_pool(&self, pool: Option<&str>) -> Result<usize, RuntimeError> {
+        let mut handled = 0;
+        loop {
+            let mut groups = self.scheduling_groups(pool);
+
+            // Rounds over the same actors until none has work, then look
+            // again for actors spawned meanwhile
+            let mut progressed = false;
+            loop {
+                let mut round_progressed = false;
+                for group in &mut groups {
+                    let slice = self.run_slice(group)?;
+                    handled += slice.handled;
+                    round_progressed |= slice.progressed;
+                }
+                if !round_progressed {
+                    break;
+                }
+                progressed = true;
+            }
+
+            if !progressed {
+                return Ok(handled);
+            }
+        }
+    }
+
+    /// The actors of `pool`, by scheduling group
+    fn scheduling_groups(&self, pool: Option<&str>) -> Vec<SchedulingGroup> {
+        let mut groups: HashMap<String, Vec<ActorId>> = HashMap::new();
+        for (id, cell) in self.cells.read().expect("cells read lock poisoned").iter() {
+            let cell = cell.lock().expect("actor cell lock poisoned");
+            if cell.pool.as_deref() == pool {
+                groups
+                    .entry(cell.group.clone())
+                    .or_default()
+                    .push(id.clone());
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(group, ids)| SchedulingGroup {
+                weight: self.group_weight(&group),
+                ids,
+                next: 0,
+            })
+            .collect()
+    }
+
+    /// Let a group handle up to its weight in messages, round-robin
+    /// between its actors from where its last slice stopped
+    fn run_slice(&self, group: &mut SchedulingGroup) -> Result<Slice, RuntimeError> {
+        let mut slice = Slice::default();
+        let mut budget = group.weight;
+        // Actors tried since one last had work
+        let mut idle = 0;
+        while budget > 0 && idle < group.ids.len() {
+            let id = &group.ids[group.next];
+            group.next = (group.next + 1) % group.ids.len();
+            match self.process_next(id) {
+                Ok(true) => slice.handled += 1,
+                Ok(false) | Err(RuntimeError::ActorNotFound(_)) => {
+                    idle += 1;
+                    continue;
+                }
+                Err(e) if e.is_message_failure() => {}
+                Err(e) => return Err(e),
+            }
+            slice.progressed = true;
+            budget -= 1;
+            idle = 0;
+        }
+        Ok(slice)
+    }
+
+    /// Set the observer notified of runtime events
+    pub fn set_observer(&self, observer: impl RuntimeObserver + 'static) {
+        *self.observer.write().expect("observer lock poisoned") = Some(Arc::new(observer));
+    }
+
+    /// Get the current observer, if any
+    fn observer(&self) -> Option<Arc<dyn RuntimeObserver>> {
+        self.observer
+            .read()
+            .expect("observer lock poisoned")
+            .clone()
+    }
+
+    /// Run a callback against the observer, if one is set
+    fn observe(&self, f: impl FnOnce(&dyn RuntimeObserver)) {
+        if let Some(observer) = self.observer() {
+            f(observer.as_ref());
+        }
+    }
+
+    /// The configuration the runtime was created with
+    pub fn config(&self) -> &RuntimeConfig {
+        &self.config
+    }
+
+    /// Point-in-time copy of the runtime counters
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Status of every actor's coroutine, sorted by name
+    ///
+    /// For scheduler dumps and diagnostics. Until actors run as May
+    /// coroutines, `Running` reports the OS thread handling the message.
+    pub fn dump_coroutines(&self) -> Vec<CoroutineInfo> {
+        let cells: Vec<(ActorId, Arc<Mutex<ActorCell>>)> = self
+            .cells
+            .read()
+            .expect("cells read lock poisoned")
+            .iter()
+            .map(|(id, cell)| (id.clone(), cell.clone()))
+            .collect();
+
+        let mut dump: Vec<CoroutineInfo> = cells
+            .into_iter()
+            .map(|(id, cell)| {
+                let cell = cell.lock().expect("actor cell lock poisoned");
+                let behavior = match &cell.actor {
+                    Some(actor) => actor.behavior.clone(),
+                    None => REGISTRY.behavior_of(&id).unwrap_or_default(),
+                };
+                let status = match (&cell.handling_since, &cell.handling_thread) {
+                    _ if cell.terminated => CoroutineStatus::Terminated,
+                    (Some(since), Some(thread)) => CoroutineStatus::Running {
+                        thread: thread.clone(),
+                        elapsed: since.elapsed(),
+                    },
+                    _ if !REGISTRY.is_running(&id) => CoroutineStatus::Stopping,
+                    _ if cell.inbox.is_empty() => CoroutineStatus::Idle,
+                    _ => CoroutineStatus::Ready,
+                };
+                CoroutineInfo {
+                    name: coroutine_name(&id, &behavior),
+                    id,
+                    behavior,
+                    status,
+                    queued: cell.inbox.len(),
+                    pool: cell.pool.clone(),
+                }
+            })
+            .collect();
+        dump.sort_by(|a, b| a.name.cmp(&b.name));
+        dump
+    }
+
+    /// Find message handlings running longer than `threshold`
+    ///
+    /// Each newly detected handling is counted in `blocked_detected` and
+    /// reported to the observer once; every blocked handling is returned.
+    pub fn check_blocked(&self, threshold: Duration) -> Vec<BlockedActor> {
+        let cells: Vec<(ActorId, Arc<Mutex<ActorCell>>)> = self
+            .cells
+            .read()
+            .expect("cells read lock poisoned")
+            .iter()
+            .map(|(id, cell)| (id.clone(), cell.clone()))
+            .collect();
+
+        let mut blocked = vec![];
+        for (id, cell) in cells {
+            let newly_blocked = {
+                let mut cell = cell.lock().expect("actor cell lock poisoned");
+                let Some(elapsed) = cell.handling_since.map(|t| t.elapsed()) else {
+                    continue;
+                };
+                if elapsed <= threshold {
+                    continue;
+                }
+                let newly_blocked = !cell.blocked_reported;
+                cell.blocked_reported = true;
+                blocked.push(BlockedActor {
+                    id: id.clone(),
+                    behavior: REGISTRY.behavior_of(&id).unwrap_or_default(),
+                    elapsed,
+                });
+                newly_blocked
+            };
+
+            if newly_blocked {
+                Metrics::incr(&self.metrics.blocked_detected);
+                if let (Some(observer), Some(b)) = (self.observer(), blocked.last()) {
+                    observer.on_blocked(&b.id, &b.behavior, b.elapsed);
+                }
+            }
+        }
+
+        blocked
+    }
+
+    /// Measure what is queued across all inboxes, and start or stop load
+    /// shedding accordingly (see `RuntimeConfig::load_shedding`)
+    ///
+    /// Called periodically by a `PressureMonitor`.
+    pub fn check_pressure(&self) -> Pressure {
+        let cells: Vec<Arc<Mutex<ActorCell>>> = self
+            .cells
+            .read()
+            .expect("cells read lock poisoned")
+            .values()
+            .cloned()
+            .collect();
+
+        let mut pressure = Pressure::default();
+        for cell in cells {
+            let cell = cell.lock().expect("actor cell lock poisoned");
+            pressure.queued += cell.inbox.len();
+            pressure.queued_bytes += cell.inbox.iter().map(|e| e.size).sum::<usize>();
+        }
+
+        let Some(shedding) = &self.config.load_shedding else {
+            return pressure;
+        };
+        pressure.shedding = shedding.exceeded(&pressure);
+        if self.shedding.swap(pressure.shedding, Ordering::Relaxed) != pressure.shedding {
+            self.observe(|o| o.on_load_shedding(&pressure));
+        }
+        pressure
+    }
+
+    /// Messages an actor has handled since it was spawned (`None` if it is
+    /// unknown)
+    pub fn message_counts(&self, id: &ActorId) -> Option<MessageCounts> {
+        let cell = self.cell(id)?;
+        let cell = cell.lock().expect("actor cell lock poisoned");
+        Some(cell.messages)
+    }
+
+    /// Drop expired messages from every inbox (see `expiry`), returning
+    /// how many were dropped
+    pub fn sweep_expired(&self) -> usize {
+        let cells: Vec<(ActorId, Arc<Mutex<ActorCell>>)> = self
+            .cells
+            .read()
+            .expect("cells read lock poisoned")
+            .iter()
+            .map(|(id, cell)| (id.clone(), cell.clone()))
+            .collect();
+
+        let now = now_millis();
+        let mut swept = 0;
+        for (id, cell) in cells {
+            let expired: VecDeque<Envelope> = {
+                let mut cell = cell.lock().expect("actor cell lock poisoned");
+                let max_age = cell.max_message_age;
+                let (expired, kept) = std::mem::take(&mut cell.inbox)
+                    .into_iter()
+                    .partition(|e| e.is_expired(now, max_age));
+                cell.inbox = kept;
+                expired
+            };
+            swept += expired.len();
+            for envelope in expired {
+                self.drop_message(&id, envelope, DropReason::Expired);
+            }
+        }
+        swept
+    }
+
+    /// Report actor liveness, stuck actors, and journal storage status
+    pub fn health(&self) -> HealthReport {
+        let now = now_millis();
+        let threshold = self.config.stuck_actor_threshold.as_millis() as u64;
+
+        let cells: Vec<(ActorId, Arc<Mutex<ActorCell>>)> = self
+            .cells
+            .read()
+            .expect("cells read lock poisoned")
+            .iter()
+            .map(|(id, cell)| (id.clone(), cell.clone()))
+            .collect();
+
+        let mut actors: Vec<ActorHealth> = cells
+            .into_iter()
+            .map(|(id, cell)| {
+                let running = REGISTRY.is_running(&id);
+                let behavior = REGISTRY.behavior_of(&id).unwrap_or_default();
+                let cell = cell.lock().expect("actor cell lock poisoned");
+
+                // Progress is measured from whichever is later: the last
+                // handled message or the arrival of the oldest queued one
+                let stuck = cell.inbox.front().is_some_and(|oldest| {
+                    let since = cell.last_processed.unwrap_or(0).max(oldest.enqueued_at);
+                    now.saturating_sub(since) > threshold
+                });
+
+                ActorHealth {
+                    id,
+                    behavior,
+                    running,
+                    mailbox_depth: cell.inbox.len(),
+                    last_processed: cell.last_processed,
+                    stuck,
+                    state_size: cell.state_size,
+                    messages: cell.messages,
+                }
+            })
+            .collect();
+        actors.sort_by_key(|a| a.id.0);
+
+        let journal = match self.journal.check_writable() {
+            Ok(()) => JournalHealth {
+                writable: true,
+                error: None,
+            },
+            Err(e) => JournalHealth {
+                writable: false,
+                error: Some(e.to_string()),
+            },
+        };
+
+        HealthReport {
+            ts: now,
+            actors,
+            journal,
+        }
+    }
+
+    /// Start recording spawns, sends, stops, and deliveries to a trace file
+    ///
+    /// Replaces any recording already in progress.
+    pub fn start_recording(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let recorder = SessionRecorder::create(path)?;
+        *self.recorder.lock().expect("recorder lock poisoned") = Some(recorder);
+        Ok(())
+    }
+
+    /// Stop recording and flush the trace file
+    pub fn stop_recording(&self) -> std::io::Result<()> {
+        match self.recorder.lock().expect("recorder lock poisoned").take() {
+            Some(mut recorder) => recorder.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Append to the session trace if recording
+    fn record(&self, record: TraceRecord) -> Result<(), RuntimeError> {
+        if let Some(recorder) = self
+            .recorder
+            .lock()
+            .expect("recorder lock poisoned")
+            .as_mut()
+        {
+            recorder.record(&record)?;
+        }
+        Ok(())
+    }
+
+    /// Trace record of a host send, copying the message only while a
+    /// session is recorded (and before it is offloaded)
+    fn traced_send(&self, to: &ActorId, envelope: &Envelope) -> Option<TraceRecord> {
+        if self
+            .recorder
+            .lock()
+            .expect("recorder lock poisoned")
+            .is_none()
+        {
+            return None;
+        }
+        let msg = match &envelope.payload {
+            Payload::Inline(msg) => msg.clone(),
+            Payload::Shared(msg) => msg.as_ref().clone(),
+            Payload::Offloaded(_) => unreachable!("host sends are traced before offloading"),
+        };
+        Some(TraceRecord::Send {
+            to: to.clone(),
+            msg,
+        })
+    }
+
+    /// Recover actor state from journal
+    ///
+    /// Returns (state, next_sequence) or None if no persisted state.
+    /// Without a behavior to fold them, events after the snapshot only
+    /// advance the sequence (state deltas are applied either way); `spawn`
+    /// recovers through the behavior's `apply_event` instead.
+    pub fn recover_state(&self, id: &ActorId) -> std::io::Result<Option<(TypedValue, u64)>> {
+        self.recover(id, None)
+    }
+
+    /// Load the latest snapshot and fold the events after it
+    fn recover(
+        &self,
+        id: &ActorId,
+        behavior: Option<&dyn Behavior>,
+    ) -> std::io::Result<Option<(TypedValue, u64)>> {
+        let Some((journal, _)) = self.storage(id) else {
+            return Ok(None);
+        };
+        let preloaded = match self.tier_of(id) {
+            StorageTier::Default => self.preloaded.take(id),
+            _ => None,
+        };
+        let (snapshot, events) = match preloaded {
+            Some(preloaded) => (preloaded.snapshot, preloaded.events),
+            None => {
+                self.flush_journal()?;
+                let snapshot = journal.load_snapshot(id)?;
+                let from_seq = snapshot.as_ref().map(|s| s.seq).unwrap_or(0);
+                let events: Vec<Event> = journal
+                    .read_events(id)?
+                    .into_iter()
+                    .filter(|e| e.seq >= from_seq)
+                    .collect();
+                (snapshot, events)
+            }
+        };
+        let from_seq = snapshot.as_ref().map(|s| s.seq).unwrap_or(0);
+        let next_seq = events.last().map(|e| e.seq + 1).unwrap_or(from_seq);
+        let events = apply_corrections(events);
+
+        let mut state = match snapshot {
+            Some(snapshot) => snapshot.state,
+            None if events.is_empty() => return Ok(None),
+            None => behavior
+                .map(|b| b.initial_state())
+                .unwrap_or_else(|| TypedValue::Map(std::collections::BTreeMap::new())),
+        };
+        for event in &events {
+            state = delta::fold(&state, event, |state, event| match behavior {
+                Some(behavior) => behavior.apply_event(state, event),
+                None => state.clone(),
+            });
+        }
+        Ok(Some((state, next_seq)))
+    }
+
+    /// Step through an actor's journaled events with `behavior`'s reducer
+    pub fn replay_session(
+        &self,
+        id: &ActorId,
+        behavior: &str,
+    ) -> Result<ReplaySession, RuntimeError> {
+        let handler = self
+            .behavior(behavior)
+            .ok_or_else(|| RuntimeError::UnknownBehavior(behavior.to_string()))?;
+        self.flush_journal()?;
+        let journal = self
+            .storage(id)
+            .map_or(&self.journal, |(journal, _)| journal);
+        Ok(ReplaySession::from_journal(journal, id, handler)?)
+    }
+
+    /// Journal a handling that ran past its timeout, returning the error
+    /// that fails the message
+    ///
+    /// The actor keeps its state from before the message.
+    fn timed_out(&self, actor: &mut Actor, timeout: Duration) -> RuntimeError {
+        Metrics::incr(&self.metrics.timed_out);
+        let mut event = Event::new(
+            actor.next_sequence(),
+            TIMED_OUT_EVENT.to_string(),
+            timed_out_payload(timeout),
+        );
+        event.actor_id = Some(actor.id.clone());
+        match self.persist_event(&actor.id, &event) {
+            Ok(()) => RuntimeError::TimedOut {
+                actor: actor.id.clone(),
+                timeout,
+            },
+            Err(e) => e.into(),
+        }
+    }
+
+    /// Dump and journal a behavior panic, returning the error that fails
+    /// the message
+    ///
+    /// The actor keeps its state from before the message.
+    fn behavior_panicked(&self, actor: &mut Actor, msg: &TypedValue, panic: Panic) -> RuntimeError {
+        self.observe(|o| o.on_panic(&actor.id, &actor.behavior, &panic.message));
+        let error = format!("panicked: {}", panic.message);
+        let journaled = self.write_crash_dump(actor, msg, panic).and_then(|path| {
+            let mut failure = Event::new(
+                actor.next_sequence(),
+                FAILURE_EVENT.to_string(),
+                failure_payload(&error, path.as_deref()),
+            );
+            failure.actor_id = Some(actor.id.clone());
+            self.persist_event(&actor.id, &failure)
+        });
+        self.publish_system(&SystemEvent::ActorCrashed {
+            actor: actor.id.clone(),
+            error: error.clone(),
+        });
+        match journaled {
+            Ok(()) => RuntimeError::Behavior(BehaviorError::new(error)),
+            Err(e) => e.into(),
+        }
+    }
+
+    /// Rebuild an actor's state from its journal after a panic
+    ///
+    /// The sequence number carries on, so no event is overwritten.
+    fn restart(&self, actor: &mut Actor, behavior: &dyn Behavior) -> Result<(), RuntimeError> {
+        actor.state = match self.recover(&actor.id, Some(behavior))? {
+            Some((state, _)) => state,
+            None => behavior.initial_state(),
+        };
+        Ok(())
+    }
+
+    /// Supervision settings for a behavior, if configured
+    fn supervision(&self, behavior: &str) -> Option<&Supervision> {
+        self.config.supervision.get(behavior)
+    }
+
+    /// The policy that applies to an actor whose behavior just panicked
+    ///
+    /// A restart past the behavior's `max_restarts` becomes a stop; an
+    /// allowed one counts against it and starts the backoff pause.
+    fn panic_policy_after(&self, cell: &Mutex<ActorCell>, behavior: &str) -> PanicPolicy {
+        let mut cell = cell.lock().expect("actor cell lock poisoned");
+        let policy = cell.panic_policy.unwrap_or(self.config.panic_policy);
+        if policy != PanicPolicy::RestartActor {
+            return policy;
+        }
+        let supervision = self.supervision(behavior);
+        if let Some(max) = supervision.and_then(|s| s.max_restarts) {
+            if cell.restarts >= max {
+                return PanicPolicy::StopActor;
+            }
+        }
+        cell.restarts += 1;
+        let restarts = cell.restarts;
+        cell.paused_until = supervision
+            .and_then(|s| s.backoff)
+            .map(|backoff| Instant::now() + backoff.delay(restarts));
+        policy
+    }
+
+    /// Whether an actor's events and snapshots are journaled
+    fn journals(&self, id: &ActorId) -> bool {
+        if self.is_shadow(id) || self.storage(id).is_none() {
+            return false;
+        }
+        if self.config.supervision.is_empty() {
+            return self.config.journaling_enabled;
+        }
+        REGISTRY
+            .behavior_of(id)
+            .and_then(|behavior| self.supervision(&behavior)?.journaling)
+            .unwrap_or(self.config.journaling_enabled)
+    }
+
+    /// Write a crash dump for a panic (None if journaling is disabled or
+    /// in memory)
+    fn write_crash_dump(
+        &self,
+        actor: &Actor,
+        msg: &TypedValue,
+        panic: Panic,
+    ) -> std::io::Result<Option<PathBuf>> {
+        // In-memory journals have nowhere to put a dump
+        let storage = self
+            .storage(&actor.id)
+            .filter(|(journal, _)| !journal.is_in_memory());
+        let Some((journal, _)) = storage.filter(|_| self.journals(&actor.id)) else {
+            return Ok(None);
+        };
+        self.flush_journal()?;
+        let mut events = journal.read_events(&actor.id)?;
+        let skipped = events.len().saturating_sub(self.config.crash_dump_events);
+        events.drain(..skipped);
+
+        let dump = CrashDump {
+            actor_id: actor.id.clone(),
+            behavior: actor.behavior.clone(),
+            ts: now_millis(),
+            panic: panic.message,
+            state: actor.state.clone(),
+            message: msg.clone(),
+            events,
+            backtrace: panic.backtrace,
+        };
+        journal.write_crash_dump(&dump).map(Some)
+    }
+
+    /// Persist an event to the journal
+    ///
+    /// Appends go through the journal writer thread; this waits as long as
+    /// the configured `durability` requires.
+    pub fn persist_event(&self, id: &ActorId, event: &Event) -> std::io::Result<()> {
+        self.persist_events(id, std::slice::from_ref(event))
+    }
+
+    /// Persist events to the journal in a single write
+    pub fn persist_events(&self, id: &ActorId, events: &[Event]) -> std::io::Result<()> {
+        self.preloaded.invalidate(id);
+        if !events.is_empty() && self.is_shadow(id) {
+            return self.shadow_journal.append_all(id, events);
+        }
+        let storage = self
+            .storage(id)
+            .filter(|_| !events.is_empty() && self.journals(id));
+        if let Some((_, writer)) = storage {
+            let appended = writer.append_all(id, events, self.config.durability);
+            self.check_disk_pressure(&appended);
+            appended?;
+        }
+        Ok(())
+    }
+
+    /// Publish `DiskPressure` if a journal write was refused by the quota
+    fn check_disk_pressure(&self, result: &std::io::Result<()>) {
+        let Err(e) = result else {
+            return;
+        };
+        if let Some(exceeded) = quota::exceeded(e) {
+            self.publish_system(&SystemEvent::DiskPressure {
+                actor: exceeded.actor_id.clone(),
+                limit: exceeded.limit,
+                used: exceeded.used,
+            });
+        }
+    }
+
+    /// Wait for queued journal appends to be written
+    ///
+    /// Returns the first error from an asynchronous append since the last
+    /// flush.
+    pub fn flush_journal(&self) -> std::io::Result<()> {
+        let flushed = self
+            .tier_journals
+            .values()
+            .fold(self.writer.flush(), |flushed, tier| {
+                flushed.and(tier.writer.flush())
+            });
+        self.check_disk_pressure(&flushed);
+        flushed
+    }
+
+    /// Save a snapshot
+    pub fn save_snapshot(&self, id: &ActorId, state: &TypedValue, seq: u64) -> std::io::Result<()> {
+        self.preloaded.invalidate(id);
+        if let Some((journal, _)) = self.storage(id).filter(|_| self.journals(id)) {
+            let snapshot = Snapshot {
+                actor_id: Some(id.clone()),
+                seq,
+                state: state.clone(),
+                ts: now_millis(),
+            };
+            journal.save_snapshot(id, &snapshot)?;
+        }
+        Ok(())
+    }
+}
+
+// Thread-local storage for current actor context
+thread_local! {
+    static CURRENT_ACTOR_ID: std::cell::RefCell<Option<ActorId>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Set the current actor ID (called when entering actor coroutine)
+pub fn set_current_actor(id: ActorId) {
+    CURRENT_ACTOR_ID.with(|cell| {
+        *cell.borrow_mut() = Some(id);
+    });
+}
+
+/// Get the current actor ID (for actor-self builtin)
+pub fn get_current_actor() -> Option<ActorId> {
+    CURRENT_ACTOR_ID.with(|cell| cell.borrow().clone())
+}
+
+/// Clear the current actor ID (called when exiting actor coroutine)
+pub fn clear_current_actor() {
+    CURRENT_ACTOR_ID.with(|cell| {
+        *cell.borrow_mut() = None;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_registry_operations() {
+        let id = ActorId::new();
+        let mailbox = Mailbox::new(42);
+
+        REGISTRY.register(id.clone(), mailbox, "test-behavior".to_string());
+
+        assert!(REGISTRY.is_running(&id));
+        assert_eq!(REGISTRY.get_mailbox(&id).unwrap().channel_id(), 42);
+
+        REGISTRY.mark_stopped(&id);
+        assert!(!REGISTRY.is_running(&id));
+
+        REGISTRY.unregister(&id);
+        assert!(REGISTRY.get_mailbox(&id).is_none());
+    }
+
+    #[test]
+    fn test_handle_interning() {
+        let id = ActorId::new();
+        let handle = REGISTRY.register(id.clone(), Mailbox::new(1), "test".to_string());
+
+        assert_ne!(handle.as_raw(), 0);
+        assert_eq!(REGISTRY.handle_of(&id), Some(handle));
+        assert_eq!(REGISTRY.resolve(handle), Some(id.clone()));
+
+        // Re-registering keeps the same handle
+        let again = REGISTRY.register(id.clone(), Mailbox::new(2), "test".to_string());
+        assert_eq!(again, handle);
+
+        REGISTRY.unregister(&id);
+        assert!(REGISTRY.resolve(handle).is_none());
+        assert!(REGISTRY.handle_of(&id).is_none());
+    }
+
+    #[test]
+    fn test_registry_sharded_concurrent_access() {
+        let registry = std::sync::Arc::new(ActorRegistry::new());
+
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let registry = registry.clone();
+                std::thread::spawn(move || {
+                    let ids: Vec<ActorId> = (0..500).map(|_| ActorId::new()).collect();
+                    for id in &ids {
+                        registry.register(id.clone(), Mailbox::new(t), "test".to_string());
+                    }
+                    for id in &ids {
+                        assert_eq!(registry.get_mailbox(id).unwrap().channel_id(), t);
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let sizes: Vec<usize> = registry
+            .shards
+            .iter()
+            .map(|s| s.read().unwrap().len())
+            .collect();
+        assert_eq!(sizes.iter().sum::<usize>(), 8 * 500);
+        // Random IDs should spread across more than one shard
+        assert!(sizes.iter().filter(|&&n| n > 0).count() > 1);
+    }
+
+    #[test]
+    fn test_runtime_creation() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            journaling_enabled: true,
+            snapshot_interval: 100,
+            ..RuntimeConfig::default()
+        };
+
+        let runtime = ActorRuntime::new(config);
+        assert!(runtime.config.journaling_enabled);
+    }
+
+    #[test]
+    fn test_current_actor_thread_local() {
+        let id = ActorId::new();
+
+        assert!(get_current_actor().is_none());
+
+        set_current_actor(id.clone());
+        assert_eq!(get_current_actor().unwrap(), id);
+
+        clear_current_actor();
+        assert!(get_current_actor().is_none());
+    }
+
+    #[test]
+    fn test_recover_empty_state() {
+        let temp_dir = TempDir::new().unwrap();
         let config = RuntimeConfig {
             journal_path: temp_dir.path().to_path_buf(),
-            journaling_enabled: true,
-            snapshot_interval: 100,
+            journaling_enabled: true,
+            snapshot_interval: 100,
+            ..RuntimeConfig::default()
+        };
+
+        let runtime = ActorRuntime::new(config);
+        let id = ActorId::new();
+
+        // No persisted state for new actor
+        let result = runtime.recover_state(&id).unwrap();
+        assert!(result.is_none());
+    }
+
+    fn test_runtime(temp_dir: &TempDir) -> ActorRuntime {
+        ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            journaling_enabled: true,
+            snapshot_interval: 100,
+            ..RuntimeConfig::default()
+        })
+    }
+
+    /// Counter behavior: state is an Int, every Int message is added to it
+    fn counter(
+        ctx: &mut crate::behavior::BehaviorContext,
+        state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, crate::behavior::BehaviorError> {
+        let total = match state {
+            TypedValue::Int(n) => *n,
+            _ => 0,
+        };
+        match msg {
+            TypedValue::Int(n) => {
+                ctx.emit("Added", msg.clone());
+                Ok(TypedValue::Int(total + n))
+            }
+            _ => Err(crate::behavior::BehaviorError::new("expected Int")),
+        }
+    }
+
+    /// Echo behavior: the last message becomes the state
+    fn echo(
+        _ctx: &mut crate::behavior::BehaviorContext,
+        _state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, crate::behavior::BehaviorError> {
+        Ok(msg.clone())
+    }
+
+    fn state_of(runtime: &ActorRuntime, id: &ActorId) -> TypedValue {
+        let cell = runtime.cell(id).unwrap();
+        let cell = cell.lock().unwrap();
+        cell.actor.as_ref().unwrap().state.clone()
+    }
+
+    #[test]
+    fn test_spawn_send_and_process() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+
+        let id = runtime.spawn("counter").unwrap();
+        assert!(runtime.is_running(&id));
+        assert!(runtime.get_mailbox(&id).unwrap().is_local());
+
+        runtime.send(&id, TypedValue::Int(2)).unwrap();
+        runtime.send(&id, TypedValue::Bool(true)).unwrap();
+        runtime.send(&id, TypedValue::Int(3)).unwrap();
+
+        assert_eq!(runtime.run_until_idle().unwrap(), 2);
+        assert!(!runtime.process_next(&id).unwrap());
+
+        assert_eq!(state_of(&runtime, &id), TypedValue::Int(5));
+        assert_eq!(runtime.journal().read_events(&id).unwrap().len(), 2);
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_large_message_offloaded_and_rehydrated() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            max_message_size: Some(64),
+            ..RuntimeConfig::default()
+        });
+        runtime.register_behavior("echo", echo);
+
+        let id = runtime.spawn("echo").unwrap();
+        let big = TypedValue::String("x".repeat(1024));
+        runtime.send(&id, TypedValue::Int(1)).unwrap();
+        runtime.send(&id, big.clone()).unwrap();
+
+        let offloaded_path = {
+            let cell = runtime.cell(&id).unwrap();
+            let cell = cell.lock().unwrap();
+            assert!(matches!(cell.inbox[0].payload, Payload::Inline(_)));
+            match &cell.inbox[1].payload {
+                Payload::Offloaded(file) => file.path().to_path_buf(),
+                _ => panic!("Expected offloaded payload"),
+            }
+        };
+        assert!(offloaded_path.exists());
+
+        runtime.run_until_idle().unwrap();
+        assert_eq!(state_of(&runtime, &id), big);
+        assert!(!offloaded_path.exists());
+
+        // Unhandled and copied to a shadow, then dropped with the actor
+        let shadow = runtime.spawn_shadow(&id, "echo").unwrap();
+        runtime.send(&id, big.clone()).unwrap();
+        let copies = [&id, &shadow].map(|actor| {
+            let cell = runtime.cell(actor).unwrap();
+            let cell = cell.lock().unwrap();
+            match &cell.inbox[0].payload {
+                Payload::Offloaded(file) => file.path().to_path_buf(),
+                _ => panic!("Expected offloaded payload"),
+            }
+        });
+        assert!(copies.iter().all(|path| path.exists()));
+        runtime.process_next(&shadow).unwrap();
+        assert_eq!(state_of(&runtime, &shadow), big);
+        runtime.unregister_actor(&shadow);
+        runtime.unregister_actor(&id);
+        assert!(copies.iter().all(|path| !path.exists()));
+    }
+
+    /// Counter that journals a final "Stopped" event with its total
+    struct StopReporting;
+
+    impl Behavior for StopReporting {
+        fn handle(
+            &self,
+            ctx: &mut crate::behavior::BehaviorContext,
+            state: &TypedValue,
+            msg: &TypedValue,
+        ) -> Result<TypedValue, crate::behavior::BehaviorError> {
+            counter(ctx, state, msg)
+        }
+
+        fn on_stop(&self, ctx: &mut crate::behavior::BehaviorContext, state: &TypedValue) {
+            ctx.emit("Stopped", state.clone());
+        }
+    }
+
+    #[test]
+    fn test_wait_for_stop_drains_and_snapshots() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", StopReporting);
+
+        let id = runtime.spawn("counter").unwrap();
+        runtime.send(&id, TypedValue::Int(4)).unwrap();
+        runtime.send(&id, TypedValue::Int(6)).unwrap();
+
+        // Not stopped yet: waiting times out and leaves queued work alone
+        assert!(!runtime
+            .wait_for_stop(&id, Duration::from_millis(20))
+            .unwrap());
+        assert_eq!(runtime.dump_actor(&id).unwrap().mailbox_depth, 2);
+        runtime.send(&id, TypedValue::Int(1)).unwrap();
+
+        runtime.stop_actor(&id);
+        assert!(!runtime.is_terminated(&id));
+        assert!(runtime.wait_for_stop(&id, Duration::from_secs(1)).unwrap());
+        assert!(runtime.is_terminated(&id));
+
+        let events = runtime.journal().read_events(&id).unwrap();
+        let [.., hook, last] = events.as_slice() else {
+            panic!("expected stop events");
+        };
+        assert_eq!(hook.event_type, "Stopped");
+        assert_eq!(hook.payload, TypedValue::Int(11));
+        assert_eq!(last.event_type, STOPPED_EVENT);
+        assert_eq!(
+            StopReason::from_value(&last.payload),
+            Some(StopReason::Normal)
+        );
+
+        let snapshot = runtime.journal().load_snapshot(&id).unwrap().unwrap();
+        assert_eq!(snapshot.state, TypedValue::Int(11));
+        assert_eq!(snapshot.seq, events.len() as u64);
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_monitor_receives_down_with_reason() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        runtime.register_behavior(
+            "watcher",
+            |_: &mut crate::behavior::BehaviorContext, state: &TypedValue, msg: &TypedValue| {
+                match crate::lifecycle::parse_down(msg) {
+                    Some((_, reason)) => Ok(TypedValue::String(reason.to_string())),
+                    None => Ok(state.clone()),
+                }
+            },
+        );
+
+        let watcher = runtime.spawn("watcher").unwrap();
+        let crashed = runtime.spawn("counter").unwrap();
+        let killed = runtime.spawn("counter").unwrap();
+        runtime.monitor(&watcher, &crashed).unwrap();
+
+        runtime.stop_actor_with(&crashed, StopReason::Error("boom".to_string()));
+        runtime.run_until_idle().unwrap();
+        assert_eq!(
+            state_of(&runtime, &watcher),
+            TypedValue::String("error: boom".to_string())
+        );
+
+        // Killing drops queued messages; monitoring afterwards still
+        // reports the stop
+        runtime.send(&killed, TypedValue::Int(5)).unwrap();
+        runtime.kill_actor(&killed);
+        assert!(runtime.is_terminated(&killed));
+        assert_eq!(runtime.stop_reason(&killed), Some(StopReason::Killed));
+        assert_eq!(runtime.metrics().dropped, 1);
+        runtime.monitor(&watcher, &killed).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert_eq!(
+            state_of(&runtime, &watcher),
+            TypedValue::String("killed".to_string())
+        );
+
+        for id in [watcher, crashed, killed] {
+            runtime.unregister_actor(&id);
+        }
+    }
+
+    #[test]
+    fn test_alias_redirects_messages() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+
+        let old = runtime.spawn("counter").unwrap();
+        runtime.stop_actor(&old);
+        let new = runtime.spawn("counter").unwrap();
+        runtime.alias(&old, &new).unwrap();
+
+        runtime.send(&old, TypedValue::Int(3)).unwrap();
+        runtime
+            .send(&ActorId::new(), TypedValue::Int(1))
+            .unwrap_err();
+        runtime.run_until_idle().unwrap();
+        assert_eq!(state_of(&runtime, &new), TypedValue::Int(3));
+        assert_eq!(runtime.journal().read_events(&old).unwrap().len(), 1);
+
+        // A cycle is refused without being audited
+        assert!(runtime.alias(&new, &old).is_err());
+        let history = runtime.config_history().unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].target, ConfigTarget::Actor(old.clone()));
+
+        runtime.unregister_actor(&old);
+        runtime.unregister_actor(&new);
+    }
+
+    #[test]
+    fn test_quota_refuses_appends() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            quota: Some(Quota {
+                max_actor_bytes: Some(200),
+                ..Quota::default()
+            }),
+            ..RuntimeConfig::default()
+        });
+        runtime.register_behavior("counter", counter);
+        runtime.register_behavior("echo", echo);
+        let id = runtime.spawn("counter").unwrap();
+        let alerts = runtime.spawn("echo").unwrap();
+        runtime.subscribe_system(&alerts);
+
+        let err = loop {
+            runtime.send(&id, TypedValue::Int(1)).unwrap();
+            if let Err(e) = runtime.process_next(&id) {
+                break e;
+            }
+        };
+        assert!(matches!(err, RuntimeError::QuotaExceeded(e) if e.actor_id == id));
+        let journaled = runtime.journal().read_events(&id).unwrap().len() as u64;
+        let dump = runtime.dump_actor(&id).unwrap();
+        assert_eq!(
+            (dump.state, dump.seq),
+            (TypedValue::Int(journaled as i64), journaled)
+        );
+
+        // Subscribers to the system actor are told
+        runtime.process_next(&alerts).unwrap();
+        let event = SystemEvent::from_message(&state_of(&runtime, &alerts));
+        assert!(matches!(
+            event,
+            Some(SystemEvent::DiskPressure { actor, limit: 200, .. }) if actor == id
+        ));
+
+        runtime.unregister_actor(&id);
+        runtime.unregister_actor(&alerts);
+    }
+
+    #[test]
+    fn test_pinned_actors_run_in_their_pool() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        runtime.register_behavior("ticker", counter);
+        runtime.pin_behavior("ticker", "latency").unwrap();
+
+        let bulk = runtime.spawn("counter").unwrap();
+        let ticker = runtime.spawn("ticker").unwrap();
+        let pinned = runtime.spawn_pinned("counter", "latency").unwrap();
+        assert_eq!(runtime.pool_of(&ticker).as_deref(), Some("latency"));
+        for id in [&bulk, &ticker, &pinned] {
+            runtime.send(id, TypedValue::Int(1)).unwrap();
+        }
+
+        assert_eq!(runtime.run_until_idle().unwrap(), 1);
+        assert_eq!(runtime.run_pool_until_idle("latency").unwrap(), 2);
+
+        runtime.pin(&pinned, None).unwrap();
+        runtime.send(&pinned, TypedValue::Int(1)).unwrap();
+        assert_eq!(runtime.run_until_idle().unwrap(), 1);
+
+        for id in [bulk, ticker, pinned] {
+            runtime.unregister_actor(&id);
+        }
+    }
+
+    #[test]
+    fn test_groups_share_run_loop_by_weight() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            journaling_enabled: false,
+            ..RuntimeConfig::default()
+        };
+        config.group_weights.insert("api".to_string(), 3);
+        let runtime = ActorRuntime::new(config);
+
+        let log = Arc::new(Mutex::new(vec![]));
+        for name in ["api", "ingest"] {
+            let log = log.clone();
+            runtime.register_behavior(
+                name,
+                move |_: &mut crate::behavior::BehaviorContext,
+                      state: &TypedValue,
+                      _: &TypedValue| {
+                    log.lock().unwrap().push(name);
+                    Ok(state.clone())
+                },
+            );
+        }
+        runtime.group_behavior("api", "api").unwrap();
+        runtime.group_behavior("ingest", "ingest").unwrap();
+        let api = runtime.spawn("api").unwrap();
+        let ingest = runtime.spawn("ingest").unwrap();
+        assert_eq!(runtime.group_of(&api).as_deref(), Some("api"));
+
+        for _ in 0..8 {
+            runtime.send(&ingest, TypedValue::Int(1)).unwrap();
+        }
+        for _ in 0..6 {
+            runtime.send(&api, TypedValue::Int(1)).unwrap();
+        }
+        assert_eq!(runtime.run_until_idle().unwrap(), 14);
+
+        // Two rounds of three api messages to one ingest message
+        let log = log.lock().unwrap();
+        assert_eq!(log[..8].iter().filter(|&&name| name == "api").count(), 6);
+
+        let usage = runtime.group_usage();
+        let handled: Vec<_> = usage
+            .iter()
+            .map(|u| (u.group.as_str(), u.weight, u.handled))
+            .collect();
+        assert_eq!(handled, [("api", 3, 6), ("ingest", 1, 8)]);
+
+        runtime.set_group(&ingest, "api").unwrap();
+        assert_eq!(runtime.group_of(&ingest).as_deref(), Some("api"));
+        runtime.unregister_actor(&api);
+        runtime.unregister_actor(&ingest);
+    }
+
+    #[test]
+    fn test_wait_for_stop_from_another_thread() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = Arc::new(test_runtime(&temp_dir));
+        runtime.register_behavior("counter", counter);
+        let id = runtime.spawn("counter").unwrap();
+
+        let waiter = {
+            let runtime = runtime.clone();
+            let id = id.clone();
+            std::thread::spawn(move || runtime.wait_for_stop(&id, Duration::from_secs(5)).unwrap())
+        };
+
+        std::thread::sleep(Duration::from_millis(20));
+        runtime.stop_actor(&id);
+        assert!(waiter.join().unwrap());
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_wait_for_stop_leaves_pinned_actor_to_its_pool() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        let id = runtime.spawn_pinned("counter", "latency").unwrap();
+        runtime.send(&id, TypedValue::Int(1)).unwrap();
+
+        runtime.stop_actor(&id);
+        assert!(!runtime
+            .wait_for_stop(&id, Duration::from_millis(20))
+            .unwrap());
+        assert_eq!(runtime.dump_actor(&id).unwrap().mailbox_depth, 1);
+
+        assert_eq!(runtime.run_pool_until_idle("latency").unwrap(), 1);
+        assert!(runtime.wait_for_stop(&id, Duration::from_secs(1)).unwrap());
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_health_reports_stuck_actor() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            stuck_actor_threshold: Duration::from_millis(20),
+            ..RuntimeConfig::default()
+        });
+        runtime.register_behavior("counter", counter);
+
+        let idle = runtime.spawn("counter").unwrap();
+        let busy = runtime.spawn("counter").unwrap();
+        runtime.send(&busy, TypedValue::Int(1)).unwrap();
+
+        let report = runtime.health();
+        assert!(report.is_ready());
+        assert_eq!(report.actors.len(), 2);
+
+        // Nobody dispatches the queued message
+        std::thread::sleep(Duration::from_millis(40));
+        let report = runtime.health();
+        assert!(!report.is_live());
+        let stuck: Vec<_> = report.stuck_actors().map(|a| a.id.clone()).collect();
+        assert_eq!(stuck, vec![busy.clone()]);
+
+        runtime.process_next(&busy).unwrap();
+        let report = runtime.health();
+        assert!(report.is_live());
+        let busy_health = report.actors.iter().find(|a| a.id == busy).unwrap();
+        assert_eq!(busy_health.mailbox_depth, 0);
+        assert!(busy_health.last_processed.is_some());
+        assert_eq!(busy_health.behavior, "counter");
+
+        runtime.unregister_actor(&idle);
+        runtime.unregister_actor(&busy);
+    }
+
+    #[test]
+    fn test_check_blocked_reports_once() {
+        use std::sync::atomic::AtomicUsize;
+
+        struct CountingObserver(Arc<AtomicUsize>);
+        impl RuntimeObserver for CountingObserver {
+            fn on_blocked(&self, _id: &ActorId, behavior: &str, _elapsed: Duration) {
+                assert_eq!(behavior, "sleepy");
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = Arc::new(test_runtime(&temp_dir));
+        let reports = Arc::new(AtomicUsize::new(0));
+        runtime.set_observer(CountingObserver(reports.clone()));
+        runtime.register_behavior(
+            "sleepy",
+            |_: &mut crate::behavior::BehaviorContext, state: &TypedValue, _: &TypedValue| {
+                std::thread::sleep(Duration::from_millis(100));
+                Ok(state.clone())
+            },
+        );
+
+        let id = runtime.spawn("sleepy").unwrap();
+        runtime.send(&id, TypedValue::Int(1)).unwrap();
+        let worker = {
+            let runtime = runtime.clone();
+            let id = id.clone();
+            std::thread::spawn(move || runtime.process_next(&id).unwrap())
+        };
+
+        std::thread::sleep(Duration::from_millis(40));
+        let blocked = runtime.check_blocked(Duration::from_millis(10));
+        assert_eq!(blocked.len(), 1);
+        assert_eq!(blocked[0].id, id);
+        runtime.check_blocked(Duration::from_millis(10));
+
+        assert!(worker.join().unwrap());
+        assert!(runtime.check_blocked(Duration::from_millis(10)).is_empty());
+        assert_eq!(reports.load(Ordering::SeqCst), 1);
+
+        let metrics = runtime.metrics();
+        assert_eq!(metrics.blocked_detected, 1);
+        assert_eq!(metrics.messages_processed, 1);
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_rate_limit_rejects_excess() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+
+        let id = runtime.spawn("counter").unwrap();
+        runtime
+            .set_rate_limit(
+                &id,
+                Some(RateLimit {
+                    per_second: 0.001,
+                    burst: 2,
+                }),
+            )
+            .unwrap();
+
+        runtime.send(&id, TypedValue::Int(1)).unwrap();
+        runtime.send(&id, TypedValue::Int(1)).unwrap();
+        assert!(matches!(
+            runtime.send(&id, TypedValue::Int(1)),
+            Err(RuntimeError::RateLimited(_))
+        ));
+        assert_eq!(runtime.metrics().rate_limited, 1);
+
+        runtime.set_rate_limit(&id, None).unwrap();
+        runtime.send(&id, TypedValue::Int(1)).unwrap();
+        assert_eq!(runtime.run_until_idle().unwrap(), 3);
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_untraced_sends_are_dropped() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        let id = runtime.spawn("counter").unwrap();
+        let limit = RateLimit {
+            per_second: 0.001,
+            burst: 1,
+        };
+        runtime.set_rate_limit(&id, Some(limit)).unwrap();
+
+        // Every write to /dev/full fails
+        runtime.start_recording("/dev/full").unwrap();
+        assert!(matches!(
+            runtime.send(&id, TypedValue::Int(1)),
+            Err(RuntimeError::Io(_))
+        ));
+        // The first send takes the refunded token and fails to trace; the
+        // other two are rate limited
+        assert!(matches!(
+            runtime.send_batch(&id, vec![TypedValue::Int(1); 3]),
+            Err(RuntimeError::Io(_))
+        ));
+        let metrics = runtime.metrics();
+        assert_eq!(metrics.dropped, 4);
+        assert_eq!(metrics.rate_limited, 2);
+        // Flushing to /dev/full fails too
+        let _ = runtime.stop_recording();
+
+        runtime.send(&id, TypedValue::Int(1)).unwrap();
+        assert_eq!(runtime.run_until_idle().unwrap(), 1);
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_load_shedding() {
+        use crate::shedding::LoadShedding;
+
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            load_shedding: Some(LoadShedding {
+                max_queued: Some(2),
+                low_priority: ["ingest".to_string()].into(),
+                is_critical: |msg| matches!(msg, TypedValue::Int(n) if *n < 0),
+                ..LoadShedding::default()
+            }),
+            ..RuntimeConfig::default()
+        });
+        runtime.register_behavior("ingest", counter);
+        runtime.register_behavior("api", counter);
+        let ingest = runtime.spawn("ingest").unwrap();
+        let api = runtime.spawn("api").unwrap();
+
+        for _ in 0..3 {
+            runtime.send(&api, TypedValue::Int(1)).unwrap();
+        }
+        let pressure = runtime.check_pressure();
+        assert_eq!(pressure.queued, 3);
+        assert!(pressure.shedding);
+
+        // Only low-priority actors shed, and only non-critical messages
+        assert!(matches!(
+            runtime.send(&ingest, TypedValue::Int(1)),
+            Err(RuntimeError::Overloaded(_))
+        ));
+        let batch = vec![TypedValue::Int(1), TypedValue::Int(-1)];
+        assert_eq!(runtime.send_batch(&ingest, batch).unwrap(), 1);
+        runtime.send(&ingest, TypedValue::Int(-1)).unwrap();
+        runtime.send(&api, TypedValue::Int(1)).unwrap();
+        assert_eq!(runtime.metrics().shed, 2);
+
+        assert_eq!(runtime.run_until_idle().unwrap(), 6);
+        assert!(!runtime.check_pressure().shedding);
+        runtime.send(&ingest, TypedValue::Int(1)).unwrap();
+
+        runtime.unregister_actor(&ingest);
+        runtime.unregister_actor(&api);
+    }
+
+    #[test]
+    fn test_expired_messages_are_dropped() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        let id = runtime.spawn("counter").unwrap();
+
+        runtime
+            .send_with_ttl(&id, TypedValue::Int(1), Duration::ZERO)
+            .unwrap();
+        runtime.send(&id, TypedValue::Int(2)).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(runtime.run_until_idle().unwrap(), 1);
+        assert_eq!(state_of(&runtime, &id), TypedValue::Int(2));
+
+        // A maximum age applies to every message, and the sweep finds them
+        // without the actor running
+        runtime
+            .set_max_message_age(&id, Some(Duration::ZERO))
+            .unwrap();
+        let reply = runtime.ask(&id, TypedValue::Int(3)).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(runtime.sweep_expired(), 1);
+        assert!(matches!(
+            reply.wait(Duration::from_secs(1)),
+            Err(RuntimeError::NoReply(_))
+        ));
+        let metrics = runtime.metrics();
+        assert_eq!((metrics.expired, metrics.dropped), (2, 2));
+
+        runtime.set_max_message_age(&id, None).unwrap();
+        runtime.send(&id, TypedValue::Int(4)).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(runtime.run_until_idle().unwrap(), 1);
+        assert_eq!(state_of(&runtime, &id), TypedValue::Int(6));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_sandbox_denies_builtins() {
+        use crate::builtins::BuiltinGroup;
+        use crate::sandbox::Sandbox;
+
+        let temp_dir = TempDir::new().unwrap();
+        let sandbox = Sandbox::new()
+            .deny_group(BuiltinGroup::Journal)
+            .deny("actor-spawn");
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            sandboxes: [("plugin".to_string(), sandbox)].into(),
+            ..RuntimeConfig::default()
+        });
+        runtime.register_behavior("plugin", counter);
+        runtime.register_behavior("counter", counter);
+        let plugin = runtime.spawn("plugin").unwrap();
+        let trusted = runtime.spawn("counter").unwrap();
+
+        runtime.check_builtin(&plugin, "actor-send").unwrap();
+        for word in ["actor-spawn", "journal-append", "actor-snapshot"] {
+            assert!(matches!(
+                runtime.check_builtin(&plugin, word),
+                Err(RuntimeError::BuiltinDenied { word: w, .. }) if w == word
+            ));
+        }
+        runtime.check_builtin(&trusted, "journal-append").unwrap();
+        assert_eq!(runtime.metrics().builtins_denied, 3);
+
+        runtime.set_sandbox(&plugin, None).unwrap();
+        runtime.check_builtin(&plugin, "actor-spawn").unwrap();
+        runtime
+            .set_sandbox(&trusted, Some(Sandbox::new().deny("actor-stop")))
+            .unwrap();
+        assert!(runtime.check_builtin(&trusted, "actor-stop").is_err());
+
+        runtime.unregister_actor(&plugin);
+        runtime.unregister_actor(&trusted);
+    }
+
+    #[test]
+    fn test_operator_calls_are_authorized() {
+        use crate::auth::{AllowAll, GrantPolicy, OperationKind};
+
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            ..RuntimeConfig::default()
+        });
+        runtime.register_behavior("counter", counter);
+        let ops = Principal::Token("ops".to_string());
+        let viewer = Principal::Certificate("CN=viewer".to_string());
+
+        // No policy set: everything is denied
+        assert!(matches!(
+            runtime.health_as(&ops),
+            Err(RuntimeError::Unauthorized {
+                op: Operation::Health,
+                ..
+            })
+        ));
+
+        runtime.set_auth_policy(
+            GrantPolicy::new()
+                .allow(ops.clone(), OperationKind::Spawn)
+                .allow(ops.clone(), OperationKind::Send)
+                .allow(viewer.clone(), OperationKind::Inspect),
+        );
+        let id = runtime.spawn_as(&ops, "counter").unwrap();
+        runtime.send_as(&ops, &id, TypedValue::Int(5)).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert_eq!(
+            runtime.dump_actor_as(&viewer, &id).unwrap().state,
+            TypedValue::Int(5)
+        );
+
+        assert!(matches!(
+            runtime.stop_actor_as(&ops, &id),
+            Err(RuntimeError::Unauthorized {
+                op: Operation::Stop(_),
+                ..
+            })
+        ));
+        assert!(runtime.is_running(&id));
+        assert!(runtime.send_as(&viewer, &id, TypedValue::Int(1)).is_err());
+        assert!(runtime.spawn_as(&viewer, "counter").is_err());
+
+        runtime.set_auth_policy(AllowAll);
+        runtime.stop_actor_as(&Principal::Anonymous, &id).unwrap();
+        assert!(!runtime.is_running(&id));
+    }
+
+    #[test]
+    fn test_shadow_mirrors_messages_into_its_own_journal() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        runtime.register_behavior("echo", echo);
+        runtime.register_behavior(
+            "rejecting",
+            |_: &mut crate::behavior::BehaviorContext, _: &TypedValue, _: &TypedValue| {
+                Err(crate::behavior::BehaviorError::new("rejected"))
+            },
+        );
+        let primary = runtime.spawn("counter").unwrap();
+        runtime.send(&primary, TypedValue::Int(1)).unwrap();
+        runtime.run_until_idle().unwrap();
+
+        // Queued but not yet handled when the shadow is spawned
+        runtime.send(&primary, TypedValue::Int(2)).unwrap();
+        let shadow = runtime.spawn_shadow(&primary, "counter").unwrap();
+        assert_eq!(runtime.shadows_of(&primary), std::slice::from_ref(&shadow));
+        assert_eq!(state_of(&runtime, &shadow), TypedValue::Int(1));
+        runtime
+            .send_batch(&primary, vec![TypedValue::Int(3), TypedValue::Int(4)])
+            .unwrap();
+        assert_eq!(runtime.run_until_idle().unwrap(), 6);
+        assert_eq!(state_of(&runtime, &shadow), state_of(&runtime, &primary));
+
+        // The shadow's events stay out of the runtime's journal
+        runtime.flush_journal().unwrap();
+        assert_eq!(runtime.journal().read_events(&primary).unwrap().len(), 4);
+        assert!(runtime.journal().read_events(&shadow).unwrap().is_empty());
+        let shadowed = runtime.shadow_journal().read_events(&shadow).unwrap();
+        let seqs: Vec<u64> = shadowed.iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, [1, 2, 3]);
+
+        // A failing shadow doesn't affect the primary
+        runtime.unregister_actor(&shadow);
+        let broken = runtime.spawn_shadow(&primary, "rejecting").unwrap();
+        runtime.send(&primary, TypedValue::Int(5)).unwrap();
+        assert_eq!(runtime.run_until_idle().unwrap(), 1);
+        assert_eq!(runtime.metrics().behavior_failures, 1);
+        assert_eq!(state_of(&runtime, &primary), TypedValue::Int(15));
+        assert_eq!(state_of(&runtime, &broken), TypedValue::Int(10));
+        runtime.flush_journal().unwrap();
+        assert_eq!(runtime.journal().read_events(&primary).unwrap().len(), 5);
+
+        // Nor does one that stopped
+        runtime.stop_actor(&broken);
+        runtime.send(&primary, TypedValue::Int(5)).unwrap();
+        runtime.unregister_actor(&broken);
+        assert!(runtime.shadows_of(&primary).is_empty());
+        runtime.send(&primary, TypedValue::Int(5)).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert_eq!(state_of(&runtime, &primary), TypedValue::Int(25));
+
+        runtime.unregister_actor(&primary);
+    }
+
+    #[test]
+    fn test_system_subscribers_get_lifecycle_events() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        // Collects the tags of the messages it gets
+        runtime.register_behavior(
+            "alerts",
+            |_ctx: &mut crate::behavior::BehaviorContext, state: &TypedValue, msg: &TypedValue| {
+                let tags = match state {
+                    TypedValue::String(tags) => tags.clone(),
+                    _ => String::new(),
+                };
+                let tag = crate::message::tag_of(msg).unwrap_or("?");
+                Ok::<_, crate::behavior::BehaviorError>(TypedValue::String(tags + tag + ","))
+            },
+        );
+        runtime.register_behavior(
+            "fragile",
+            |_ctx: &mut crate::behavior::BehaviorContext,
+             _state: &TypedValue,
+             _msg: &TypedValue| { panic!("fragile actor exploded") },
+        );
+        let crashes = runtime.spawn("alerts").unwrap();
+        let filter = SubscriptionFilter::tags(&["ActorCrashed", "NodeJoined"]);
+        runtime.subscribe_system_filtered(&crashes, filter);
+        let alerts = runtime.spawn("alerts").unwrap();
+        runtime.subscribe_system(&alerts);
+
+        let worker = runtime.spawn("counter").unwrap();
+        let fragile = runtime.spawn("fragile").unwrap();
+        runtime.send(&fragile, TypedValue::Int(1)).unwrap();
+        let _ = runtime.run_until_idle();
+        runtime.stop_actor(&worker);
+        runtime.publish_system(&SystemEvent::NodeJoined {
+            node: "node-2".to_string(),
+        });
+        runtime.run_until_idle().unwrap();
+        assert_eq!(
+            state_of(&runtime, &alerts),
+            TypedValue::String(
+                "ActorSpawned,ActorSpawned,ActorCrashed,ActorStopped,NodeJoined,".to_string()
+            )
+        );
+        let filtered = TypedValue::String("ActorCrashed,NodeJoined,".to_string());
+        assert_eq!(state_of(&runtime, &crashes), filtered);
+
+        // Unregistered subscribers are dropped
+        runtime.unregister_actor(&crashes);
+        runtime.unregister_actor(&alerts);
+        runtime.unregister_actor(&fragile);
+        assert!(runtime.system_subscribers.read().unwrap().is_empty());
+        runtime.unregister_actor(&worker);
+    }
+
+    #[test]
+    fn test_stopped_actor_hands_off_queued_messages() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        let old = runtime.spawn("counter").unwrap();
+        let successor = runtime.spawn("counter").unwrap();
+        runtime
+            .set_handoff(&old, Some(Handoff::Successor(successor.clone())))
+            .unwrap();
+        for n in 1..=3 {
+            runtime.send(&old, TypedValue::Int(n)).unwrap();
+        }
+
+        runtime.stop_actor(&old);
+        assert!(runtime.wait_for_stop(&old, Duration::from_secs(1)).unwrap());
+        runtime.run_until_idle().unwrap();
+        // The old actor handled none of them
+        assert_eq!(
+            state_of(&runtime, &old),
+            TypedValue::Map(Default::default())
+        );
+        assert_eq!(state_of(&runtime, &successor), TypedValue::Int(6));
+
+        // Or report them as dead letters rather than drops
+        runtime
+            .set_handoff(&successor, Some(Handoff::DeadLetters))
+            .unwrap();
+        runtime.send(&successor, TypedValue::Int(1)).unwrap();
+        runtime.send(&successor, TypedValue::Int(2)).unwrap();
+        runtime.kill_actor(&successor);
+        let metrics = runtime.metrics();
+        assert_eq!((metrics.dropped, metrics.dead_letters), (0, 2));
+
+        runtime.unregister_actor(&old);
+        runtime.unregister_actor(&successor);
+    }
+
+    #[test]
+    fn test_storage_tiers() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = || RuntimeConfig {
+            journal_path: temp_dir.path().join("bulk"),
+            storage_tiers: [("fast".to_string(), temp_dir.path().join("fast"))].into(),
+            behavior_tiers: [("scratch".to_string(), StorageTier::MemoryOnly)].into(),
+            ..RuntimeConfig::default()
+        };
+        let runtime = ActorRuntime::new(config());
+        runtime.register_behavior("counter", counter);
+        runtime.register_behavior("scratch", counter);
+        let hot = runtime
+            .spawn_in_tier("counter", StorageTier::named("fast"))
+            .unwrap();
+        let scratch = runtime.spawn("scratch").unwrap();
+        assert_eq!(runtime.tier_of(&scratch), StorageTier::MemoryOnly);
+        for id in [&hot, &scratch] {
+            runtime.send(id, TypedValue::Int(3)).unwrap();
+        }
+        runtime.run_until_idle().unwrap();
+        runtime.flush_journal().unwrap();
+
+        let fast = runtime.tier_journal("fast").unwrap();
+        assert_eq!(fast.read_events(&hot).unwrap().len(), 1);
+        assert!(runtime.journal().read_events(&hot).unwrap().is_empty());
+        assert!(runtime.journal().read_events(&scratch).unwrap().is_empty());
+        assert!(matches!(
+            runtime.spawn_in_tier("counter", StorageTier::named("tape")),
+            Err(RuntimeError::UnknownTier(name)) if name == "tape"
+        ));
+        runtime.stop_actor(&hot);
+        assert!(runtime.wait_for_stop(&hot, Duration::from_secs(1)).unwrap());
+        assert!(fast.load_snapshot(&hot).unwrap().is_some());
+        runtime.unregister_actor(&hot);
+        runtime.unregister_actor(&scratch);
+
+        // Recovery reads from the tier the actor is spawned in
+        let restarted = ActorRuntime::new(config());
+        restarted.register_behavior("counter", counter);
+        let tier = StorageTier::named("fast");
+        restarted
+            .spawn_with_id_in_tier(hot.clone(), "counter", tier)
+            .unwrap();
+        assert_eq!(state_of(&restarted, &hot), TypedValue::Int(3));
+        restarted.unregister_actor(&hot);
+    }
+
+    #[test]
+    fn test_in_memory_runtime_leaves_no_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal_path = temp_dir.path().join("journal");
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: journal_path.clone(),
+            ..RuntimeConfig::in_memory()
+        });
+        runtime.register_behavior("counter", counter);
+        runtime.register_behavior(
+            "fragile",
+            |_ctx: &mut crate::behavior::BehaviorContext,
+             _state: &TypedValue,
+             _msg: &TypedValue| { panic!("fragile actor exploded") },
+        );
+        let id = runtime.spawn_singleton("totals", "counter").unwrap();
+        runtime.send(&id, TypedValue::Int(2)).unwrap();
+        runtime.alias(&ActorId::new(), &id).unwrap();
+        let fragile = runtime.spawn("fragile").unwrap();
+        runtime.send(&fragile, TypedValue::Int(1)).unwrap();
+        let _ = runtime.run_until_idle();
+        runtime.stop_actor(&id);
+        assert!(runtime.wait_for_stop(&id, Duration::from_secs(1)).unwrap());
+        assert!(runtime.health().journal.writable);
+        runtime.unregister_actor(&id);
+
+        // Recovery works within the process
+        assert_eq!(runtime.journal().read_events(&id).unwrap().len(), 2);
+        assert_eq!(
+            runtime.recover_state(&id).unwrap().unwrap().0,
+            TypedValue::Int(2)
+        );
+        assert!(!journal_path.exists());
+
+        runtime.unregister_actor(&fragile);
+    }
+
+    #[test]
+    fn test_checkpoint_all_snapshots_every_actor() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        runtime.register_behavior("echo", echo);
+        let counter_id = runtime.spawn("counter").unwrap();
+        let echo_id = runtime.spawn("echo").unwrap();
+        for n in 1..=3 {
+            runtime.send(&counter_id, TypedValue::Int(n)).unwrap();
+        }
+        runtime.send(&echo_id, TypedValue::Int(7)).unwrap();
+        runtime.run_until_idle().unwrap();
+
+        let checkpoint = runtime.checkpoint_all().unwrap();
+        let counted = checkpoint.entry(&counter_id).unwrap();
+        assert_eq!((counted.behavior.as_str(), counted.seq), ("counter", 3));
+        assert_eq!(checkpoint.entry(&echo_id).unwrap().behavior, "echo");
+        let snapshot = runtime
+            .journal()
+            .load_snapshot(&counter_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!((snapshot.seq, snapshot.state), (3, TypedValue::Int(6)));
+        assert_eq!(runtime.journal().checkpoints().unwrap(), [checkpoint]);
+
+        // Handling picks up again afterwards
+        runtime.send(&counter_id, TypedValue::Int(4)).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert_eq!(state_of(&runtime, &counter_id), TypedValue::Int(10));
+
+        runtime.unregister_actor(&counter_id);
+        runtime.unregister_actor(&echo_id);
+    }
+
+    #[test]
+    fn test_delta_behavior_journals_state_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            delta_behaviors: HashSet::from(["counter".to_string()]),
+            ..RuntimeConfig::default()
+        });
+        runtime.register_behavior("counter", counter);
+        let id = runtime.spawn("counter").unwrap();
+        for n in [1, 0, 2, 3] {
+            runtime.send(&id, TypedValue::Int(n)).unwrap();
+        }
+        runtime.run_until_idle().unwrap();
+        runtime.unregister_actor(&id);
+        runtime.flush_journal().unwrap();
+
+        // The unchanged state after 0 journals nothing
+        let events = runtime.journal().read_events(&id).unwrap();
+        let journaled: Vec<_> = events
+            .iter()
+            .map(|e| (e.seq, e.event_type.as_str()))
+            .collect();
+        let delta = delta::STATE_DELTA_EVENT;
+        assert_eq!(journaled, [(0, delta), (1, delta), (2, delta)]);
+
+        // counter has no reducer: only the deltas bring its state back
+        runtime.spawn_with_id(id.clone(), "counter").unwrap();
+        assert_eq!(state_of(&runtime, &id), TypedValue::Int(6));
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_delta_behavior_keeps_outbox_intents() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = Arc::new(ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            delta_behaviors: HashSet::from(["orders".to_string()]),
+            ..RuntimeConfig::default()
+        }));
+        runtime.register_behavior("echo", echo);
+        let inbox = runtime.spawn("echo").unwrap();
+        runtime.register_behavior("orders", {
+            let inbox = inbox.clone();
+            move |ctx: &mut crate::behavior::BehaviorContext,
+                  _state: &TypedValue,
+                  msg: &TypedValue| {
+                ctx.emit("OrderPlaced", msg.clone());
+                ctx.outbox(crate::outbox::Intent::send(&inbox, msg.clone()));
+                Ok::<_, BehaviorError>(msg.clone())
+            }
+        });
+        let orders = runtime.spawn("orders").unwrap();
+        runtime.send(&orders, TypedValue::Int(7)).unwrap();
+        runtime.run_until_idle().unwrap();
+        runtime.flush_journal().unwrap();
+
+        let events = runtime.journal().read_events(&orders).unwrap();
+        let journaled: Vec<_> = events
+            .iter()
+            .map(|e| (e.seq, e.event_type.as_str()))
+            .collect();
+        assert_eq!(
+            journaled,
+            [
+                (0, delta::STATE_DELTA_EVENT),
+                (1, crate::outbox::INTENT_EVENT)
+            ]
+        );
+
+        let report = crate::outbox::Outbox::new(&runtime)
+            .deliver_pending()
+            .unwrap();
+        assert_eq!(report.delivered, 1);
+        runtime.run_until_idle().unwrap();
+        assert_eq!(state_of(&runtime, &inbox), TypedValue::Int(7));
+
+        runtime.unregister_actor(&orders);
+        runtime.unregister_actor(&inbox);
+    }
+
+    #[test]
+    fn test_delivery_group_shares_messages() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        let workers: Vec<_> = (0..3).map(|_| runtime.spawn("counter").unwrap()).collect();
+        for worker in &workers {
+            runtime.join_delivery_group("jobs", worker);
+        }
+        runtime.stop_actor(&workers[2]);
+        for _ in 0..4 {
+            runtime.send_to_group("jobs", TypedValue::Int(1)).unwrap();
+        }
+        runtime.run_until_idle().unwrap();
+        assert_eq!(state_of(&runtime, &workers[0]), TypedValue::Int(2));
+        assert_eq!(state_of(&runtime, &workers[1]), TypedValue::Int(2));
+
+        runtime.unregister_actor(&workers[1]);
+        let members = runtime.delivery_group_members("jobs");
+        assert_eq!(members, [workers[0].clone(), workers[2].clone()]);
+        runtime.unregister_actor(&workers[0]);
+        assert!(matches!(
+            runtime.send_to_group("jobs", TypedValue::Int(1)),
+            Err(RuntimeError::NoGroupMember(_))
+        ));
+        runtime.unregister_actor(&workers[2]);
+    }
+
+    #[test]
+    fn test_reference_data_follows_the_actor() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("echo", echo);
+        let rates = runtime.spawn("echo").unwrap();
+        let eur = TypedMapKey::String("EUR".to_string());
+        let table = |rate: f64| TypedValue::Map([(eur.clone(), TypedValue::Float(rate))].into());
+        runtime.send(&rates, table(1.1)).unwrap();
+        runtime.run_until_idle().unwrap();
+
+        runtime.publish_reference("rates", &rates).unwrap();
+        let before = runtime.reference_data("rates").unwrap();
+        assert_eq!(
+            runtime.reference_get("rates", &eur),
+            Some(TypedValue::Float(1.1))
+        );
+
+        runtime.send(&rates, table(1.2)).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert_eq!(
+            runtime.reference_get("rates", &eur),
+            Some(TypedValue::Float(1.2))
+        );
+        assert_eq!(*before, table(1.1), "readers keep the state they took");
+
+        runtime.unregister_actor(&rates);
+        assert!(runtime.reference_data("rates").is_none());
+    }
+
+    #[test]
+    fn test_restore_checkpoint_ignores_later_events() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        let live = runtime.spawn("counter").unwrap();
+        let stopped = runtime.spawn("counter").unwrap();
+        runtime.send(&live, TypedValue::Int(5)).unwrap();
+        runtime.send(&stopped, TypedValue::Int(2)).unwrap();
+        runtime.run_until_idle().unwrap();
+        let checkpoint = runtime.checkpoint_all().unwrap();
+
+        runtime.send(&live, TypedValue::Int(100)).unwrap();
+        runtime.send(&stopped, TypedValue::Int(100)).unwrap();
+        runtime.run_until_idle().unwrap();
+        runtime.unregister_actor(&stopped);
+        assert_eq!(runtime.journal().collect_blobs().unwrap(), 0);
+
+        runtime.restore_checkpoint(&checkpoint).unwrap();
+        assert_eq!(state_of(&runtime, &live), TypedValue::Int(5));
+        assert_eq!(state_of(&runtime, &stopped), TypedValue::Int(2));
+        runtime.flush_journal().unwrap();
+        let events = runtime.journal().read_events(&live).unwrap();
+        assert_eq!(events.len(), 3, "history is kept");
+        assert_eq!(events[2].event_type, RESTORED_EVENT);
+
+        // Recovery starts from the restored state, and handling resumes
+        runtime.unregister_actor(&live);
+        runtime.spawn_with_id(live.clone(), "counter").unwrap();
+        runtime.send(&live, TypedValue::Int(1)).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert_eq!(state_of(&runtime, &live), TypedValue::Int(6));
+
+        runtime.unregister_actor(&live);
+        runtime.unregister_actor(&stopped);
+    }
+
+    #[test]
+    fn test_throughput_is_journaled() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        let (busy, idle) = (
+            runtime.spawn("counter").unwrap(),
+            runtime.spawn("counter").unwrap(),
+        );
+        assert_eq!(runtime.record_throughput().unwrap(), None);
+
+        for n in [1, 2, 3] {
+            runtime.send(&busy, TypedValue::Int(n)).unwrap();
+        }
+        runtime.run_until_idle().unwrap();
+        assert_eq!(runtime.record_throughput().unwrap(), Some(0));
+        runtime.send(&busy, TypedValue::Bool(true)).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert_eq!(runtime.record_throughput().unwrap(), Some(1));
+
+        let restarted = test_runtime(&temp_dir);
+        let history = restarted.throughput_history().unwrap();
+        let counts: Vec<_> = history.iter().map(|r| r.actors.clone()).collect();
+        let counts_of = |handled, failed| vec![(busy.clone(), MessageCounts { handled, failed })];
+        assert_eq!(counts, [counts_of(3, 0), counts_of(1, 1)]);
+
+        runtime.unregister_actor(&busy);
+        runtime.unregister_actor(&idle);
+    }
+
+    #[test]
+    fn test_config_changes_are_journaled() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        let id = runtime.spawn("counter").unwrap();
+        let other = ActorId::new();
+        runtime
+            .set_rate_limit(&id, Some(RateLimit::per_second(2)))
+            .unwrap();
+        runtime.set_group(&id, "batch").unwrap();
+        runtime.alias(&other, &id).unwrap();
+        runtime.pin_behavior("counter", "latency").unwrap();
+        runtime
+            .set_delivery_strategy("workers", DeliveryStrategy::LeastLoaded)
+            .unwrap();
+        // A failed change isn't recorded
+        assert!(runtime.set_group(&ActorId::new(), "batch").is_err());
+
+        let history = runtime.config_history().unwrap();
+        let settings: Vec<&str> = history.iter().map(|c| c.setting.as_str()).collect();
+        assert_eq!(
+            settings,
+            ["rate_limit", "group", "alias", "pool", "delivery_strategy"]
+        );
+        assert_eq!(history[1].target, ConfigTarget::Actor(id.clone()));
+        assert_eq!(history[1].value, "\"batch\"");
+        assert_eq!(history[2].target, ConfigTarget::Actor(other));
+        assert_eq!(
+            history[3].target,
+            ConfigTarget::Behavior("counter".to_string())
+        );
+        assert_eq!(
+            history[4].target,
+            ConfigTarget::DeliveryGroup("workers".to_string())
+        );
+        let seqs: Vec<u64> = history.iter().map(|c| c.seq).collect();
+        assert_eq!(seqs, [0, 1, 2, 3, 4]);
+
+        // A restarted runtime continues the same history
+        let restarted = test_runtime(&temp_dir);
+        restarted.alias(&ActorId::new(), &id).unwrap();
+        let last = restarted.config_history().unwrap().pop().unwrap();
+        assert_eq!((last.seq, last.setting.as_str()), (5, "alias"));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_state_limit() {
+        use crate::state_size::{state_size, StateLimit};
+
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("echo", echo);
+        let small = TypedValue::String("x".to_string());
+        let large = TypedValue::String("x".repeat(100));
+        let max_bytes = state_size(&small);
+
+        let warned = runtime.spawn("echo").unwrap();
+        runtime
+            .set_state_limit(&warned, Some(StateLimit::warn(max_bytes)))
+            .unwrap();
+        for msg in [&small, &large, &large, &small, &large] {
+            runtime.send(&warned, msg.clone()).unwrap();
+        }
+        runtime.run_until_idle().unwrap();
+        // Reported each time it crosses the limit, and left running
+        assert_eq!(runtime.metrics().state_limit_exceeded, 2);
+        assert!(runtime.is_running(&warned));
+        let health = runtime.health();
+        let actor = health.actors.iter().find(|a| a.id == warned).unwrap();
+        assert_eq!(actor.state_size, Some(state_size(&large)));
+
+        let passivated = runtime.spawn("echo").unwrap();
+        runtime
+            .set_state_limit(&passivated, Some(StateLimit::passivate(max_bytes)))
+            .unwrap();
+        runtime.send(&passivated, large.clone()).unwrap();
+        runtime.send(&passivated, small.clone()).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert!(!runtime.is_running(&passivated));
+        assert_eq!(
+            runtime.stop_reason(&passivated),
+            Some(StopReason::Passivated)
+        );
+        // Like any stop, messages already queued are handled first
+        let snapshot = runtime
+            .journal()
+            .load_snapshot(&passivated)
+            .unwrap()
+            .unwrap();
+        assert_eq!(snapshot.state, small);
+
+        runtime.unregister_actor(&warned);
+        runtime.unregister_actor(&passivated);
+    }
+
+    #[test]
+    fn test_async_durability_recovers_after_flush() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            durability: Durability::Async,
+            ..RuntimeConfig::default()
+        });
+        runtime.register_behavior("counter", counter);
+
+        let id = runtime.spawn("counter").unwrap();
+        for _ in 0..10 {
+            runtime.send(&id, TypedValue::Int(1)).unwrap();
+        }
+        runtime.run_until_idle().unwrap();
+        runtime.flush_journal().unwrap();
+        assert_eq!(runtime.journal().read_events(&id).unwrap().len(), 10);
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_observer_sees_mailbox_activity() {
+        #[derive(Default)]
+        struct Log(Mutex<Vec<String>>);
+        impl RuntimeObserver for Arc<Log> {
+            fn on_enqueue(&self, _to: &ActorId, _size: usize, depth: usize) {
+                self.0.lock().unwrap().push(format!("enqueue {}", depth));
+            }
+            fn on_dequeue(&self, _id: &ActorId, size: usize, _waited: Duration) {
+                self.0.lock().unwrap().push(format!("dequeue {}", size > 0));
+            }
+            fn on_drop(&self, _to: &ActorId, _size: usize, reason: DropReason) {
+                self.0.lock().unwrap().push(format!("drop {:?}", reason));
+            }
+            fn on_dead_letter(&self, _to: &ActorId, _size: usize) {
+                self.0.lock().unwrap().push("dead letter".to_string());
+            }
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        let log = Arc::new(Log::default());
+        runtime.set_observer(log.clone());
+        runtime.register_behavior("counter", counter);
+
+        let id = runtime.spawn("counter").unwrap();
+        runtime.send(&id, TypedValue::Int(1)).unwrap();
+        runtime.send(&id, TypedValue::Int(2)).unwrap();
+        runtime.process_next(&id).unwrap();
+        runtime.unregister_actor(&id);
+        assert!(runtime.send(&id, TypedValue::Int(3)).is_err());
+
+        assert_eq!(
+            *log.0.lock().unwrap(),
+            [
+                "enqueue 1",
+                "enqueue 2",
+                "dequeue true",
+                "drop Unregistered",
+                "dead letter"
+            ]
+        );
+        let metrics = runtime.metrics();
+        assert_eq!((metrics.dropped, metrics.dead_letters), (1, 1));
+    }
+
+    #[test]
+    fn test_send_self_and_loop_detection() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            self_send_loop_threshold: Some(3),
+            ..RuntimeConfig::default()
+        });
+        // Counts down: each message n > 0 sends itself n - 1
+        runtime.register_behavior(
+            "countdown",
+            |ctx: &mut crate::behavior::BehaviorContext, _: &TypedValue, msg: &TypedValue| {
+                if let TypedValue::Int(n) = msg {
+                    if *n > 0 {
+                        ctx.send_self(TypedValue::Int(n - 1));
+                    }
+                }
+                Ok(msg.clone())
+            },
+        );
+
+        let id = runtime.spawn("countdown").unwrap();
+        runtime.send(&id, TypedValue::Int(2)).unwrap();
+        assert_eq!(runtime.run_until_idle().unwrap(), 3);
+        assert_eq!(state_of(&runtime, &id), TypedValue::Int(0));
+        assert_eq!(runtime.metrics().self_send_loops, 0);
+
+        runtime.send(&id, TypedValue::Int(10)).unwrap();
+        assert_eq!(runtime.run_until_idle().unwrap(), 11);
+        assert_eq!(runtime.metrics().self_send_loops, 1);
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_forward_keeps_sender_and_ask() {
+        use crate::behavior::{BehaviorContext, BehaviorError};
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior(
+            "whoami",
+            |ctx: &mut BehaviorContext, _state: &TypedValue, _msg: &TypedValue| {
+                let sender = ctx.sender().map_or("none".to_string(), |id| id.to_string());
+                ctx.reply(TypedValue::String(sender.clone()));
+                Ok::<_, BehaviorError>(TypedValue::String(sender))
+            },
+        );
+        let target = runtime.spawn("whoami").unwrap();
+        let to = target.clone();
+        runtime.register_behavior(
+            "router",
+            move |ctx: &mut BehaviorContext, state: &TypedValue, msg: &TypedValue| {
+                match msg {
+                    TypedValue::Bool(true) => ctx.send_self(TypedValue::Int(1)),
+                    _ => ctx.forward(to.clone(), msg.clone()),
+                }
+                Ok::<_, BehaviorError>(state.clone())
+            },
+        );
+        let router = runtime.spawn("router").unwrap();
+
+        // The reply comes from the actor the ask was forwarded to
+        let reply = runtime.ask(&router, TypedValue::Int(1)).unwrap();
+        runtime.run_until_idle().unwrap();
+        let none = TypedValue::String("none".to_string());
+        assert_eq!(reply.wait(Duration::from_secs(1)).unwrap(), none);
+
+        // A message the router sent itself is forwarded in its name
+        runtime.send(&router, TypedValue::Bool(true)).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert_eq!(
+            state_of(&runtime, &target),
+            TypedValue::String(router.to_string())
+        );
+
+        runtime.unregister_actor(&router);
+        runtime.unregister_actor(&target);
+    }
+
+    #[test]
+    fn test_interceptor_applies_per_behavior() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        runtime.register_behavior("other", counter);
+        runtime.add_interceptor(
+            "counter",
+            |_: &mut crate::behavior::BehaviorContext, msg: TypedValue| match msg {
+                TypedValue::Int(n) if n < 0 => Err(crate::behavior::BehaviorError::new("negative")),
+                msg => Ok(msg),
+            },
+        );
+
+        let guarded = runtime.spawn("counter").unwrap();
+        let open = runtime.spawn("other").unwrap();
+        for id in [&guarded, &open] {
+            runtime.send(id, TypedValue::Int(5)).unwrap();
+            runtime.send(id, TypedValue::Int(-2)).unwrap();
+        }
+        runtime.run_until_idle().unwrap();
+
+        assert_eq!(state_of(&runtime, &guarded), TypedValue::Int(5));
+        assert_eq!(state_of(&runtime, &open), TypedValue::Int(3));
+        assert_eq!(runtime.metrics().behavior_failures, 1);
+
+        runtime.unregister_actor(&guarded);
+        runtime.unregister_actor(&open);
+    }
+
+    #[test]
+    fn test_message_timeout_fails_handling() {
+        use crate::behavior::{BehaviorContext, BehaviorError};
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior(
+            "slow",
+            |ctx: &mut BehaviorContext, _state: &TypedValue, msg: &TypedValue| {
+                ctx.emit("Handled", msg.clone());
+                match msg {
+                    // Cooperative: gives up once cancelled
+                    TypedValue::Int(1) => {
+                        while !ctx.cancelled() {
+                            std::thread::sleep(Duration::from_millis(2));
+                        }
+                        Err(BehaviorError::new("gave up"))
+                    }
+                    // Never checks, and finishes late
+                    TypedValue::Int(2) => {
+                        std::thread::sleep(Duration::from_millis(40));
+                        Ok(msg.clone())
+                    }
+                    _ => Ok(msg.clone()),
+                }
+            },
+        );
+
+        let id = runtime.spawn("slow").unwrap();
+        runtime
+            .set_message_timeout(&id, Some(Duration::from_millis(20)))
+            .unwrap();
+        runtime.send(&id, TypedValue::Int(1)).unwrap();
+        let late = runtime.ask(&id, TypedValue::Int(2)).unwrap();
+        runtime.send(&id, TypedValue::Int(3)).unwrap();
+        runtime.run_until_idle().unwrap();
+
+        assert!(matches!(
+            late.wait(Duration::from_secs(1)),
+            Err(RuntimeError::TimedOut { timeout, .. }) if timeout == Duration::from_millis(20)
+        ));
+        assert_eq!(state_of(&runtime, &id), TypedValue::Int(3));
+        assert_eq!(runtime.metrics().timed_out, 2);
+        runtime.flush_journal().unwrap();
+        let events = runtime.journal().read_events(&id).unwrap();
+        let types: Vec<_> = events.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(types, [TIMED_OUT_EVENT, TIMED_OUT_EVENT, "Handled"]);
+        assert_eq!(
+            events[0].payload,
+            timed_out_payload(Duration::from_millis(20))
+        );
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_rejected_events_fail_the_message() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        runtime.add_event_validator(|_: &ActorId, event: &Event| match event.payload {
+            TypedValue::Int(n) if n < 0 => Err("negative amount".to_string()),
+            _ => Ok(()),
+        });
+
+        let id = runtime.spawn("counter").unwrap();
+        runtime.send(&id, TypedValue::Int(5)).unwrap();
+        let rejected = runtime.ask(&id, TypedValue::Int(-2)).unwrap();
+        runtime.send(&id, TypedValue::Int(4)).unwrap();
+        runtime.run_until_idle().unwrap();
+
+        match rejected.wait(Duration::from_secs(1)) {
+            Err(RuntimeError::EventRejected(e)) => {
+                assert_eq!((e.event_type.as_str(), e.seq), ("Added", 1));
+                assert_eq!(e.reason, "negative amount");
+            }
+            other => panic!("expected a rejection, got {:?}", other),
+        }
+        // State unchanged and nothing journaled: no gap in the sequence
+        assert_eq!(state_of(&runtime, &id), TypedValue::Int(9));
+        runtime.flush_journal().unwrap();
+        let events = runtime.journal().read_events(&id).unwrap();
+        let seqs: Vec<_> = events.iter().map(|e| (e.seq, e.payload.clone())).collect();
+        assert_eq!(seqs, [(0, TypedValue::Int(5)), (1, TypedValue::Int(4))]);
+        let metrics = runtime.metrics();
+        assert_eq!((metrics.events_rejected, metrics.behavior_failures), (1, 0));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_unhandled_tag_is_dead_letter() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior(
+            "pinger",
+            crate::dispatch::Dispatch::new().on(
+                "Ping",
+                |_: &mut crate::behavior::BehaviorContext, _: &TypedValue, _: &TypedValue| {
+                    Ok(TypedValue::String("pinged".to_string()))
+                },
+            ),
+        );
+
+        let id = runtime.spawn("pinger").unwrap();
+        runtime
+            .send(&id, TypedValue::String("Pong".to_string()))
+            .unwrap();
+        runtime
+            .send(&id, TypedValue::String("Ping".to_string()))
+            .unwrap();
+        runtime.run_until_idle().unwrap();
+
+        assert_eq!(
+            state_of(&runtime, &id),
+            TypedValue::String("pinged".to_string())
+        );
+        let metrics = runtime.metrics();
+        assert_eq!((metrics.dead_letters, metrics.messages_processed), (1, 2));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_panic_writes_crash_dump() {
+        #[derive(Default)]
+        struct Panics(Mutex<Vec<String>>);
+        impl RuntimeObserver for Arc<Panics> {
+            fn on_panic(&self, _id: &ActorId, behavior: &str, message: &str) {
+                let panic = format!("{}: {}", behavior, message);
+                self.0.lock().unwrap().push(panic);
+            }
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        let panics = Arc::new(Panics::default());
+        runtime.set_observer(panics.clone());
+        runtime.register_behavior(
+            "fragile",
+            |ctx: &mut crate::behavior::BehaviorContext, state: &TypedValue, msg: &TypedValue| {
+                if msg == &TypedValue::String("explode".to_string()) {
+                    panic!("fragile actor exploded");
+                }
+                counter(ctx, state, msg)
+            },
+        );
+
+        let id = runtime.spawn("fragile").unwrap();
+        runtime.send(&id, TypedValue::Int(5)).unwrap();
+        runtime
+            .send(&id, TypedValue::String("explode".to_string()))
+            .unwrap();
+        runtime.send(&id, TypedValue::Int(2)).unwrap();
+
+        assert!(runtime.process_next(&id).unwrap());
+        let err = runtime.process_next(&id).unwrap_err();
+        assert!(err.to_string().contains("fragile actor exploded"));
+        assert_eq!(
+            *panics.0.lock().unwrap(),
+            ["fragile: fragile actor exploded"]
+        );
+        assert!(runtime.process_next(&id).unwrap());
+        assert_eq!(state_of(&runtime, &id), TypedValue::Int(7));
+
+        runtime.flush_journal().unwrap();
+        let events = runtime.journal().read_events(&id).unwrap();
+        let types: Vec<_> = events.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(types, ["Added", FAILURE_EVENT, "Added"]);
+
+        let path = crate::crash::crash_dump_path(&events[1]).unwrap();
+        assert!(path.starts_with(temp_dir.path()));
+        let dump = std::fs::read_to_string(path).unwrap();
+        assert!(dump.contains("fragile actor exploded"));
+        assert!(dump.contains("== last 1 events =="));
+        assert!(dump.contains("== backtrace =="));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_spawn_singleton() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+
+        let id = runtime.spawn_singleton("scheduler", "counter").unwrap();
+        assert_eq!(runtime.spawn_singleton("scheduler", "counter").unwrap(), id);
+        let coordinator = runtime.spawn_singleton("coordinator", "counter").unwrap();
+        assert_ne!(coordinator, id);
+        runtime.send(&id, TypedValue::Int(3)).unwrap();
+        runtime.run_until_idle().unwrap();
+
+        // Stopped: the next call brings it back with its state
+        runtime.stop_actor(&id);
+        runtime.run_until_idle().unwrap();
+        assert!(runtime.wait_for_stop(&id, Duration::from_secs(1)).unwrap());
+        assert_eq!(runtime.spawn_singleton("scheduler", "counter").unwrap(), id);
+        assert_eq!(state_of(&runtime, &id), TypedValue::Int(3));
+        runtime.unregister_actor(&id);
+
+        // A new runtime over the same journal finds the same singleton
+        let restarted = test_runtime(&temp_dir);
+        restarted.register_behavior("counter", counter);
+        assert_eq!(
+            restarted.spawn_singleton("scheduler", "counter").unwrap(),
+            id
+        );
+        assert!(matches!(
+            restarted.spawn_singleton("../scheduler", "counter"),
+            Err(RuntimeError::Io(_))
+        ));
+        restarted.unregister_actor(&id);
+        runtime.unregister_actor(&coordinator);
+    }
+
+    #[test]
+    fn test_snapshot_now() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+
+        let id = runtime.spawn("counter").unwrap();
+        runtime.send(&id, TypedValue::Int(4)).unwrap();
+        runtime.send(&id, TypedValue::Int(6)).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert!(runtime.journal().load_snapshot(&id).unwrap().is_none());
+
+        assert_eq!(runtime.snapshot_now(&id).unwrap(), 2);
+        let snapshot = runtime.journal().load_snapshot(&id).unwrap().unwrap();
+        assert_eq!((snapshot.seq, snapshot.state), (2, TypedValue::Int(10)));
+
+        assert!(matches!(
+            runtime.snapshot_now(&ActorId::new()),
+            Err(RuntimeError::ActorNotFound(_))
+        ));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_read_state_waits_out_in_flight_message() {
+        static YIELDS: AtomicUsize = AtomicUsize::new(0);
+        fn count_yield() {
+            YIELDS.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = Arc::new(test_runtime(&temp_dir));
+        runtime.register_behavior(
+            "slow-counter",
+            |ctx: &mut crate::behavior::BehaviorContext, state: &TypedValue, msg: &TypedValue| {
+                std::thread::sleep(Duration::from_millis(50));
+                counter(ctx, state, msg)
+            },
+        );
+        let id = runtime.spawn("slow-counter").unwrap();
+
+        for (n, expected) in [(3, 3), (4, 7)] {
+            runtime.send(&id, TypedValue::Int(n)).unwrap();
+            let worker = {
+                let (runtime, id) = (runtime.clone(), id.clone());
+                std::thread::spawn(move || runtime.process_next(&id).unwrap())
+            };
+            std::thread::sleep(Duration::from_millis(10));
+            // The second time waiting as a builtin on a strand does
+            let state = if n == 3 {
+                runtime.read_state(&id)
+            } else {
+                strand::yielding(count_yield, || runtime.read_state(&id))
+            };
+            assert_eq!(state.unwrap(), TypedValue::Int(expected));
+            assert!(worker.join().unwrap());
+        }
+        assert!(YIELDS.load(Ordering::SeqCst) > 0);
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_lazy_snapshots_recover_and_read_by_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = || RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            lazy_snapshots: true,
+            ..RuntimeConfig::default()
+        };
+        let key = |name: &str| TypedMapKey::String(name.to_string());
+        let state = TypedValue::Map(
+            (0..50)
+                .map(|i| (key(&format!("k{}", i)), TypedValue::Int(i)))
+                .collect(),
+        );
+        let runtime = ActorRuntime::new(config());
+        runtime.register_behavior("echo", echo);
+        let id = runtime.spawn("echo").unwrap();
+        runtime.send(&id, state.clone()).unwrap();
+        runtime.run_until_idle().unwrap();
+        runtime.snapshot_now(&id).unwrap();
+        runtime.unregister_actor(&id);
+        drop(runtime);
+
+        let restarted = ActorRuntime::new(config());
+        restarted.register_behavior("echo", echo);
+        restarted.spawn_with_id(id.clone(), "echo").unwrap();
+        assert_eq!(state_of(&restarted, &id), state);
+        assert_eq!(
+            restarted.read_state_key(&id, &key("k7")).unwrap(),
+            Some(TypedValue::Int(7))
+        );
+        assert_eq!(restarted.read_state_key(&id, &key("nope")).unwrap(), None);
+
+        restarted.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_read_state_between_messages() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = Arc::new(test_runtime(&temp_dir));
+        let (entered_tx, entered) = std::sync::mpsc::channel();
+        let (release, release_rx) = std::sync::mpsc::channel::<()>();
+        let release_rx = Mutex::new(release_rx);
+        runtime.register_behavior(
+            "slow",
+            move |ctx: &mut crate::behavior::BehaviorContext,
+                  state: &TypedValue,
+                  msg: &TypedValue| {
+                entered_tx.send(()).unwrap();
+                release_rx.lock().unwrap().recv().unwrap();
+                counter(ctx, state, msg)
+            },
+        );
+
+        let id = runtime.spawn("slow").unwrap();
+        runtime.send(&id, TypedValue::Int(3)).unwrap();
+        assert_eq!(
+            runtime.read_state(&id).unwrap(),
+            TypedValue::Map(Default::default())
+        );
+
+        let worker = {
+            let (runtime, id) = (runtime.clone(), id.clone());
+            std::thread::spawn(move || runtime.process_next(&id).unwrap())
+        };
+        entered.recv().unwrap();
+        let reader = {
+            let (runtime, id) = (runtime.clone(), id.clone());
+            std::thread::spawn(move || runtime.read_state(&id).unwrap())
+        };
+        release.send(()).unwrap();
+
+        assert!(worker.join().unwrap());
+        assert_eq!(reader.join().unwrap(), TypedValue::Int(3));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_preload_reads_journals_ahead_of_spawn() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        let id = runtime.spawn("counter").unwrap();
+        runtime.send(&id, TypedValue::Int(3)).unwrap();
+        runtime.run_until_idle().unwrap();
+        runtime.snapshot_now(&id).unwrap();
+        runtime.unregister_actor(&id);
+        drop(runtime);
+
+        let restarted = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            preload: Some(Preload::Actors(vec![id.clone()])),
+            ..RuntimeConfig::default()
+        });
+        assert!(restarted.wait_for_preload(Duration::from_secs(5)));
+        assert_eq!(restarted.preloaded_actors(), 1);
+        restarted.register_behavior("counter", counter);
+        restarted.spawn_with_id(id.clone(), "counter").unwrap();
+        assert_eq!(restarted.preloaded_actors(), 0);
+        assert_eq!(state_of(&restarted, &id), TypedValue::Int(3));
+
+        restarted.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_dump_actor() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+        let id = runtime.spawn("counter").unwrap();
+        runtime.send(&id, TypedValue::Int(3)).unwrap();
+        runtime.process_next(&id).unwrap();
+        runtime.send(&id, TypedValue::Int(4)).unwrap();
+
+        let dump = runtime.dump_actor(&id).unwrap();
+        assert_eq!(dump.behavior, "counter");
+        assert_eq!(dump.state, TypedValue::Int(3));
+        assert_eq!((dump.mailbox_depth, dump.seq, dump.restarts), (1, 1, 0));
+        let TypedValue::Map(fields) = dump.to_value() else {
+            panic!("expected a map");
         };
+        let field = |name: &str| {
+            let key = TypedMapKey::String(name.to_string());
+            fields[&key].clone()
+        };
+        assert_eq!(field("mailbox_depth"), TypedValue::Int(1));
+        assert!(matches!(field("uptime_ms"), TypedValue::Int(ms) if ms >= 0));
+        assert!(matches!(
+            runtime.dump_actor(&ActorId::new()),
+            Err(RuntimeError::ActorNotFound(_))
+        ));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_deferred_reply_fulfilled_later() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior(
+            "front",
+            |ctx: &mut crate::behavior::BehaviorContext, state: &TypedValue, msg: &TypedValue| {
+                match msg {
+                    // A request: answer once the backend responds
+                    TypedValue::Int(_) => Ok(ctx.defer_reply().to_value()),
+                    // The backend's response
+                    _ => {
+                        let token = ReplyToken::from_value(state).unwrap();
+                        ctx.fulfill(&token, msg.clone());
+                        Ok(state.clone())
+                    }
+                }
+            },
+        );
+
+        let id = runtime.spawn("front").unwrap();
+        let reply = runtime.ask(&id, TypedValue::Int(1)).unwrap();
+        runtime.run_until_idle().unwrap();
+        let token = ReplyToken::from_value(&state_of(&runtime, &id)).unwrap();
+
+        runtime
+            .send(&id, TypedValue::String("answer".to_string()))
+            .unwrap();
+        runtime.run_until_idle().unwrap();
+        assert_eq!(
+            reply.wait(Duration::from_secs(1)).unwrap(),
+            TypedValue::String("answer".to_string())
+        );
+        assert!(!runtime.fulfill(&token, TypedValue::Int(0)));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_dump_coroutines() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+
+        let idle = runtime.spawn("counter").unwrap();
+        let ready = runtime.spawn_pinned("counter", "batch").unwrap();
+        runtime.send(&ready, TypedValue::Int(1)).unwrap();
+
+        let dump = runtime.dump_coroutines();
+        let find = |id: &ActorId| dump.iter().find(|c| &c.id == id).unwrap();
+        assert_eq!(find(&idle).name, coroutine_name(&idle, "counter"));
+        assert_eq!(find(&idle).status, CoroutineStatus::Idle);
+        assert_eq!(
+            (&find(&ready).status, find(&ready).queued),
+            (&CoroutineStatus::Ready, 1)
+        );
+        assert_eq!(find(&ready).pool.as_deref(), Some("batch"));
+
+        runtime.stop_actor(&idle);
+        assert!(runtime
+            .wait_for_stop(&idle, Duration::from_secs(1))
+            .unwrap());
+        let dump = runtime.dump_coroutines();
+        let idle_status = &dump.iter().find(|c| c.id == idle).unwrap().status;
+        assert_eq!(idle_status, &CoroutineStatus::Terminated);
+
+        runtime.unregister_actor(&idle);
+        runtime.unregister_actor(&ready);
+    }
+
+    #[test]
+    fn test_panic_policies() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior(
+            "fragile",
+            |ctx: &mut crate::behavior::BehaviorContext, state: &TypedValue, msg: &TypedValue| {
+                if msg == &TypedValue::Bool(true) {
+                    panic!("fragile actor exploded");
+                }
+                counter(ctx, state, msg)
+            },
+        );
+        let explode = TypedValue::Bool(true);
+
+        // Restart: back to the snapshot, since the behavior folds no events
+        let restarted = runtime.spawn("fragile").unwrap();
+        runtime
+            .set_panic_policy(&restarted, Some(PanicPolicy::RestartActor))
+            .unwrap();
+        runtime.send(&restarted, TypedValue::Int(5)).unwrap();
+        runtime.run_until_idle().unwrap();
+        runtime.snapshot_now(&restarted).unwrap();
+        runtime.send(&restarted, TypedValue::Int(2)).unwrap();
+        runtime.send(&restarted, explode.clone()).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert_eq!(state_of(&runtime, &restarted), TypedValue::Int(5));
+        assert!(runtime.is_running(&restarted));
+        assert_eq!(runtime.dump_actor(&restarted).unwrap().restarts, 1);
+
+        // Stop: queued messages are dropped and the reason is the error
+        let stopped = runtime.spawn("fragile").unwrap();
+        runtime
+            .set_panic_policy(&stopped, Some(PanicPolicy::StopActor))
+            .unwrap();
+        runtime.send(&stopped, explode.clone()).unwrap();
+        runtime.send(&stopped, TypedValue::Int(1)).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert!(matches!(
+            runtime.stop_reason(&stopped),
+            Some(StopReason::Error(e)) if e.contains("exploded")
+        ));
+        assert!(runtime
+            .wait_for_stop(&stopped, Duration::from_secs(1))
+            .unwrap());
+
+        // Escalate: the run loop fails
+        let escalated = runtime.spawn("fragile").unwrap();
+        runtime
+            .set_panic_policy(&escalated, Some(PanicPolicy::EscalateToSupervisor))
+            .unwrap();
+        runtime.send(&escalated, explode).unwrap();
+        assert!(matches!(
+            runtime.run_until_idle(),
+            Err(RuntimeError::Escalated(id, _)) if id == escalated
+        ));
+        assert!(!runtime.is_running(&escalated));
+
+        for id in [restarted, stopped, escalated] {
+            runtime.unregister_actor(&id);
+        }
+    }
+
+    #[test]
+    fn test_supervision_per_behavior() {
+        use crate::supervision::{Backoff, Supervision};
 
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            ..RuntimeConfig::default()
+        };
+        config.supervision.insert(
+            "fragile".to_string(),
+            Supervision {
+                panic_policy: Some(PanicPolicy::RestartActor),
+                max_restarts: Some(1),
+                backoff: Some(Backoff::fixed(Duration::from_millis(50))),
+                journaling: Some(false),
+            },
+        );
         let runtime = ActorRuntime::new(config);
-        let id = ActorId::new();
+        runtime.register_behavior(
+            "fragile",
+            |ctx: &mut crate::behavior::BehaviorContext, state: &TypedValue, msg: &TypedValue| {
+                if msg == &TypedValue::Bool(true) {
+                    panic!("fragile actor exploded");
+                }
+                counter(ctx, state, msg)
+            },
+        );
+        let id = runtime.spawn("fragile").unwrap();
 
-        // No persisted state for new actor
-        let result = runtime.recover_state(&id).unwrap();
-        assert!(result.is_none());
+        // Restarted, then paused for the backoff
+        runtime.send(&id, TypedValue::Bool(true)).unwrap();
+        runtime.send(&id, TypedValue::Int(1)).unwrap();
+        assert_eq!(runtime.run_until_idle().unwrap(), 0);
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(runtime.run_until_idle().unwrap(), 1);
+        assert_eq!(state_of(&runtime, &id), TypedValue::Int(1));
+        assert!(runtime.journal().read_events(&id).unwrap().is_empty());
+
+        // Out of restarts: stopped instead
+        runtime.send(&id, TypedValue::Bool(true)).unwrap();
+        runtime.run_until_idle().unwrap();
+        assert!(matches!(
+            runtime.stop_reason(&id),
+            Some(StopReason::Error(_))
+        ));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_send_batch() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+
+        let id = runtime.spawn("counter").unwrap();
+        let batch = (1..=4).map(TypedValue::Int).collect();
+        assert_eq!(runtime.send_batch(&id, batch).unwrap(), 4);
+        assert_eq!(runtime.run_until_idle().unwrap(), 4);
+        assert_eq!(state_of(&runtime, &id), TypedValue::Int(10));
+
+        runtime
+            .set_rate_limit(&id, Some(RateLimit::per_second(2)))
+            .unwrap();
+        let batch = vec![TypedValue::Int(1); 3];
+        assert_eq!(runtime.send_batch(&id, batch).unwrap(), 2);
+        assert_eq!(runtime.metrics().rate_limited, 1);
+
+        assert!(matches!(
+            runtime.send_batch(&ActorId::new(), vec![TypedValue::Int(1)]),
+            Err(RuntimeError::ActorNotFound(_))
+        ));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_actor_count_and_ids() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+
+        // Other tests share the registry, so only check our own actors
+        let a = runtime.spawn("counter").unwrap();
+        let b = runtime.spawn("counter").unwrap();
+        assert!(runtime.actor_count() >= 2);
+        let ids = runtime.actor_ids();
+        assert!(ids.contains(&a) && ids.contains(&b));
+
+        runtime.stop_actor(&b);
+        assert!(!runtime.actor_ids().contains(&b));
+        assert!(runtime.uptime() > Duration::ZERO);
+
+        runtime.unregister_actor(&a);
+        runtime.unregister_actor(&b);
+    }
+
+    #[test]
+    fn test_send_shared() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            max_message_size: Some(16),
+            ..RuntimeConfig::default()
+        });
+        // Keeps the size of the last Map it was sent
+        runtime.register_behavior(
+            "measure",
+            |_ctx: &mut crate::behavior::BehaviorContext, state: &TypedValue, msg: &TypedValue| {
+                Ok::<_, crate::behavior::BehaviorError>(match msg {
+                    TypedValue::Map(fields) => TypedValue::Int(fields.len() as i64),
+                    _ => state.clone(),
+                })
+            },
+        );
+
+        let ids: Vec<_> = (0..3).map(|_| runtime.spawn("measure").unwrap()).collect();
+        let big = Arc::new(TypedValue::Map(
+            (0..100)
+                .map(|i| (crate::serialize::TypedMapKey::Int(i), TypedValue::Int(i)))
+                .collect(),
+        ));
+        for id in &ids {
+            runtime.send_shared(id, big.clone()).unwrap();
+        }
+        // One copy, shared by every inbox and not offloaded despite its size
+        assert_eq!(Arc::strong_count(&big), 4);
+        for id in &ids {
+            let cell = runtime.cell(id).unwrap();
+            let cell = cell.lock().unwrap();
+            assert!(matches!(cell.inbox[0].payload, Payload::Shared(_)));
+        }
+
+        assert_eq!(runtime.run_until_idle().unwrap(), 3);
+        assert_eq!(Arc::strong_count(&big), 1);
+        for id in &ids {
+            assert_eq!(state_of(&runtime, id), TypedValue::Int(100));
+            runtime.unregister_actor(id);
+        }
+    }
+
+    #[test]
+    fn test_ask_replies() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior(
+            "doubler",
+            |ctx: &mut crate::behavior::BehaviorContext, state: &TypedValue, msg: &TypedValue| {
+                match msg {
+                    TypedValue::Int(n) if *n >= 0 => ctx.reply(TypedValue::Int(n * 2)),
+                    TypedValue::Int(_) => {
+                        return Err(crate::behavior::BehaviorError::new("negative"))
+                    }
+                    _ => {}
+                }
+                Ok(state.clone())
+            },
+        );
+
+        let id = runtime.spawn("doubler").unwrap();
+        let doubled = runtime.ask(&id, TypedValue::Int(21)).unwrap();
+        let failed = runtime.ask(&id, TypedValue::Int(-1)).unwrap();
+        let ignored = runtime.ask(&id, TypedValue::Bool(true)).unwrap();
+        runtime.run_until_idle().unwrap();
+
+        let timeout = Duration::from_secs(1);
+        assert_eq!(doubled.wait(timeout).unwrap(), TypedValue::Int(42));
+        assert!(matches!(
+            failed.wait(timeout),
+            Err(RuntimeError::Behavior(_))
+        ));
+        assert!(matches!(
+            ignored.wait(timeout),
+            Err(RuntimeError::NoReply(_))
+        ));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_spawn_unknown_behavior() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+
+        assert!(matches!(
+            runtime.spawn("missing"),
+            Err(RuntimeError::UnknownBehavior(_))
+        ));
+    }
+
+    #[test]
+    fn test_send_to_stopped_actor() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = test_runtime(&temp_dir);
+        runtime.register_behavior("counter", counter);
+
+        let id = runtime.spawn("counter").unwrap();
+        runtime.stop_actor(&id);
+
+        assert!(matches!(
+            runtime.send(&id, TypedValue::Int(1)),
+            Err(RuntimeError::ActorStopped(_))
+        ));
+        assert!(matches!(
+            runtime.send(&ActorId::new(), TypedValue::Int(1)),
+            Err(RuntimeError::ActorNotFound(_))
+        ));
+
+        runtime.unregister_actor(&id);
     }
 
     #[test]
@@ -358,6 +6120,7 @@ mod tests {
             journal_path: temp_dir.path().to_path_buf(),
             journaling_enabled: true,
             snapshot_interval: 100,
+            ..RuntimeConfig::default()
         };
 
         let runtime = ActorRuntime::new(config);
@@ -0,0 +1,59 @@
+//! Per-behavior builtin deny-lists
+//!
+//! Behaviors written in Seq reach the runtime only through the actor
+//! builtins, so denying builtins is enough to sandbox one: a behavior
+//! loaded from an untrusted source can be allowed to message its peers but
+//! not append to the journal, spawn, or (once they exist) reach remote
+//! nodes. Sandboxes are assigned by behavior name in
+//! `RuntimeConfig::sandboxes`, applied on spawn, or per actor with
+//! `ActorRuntime::set_sandbox`:
+//!
+//! ```rust,ignore
+//! let sandbox = Sandbox::new()
+//!     .deny_group(BuiltinGroup::Journal)
+//!     .deny_group(BuiltinGroup::Cluster)
+//!     .deny("actor-spawn");
+//! let config = RuntimeConfig {
+//!     sandboxes: [("plugin".to_string(), sandbox)].into(),
+//!     ..RuntimeConfig::default()
+//! };
+//! ```
+//!
+//! Builtins ask `ActorRuntime::check_builtin` before acting. A denied call
+//! is counted in `builtins_denied` and reported to the observer
+//! (`on_builtin_denied`); Result-flavor words push `Err`, legacy words
+//! panic, which the actor's `PanicPolicy` then handles like any other
+//! behavior failure. Words are named without a namespace (`actor-spawn`,
+//! not `actors.spawn`).
+
+use crate::builtins::{group_words, BuiltinGroup};
+use std::collections::HashSet;
+
+/// Builtins a behavior may not call
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sandbox {
+    denied: HashSet<String>,
+}
+
+impl Sandbox {
+    /// A sandbox that allows every builtin
+    pub fn new() -> Self {
+        Sandbox::default()
+    }
+
+    /// Deny one builtin word
+    pub fn deny(mut self, word: &str) -> Self {
+        self.denied.insert(word.to_string());
+        self
+    }
+
+    /// Deny every builtin in a group
+    pub fn deny_group(mut self, group: BuiltinGroup) -> Self {
+        self.denied.extend(group_words(group).map(str::to_string));
+        self
+    }
+
+    pub fn allows(&self, word: &str) -> bool {
+        !self.denied.contains(word)
+    }
+}
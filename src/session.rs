@@ -0,0 +1,290 @@
+//! Session record/replay
+//!
+//! A session trace captures every external input to an `ActorRuntime` —
+//! spawns (including the generated ActorIds), the host sends actors
+//! accepted, rate limits, stops — and the order in which actors handled
+//! their messages. Replaying the trace into a fresh runtime re-executes
+//! exactly the same interleaving, so concurrency bugs in actor programs
+//! can be reproduced deterministically.
+//!
+//! Host sends an actor rejected (rate limited, shed, stopped) never
+//! reached it and aren't traced; replayed sends skip admission, since
+//! they passed it once. The replaying runtime needs the recorded one's
+//! `RuntimeConfig` (load shedding, message size limits) for the sends
+//! behaviors make to be admitted the same way.
+//!
+//! # Format
+//!
+//! Traces use the journal's framing: length-prefixed bincode records.
+//!
+//! # Usage
+//!
+//! ```rust,ignore
+//! runtime.start_recording("session.trace")?;
+//! // ... run the program ...
+//! runtime.stop_recording()?;
+//!
+//! // Later, against a runtime with the same behaviors registered
+//! // and an empty journal path:
+//! let report = session::replay(&fresh_runtime, "session.trace")?;
+//! ```
+//!
+//! TODO: Record timer firings once the runtime has timers.
+
+use crate::actor::ActorId;
+use crate::error::RuntimeError;
+use crate::journal::{read_frame, write_frame};
+use crate::lifecycle::StopReason;
+use crate::ratelimit::RateLimit;
+use crate::runtime::ActorRuntime;
+use crate::serialize::TypedValue;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// One recorded runtime input
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TraceRecord {
+    /// An actor was spawned with this ID and behavior
+    Spawn { id: ActorId, behavior: String },
+    /// The host sent a message, which the actor accepted
+    Send { to: ActorId, msg: TypedValue },
+    /// An actor handled its next queued message
+    Deliver { to: ActorId },
+    /// An actor was stopped
+    Stop { id: ActorId },
+    /// An actor was stopped for a reason other than `StopReason::Normal`
+    StopWith { id: ActorId, reason: StopReason },
+    /// An actor's rate limit was set (`None`: removed)
+    RateLimit {
+        id: ActorId,
+        limit: Option<RateLimit>,
+    },
+}
+
+/// Writes trace records to a file
+pub struct SessionRecorder {
+    writer: BufWriter<File>,
+}
+
+impl SessionRecorder {
+    /// Create (or truncate) a trace file
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(SessionRecorder {
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+
+    /// Append a record
+    ///
+    /// Each record is flushed so the trace survives a crash.
+    pub fn record(&mut self, record: &TraceRecord) -> std::io::Result<()> {
+        let data = bincode::serialize(record)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        write_frame(&mut self.writer, &data)?;
+        self.writer.flush()
+    }
+
+    /// Flush buffered records
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Read all records from a trace file
+pub fn read_trace(path: impl AsRef<Path>) -> std::io::Result<Vec<TraceRecord>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut records = vec![];
+    while let Some(data) = read_frame(&mut reader)? {
+        let record = bincode::deserialize(&data)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Summary of a replay
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// Records applied
+    pub records: usize,
+    /// Messages handled
+    pub deliveries: usize,
+    /// Messages the behavior rejected (same as in the original run)
+    pub failures: usize,
+}
+
+/// Replay a trace into a runtime
+///
+/// The runtime must have the same behaviors registered as the recorded
+/// one, and should use an empty journal path so recovered state doesn't
+/// differ from the original run.
+pub fn replay(
+    runtime: &ActorRuntime,
+    path: impl AsRef<Path>,
+) -> Result<ReplayReport, RuntimeError> {
+    let mut report = ReplayReport::default();
+
+    for record in read_trace(path)? {
+        match record {
+            TraceRecord::Spawn { id, behavior } => {
+                runtime.spawn_with_id(id, &behavior)?;
+            }
+            TraceRecord::Send { to, msg } => runtime.replay_send(&to, msg)?,
+            TraceRecord::Deliver { to } => match runtime.process_next(&to) {
+                Ok(_) => report.deliveries += 1,
+                Err(e) if e.is_message_failure() => {
+                    report.deliveries += 1;
+                    report.failures += 1;
+                }
+                Err(e) => return Err(e),
+            },
+            TraceRecord::Stop { id } => runtime.stop_actor(&id),
+            TraceRecord::StopWith { id, reason } => match reason {
+                StopReason::Killed => runtime.kill_actor(&id),
+                reason => runtime.stop_actor_with(&id, reason),
+            },
+            TraceRecord::RateLimit { id, limit } => runtime.set_rate_limit(&id, limit)?,
+        }
+        report.records += 1;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavior::{BehaviorContext, BehaviorError};
+    use crate::runtime::RuntimeConfig;
+    use tempfile::TempDir;
+
+    /// Appends each message to a running String so order is observable
+    fn appender(
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        let so_far = match state {
+            TypedValue::String(s) => s.clone(),
+            _ => String::new(),
+        };
+        match msg {
+            TypedValue::String(s) => {
+                ctx.emit("Appended", msg.clone());
+                Ok(TypedValue::String(so_far + s))
+            }
+            _ => Err(BehaviorError::new("expected String")),
+        }
+    }
+
+    fn runtime_at(path: &Path) -> ActorRuntime {
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: path.to_path_buf(),
+            journaling_enabled: true,
+            snapshot_interval: 100,
+            ..RuntimeConfig::default()
+        });
+        runtime.register_behavior("appender", appender);
+        runtime
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let temp_dir = TempDir::new().unwrap();
+        let trace = temp_dir.path().join("session.trace");
+
+        let original = runtime_at(&temp_dir.path().join("original"));
+        original.start_recording(&trace).unwrap();
+        let a = original.spawn("appender").unwrap();
+        let b = original.spawn("appender").unwrap();
+        original
+            .send(&a, TypedValue::String("x".to_string()))
+            .unwrap();
+        original.send(&b, TypedValue::Int(1)).unwrap();
+        original
+            .send(&a, TypedValue::String("y".to_string()))
+            .unwrap();
+        original.run_until_idle().unwrap();
+        original.stop_recording().unwrap();
+        original.unregister_actor(&a);
+        original.unregister_actor(&b);
+
+        let records = read_trace(&trace).unwrap();
+        assert!(matches!(&records[0], TraceRecord::Spawn { id, .. } if *id == a));
+        let deliveries = records
+            .iter()
+            .filter(|r| matches!(r, TraceRecord::Deliver { .. }))
+            .count();
+        assert_eq!(deliveries, 3);
+
+        let replayed = runtime_at(&temp_dir.path().join("replay"));
+        let report = replay(&replayed, &trace).unwrap();
+        assert_eq!(report.deliveries, 3);
+        assert_eq!(report.failures, 1);
+
+        let events = replayed.journal().read_events(&a).unwrap();
+        let payloads: Vec<_> = events.iter().map(|e| e.payload.clone()).collect();
+        assert_eq!(
+            payloads,
+            [
+                TypedValue::String("x".to_string()),
+                TypedValue::String("y".to_string())
+            ]
+        );
+
+        replayed.unregister_actor(&a);
+        replayed.unregister_actor(&b);
+    }
+
+    #[test]
+    fn test_rejected_sends_are_not_traced() {
+        let temp_dir = TempDir::new().unwrap();
+        let trace = temp_dir.path().join("session.trace");
+        let limit = RateLimit {
+            per_second: 0.001,
+            burst: 1,
+        };
+
+        let original = runtime_at(&temp_dir.path().join("original"));
+        original.start_recording(&trace).unwrap();
+        let id = original.spawn("appender").unwrap();
+        original.set_rate_limit(&id, Some(limit)).unwrap();
+        original
+            .send(&id, TypedValue::String("x".to_string()))
+            .unwrap();
+        assert!(matches!(
+            original.send(&id, TypedValue::String("y".to_string())),
+            Err(RuntimeError::RateLimited(_))
+        ));
+        original.run_until_idle().unwrap();
+        original.stop_recording().unwrap();
+        original.unregister_actor(&id);
+
+        let records = read_trace(&trace).unwrap();
+        assert_eq!(
+            records[1..],
+            [
+                TraceRecord::RateLimit {
+                    id: id.clone(),
+                    limit: Some(limit)
+                },
+                TraceRecord::Send {
+                    to: id.clone(),
+                    msg: TypedValue::String("x".to_string())
+                },
+                TraceRecord::Deliver { to: id.clone() },
+            ]
+        );
+
+        let replayed = runtime_at(&temp_dir.path().join("replay"));
+        let report = replay(&replayed, &trace).unwrap();
+        assert_eq!(report.deliveries, 1);
+        assert_eq!(
+            replayed.read_state(&id).unwrap(),
+            TypedValue::String("x".to_string())
+        );
+        replayed.unregister_actor(&id);
+    }
+}
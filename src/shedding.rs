@@ -0,0 +1,137 @@
+//! Load shedding under system pressure
+//!
+//! With `RuntimeConfig::load_shedding` set, the runtime tracks how much is
+//! queued across every inbox. Once that passes a threshold, actors of the
+//! low-priority behaviors start rejecting messages with
+//! `RuntimeError::Overloaded`, except those the `is_critical` predicate
+//! accepts, so the critical actors keep their latency while the backlog
+//! drains. Shedding stops as soon as pressure is back under the thresholds.
+//!
+//! Pressure is measured by `ActorRuntime::check_pressure`, which walks the
+//! inboxes; sends only read the resulting flag. A `PressureMonitor` calls
+//! it periodically:
+//!
+//! ```rust,ignore
+//! let config = RuntimeConfig {
+//!     load_shedding: Some(LoadShedding {
+//!         max_queued: Some(100_000),
+//!         low_priority: ["ingest".to_string()].into(),
+//!         ..LoadShedding::default()
+//!     }),
+//!     ..RuntimeConfig::default()
+//! };
+//! let runtime = Arc::new(ActorRuntime::new(config));
+//! let _monitor = PressureMonitor::spawn(&runtime, Duration::from_millis(50));
+//! ```
+
+use crate::runtime::{ActorRuntime, Envelope, Payload};
+use crate::serialize::TypedValue;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// When and what to shed
+#[derive(Debug, Clone)]
+pub struct LoadShedding {
+    /// Messages queued across all inboxes before shedding starts (None =
+    /// no limit)
+    pub max_queued: Option<usize>,
+    /// Serialized bytes queued across all inboxes before shedding starts
+    /// (None = no limit)
+    pub max_queued_bytes: Option<usize>,
+    /// Behaviors whose actors reject messages while shedding
+    pub low_priority: HashSet<String>,
+    /// Messages low-priority actors still accept while shedding
+    pub is_critical: fn(&TypedValue) -> bool,
+}
+
+impl Default for LoadShedding {
+    fn default() -> Self {
+        LoadShedding {
+            max_queued: None,
+            max_queued_bytes: None,
+            low_priority: HashSet::new(),
+            is_critical: |_| false,
+        }
+    }
+}
+
+impl LoadShedding {
+    /// Whether `pressure` is past either threshold
+    pub fn exceeded(&self, pressure: &Pressure) -> bool {
+        self.max_queued.is_some_and(|max| pressure.queued > max)
+            || self
+                .max_queued_bytes
+                .is_some_and(|max| pressure.queued_bytes > max)
+    }
+
+    /// Whether a low-priority actor takes `envelope` while shedding
+    pub(crate) fn accepts(&self, envelope: &Envelope) -> bool {
+        match &envelope.payload {
+            Payload::Inline(msg) => (self.is_critical)(msg),
+            Payload::Shared(msg) => (self.is_critical)(msg),
+            Payload::Offloaded(_) => false,
+        }
+    }
+}
+
+/// Queued work across the runtime at one point in time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pressure {
+    /// Messages waiting in all inboxes
+    pub queued: usize,
+    /// Their serialized size in bytes (an estimate of the memory they hold)
+    pub queued_bytes: usize,
+    /// Low-priority actors are shedding
+    pub shedding: bool,
+}
+
+/// Background thread measuring the runtime's pressure
+///
+/// Stops when dropped, or when the runtime it watches is dropped.
+pub struct PressureMonitor {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PressureMonitor {
+    /// Start calling `runtime.check_pressure()` every `interval`
+    pub fn spawn(runtime: &Arc<ActorRuntime>, interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let runtime: Weak<ActorRuntime> = Arc::downgrade(runtime);
+
+        let thread = {
+            let stop = stop.clone();
+            std::thread::Builder::new()
+                .name("seq-actors-pressure".to_string())
+                .spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        let Some(runtime) = runtime.upgrade() else {
+                            break;
+                        };
+                        runtime.check_pressure();
+                        drop(runtime);
+                        std::thread::park_timeout(interval);
+                    }
+                })
+                .expect("failed to spawn pressure monitor thread")
+        };
+
+        PressureMonitor {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for PressureMonitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
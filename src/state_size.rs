@@ -0,0 +1,59 @@
+//! Actor state size accounting
+//!
+//! An actor's state stays in memory for as long as it runs, so one whose
+//! state only grows (a map of every session it has seen, say) eventually
+//! takes the process down with it. Give actors a `StateLimit`
+//! (`RuntimeConfig::state_limit`, or `ActorRuntime::set_state_limit` per
+//! actor) and the runtime measures their state after every message.
+//!
+//! When a state grows past the limit, the runtime counts it in
+//! `state_limit_exceeded`, tells the observer (`on_state_limit`), and
+//! applies the limit's action: `Warn` leaves the actor running;
+//! `Passivate` stops it with `StopReason::Passivated`. Like any stop, that
+//! handles the messages already queued and writes a final snapshot, so the
+//! actor can be spawned again with the same ID once memory allows.
+//!
+//! Sizes are the state's serialized size, the same estimate used for
+//! message sizes; the last measurement is reported by
+//! `ActorRuntime::health`.
+
+use crate::serialize::TypedValue;
+
+/// Estimated size of a state in bytes
+pub fn state_size(state: &TypedValue) -> usize {
+    bincode::serialized_size(state)
+        .map(|n| n as usize)
+        .unwrap_or(0)
+}
+
+/// What happens when an actor's state outgrows its limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateLimitAction {
+    /// Report it and carry on
+    Warn,
+    /// Report it, then stop the actor with a final snapshot
+    Passivate,
+}
+
+/// Cap on an actor's state size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateLimit {
+    pub max_bytes: usize,
+    pub action: StateLimitAction,
+}
+
+impl StateLimit {
+    pub fn warn(max_bytes: usize) -> Self {
+        StateLimit {
+            max_bytes,
+            action: StateLimitAction::Warn,
+        }
+    }
+
+    pub fn passivate(max_bytes: usize) -> Self {
+        StateLimit {
+            max_bytes,
+            action: StateLimitAction::Passivate,
+        }
+    }
+}
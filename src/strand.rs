@@ -0,0 +1,133 @@
+//! Waiting on seq-runtime strands
+//!
+//! Seq code runs on seq-runtime strands (May coroutines), many to an OS
+//! thread. A builtin that blocks its thread while it waits (for the
+//! journal writer, for an actor to go idle or stop) stalls every other
+//! strand scheduled there. Builtins that may wait run their work under
+//! `yielding`, which tells waits on the thread how to give way to the
+//! scheduler instead:
+//!
+//! ```rust,ignore
+//! strand::yielding(strand_yield, || runtime.snapshot_now(&id))
+//! ```
+//!
+//! Everywhere else (behaviors on the runtime's own threads, embedding
+//! code) waits block their thread as usual.
+
+use std::cell::Cell;
+use std::sync::mpsc::{Receiver, RecvError, TryRecvError};
+use std::sync::{Condvar, LockResult, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+thread_local! {
+    /// How waits on this thread yield, while a builtin runs on a strand
+    static YIELD: Cell<Option<fn()>> = const { Cell::new(None) };
+}
+
+/// Run `f` with waits on this thread yielding through `yield_now`
+pub(crate) fn yielding<R>(yield_now: fn(), f: impl FnOnce() -> R) -> R {
+    /// Puts the previous hook back, even if `f` panics
+    struct Restore(Option<fn()>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            YIELD.set(self.0);
+        }
+    }
+
+    let _restore = Restore(YIELD.replace(Some(yield_now)));
+    f()
+}
+
+/// How waits on this thread yield (`None` off a strand: they block)
+pub(crate) fn yield_hook() -> Option<fn()> {
+    YIELD.get()
+}
+
+/// Receive from `receiver`, yielding between polls on a strand
+pub(crate) fn recv<T>(receiver: &Receiver<T>) -> Result<T, RecvError> {
+    let Some(yield_now) = yield_hook() else {
+        return receiver.recv();
+    };
+    loop {
+        match receiver.try_recv() {
+            Ok(value) => return Ok(value),
+            Err(TryRecvError::Empty) => yield_now(),
+            Err(TryRecvError::Disconnected) => return Err(RecvError),
+        }
+    }
+}
+
+/// Wait on `signal` for at most `timeout`, as `Condvar::wait_timeout`
+///
+/// On a strand this unlocks, yields once and locks `lock` again instead,
+/// so it may return before `timeout` without a notification: callers
+/// check their condition in a loop, as they must for spurious wakeups.
+pub(crate) fn wait_timeout<'a, T>(
+    lock: &'a Mutex<T>,
+    guard: MutexGuard<'a, T>,
+    signal: &Condvar,
+    timeout: Duration,
+) -> LockResult<MutexGuard<'a, T>> {
+    let Some(yield_now) = yield_hook() else {
+        return match signal.wait_timeout(guard, timeout) {
+            Ok((guard, _)) => Ok(guard),
+            Err(e) => Err(PoisonError::new(e.into_inner().0)),
+        };
+    };
+    drop(guard);
+    yield_now();
+    lock.lock()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Instant;
+
+    thread_local! {
+        static YIELDS: Cell<u32> = const { Cell::new(0) };
+    }
+
+    fn count_yield() {
+        YIELDS.set(YIELDS.get() + 1);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_recv_yields_on_a_strand() {
+        let (sender, receiver) = mpsc::channel();
+        let later = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            sender.send(7).unwrap();
+        });
+        assert_eq!(yielding(count_yield, || recv(&receiver)), Ok(7));
+        later.join().unwrap();
+        assert!(YIELDS.get() > 0);
+
+        // Off the strand again, waits block
+        assert!(yield_hook().is_none());
+        assert_eq!(recv(&receiver), Err(RecvError));
+    }
+
+    #[test]
+    fn test_wait_timeout_yields_on_a_strand() {
+        let lock = Mutex::new(());
+        let signal = Condvar::new();
+        let started = Instant::now();
+        let before = YIELDS.get();
+        yielding(count_yield, || {
+            let guard = lock.lock().unwrap();
+            drop(wait_timeout(&lock, guard, &signal, Duration::from_secs(10)).unwrap());
+        });
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(YIELDS.get(), before + 1);
+
+        // Off a strand it waits on the condvar
+        let started = Instant::now();
+        let guard = lock.lock().unwrap();
+        drop(wait_timeout(&lock, guard, &signal, Duration::from_millis(20)).unwrap());
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+}
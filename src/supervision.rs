@@ -0,0 +1,89 @@
+//! Supervision settings per behavior
+//!
+//! `RuntimeConfig::supervision` maps behavior names to a `Supervision`,
+//! applied to every actor spawned with that behavior, so failure handling
+//! lives in one place instead of at each spawn site:
+//!
+//! ```rust,ignore
+//! let mut config = RuntimeConfig::default();
+//! config.supervision.insert(
+//!     "payment".to_string(),
+//!     Supervision {
+//!         panic_policy: Some(PanicPolicy::RestartActor),
+//!         max_restarts: Some(3),
+//!         backoff: Some(Backoff::exponential(Duration::from_millis(100), Duration::from_secs(5))),
+//!         ..Supervision::default()
+//!     },
+//! );
+//! ```
+//!
+//! `ActorRuntime::set_panic_policy` still overrides the policy for a single
+//! actor.
+
+use crate::crash::PanicPolicy;
+use std::time::Duration;
+
+/// How an actor's behavior is supervised
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Supervision {
+    /// What happens when the behavior panics (None =
+    /// `RuntimeConfig::panic_policy`)
+    pub panic_policy: Option<PanicPolicy>,
+    /// Restarts (`PanicPolicy::RestartActor`) allowed over the actor's
+    /// life; the next panic stops it instead (None = unlimited)
+    pub max_restarts: Option<u32>,
+    /// Pause after each restart before the actor handles its next message
+    /// (None = resume straight away)
+    pub backoff: Option<Backoff>,
+    /// Journal the actor's events and snapshots (None =
+    /// `RuntimeConfig::journaling_enabled`)
+    pub journaling: Option<bool>,
+}
+
+/// Delay between restarts, doubling from `initial` up to `max`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl Backoff {
+    pub fn exponential(initial: Duration, max: Duration) -> Self {
+        Backoff { initial, max }
+    }
+
+    /// The same delay after every restart
+    pub fn fixed(delay: Duration) -> Self {
+        Backoff {
+            initial: delay,
+            max: delay,
+        }
+    }
+
+    /// Delay after the `restart`th restart (counting from 1)
+    pub fn delay(&self, restart: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(restart.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let backoff = Backoff::exponential(Duration::from_millis(100), Duration::from_secs(1));
+        let delays: Vec<_> = (1..=6).map(|n| backoff.delay(n).as_millis()).collect();
+        assert_eq!(delays, [100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(backoff.delay(64), Duration::from_secs(1));
+        assert_eq!(
+            Backoff::fixed(Duration::from_millis(5))
+                .delay(9)
+                .as_millis(),
+            5
+        );
+    }
+}
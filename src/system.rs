@@ -0,0 +1,279 @@
+//! The system actor
+//!
+//! `ActorId::SYSTEM` is a reserved ID no behavior runs under. Actors that
+//! subscribe to it (`ActorRuntime::subscribe_system`, or `system-subscribe`
+//! from Seq) are sent a tagged message for each runtime lifecycle event,
+//! so monitoring and alerting can be written as ordinary actors:
+//!
+//! ```text
+//! { tag: "ActorSpawned", fields: { actor: "<id>", behavior: "..." } }
+//! { tag: "ActorStopped", fields: { actor: "<id>", reason: "error", error: "..." } }
+//! { tag: "ActorCrashed", fields: { actor: "<id>", error: "panicked: ..." } }
+//! { tag: "DiskPressure", fields: { actor: "<id>", limit: 1048576, used: 1048000 } }
+//! { tag: "NodeJoined", fields: { node: "..." } }
+//! ```
+//!
+//! `ActorStopped` carries the `StopReason` fields of a `Down` message
+//! (see `lifecycle`). `ActorCrashed` is sent when a behavior panics,
+//! whatever its `PanicPolicy` then does. `DiskPressure` is sent when a
+//! journal append is refused by the quota, naming the actor whose append
+//! failed. The runtime has no cluster membership of its own, so it never
+//! sends `NodeJoined`; hosts that do publish it (and any other event)
+//! with `ActorRuntime::publish_system`.
+//!
+//! # Filters
+//!
+//! High-volume events needn't flood a subscriber that only wants some of
+//! them. `ActorRuntime::subscribe_system_filtered` registers a
+//! `SubscriptionFilter`, evaluated when an event is published: only
+//! events with one of its tags (any tag if it lists none) and whose
+//! fields equal every field it names are sent.
+//!
+//! ```rust,ignore
+//! let filter = SubscriptionFilter::tags(&["ActorStopped", "ActorCrashed"])
+//!     .field("reason", TypedValue::String("error".to_string()));
+//! runtime.subscribe_system_filtered(&alerts, filter);
+//! ```
+//!
+//! Messages are sent from `ActorId::SYSTEM`, which can't be replied to.
+//! A subscriber isn't told about itself: a stopped actor can't receive
+//! its own `ActorStopped`. Subscriptions end when the subscriber is
+//! unregistered.
+
+use crate::actor::ActorId;
+use crate::lifecycle::StopReason;
+use crate::message::TypedMessage;
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::collections::BTreeMap;
+
+fn key(name: &str) -> TypedMapKey {
+    TypedMapKey::String(name.to_string())
+}
+
+/// Which system events a subscriber is sent
+///
+/// The default filter lets every event through.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubscriptionFilter {
+    /// Tags let through (empty = any tag)
+    pub tags: Vec<String>,
+    /// Message fields that must have these values
+    pub fields: Vec<(String, TypedValue)>,
+}
+
+impl SubscriptionFilter {
+    /// Let through events with one of these tags
+    pub fn tags(tags: &[&str]) -> Self {
+        SubscriptionFilter {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            fields: vec![],
+        }
+    }
+
+    /// Also require field `name` of the message to equal `value`
+    pub fn field(mut self, name: &str, value: TypedValue) -> Self {
+        self.fields.push((name.to_string(), value));
+        self
+    }
+
+    /// Whether `msg`, a system event's message, passes the filter
+    pub fn matches(&self, msg: &TypedValue) -> bool {
+        let Ok(message) = TypedMessage::try_from(msg) else {
+            return false;
+        };
+        if !self.tags.is_empty() && !self.tags.contains(&message.tag) {
+            return false;
+        }
+        let TypedValue::Map(fields) = &message.fields else {
+            return self.fields.is_empty();
+        };
+        self.fields
+            .iter()
+            .all(|(name, value)| fields.get(&key(name)) == Some(value))
+    }
+}
+
+/// A runtime lifecycle event, as sent to system subscribers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SystemEvent {
+    ActorSpawned {
+        actor: ActorId,
+        behavior: String,
+    },
+    /// An actor terminated
+    ActorStopped {
+        actor: ActorId,
+        reason: StopReason,
+    },
+    /// An actor's behavior panicked
+    ActorCrashed {
+        actor: ActorId,
+        error: String,
+    },
+    /// A journal append was refused by the disk quota
+    DiskPressure {
+        actor: ActorId,
+        limit: u64,
+        used: u64,
+    },
+    NodeJoined {
+        node: String,
+    },
+}
+
+impl SystemEvent {
+    /// Tag of the event's message
+    pub fn tag(&self) -> &'static str {
+        match self {
+            SystemEvent::ActorSpawned { .. } => "ActorSpawned",
+            SystemEvent::ActorStopped { .. } => "ActorStopped",
+            SystemEvent::ActorCrashed { .. } => "ActorCrashed",
+            SystemEvent::DiskPressure { .. } => "DiskPressure",
+            SystemEvent::NodeJoined { .. } => "NodeJoined",
+        }
+    }
+
+    /// The actor the event is about, if any
+    pub fn actor(&self) -> Option<&ActorId> {
+        match self {
+            SystemEvent::ActorSpawned { actor, .. }
+            | SystemEvent::ActorStopped { actor, .. }
+            | SystemEvent::ActorCrashed { actor, .. }
+            | SystemEvent::DiskPressure { actor, .. } => Some(actor),
+            SystemEvent::NodeJoined { .. } => None,
+        }
+    }
+
+    /// The tagged message sent to subscribers
+    pub fn to_message(&self) -> TypedValue {
+        let string = |s: &str| TypedValue::String(s.to_string());
+        let mut fields = BTreeMap::new();
+        match self {
+            SystemEvent::ActorSpawned { behavior, .. } => {
+                fields.insert(key("behavior"), string(behavior));
+            }
+            SystemEvent::ActorStopped { reason, .. } => {
+                let TypedValue::Map(reason) = reason.to_value() else {
+                    unreachable!("StopReason::to_value is a Map");
+                };
+                fields.extend(reason);
+            }
+            SystemEvent::ActorCrashed { error, .. } => {
+                fields.insert(key("error"), string(error));
+            }
+            SystemEvent::DiskPressure { limit, used, .. } => {
+                fields.insert(key("limit"), TypedValue::Int(*limit as i64));
+                fields.insert(key("used"), TypedValue::Int(*used as i64));
+            }
+            SystemEvent::NodeJoined { node } => {
+                fields.insert(key("node"), string(node));
+            }
+        }
+        if let Some(actor) = self.actor() {
+            fields.insert(key("actor"), TypedValue::String(actor.to_string()));
+        }
+        TypedMessage::new(self.tag(), TypedValue::Map(fields)).into()
+    }
+
+    /// Inverse of `to_message`
+    pub fn from_message(msg: &TypedValue) -> Option<Self> {
+        let message = TypedMessage::try_from(msg).ok()?;
+        let TypedValue::Map(fields) = &message.fields else {
+            return None;
+        };
+        let string = |name| match fields.get(&key(name)) {
+            Some(TypedValue::String(s)) => Some(s.clone()),
+            _ => None,
+        };
+        let int = |name| match fields.get(&key(name)) {
+            Some(TypedValue::Int(n)) => Some(*n as u64),
+            _ => None,
+        };
+        let actor = || string("actor")?.parse::<ActorId>().ok();
+        Some(match message.tag.as_str() {
+            "ActorSpawned" => SystemEvent::ActorSpawned {
+                actor: actor()?,
+                behavior: string("behavior")?,
+            },
+            "ActorStopped" => SystemEvent::ActorStopped {
+                actor: actor()?,
+                reason: StopReason::from_value(&message.fields)?,
+            },
+            "ActorCrashed" => SystemEvent::ActorCrashed {
+                actor: actor()?,
+                error: string("error")?,
+            },
+            "DiskPressure" => SystemEvent::DiskPressure {
+                actor: actor()?,
+                limit: int("limit")?,
+                used: int("used")?,
+            },
+            "NodeJoined" => SystemEvent::NodeJoined {
+                node: string("node")?,
+            },
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_event_round_trip() {
+        let actor = ActorId::new();
+        for event in [
+            SystemEvent::ActorSpawned {
+                actor: actor.clone(),
+                behavior: "counter".to_string(),
+            },
+            SystemEvent::ActorStopped {
+                actor: actor.clone(),
+                reason: StopReason::Error("boom".to_string()),
+            },
+            SystemEvent::ActorCrashed {
+                actor: actor.clone(),
+                error: "panicked: boom".to_string(),
+            },
+            SystemEvent::DiskPressure {
+                actor: actor.clone(),
+                limit: 1024,
+                used: 1000,
+            },
+            SystemEvent::NodeJoined {
+                node: "node-2".to_string(),
+            },
+        ] {
+            let msg = event.to_message();
+            assert_eq!(crate::message::tag_of(&msg), Some(event.tag()));
+            assert_eq!(SystemEvent::from_message(&msg), Some(event));
+        }
+        assert_eq!(SystemEvent::from_message(&TypedValue::Int(1)), None);
+    }
+
+    #[test]
+    fn test_subscription_filter() {
+        let actor = ActorId::new();
+        let stopped = |reason| {
+            SystemEvent::ActorStopped {
+                actor: actor.clone(),
+                reason,
+            }
+            .to_message()
+        };
+        let crashed = SystemEvent::ActorCrashed {
+            actor: actor.clone(),
+            error: "boom".to_string(),
+        }
+        .to_message();
+
+        assert!(SubscriptionFilter::default().matches(&crashed));
+        let filter = SubscriptionFilter::tags(&["ActorStopped"]);
+        assert!(filter.matches(&stopped(StopReason::Normal)));
+        assert!(!filter.matches(&crashed));
+        let errors = filter.field("reason", TypedValue::String("error".to_string()));
+        assert!(errors.matches(&stopped(StopReason::Error("boom".to_string()))));
+        assert!(!errors.matches(&stopped(StopReason::Normal)));
+    }
+}
@@ -0,0 +1,223 @@
+//! Test utilities for actor behaviors
+//!
+//! `BehaviorHarness` drives a single behavior against in-memory state with
+//! a scripted sequence of messages. No coroutines, channels, or journal
+//! files are involved, so behavior logic can be unit-tested directly.
+//!
+//! ```rust,ignore
+//! use seq_actors::testkit::BehaviorHarness;
+//!
+//! let outcome = BehaviorHarness::new(account_behavior)
+//!     .with_state(opening_balance)
+//!     .run([deposit(100), withdraw(30)])?;
+//!
+//! assert_eq!(outcome.events.len(), 2);
+//! ```
+
+use crate::actor::{Actor, ActorId};
+use crate::behavior::{handle_message, Behavior, BehaviorError};
+use crate::journal::Event;
+use crate::serialize::TypedValue;
+use std::collections::VecDeque;
+
+/// Messages one `send` may deliver (including self-sends) before the
+/// harness assumes a self-send loop
+const MAX_SELF_SENDS: usize = 10_000;
+
+/// Final state and emitted events after running a script
+#[derive(Debug, Clone)]
+pub struct HarnessOutcome {
+    /// State after the last message
+    pub state: TypedValue,
+    /// Every event emitted, in order
+    pub events: Vec<Event>,
+}
+
+/// Drives one behavior with scripted messages
+pub struct BehaviorHarness<B: Behavior> {
+    behavior: B,
+    actor: Actor,
+    events: Vec<Event>,
+}
+
+impl<B: Behavior> BehaviorHarness<B> {
+    /// Create a harness with an empty Map as the initial state
+    pub fn new(behavior: B) -> Self {
+        let state = behavior.initial_state();
+        BehaviorHarness {
+            behavior,
+            actor: Actor::with_state(ActorId::new(), "test".to_string(), state, 0),
+            events: vec![],
+        }
+    }
+
+    /// Start from a specific state
+    pub fn with_state(mut self, state: TypedValue) -> Self {
+        self.actor.state = state;
+        self
+    }
+
+    /// Deliver one message, returning the events it emitted
+    ///
+    /// Messages the behavior sends itself are delivered right after, and
+    /// their events are included. As in the runtime, each of them is a
+    /// message of its own: one that fails leaves the state as the previous
+    /// one left it, and what earlier ones changed and emitted stays. The
+    /// self-sends still queued after a failure are dropped. Panics if
+    /// self-sends never settle.
+    pub fn send(&mut self, msg: TypedValue) -> Result<&[Event], BehaviorError> {
+        let start = self.events.len();
+        let mut pending = VecDeque::from([msg]);
+        let mut delivered = 0;
+
+        while let Some(msg) = pending.pop_front() {
+            delivered += 1;
+            assert!(
+                delivered <= MAX_SELF_SENDS,
+                "self-send loop in behavior under test"
+            );
+            let handled = handle_message(&self.behavior, &mut self.actor, &msg)?;
+            self.events.extend(handled.events);
+            pending.extend(handled.self_sends);
+        }
+        Ok(&self.events[start..])
+    }
+
+    /// Deliver every message in order, stopping at the first failure
+    pub fn run(
+        mut self,
+        messages: impl IntoIterator<Item = TypedValue>,
+    ) -> Result<HarnessOutcome, BehaviorError> {
+        for msg in messages {
+            self.send(msg)?;
+        }
+        Ok(self.finish())
+    }
+
+    /// Current state
+    pub fn state(&self) -> &TypedValue {
+        &self.actor.state
+    }
+
+    /// Events emitted so far
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// ID of the simulated actor
+    pub fn actor_id(&self) -> &ActorId {
+        &self.actor.id
+    }
+
+    /// Consume the harness, returning the final state and events
+    pub fn finish(self) -> HarnessOutcome {
+        HarnessOutcome {
+            state: self.actor.state,
+            events: self.events,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavior::BehaviorContext;
+    use crate::serialize::MapKey;
+    use std::collections::BTreeMap;
+
+    /// Account behavior: Int messages are deposits (positive) or withdrawals
+    fn account(
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        let balance_key = MapKey::String("balance".to_string());
+        let (TypedValue::Map(map), TypedValue::Int(amount)) = (state, msg) else {
+            return Err(BehaviorError::new("unexpected message"));
+        };
+        let balance = match map.get(&balance_key) {
+            Some(TypedValue::Int(b)) => *b,
+            _ => 0,
+        };
+        if balance + amount < 0 {
+            return Err(BehaviorError::new("insufficient funds"));
+        }
+
+        ctx.emit(
+            if *amount >= 0 { "Deposit" } else { "Withdraw" },
+            msg.clone(),
+        );
+        let mut next = map.clone();
+        next.insert(balance_key, TypedValue::Int(balance + amount));
+        Ok(TypedValue::Map(next))
+    }
+
+    fn balance(state: &TypedValue) -> i64 {
+        match state {
+            TypedValue::Map(m) => match m.get(&MapKey::String("balance".to_string())) {
+                Some(TypedValue::Int(b)) => *b,
+                _ => 0,
+            },
+            _ => panic!("Expected Map"),
+        }
+    }
+
+    #[test]
+    fn test_run_script() {
+        let outcome = BehaviorHarness::new(account)
+            .run([TypedValue::Int(100), TypedValue::Int(-30)])
+            .unwrap();
+
+        assert_eq!(balance(&outcome.state), 70);
+        let types: Vec<&str> = outcome
+            .events
+            .iter()
+            .map(|e| e.event_type.as_str())
+            .collect();
+        assert_eq!(types, ["Deposit", "Withdraw"]);
+        assert_eq!(outcome.events[1].seq, 1);
+    }
+
+    #[test]
+    fn test_failed_message_keeps_state() {
+        let mut state = BTreeMap::new();
+        state.insert(MapKey::String("balance".to_string()), TypedValue::Int(10));
+        let mut harness = BehaviorHarness::new(account).with_state(TypedValue::Map(state));
+
+        assert!(harness.send(TypedValue::Int(-50)).is_err());
+        assert_eq!(balance(harness.state()), 10);
+        assert!(harness.events().is_empty());
+
+        let emitted = harness.send(TypedValue::Int(5)).unwrap();
+        assert_eq!(emitted.len(), 1);
+        assert_eq!(balance(harness.state()), 15);
+    }
+
+    /// Counts down by 2 through self-sends, failing below zero
+    fn countdown(
+        ctx: &mut BehaviorContext,
+        _state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        match msg {
+            TypedValue::Int(n) if *n >= 0 => {
+                ctx.emit("Counted", msg.clone());
+                if *n > 0 {
+                    ctx.send_self(TypedValue::Int(n - 2));
+                }
+                Ok(msg.clone())
+            }
+            _ => Err(BehaviorError::new("below zero")),
+        }
+    }
+
+    #[test]
+    fn test_failed_self_send_keeps_earlier_links() {
+        let mut harness = BehaviorHarness::new(countdown);
+
+        // 3 and 1 are handled, then -1 fails
+        assert!(harness.send(TypedValue::Int(3)).is_err());
+        assert_eq!(harness.state(), &TypedValue::Int(1));
+        assert_eq!(harness.events().len(), 2);
+    }
+}
@@ -0,0 +1,214 @@
+//! Throughput history in the journal
+//!
+//! The runtime counts the messages each actor handled and failed
+//! (`health::MessageCounts`), but only in memory.
+//! `ActorRuntime::record_throughput` journals what every actor handled
+//! since the previous recording as one `MetricsRecorded` event under the
+//! reserved actor `ActorId::SYSTEM`, so historical throughput can be
+//! reconstructed from the journal alone (`ActorRuntime::throughput_history`),
+//! without an external metrics store. Recording is opt-in: run a `ThroughputRecorder` to record
+//! periodically.
+//!
+//! ```rust,ignore
+//! let runtime = Arc::new(ActorRuntime::new(config));
+//! let _recorder = ThroughputRecorder::spawn(&runtime, Duration::from_secs(60));
+//! // later, or after a restart
+//! for record in runtime.throughput_history()? {
+//!     println!("{}: {} actors busy", record.ts, record.actors.len());
+//! }
+//! ```
+//!
+//! Each event's payload maps actor IDs to `handled` and `failed` counts;
+//! actors with nothing to report are left out, and so is an interval in
+//! which no actor handled anything. Counts of an actor unregistered
+//! between two recordings are lost. Nothing is recorded with journaling
+//! disabled.
+
+use crate::actor::ActorId;
+use crate::health::MessageCounts;
+use crate::journal::Event;
+use crate::runtime::ActorRuntime;
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Event type of a throughput recording
+pub const METRICS_RECORDED: &str = "MetricsRecorded";
+
+fn key(name: &str) -> TypedMapKey {
+    TypedMapKey::String(name.to_string())
+}
+
+/// One recorded interval
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThroughputRecord {
+    pub seq: u64,
+    /// Unix timestamp (milliseconds) the interval ended
+    pub ts: u64,
+    /// Messages each actor handled in the interval, by ID
+    pub actors: Vec<(ActorId, MessageCounts)>,
+}
+
+impl ThroughputRecord {
+    /// Payload of the `MetricsRecorded` event for an interval
+    pub(crate) fn payload(actors: &[(ActorId, MessageCounts)]) -> TypedValue {
+        let counts = |counts: &MessageCounts| {
+            TypedValue::Map(BTreeMap::from([
+                (key("handled"), TypedValue::Int(counts.handled as i64)),
+                (key("failed"), TypedValue::Int(counts.failed as i64)),
+            ]))
+        };
+        let actors = actors
+            .iter()
+            .map(|(id, c)| (TypedMapKey::String(id.to_string()), counts(c)));
+        TypedValue::Map(BTreeMap::from([(
+            key("actors"),
+            TypedValue::Map(actors.collect()),
+        )]))
+    }
+
+    /// Read a record back from its journaled event
+    pub fn from_event(event: &Event) -> Option<Self> {
+        if event.event_type != METRICS_RECORDED {
+            return None;
+        }
+        let TypedValue::Map(fields) = &event.payload else {
+            return None;
+        };
+        let Some(TypedValue::Map(actors)) = fields.get(&key("actors")) else {
+            return None;
+        };
+        let count = |counts: &BTreeMap<TypedMapKey, TypedValue>, name| match counts.get(&key(name))
+        {
+            Some(TypedValue::Int(n)) => Some(*n as u64),
+            _ => None,
+        };
+        let actors = actors
+            .iter()
+            .map(|(id, counts)| {
+                let (TypedMapKey::String(id), TypedValue::Map(counts)) = (id, counts) else {
+                    return None;
+                };
+                let counts = MessageCounts {
+                    handled: count(counts, "handled")?,
+                    failed: count(counts, "failed")?,
+                };
+                Some((id.parse().ok()?, counts))
+            })
+            .collect::<Option<_>>()?;
+        Some(ThroughputRecord {
+            seq: event.seq,
+            ts: event.ts,
+            actors,
+        })
+    }
+}
+
+/// What the runtime recorded last
+#[derive(Debug, Default)]
+pub(crate) struct ThroughputLog {
+    /// Next seq in `ActorId::SYSTEM`'s journal (loaded on first use)
+    pub(crate) next_seq: Option<u64>,
+    /// Counts as of the last recording
+    recorded: HashMap<ActorId, MessageCounts>,
+}
+
+impl ThroughputLog {
+    /// Counts accumulated since the last recording, for actors that have
+    /// any
+    pub(crate) fn since_last(
+        &self,
+        current: &[(ActorId, MessageCounts)],
+    ) -> Vec<(ActorId, MessageCounts)> {
+        let mut deltas: Vec<_> = current
+            .iter()
+            .map(|(id, counts)| {
+                let before = self.recorded.get(id).copied().unwrap_or_default();
+                (id.clone(), counts.since(&before))
+            })
+            .filter(|(_, delta)| delta.handled > 0)
+            .collect();
+        deltas.sort_by_key(|(id, _)| id.0);
+        deltas
+    }
+
+    /// Remember `current` as recorded
+    pub(crate) fn recorded(&mut self, current: Vec<(ActorId, MessageCounts)>) {
+        self.recorded = current.into_iter().collect();
+    }
+}
+
+/// Background thread journaling throughput periodically
+///
+/// Stops when dropped, or when the runtime it records is dropped.
+pub struct ThroughputRecorder {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ThroughputRecorder {
+    /// Start calling `runtime.record_throughput()` every `interval`
+    pub fn spawn(runtime: &Arc<ActorRuntime>, interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let runtime: Weak<ActorRuntime> = Arc::downgrade(runtime);
+
+        let thread = {
+            let stop = stop.clone();
+            std::thread::Builder::new()
+                .name("seq-actors-throughput".to_string())
+                .spawn(move || {
+                    std::thread::park_timeout(interval);
+                    while !stop.load(Ordering::Relaxed) {
+                        let Some(runtime) = runtime.upgrade() else {
+                            break;
+                        };
+                        // A failed recording is retried with the next interval
+                        let _ = runtime.record_throughput();
+                        drop(runtime);
+                        std::thread::park_timeout(interval);
+                    }
+                })
+                .expect("failed to spawn throughput recorder thread")
+        };
+
+        ThroughputRecorder {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for ThroughputRecorder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_deltas_and_reads_them_back() {
+        let (busy, idle) = (ActorId::new(), ActorId::new());
+        let counts = |handled, failed| MessageCounts { handled, failed };
+        let mut log = ThroughputLog::default();
+        let current = vec![(busy.clone(), counts(3, 1)), (idle.clone(), counts(0, 0))];
+        assert_eq!(log.since_last(&current), [(busy.clone(), counts(3, 1))]);
+        log.recorded(current);
+        let second = log.since_last(&[(busy.clone(), counts(5, 1)), (idle, counts(0, 0))]);
+        assert_eq!(second, [(busy.clone(), counts(2, 0))]);
+
+        let payload = ThroughputRecord::payload(&second);
+        let event = Event::new(4, METRICS_RECORDED.to_string(), payload);
+        let record = ThroughputRecord::from_event(&event).unwrap();
+        assert_eq!((record.seq, record.actors), (4, second));
+    }
+}
@@ -0,0 +1,52 @@
+//! Storage tiers
+//!
+//! Every actor's journal and snapshots normally live under
+//! `RuntimeConfig::journal_path`. A `StorageTier` puts them somewhere
+//! else, so hot actors can sit on fast disks next to archival ones on
+//! bulk storage, and throwaway ones skip storage altogether:
+//!
+//! ```rust,ignore
+//! let config = RuntimeConfig {
+//!     journal_path: "/mnt/hdd/actors".into(),
+//!     storage_tiers: [("nvme".to_string(), "/mnt/nvme/actors".into())].into(),
+//!     behavior_tiers: [
+//!         ("order-book".to_string(), StorageTier::named("nvme")),
+//!         ("session".to_string(), StorageTier::MemoryOnly),
+//!     ]
+//!     .into(),
+//!     ..RuntimeConfig::default()
+//! };
+//! let hot = runtime.spawn_in_tier("pricing", StorageTier::named("nvme"))?;
+//! ```
+//!
+//! Each named tier is a journal of its own (`ActorRuntime::tier_journal`)
+//! with its own writer thread and, if configured, its own `quota`. An
+//! actor's tier is fixed when it is spawned (recovery reads from it), so
+//! respawn an actor in the tier it was journaled in. Memory-only actors
+//! are never journaled or snapshotted and start from their initial state.
+//!
+//! Runtime-wide records (aliases, singletons, offloaded messages, the
+//! configuration audit) stay in the default journal.
+//!
+//! A persistent subscription reads one journal, so a consumer following
+//! every actor subscribes to each tier's journal as well; `crate::outbox`
+//! does.
+
+/// Where an actor's journal and snapshots are stored
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum StorageTier {
+    /// Under `RuntimeConfig::journal_path`
+    #[default]
+    Default,
+    /// Under the root of the tier by this name in
+    /// `RuntimeConfig::storage_tiers`
+    Named(String),
+    /// Nowhere: the actor is not persisted
+    MemoryOnly,
+}
+
+impl StorageTier {
+    pub fn named(name: &str) -> Self {
+        StorageTier::Named(name.to_string())
+    }
+}
@@ -0,0 +1,81 @@
+//! Message handling timeouts
+//!
+//! With `RuntimeConfig::message_timeout` (or `ActorRuntime::set_message_timeout`
+//! for one actor) the runtime arms a deadline before handing a message to
+//! the behavior. Behaviors can't be interrupted, so cancellation is
+//! cooperative: long-running code polls `cancelled()` (or
+//! `BehaviorContext::cancelled`) and gives up early, and long-running
+//! builtins such as `actor-await` stop waiting once the deadline passes.
+//!
+//! ```rust,ignore
+//! for row in rows {
+//!     if ctx.cancelled() {
+//!         return Err(BehaviorError::new("gave up"));
+//!     }
+//!     // ...
+//! }
+//! ```
+//!
+//! Whatever the behavior returns, a handling that ends past its deadline
+//! fails with `RuntimeError::TimedOut`: the actor's state is left
+//! unchanged, the events it emitted are discarded, and a `TimedOut` event
+//! is journaled in their place. A behavior that never checks still blocks
+//! its thread until it returns (the `watchdog` reports it).
+
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Event type journaled for a handling that ran past its deadline
+pub const TIMED_OUT_EVENT: &str = "TimedOut";
+
+/// Payload of a `TimedOut` event
+pub fn timed_out_payload(timeout: Duration) -> TypedValue {
+    let mut fields = BTreeMap::new();
+    fields.insert(
+        TypedMapKey::String("timeout_ms".to_string()),
+        TypedValue::Int(timeout.as_millis() as i64),
+    );
+    TypedValue::Map(fields)
+}
+
+thread_local! {
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Set (or clear) the deadline of the message handled on this thread
+pub(crate) fn arm(deadline: Option<Instant>) {
+    DEADLINE.with(|d| d.set(deadline));
+}
+
+/// Whether the message being handled on this thread has run past its
+/// deadline
+pub fn cancelled() -> bool {
+    DEADLINE
+        .with(|d| d.get())
+        .is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+/// Time left before the current message's deadline (None without one)
+pub fn remaining() -> Option<Duration> {
+    DEADLINE
+        .with(|d| d.get())
+        .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline_is_per_thread() {
+        assert!(!cancelled());
+        arm(Some(Instant::now()));
+        assert!(cancelled());
+        assert_eq!(remaining(), Some(Duration::ZERO));
+        std::thread::spawn(|| assert!(!cancelled())).join().unwrap();
+        arm(None);
+        assert!(!cancelled() && remaining().is_none());
+    }
+}
@@ -0,0 +1,255 @@
+//! Embedding the runtime in tokio-based servers
+//!
+//! `TokioBridge` wraps a shared `ActorRuntime` for use from async tasks.
+//! Calls that may touch the journal (spawn, driving the actors) run on
+//! tokio's blocking pool, so they never stall the async workers; `ask`
+//! awaits the reply without holding a thread.
+//!
+//! Actor output reaches async code through channel actors: every message
+//! sent to the ID returned by `channel` comes out of a tokio mpsc receiver.
+//!
+//! ```rust,ignore
+//! let bridge = TokioBridge::new(Arc::new(ActorRuntime::with_defaults()));
+//! let _driver = bridge.drive(Duration::from_millis(5));
+//!
+//! let counter = bridge.spawn("counter").await?;
+//! let (inbox, mut output) = bridge.channel().await?;
+//! bridge.send(&counter, notify_me(inbox))?;
+//! let total = bridge.ask(&counter, get_total()).await?;
+//! ```
+//!
+//! Requires the `tokio-bridge` feature.
+
+use crate::actor::ActorId;
+use crate::behavior::{Behavior, BehaviorContext, BehaviorError};
+use crate::error::RuntimeError;
+use crate::runtime::ActorRuntime;
+use crate::serialize::TypedValue;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Prefix for the behavior each bridge registers for its channels
+const CHANNEL_BEHAVIOR_PREFIX: &str = "tokio-channel-";
+
+/// Senders of a bridge's channels, by channel actor
+type Channels = Arc<Mutex<HashMap<ActorId, mpsc::UnboundedSender<TypedValue>>>>;
+
+/// Async front end for a shared runtime
+#[derive(Clone)]
+pub struct TokioBridge {
+    runtime: Arc<ActorRuntime>,
+    /// Behavior of this bridge's channel actors, registered once
+    channel_behavior: String,
+    channels: Channels,
+}
+
+impl TokioBridge {
+    /// Wrap `runtime`, registering the behavior `channel` spawns
+    pub fn new(runtime: Arc<ActorRuntime>) -> Self {
+        let channel_behavior = format!("{}{}", CHANNEL_BEHAVIOR_PREFIX, ActorId::new());
+        let channels = Channels::default();
+        runtime.register_behavior(
+            channel_behavior.clone(),
+            ChannelForwarder {
+                channels: channels.clone(),
+            },
+        );
+        TokioBridge {
+            runtime,
+            channel_behavior,
+            channels,
+        }
+    }
+
+    /// The wrapped runtime
+    pub fn runtime(&self) -> &Arc<ActorRuntime> {
+        &self.runtime
+    }
+
+    /// Spawn an actor (recovery reads the journal, so this runs on the
+    /// blocking pool)
+    pub async fn spawn(&self, behavior: &str) -> Result<ActorId, RuntimeError> {
+        let behavior = behavior.to_string();
+        self.blocking(move |runtime| runtime.spawn(&behavior)).await
+    }
+
+    /// Queue a message (never blocks: it is only enqueued)
+    pub fn send(&self, to: &ActorId, msg: TypedValue) -> Result<(), RuntimeError> {
+        self.runtime.send(to, msg)
+    }
+
+    /// Send a message and await the behavior's reply
+    ///
+    /// Something must be driving the actors, e.g. `drive`.
+    pub async fn ask(&self, to: &ActorId, msg: TypedValue) -> Result<TypedValue, RuntimeError> {
+        self.runtime.ask(to, msg)?.await
+    }
+
+    /// Spawn an actor that forwards every message it receives to the
+    /// returned receiver
+    ///
+    /// Every channel of a bridge shares one registered behavior. Messages
+    /// arriving after the receiver is dropped are dead letters; the actor
+    /// stays until it is unregistered.
+    pub async fn channel(
+        &self,
+    ) -> Result<(ActorId, mpsc::UnboundedReceiver<TypedValue>), RuntimeError> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let id = self.runtime.new_actor_id();
+        {
+            let mut channels = self.channels.lock().expect("channels lock poisoned");
+            channels.retain(|_, tx| !tx.is_closed());
+            channels.insert(id.clone(), tx);
+        }
+        let behavior = self.channel_behavior.clone();
+        let spawn_id = id.clone();
+        let spawned = self
+            .blocking(move |runtime| runtime.spawn_with_id(spawn_id, &behavior))
+            .await;
+        if let Err(e) = spawned {
+            let mut channels = self.channels.lock().expect("channels lock poisoned");
+            channels.remove(&id);
+            return Err(e);
+        }
+        Ok((id, rx))
+    }
+
+    /// Keep handling messages in the background, checking for new ones
+    /// every `idle_interval` once all inboxes are empty
+    ///
+    /// The task ends with the first error other than a behavior failure;
+    /// abort the handle to stop it.
+    pub fn drive(&self, idle_interval: Duration) -> JoinHandle<Result<(), RuntimeError>> {
+        let bridge = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let handled = bridge.blocking(|runtime| runtime.run_until_idle()).await?;
+                if handled == 0 {
+                    tokio::time::sleep(idle_interval).await;
+                }
+            }
+        })
+    }
+
+    /// Run `f` against the runtime on tokio's blocking pool
+    async fn blocking<T, F>(&self, f: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce(&ActorRuntime) -> T + Send + 'static,
+    {
+        let runtime = self.runtime.clone();
+        match tokio::task::spawn_blocking(move || f(&runtime)).await {
+            Ok(output) => output,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+}
+
+/// Behavior behind `TokioBridge::channel`, forwarding to the sender kept
+/// for the handling actor
+struct ChannelForwarder {
+    channels: Channels,
+}
+
+impl Behavior for ChannelForwarder {
+    fn handle(
+        &self,
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        let mut channels = self.channels.lock().expect("channels lock poisoned");
+        let sent = match channels.get(ctx.actor_id()) {
+            Some(tx) => tx.send(msg.clone()).is_ok(),
+            None => false,
+        };
+        if !sent {
+            channels.remove(ctx.actor_id());
+            ctx.dead_letter();
+        }
+        Ok(state.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::RuntimeConfig;
+    use tempfile::TempDir;
+
+    fn test_bridge(temp_dir: &TempDir) -> TokioBridge {
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            ..RuntimeConfig::default()
+        });
+        runtime.register_behavior(
+            "doubler",
+            |ctx: &mut BehaviorContext, state: &TypedValue, msg: &TypedValue| {
+                if let TypedValue::Int(n) = msg {
+                    ctx.reply(TypedValue::Int(n * 2));
+                }
+                Ok(state.clone())
+            },
+        );
+        TokioBridge::new(Arc::new(runtime))
+    }
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn test_spawn_and_ask() {
+        let temp_dir = TempDir::new().unwrap();
+        let bridge = test_bridge(&temp_dir);
+
+        block_on(async {
+            let driver = bridge.drive(Duration::from_millis(1));
+            let id = bridge.spawn("doubler").await.unwrap();
+            assert_eq!(
+                bridge.ask(&id, TypedValue::Int(21)).await.unwrap(),
+                TypedValue::Int(42)
+            );
+            driver.abort();
+            bridge.runtime().unregister_actor(&id);
+        });
+    }
+
+    #[test]
+    fn test_channel_receives_messages() {
+        let temp_dir = TempDir::new().unwrap();
+        let bridge = test_bridge(&temp_dir);
+
+        block_on(async {
+            let driver = bridge.drive(Duration::from_millis(1));
+            let (id, mut rx) = bridge.channel().await.unwrap();
+            bridge.send(&id, TypedValue::Int(1)).unwrap();
+            bridge.send(&id, TypedValue::Int(2)).unwrap();
+            assert_eq!(rx.recv().await, Some(TypedValue::Int(1)));
+            assert_eq!(rx.recv().await, Some(TypedValue::Int(2)));
+
+            // A second channel reuses the behavior; the first one's sender
+            // goes once its receiver is dropped
+            let (other, mut other_rx) = bridge.channel().await.unwrap();
+            let behavior = bridge.runtime().behavior_of(&id);
+            assert_eq!(bridge.runtime().behavior_of(&other), behavior);
+            drop(rx);
+            bridge.send(&other, TypedValue::Int(3)).unwrap();
+            assert_eq!(other_rx.recv().await, Some(TypedValue::Int(3)));
+            let (last, _last_rx) = bridge.channel().await.unwrap();
+            assert_eq!(bridge.channels.lock().unwrap().len(), 2);
+
+            driver.abort();
+            for id in [id, other, last] {
+                bridge.runtime().unregister_actor(&id);
+            }
+        });
+    }
+}
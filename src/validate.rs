@@ -0,0 +1,134 @@
+//! Event validation before append
+//!
+//! Validators registered with `ActorRuntime::add_event_validator` see each
+//! event a behavior emits before anything about the message is committed.
+//! If any of them rejects one, the message fails as if the behavior had
+//! failed: the actor's state is left unchanged, none of its events are
+//! journaled, and the error is a typed `RuntimeError::EventRejected`
+//! (also the answer to an `ask`). Rejections are counted in the
+//! `events_rejected` metric.
+//!
+//! ```rust,ignore
+//! runtime.add_event_validator(MaxEventSize(64 * 1024));
+//! runtime.add_event_validator(ForbiddenFields::new(&["password", "ssn"]));
+//! runtime.add_event_validator(|_actor: &ActorId, event: &Event| {
+//!     match event.payload {
+//!         TypedValue::Map(_) => Ok(()),
+//!         _ => Err("payload must be a map".to_string()),
+//!     }
+//! });
+//! ```
+//!
+//! Validators run for every actor, in registration order, and should be
+//! cheap: they run on the scheduler thread. Events the runtime writes
+//! itself (stops, failures, stop hooks, configuration changes) are not
+//! validated.
+
+use crate::actor::ActorId;
+use crate::journal::Event;
+use crate::serialize::{TypedMapKey, TypedValue};
+use crate::state_size::state_size;
+
+/// Check applied to events before they are journaled
+pub trait EventValidator: Send + Sync {
+    /// Accept the event, or reject it with a reason
+    fn validate(&self, actor: &ActorId, event: &Event) -> Result<(), String>;
+}
+
+impl<F> EventValidator for F
+where
+    F: Fn(&ActorId, &Event) -> Result<(), String> + Send + Sync,
+{
+    fn validate(&self, actor: &ActorId, event: &Event) -> Result<(), String> {
+        self(actor, event)
+    }
+}
+
+/// Rejects events whose serialized payload is over this many bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxEventSize(pub usize);
+
+impl EventValidator for MaxEventSize {
+    fn validate(&self, _actor: &ActorId, event: &Event) -> Result<(), String> {
+        let size = state_size(&event.payload);
+        if size > self.0 {
+            return Err(format!("payload of {} bytes exceeds {}", size, self.0));
+        }
+        Ok(())
+    }
+}
+
+/// Rejects events whose payload map has any of these keys
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForbiddenFields(pub Vec<String>);
+
+impl ForbiddenFields {
+    pub fn new(fields: &[&str]) -> Self {
+        ForbiddenFields(fields.iter().map(|f| f.to_string()).collect())
+    }
+}
+
+impl EventValidator for ForbiddenFields {
+    fn validate(&self, _actor: &ActorId, event: &Event) -> Result<(), String> {
+        let TypedValue::Map(fields) = &event.payload else {
+            return Ok(());
+        };
+        match self
+            .0
+            .iter()
+            .find(|f| fields.contains_key(&TypedMapKey::String(f.to_string())))
+        {
+            Some(field) => Err(format!("forbidden field: {}", field)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// An event refused by a validator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventRejected {
+    pub actor: ActorId,
+    pub event_type: String,
+    /// Sequence number the event would have had
+    pub seq: u64,
+    pub reason: String,
+}
+
+impl std::fmt::Display for EventRejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "event {} (seq {}) of {} rejected: {}",
+            self.event_type, self.seq, self.actor, self.reason
+        )
+    }
+}
+
+impl std::error::Error for EventRejected {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_builtin_validators() {
+        let actor = ActorId::new();
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            TypedMapKey::String("password".to_string()),
+            TypedValue::String("hunter2".to_string()),
+        );
+        let event = Event::new(0, "Login".to_string(), TypedValue::Map(fields));
+
+        assert!(MaxEventSize(1024).validate(&actor, &event).is_ok());
+        assert!(MaxEventSize(8).validate(&actor, &event).is_err());
+        let forbidden = ForbiddenFields::new(&["ssn", "password"]);
+        assert_eq!(
+            forbidden.validate(&actor, &event),
+            Err("forbidden field: password".to_string())
+        );
+        let plain = Event::new(1, "Tick".to_string(), TypedValue::Int(1));
+        assert!(forbidden.validate(&actor, &plain).is_ok());
+    }
+}
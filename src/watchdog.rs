@@ -0,0 +1,70 @@
+//! Watchdog for blocked behaviors
+//!
+//! Behaviors run cooperatively; one that calls a blocking syscall (or
+//! loops forever) stalls its scheduler thread. The watchdog thread
+//! periodically asks the runtime which message handlings have run longer
+//! than a threshold and reports each one once, through the runtime's
+//! metrics (`blocked_detected`) and observer (`on_blocked`).
+//!
+//! ```rust,ignore
+//! let runtime = Arc::new(ActorRuntime::with_defaults());
+//! let _watchdog = Watchdog::spawn(&runtime, Duration::from_millis(100), Duration::from_secs(1));
+//! // ... the watchdog stops when dropped
+//! ```
+//!
+//! TODO: Optionally restart the blocked actor once supervision exists.
+
+use crate::runtime::ActorRuntime;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Background thread checking for blocked behaviors
+///
+/// Stops when dropped, or when the runtime it watches is dropped.
+pub struct Watchdog {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    /// Start checking `runtime` every `interval` for handlings older than
+    /// `threshold`
+    pub fn spawn(runtime: &Arc<ActorRuntime>, interval: Duration, threshold: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let runtime: Weak<ActorRuntime> = Arc::downgrade(runtime);
+
+        let thread = {
+            let stop = stop.clone();
+            std::thread::Builder::new()
+                .name("seq-actors-watchdog".to_string())
+                .spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        let Some(runtime) = runtime.upgrade() else {
+                            break;
+                        };
+                        runtime.check_blocked(threshold);
+                        drop(runtime);
+                        std::thread::park_timeout(interval);
+                    }
+                })
+                .expect("failed to spawn watchdog thread")
+        };
+
+        Watchdog {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,489 @@
+//! Workflow actors: ordered steps with retries and timeouts
+//!
+//! A `Workflow` is a behavior that runs a fixed list of steps in order,
+//! passing each step's output to the next. The first message starts the
+//! workflow and becomes the input of the first step. Progress is
+//! journaled as a start event holding that input and one event per step,
+//! so a respawned workflow resumes at the first step that hasn't
+//! completed, with the input it should get.
+//!
+//! ```rust,ignore
+//! let order = Workflow::new()
+//!     .step(Step::new("reserve", reserve_stock))
+//!     .step(Step::new("charge", charge_card).retries(3, Duration::from_millis(200)))
+//!     .step(Step::new("ship", ship_order).timeout(Duration::from_secs(5)));
+//! runtime.register_behavior("order", order);
+//! ```
+//!
+//! All remaining steps run within a single message handling, so steps
+//! should be idempotent: if the process dies mid-handling, the steps of
+//! that handling run again on recovery. A step that exhausts its retries
+//! leaves the workflow `failed` at that step; the next message retries it
+//! from there.
+//!
+//! Timeouts are checked when a step returns; a running step is never
+//! interrupted, and backoff sleeps the handling thread.
+//!
+//! TODO: Run Seq quotations as steps once quotation invocation is wired
+//! through the FFI layer.
+
+use crate::behavior::{Behavior, BehaviorContext, BehaviorError};
+use crate::journal::Event;
+use crate::serialize::{TypedMapKey, TypedValue};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Event journaled when the first message starts a workflow
+pub const STARTED_EVENT: &str = "WorkflowStarted";
+
+/// Event journaled when a step succeeds
+pub const STEP_COMPLETED_EVENT: &str = "WorkflowStepCompleted";
+
+/// Event journaled when a step exhausts its retries
+pub const STEP_FAILED_EVENT: &str = "WorkflowStepFailed";
+
+type StepFn = dyn Fn(&TypedValue) -> Result<TypedValue, BehaviorError> + Send + Sync;
+
+/// One step of a workflow
+#[derive(Clone)]
+pub struct Step {
+    name: String,
+    run: Arc<StepFn>,
+    attempts: u32,
+    backoff: Duration,
+    timeout: Option<Duration>,
+}
+
+impl Step {
+    /// A step that runs once, with no timeout
+    pub fn new(
+        name: impl Into<String>,
+        run: impl Fn(&TypedValue) -> Result<TypedValue, BehaviorError> + Send + Sync + 'static,
+    ) -> Self {
+        Step {
+            name: name.into(),
+            run: Arc::new(run),
+            attempts: 1,
+            backoff: Duration::ZERO,
+            timeout: None,
+        }
+    }
+
+    /// Retry up to `retries` more times, waiting `backoff` before the first
+    /// retry and doubling it each time after
+    pub fn retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.attempts = retries + 1;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Fail an attempt that takes longer than `timeout`
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Run with retries, returning the output or the last error
+    fn execute(&self, input: &TypedValue) -> Result<TypedValue, BehaviorError> {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            let started = Instant::now();
+            let result = (self.run)(input).and_then(|output| match self.timeout {
+                Some(timeout) if started.elapsed() > timeout => Err(BehaviorError::new(format!(
+                    "step {} timed out after {:?}",
+                    self.name, timeout
+                ))),
+                _ => Ok(output),
+            });
+
+            match result {
+                Ok(output) => return Ok(output),
+                Err(e) if attempt >= self.attempts => return Err(e),
+                Err(_) => {
+                    std::thread::sleep(backoff);
+                    backoff *= 2;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Where a workflow is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkflowStatus {
+    /// Waiting for the message that starts it
+    Pending,
+    /// Some steps done; the next message continues
+    Running,
+    /// Stopped at a step that exhausted its retries
+    Failed,
+    /// Every step completed
+    Completed,
+}
+
+impl WorkflowStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WorkflowStatus::Pending => "pending",
+            WorkflowStatus::Running => "running",
+            WorkflowStatus::Failed => "failed",
+            WorkflowStatus::Completed => "completed",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(WorkflowStatus::Pending),
+            "running" => Some(WorkflowStatus::Running),
+            "failed" => Some(WorkflowStatus::Failed),
+            "completed" => Some(WorkflowStatus::Completed),
+            _ => None,
+        }
+    }
+}
+
+/// Workflow progress, stored as the actor's state Map
+///
+/// Keys: `status` (String), `next_step` (Int), and `data` (the last
+/// completed step's output, or the starting message).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub status: WorkflowStatus,
+    pub next_step: usize,
+    pub data: TypedValue,
+}
+
+fn key(name: &str) -> TypedMapKey {
+    TypedMapKey::String(name.to_string())
+}
+
+impl Progress {
+    fn to_value(&self) -> TypedValue {
+        TypedValue::Map(BTreeMap::from([
+            (
+                key("status"),
+                TypedValue::String(self.status.as_str().to_string()),
+            ),
+            (key("next_step"), TypedValue::Int(self.next_step as i64)),
+            (key("data"), self.data.clone()),
+        ]))
+    }
+
+    /// Read progress back from an actor state value
+    pub fn from_value(value: &TypedValue) -> Option<Self> {
+        let TypedValue::Map(fields) = value else {
+            return None;
+        };
+        let status = match fields.get(&key("status")) {
+            Some(TypedValue::String(s)) => WorkflowStatus::parse(s)?,
+            _ => return None,
+        };
+        let next_step = match fields.get(&key("next_step")) {
+            Some(TypedValue::Int(n)) if *n >= 0 => *n as usize,
+            _ => return None,
+        };
+        let data = fields.get(&key("data")).cloned()?;
+        Some(Progress {
+            status,
+            next_step,
+            data,
+        })
+    }
+}
+
+/// A workflow behavior
+#[derive(Clone, Default)]
+pub struct Workflow {
+    steps: Vec<Step>,
+}
+
+impl Workflow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a step
+    pub fn step(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    fn step_event_payload(index: usize, step: &Step, field: &str, value: TypedValue) -> TypedValue {
+        TypedValue::Map(BTreeMap::from([
+            (key("step"), TypedValue::Int(index as i64)),
+            (key("name"), TypedValue::String(step.name.clone())),
+            (key(field), value),
+        ]))
+    }
+}
+
+impl Behavior for Workflow {
+    fn handle(
+        &self,
+        ctx: &mut BehaviorContext,
+        state: &TypedValue,
+        msg: &TypedValue,
+    ) -> Result<TypedValue, BehaviorError> {
+        let mut progress = Progress::from_value(state)
+            .ok_or_else(|| BehaviorError::new("workflow state is corrupt"))?;
+
+        match progress.status {
+            WorkflowStatus::Completed => {
+                return Err(BehaviorError::new("workflow already completed"));
+            }
+            WorkflowStatus::Pending => {
+                let input = BTreeMap::from([(key("input"), msg.clone())]);
+                ctx.emit(STARTED_EVENT, TypedValue::Map(input));
+                progress.data = msg.clone();
+            }
+            WorkflowStatus::Running | WorkflowStatus::Failed => {}
+        }
+        progress.status = WorkflowStatus::Running;
+
+        while let Some(step) = self.steps.get(progress.next_step) {
+            match step.execute(&progress.data) {
+                Ok(output) => {
+                    ctx.emit(
+                        STEP_COMPLETED_EVENT,
+                        Self::step_event_payload(
+                            progress.next_step,
+                            step,
+                            "output",
+                            output.clone(),
+                        ),
+                    );
+                    progress.data = output;
+                    progress.next_step += 1;
+                }
+                Err(e) => {
+                    ctx.emit(
+                        STEP_FAILED_EVENT,
+                        Self::step_event_payload(
+                            progress.next_step,
+                            step,
+                            "error",
+                            TypedValue::String(e.0),
+                        ),
+                    );
+                    progress.status = WorkflowStatus::Failed;
+                    return Ok(progress.to_value());
+                }
+            }
+        }
+
+        progress.status = WorkflowStatus::Completed;
+        Ok(progress.to_value())
+    }
+
+    fn initial_state(&self) -> TypedValue {
+        Progress {
+            status: WorkflowStatus::Pending,
+            next_step: 0,
+            data: TypedValue::Map(BTreeMap::new()),
+        }
+        .to_value()
+    }
+
+    fn apply_event(&self, state: &TypedValue, event: &Event) -> TypedValue {
+        let (Some(mut progress), TypedValue::Map(fields)) =
+            (Progress::from_value(state), &event.payload)
+        else {
+            return state.clone();
+        };
+        if event.event_type == STARTED_EVENT {
+            if let Some(input) = fields.get(&key("input")) {
+                progress.data = input.clone();
+                progress.status = WorkflowStatus::Running;
+            }
+            return progress.to_value();
+        }
+        let step = match fields.get(&key("step")) {
+            Some(TypedValue::Int(n)) if *n >= 0 => *n as usize,
+            _ => return state.clone(),
+        };
+
+        match event.event_type.as_str() {
+            STEP_COMPLETED_EVENT => {
+                if let Some(output) = fields.get(&key("output")) {
+                    progress.data = output.clone();
+                }
+                progress.next_step = step + 1;
+                progress.status = if progress.next_step >= self.steps.len() {
+                    WorkflowStatus::Completed
+                } else {
+                    WorkflowStatus::Running
+                };
+            }
+            STEP_FAILED_EVENT => {
+                progress.next_step = step;
+                progress.status = WorkflowStatus::Failed;
+            }
+            _ => return state.clone(),
+        }
+        progress.to_value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::{ActorRuntime, RuntimeConfig};
+    use crate::testkit::BehaviorHarness;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use tempfile::TempDir;
+
+    fn add(n: i64) -> impl Fn(&TypedValue) -> Result<TypedValue, BehaviorError> {
+        move |input| match input {
+            TypedValue::Int(v) => Ok(TypedValue::Int(v + n)),
+            _ => Err(BehaviorError::new("expected Int")),
+        }
+    }
+
+    /// Fails until called `failures + 1` times
+    fn flaky(
+        calls: Arc<AtomicU32>,
+        failures: u32,
+    ) -> impl Fn(&TypedValue) -> Result<TypedValue, BehaviorError> {
+        move |input| {
+            if calls.fetch_add(1, Ordering::SeqCst) < failures {
+                Err(BehaviorError::new("transient"))
+            } else {
+                Ok(input.clone())
+            }
+        }
+    }
+
+    fn progress(harness: &BehaviorHarness<Workflow>) -> Progress {
+        Progress::from_value(harness.state()).unwrap()
+    }
+
+    #[test]
+    fn test_steps_run_in_order_with_retries() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let workflow = Workflow::new()
+            .step(Step::new("double", |v: &TypedValue| match v {
+                TypedValue::Int(n) => Ok(TypedValue::Int(n * 2)),
+                _ => Err(BehaviorError::new("expected Int")),
+            }))
+            .step(Step::new("flaky", flaky(calls.clone(), 2)).retries(2, Duration::from_millis(1)))
+            .step(Step::new("inc", add(1)));
+
+        let mut harness = BehaviorHarness::new(workflow);
+        harness.send(TypedValue::Int(5)).unwrap();
+
+        let done = progress(&harness);
+        assert_eq!(done.status, WorkflowStatus::Completed);
+        assert_eq!(done.data, TypedValue::Int(11));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(harness.events().len(), 4);
+        assert_eq!(harness.events()[0].event_type, STARTED_EVENT);
+        assert!(harness.send(TypedValue::Int(1)).is_err());
+    }
+
+    #[test]
+    fn test_failed_step_resumes_on_next_message() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let workflow = Workflow::new()
+            .step(Step::new("inc", add(1)))
+            .step(Step::new("flaky", flaky(calls.clone(), 1)));
+
+        let mut harness = BehaviorHarness::new(workflow);
+        harness.send(TypedValue::Int(1)).unwrap();
+        let failed = progress(&harness);
+        assert_eq!(failed.status, WorkflowStatus::Failed);
+        assert_eq!(failed.next_step, 1);
+        assert_eq!(harness.events()[2].event_type, STEP_FAILED_EVENT);
+
+        // The retry message is ignored as input; the step gets its saved input
+        harness.send(TypedValue::Int(100)).unwrap();
+        let done = progress(&harness);
+        assert_eq!(done.status, WorkflowStatus::Completed);
+        assert_eq!(done.data, TypedValue::Int(2));
+    }
+
+    #[test]
+    fn test_timeout_fails_attempt() {
+        let workflow = Workflow::new().step(
+            Step::new("slow", |v: &TypedValue| {
+                std::thread::sleep(Duration::from_millis(20));
+                Ok(v.clone())
+            })
+            .timeout(Duration::from_millis(1)),
+        );
+
+        let mut harness = BehaviorHarness::new(workflow);
+        harness.send(TypedValue::Int(1)).unwrap();
+        assert_eq!(progress(&harness).status, WorkflowStatus::Failed);
+    }
+
+    #[test]
+    fn test_respawn_resumes_at_failed_step() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            ..RuntimeConfig::default()
+        });
+        let calls = Arc::new(AtomicU32::new(0));
+        runtime.register_behavior(
+            "wf",
+            Workflow::new()
+                .step(Step::new("inc", add(1)))
+                .step(Step::new("flaky", flaky(calls.clone(), 1)))
+                .step(Step::new("inc", add(10))),
+        );
+
+        let id = runtime.spawn("wf").unwrap();
+        runtime.send(&id, TypedValue::Int(1)).unwrap();
+        runtime.run_until_idle().unwrap();
+
+        runtime.unregister_actor(&id);
+        runtime.spawn_with_id(id.clone(), "wf").unwrap();
+        let recovered = Progress::from_value(&runtime.peek_state(&id).unwrap()).unwrap();
+        assert_eq!(recovered.status, WorkflowStatus::Failed);
+        assert_eq!(recovered.next_step, 1);
+        assert_eq!(recovered.data, TypedValue::Int(2));
+
+        runtime.send(&id, TypedValue::Int(0)).unwrap();
+        runtime.run_until_idle().unwrap();
+        let done = Progress::from_value(&runtime.peek_state(&id).unwrap()).unwrap();
+        assert_eq!(done.status, WorkflowStatus::Completed);
+        assert_eq!(done.data, TypedValue::Int(12));
+
+        runtime.unregister_actor(&id);
+    }
+
+    #[test]
+    fn test_respawn_keeps_start_input_when_first_step_failed() {
+        let temp_dir = TempDir::new().unwrap();
+        let runtime = ActorRuntime::new(RuntimeConfig {
+            journal_path: temp_dir.path().to_path_buf(),
+            ..RuntimeConfig::default()
+        });
+        let calls = Arc::new(AtomicU32::new(0));
+        runtime.register_behavior(
+            "wf",
+            Workflow::new().step(Step::new("flaky", flaky(calls.clone(), 1))),
+        );
+
+        let id = runtime.spawn("wf").unwrap();
+        runtime.send(&id, TypedValue::Int(7)).unwrap();
+        runtime.run_until_idle().unwrap();
+
+        runtime.unregister_actor(&id);
+        runtime.spawn_with_id(id.clone(), "wf").unwrap();
+        let recovered = Progress::from_value(&runtime.peek_state(&id).unwrap()).unwrap();
+        assert_eq!(recovered.status, WorkflowStatus::Failed);
+        assert_eq!(recovered.data, TypedValue::Int(7));
+
+        runtime.send(&id, TypedValue::Int(0)).unwrap();
+        runtime.run_until_idle().unwrap();
+        let done = Progress::from_value(&runtime.peek_state(&id).unwrap()).unwrap();
+        assert_eq!(done.status, WorkflowStatus::Completed);
+        assert_eq!(done.data, TypedValue::Int(7));
+
+        runtime.unregister_actor(&id);
+    }
+}
@@ -0,0 +1,102 @@
+//! Actor builtins called through their C symbols
+//!
+//! Links the real seq-runtime, so `seq_actors_*` run against the same
+//! `patch_seq_*` functions a compiled Seq program uses. Stacks are built
+//! with seq-runtime's push functions and read back through the node
+//! layout `ffi` assumes (an Int in the value's first 8 bytes).
+
+use seq_actors::{
+    ActorHandle, ActorRuntime, BehaviorContext, BehaviorError, RuntimeConfig, TypedValue,
+};
+use std::ffi::c_char;
+use tempfile::TempDir;
+
+#[repr(C)]
+struct StackNode {
+    int_val: i64,
+    _rest: [u8; 24],
+    next: *mut StackNode,
+}
+
+type Stack = *mut StackNode;
+
+extern "C" {
+    fn patch_seq_push_int(stack: Stack, value: i64) -> Stack;
+    fn patch_seq_push_string(stack: Stack, s: *const c_char) -> Stack;
+
+    fn seq_actors_spawn(stack: Stack) -> Stack;
+    fn seq_actors_send(stack: Stack) -> Stack;
+    fn seq_actors_self(stack: Stack) -> Stack;
+}
+
+unsafe fn pop_int(stack: Stack) -> (Stack, i64) {
+    assert!(!stack.is_null(), "stack underflow");
+    let node = Box::from_raw(stack);
+    (node.next, node.int_val)
+}
+
+fn runtime(temp_dir: &TempDir) -> ActorRuntime {
+    ActorRuntime::new(RuntimeConfig {
+        journal_path: temp_dir.path().to_path_buf(),
+        ..RuntimeConfig::default()
+    })
+}
+
+#[test]
+fn spawn_registers_actor_and_pushes_handle() {
+    let temp_dir = TempDir::new().unwrap();
+    let runtime = runtime(&temp_dir);
+
+    let stack = unsafe { patch_seq_push_string(std::ptr::null_mut(), c"counter".as_ptr()) };
+    let stack = unsafe { seq_actors_spawn(stack) };
+    let (stack, raw) = unsafe { pop_int(stack) };
+    assert!(stack.is_null());
+
+    let id = runtime
+        .resolve_handle(ActorHandle(raw as u64))
+        .expect("spawned actor is registered");
+    assert!(runtime.is_running(&id));
+    runtime.unregister_actor(&id);
+}
+
+#[test]
+fn send_consumes_handle_and_message() {
+    let temp_dir = TempDir::new().unwrap();
+    let runtime = runtime(&temp_dir);
+
+    let stack = unsafe { patch_seq_push_string(std::ptr::null_mut(), c"counter".as_ptr()) };
+    let (_, raw) = unsafe { pop_int(seq_actors_spawn(stack)) };
+
+    let stack = unsafe { patch_seq_push_int(std::ptr::null_mut(), 42) };
+    let stack = unsafe { patch_seq_push_int(stack, raw) };
+    let stack = unsafe { seq_actors_send(stack) };
+    assert!(stack.is_null());
+
+    let id = runtime.resolve_handle(ActorHandle(raw as u64)).unwrap();
+    runtime.unregister_actor(&id);
+}
+
+#[test]
+fn self_pushes_current_actor_handle() {
+    let temp_dir = TempDir::new().unwrap();
+    let runtime = runtime(&temp_dir);
+    runtime.register_behavior(
+        "whoami",
+        |ctx: &mut BehaviorContext, state: &TypedValue, _msg: &TypedValue| {
+            let (stack, raw) = unsafe { pop_int(seq_actors_self(std::ptr::null_mut())) };
+            assert!(stack.is_null());
+            ctx.reply(TypedValue::Int(raw));
+            Ok::<_, BehaviorError>(state.clone())
+        },
+    );
+
+    let id = runtime.spawn("whoami").unwrap();
+    let reply = runtime.ask(&id, TypedValue::Int(0)).unwrap();
+    runtime.run_until_idle().unwrap();
+    let handle = runtime.handle_of(&id).unwrap();
+    assert_eq!(
+        reply.wait(std::time::Duration::from_secs(1)).unwrap(),
+        TypedValue::Int(handle.as_raw() as i64)
+    );
+    runtime.unregister_actor(&id);
+}